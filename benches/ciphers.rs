@@ -0,0 +1,72 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use playfair_cipher::digram_table::DigramTable;
+use playfair_cipher::prelude::*;
+
+const TEXT: &str = "The quick red fox jumps over the lazy brown dog. ";
+const SIZES: [usize; 3] = [64, 4 * 1024, 256 * 1024];
+
+fn payload(size: usize) -> String {
+    TEXT.chars().cycle().take(size).collect()
+}
+
+fn key_construction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key_construction");
+    group.bench_function("playfair", |b| {
+        b.iter(|| PlayFairKey::new(black_box("playfair example")))
+    });
+    group.bench_function("two_square", |b| {
+        b.iter(|| TwoSquare::new(black_box("EXAMPLE"), black_box("KEYWORD")))
+    });
+    group.bench_function("four_square", |b| {
+        b.iter(|| FourSquare::new(black_box("EXAMPLE"), black_box("KEYWORD")))
+    });
+    group.bench_function("digram_table", |b| {
+        b.iter(|| DigramTable::new(PlayFairKey::new(black_box("playfair example"))))
+    });
+    group.finish();
+}
+
+fn normalization(c: &mut Criterion) {
+    let options = PayloadOptions::default();
+    let mut group = c.benchmark_group("normalize");
+    for size in SIZES {
+        let payload = payload(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &payload, |b, p| {
+            b.iter(|| normalize(black_box(p), &options))
+        });
+    }
+    group.finish();
+}
+
+fn bench_cipher<C: Cypher>(c: &mut Criterion, name: &str, cipher: &C) {
+    let mut group = c.benchmark_group(name);
+    for size in SIZES {
+        let payload = payload(size);
+        let crypt = cipher.encrypt(&payload).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("encrypt", size), &payload, |b, p| {
+            b.iter(|| cipher.encrypt(black_box(p)))
+        });
+        group.bench_with_input(BenchmarkId::new("decrypt", size), &crypt, |b, p| {
+            b.iter(|| cipher.decrypt(black_box(p)))
+        });
+    }
+    group.finish();
+}
+
+fn ciphers(c: &mut Criterion) {
+    bench_cipher(c, "playfair", &PlayFairKey::new("playfair example"));
+    bench_cipher(c, "two_square", &TwoSquare::new("EXAMPLE", "KEYWORD"));
+    bench_cipher(c, "four_square", &FourSquare::new("EXAMPLE", "KEYWORD"));
+    bench_cipher(
+        c,
+        "playfair_digram_table",
+        &DigramTable::new(PlayFairKey::new("playfair example")),
+    );
+}
+
+criterion_group!(benches, key_construction, normalization, ciphers);
+criterion_main!(benches);
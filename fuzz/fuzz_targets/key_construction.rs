@@ -0,0 +1,46 @@
+//! Builds the keys of all ciphers from arbitrary keywords and options. A
+//! key which builds must consist of the 25 letters other than the omitted one.
+//!
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use playfair_cipher::prelude::*;
+
+fn assert_complete(square: &[char; 25], omit: char) {
+    let mut letters = square.to_vec();
+    letters.sort_unstable();
+    let expected: Vec<char> = ('A'..='Z').filter(|c| *c != omit).collect();
+    assert_eq!(letters, expected, "square {:?} omitting {}", square, omit);
+}
+
+fuzz_target!(|input: (String, String, char, char)| {
+    let (key0, key1, padding, omit) = input;
+    let Ok(pfc) = PlayFairKey::builder()
+        .key(&key0)
+        .padding(padding)
+        .omit(omit)
+        .build()
+    else {
+        return;
+    };
+    let omit = pfc.options().omit();
+    assert_complete(pfc.square(), omit);
+
+    let tsq = TwoSquare::builder()
+        .keys(&key0, &key1)
+        .padding(padding)
+        .omit(omit)
+        .build()
+        .expect("options valid for PlayFair are valid for Two Square");
+    assert_complete(tsq.top().square(), omit);
+    assert_complete(tsq.bottom().square(), omit);
+
+    let fsq = FourSquare::builder()
+        .keys(&key0, &key1)
+        .padding(padding)
+        .omit(omit)
+        .build()
+        .expect("options valid for PlayFair are valid for Four Square");
+    assert_complete(fsq.top_right().square(), omit);
+    assert_complete(fsq.bottom_left().square(), omit);
+});
@@ -0,0 +1,22 @@
+//! Normalizes arbitrary, mostly non ASCII, payloads. The result must only
+//! consist of letters of the square and normalizing it again must not
+//! change it.
+//!
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use playfair_cipher::prelude::*;
+
+fuzz_target!(|input: (String, char)| {
+    let (payload, omit) = input;
+    let Ok(options) =
+        PayloadOptions::new('X', omit, None).or_else(|_| PayloadOptions::new('Q', omit, None))
+    else {
+        return;
+    };
+    let normalized = normalize(&payload, &options);
+    assert!(normalized
+        .chars()
+        .all(|c| c.is_ascii_uppercase() && c != options.omit()));
+    assert_eq!(normalize(&normalized, &options), normalized);
+});
@@ -0,0 +1,51 @@
+//! Encrypts arbitrary payloads and decrypts the result. Decrypting must
+//! yield the normalized payload as split into digrams: a doubled letter is
+//! split by the padding and a payload of odd length is padded.
+//!
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use playfair_cipher::prelude::*;
+
+// The normalized payload split into digrams, written independently of the
+// crate's own implementation.
+fn digrams(normalized: &str, padding: char) -> String {
+    let letters: Vec<char> = normalized.chars().collect();
+    let mut result = String::new();
+    let mut i = 0;
+    while i < letters.len() {
+        let first = letters[i];
+        match letters.get(i + 1) {
+            Some(second) if *second != first => {
+                result.push(first);
+                result.push(*second);
+                i += 2;
+            }
+            _ => {
+                result.push(first);
+                result.push(padding);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+fn check(cipher: &impl Cypher, options: &PayloadOptions, payload: &str) {
+    let crypt = cipher
+        .encrypt(payload)
+        .expect("normalized payload is part of the key");
+    let plain = cipher
+        .decrypt(&crypt)
+        .expect("cipher text is part of the key");
+    let expected = digrams(&normalize(payload, options), options.padding());
+    assert_eq!(plain, expected, "payload {:?}", payload);
+}
+
+fuzz_target!(|input: (String, String, String)| {
+    let (key0, key1, payload) = input;
+    let options = PayloadOptions::default();
+    check(&PlayFairKey::new(&key0), &options, &payload);
+    check(&TwoSquare::new(&key0, &key1), &options, &payload);
+    check(&FourSquare::new(&key0, &key1), &options, &payload);
+});
@@ -0,0 +1,356 @@
+//! Node.js bindings of playfair_cipher. Built with `npm run build`, the
+//! addon offers the classes PlayFairKey, TwoSquare and FourSquare:
+//!
+//! ```js
+//! const { PlayFairKey } = require('./playfair_cipher.node')
+//!
+//! const pfc = new PlayFairKey('playfair example', { grouping: 5 })
+//! pfc.encrypt('hide the gold') // 'BMODZ BXDNA GE'
+//! await pfc.encryptFile('plain.txt', 'crypt.txt')
+//! ```
+//!
+//! Errors are thrown as JS errors: invalid options and characters not
+//! found in the key with the code "InvalidArg", failing file operations
+//! with the code "GenericFailure".
+//!
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use napi_derive::napi;
+
+use std::fmt::Display;
+
+use playfair_cipher::chunked::{decrypt_file, encrypt_file};
+use playfair_cipher::cryptable::Cypher;
+use playfair_cipher::errors::StreamError;
+use playfair_cipher::four_square::FourSquare;
+use playfair_cipher::playfair::PlayFairKey;
+use playfair_cipher::two_square::TwoSquare;
+
+// Errors of the crate caused by the arguments given from JS.
+fn invalid_arg(e: impl Display) -> Error {
+    Error::new(Status::InvalidArg, e.to_string())
+}
+
+fn stream_error(e: StreamError) -> Error {
+    match e {
+        StreamError::CharNotInKey(e) => invalid_arg(e),
+        StreamError::Io(e) => Error::new(Status::GenericFailure, e.to_string()),
+    }
+}
+
+/// Options of a cipher, any of them may be left out.
+#[napi(object)]
+pub struct CipherOptions {
+    pub padding: Option<String>,
+    pub omit: Option<String>,
+    pub grouping: Option<u32>,
+}
+
+// The single char of an option given as JS string.
+fn single_char(option: &str, value: &str) -> Result<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(Error::new(
+            Status::InvalidArg,
+            format!("{} must be a single char - got '{}'", option, value),
+        )),
+    }
+}
+
+/// En- or decrypts a file on the thread pool of Node.js.
+pub struct FileTask<C> {
+    cipher: C,
+    path_in: String,
+    path_out: String,
+    encrypt: bool,
+}
+
+impl<C: Cypher + Send + 'static> Task for FileTask<C> {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        match self.encrypt {
+            true => encrypt_file(&self.cipher, &self.path_in, &self.path_out),
+            false => decrypt_file(&self.cipher, &self.path_in, &self.path_out),
+        }
+        .map_err(stream_error)
+    }
+
+    fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+        Ok(())
+    }
+}
+
+/// The PlayFair cipher, see playfair::PlayFairKey.
+#[napi(js_name = "PlayFairKey")]
+pub struct JsPlayFairKey {
+    cipher: PlayFairKey,
+}
+
+#[napi]
+impl JsPlayFairKey {
+    #[napi(constructor)]
+    pub fn new(key: String, options: Option<CipherOptions>) -> Result<Self> {
+        let mut builder = PlayFairKey::builder().key(&key);
+        if let Some(options) = options {
+            if let Some(padding) = options.padding {
+                builder = builder.padding(single_char("padding", &padding)?);
+            }
+            if let Some(omit) = options.omit {
+                builder = builder.omit(single_char("omit", &omit)?);
+            }
+            if let Some(grouping) = options.grouping {
+                builder = builder.grouping(grouping as usize);
+            }
+        }
+        Ok(JsPlayFairKey {
+            cipher: builder.build().map_err(invalid_arg)?,
+        })
+    }
+
+    #[napi]
+    pub fn encrypt(&self, payload: String) -> Result<String> {
+        self.cipher.encrypt(&payload).map_err(invalid_arg)
+    }
+
+    #[napi]
+    pub fn decrypt(&self, payload: String) -> Result<String> {
+        self.cipher.decrypt(&payload).map_err(invalid_arg)
+    }
+
+    /// Encrypts a file, resolves when done.
+    #[napi]
+    pub fn encrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<PlayFairKey>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: true,
+        })
+    }
+
+    /// Decrypts a file, resolves when done.
+    #[napi]
+    pub fn decrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<PlayFairKey>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: false,
+        })
+    }
+
+    #[napi(js_name = "toString")]
+    pub fn to_js_string(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+/// The Two Square cipher, see two_square::TwoSquare.
+#[napi(js_name = "TwoSquare")]
+pub struct JsTwoSquare {
+    cipher: TwoSquare,
+}
+
+#[napi]
+impl JsTwoSquare {
+    #[napi(constructor)]
+    pub fn new(key0: String, key1: String, options: Option<CipherOptions>) -> Result<Self> {
+        let mut builder = TwoSquare::builder().keys(&key0, &key1);
+        if let Some(options) = options {
+            if let Some(padding) = options.padding {
+                builder = builder.padding(single_char("padding", &padding)?);
+            }
+            if let Some(omit) = options.omit {
+                builder = builder.omit(single_char("omit", &omit)?);
+            }
+            if let Some(grouping) = options.grouping {
+                builder = builder.grouping(grouping as usize);
+            }
+        }
+        Ok(JsTwoSquare {
+            cipher: builder.build().map_err(invalid_arg)?,
+        })
+    }
+
+    #[napi]
+    pub fn encrypt(&self, payload: String) -> Result<String> {
+        self.cipher.encrypt(&payload).map_err(invalid_arg)
+    }
+
+    #[napi]
+    pub fn decrypt(&self, payload: String) -> Result<String> {
+        self.cipher.decrypt(&payload).map_err(invalid_arg)
+    }
+
+    /// Encrypts a file, resolves when done.
+    #[napi]
+    pub fn encrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<TwoSquare>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: true,
+        })
+    }
+
+    /// Decrypts a file, resolves when done.
+    #[napi]
+    pub fn decrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<TwoSquare>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: false,
+        })
+    }
+
+    #[napi(js_name = "toString")]
+    pub fn to_js_string(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+/// The Four Square cipher, see four_square::FourSquare.
+#[napi(js_name = "FourSquare")]
+pub struct JsFourSquare {
+    cipher: FourSquare,
+}
+
+#[napi]
+impl JsFourSquare {
+    #[napi(constructor)]
+    pub fn new(key0: String, key1: String, options: Option<CipherOptions>) -> Result<Self> {
+        let mut builder = FourSquare::builder().keys(&key0, &key1);
+        if let Some(options) = options {
+            if let Some(padding) = options.padding {
+                builder = builder.padding(single_char("padding", &padding)?);
+            }
+            if let Some(omit) = options.omit {
+                builder = builder.omit(single_char("omit", &omit)?);
+            }
+            if let Some(grouping) = options.grouping {
+                builder = builder.grouping(grouping as usize);
+            }
+        }
+        Ok(JsFourSquare {
+            cipher: builder.build().map_err(invalid_arg)?,
+        })
+    }
+
+    #[napi]
+    pub fn encrypt(&self, payload: String) -> Result<String> {
+        self.cipher.encrypt(&payload).map_err(invalid_arg)
+    }
+
+    #[napi]
+    pub fn decrypt(&self, payload: String) -> Result<String> {
+        self.cipher.decrypt(&payload).map_err(invalid_arg)
+    }
+
+    /// Encrypts a file, resolves when done.
+    #[napi]
+    pub fn encrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<FourSquare>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: true,
+        })
+    }
+
+    /// Decrypts a file, resolves when done.
+    #[napi]
+    pub fn decrypt_file(
+        &self,
+        path_in: String,
+        path_out: String,
+    ) -> AsyncTask<FileTask<FourSquare>> {
+        AsyncTask::new(FileTask {
+            cipher: self.cipher.clone(),
+            path_in,
+            path_out,
+            encrypt: false,
+        })
+    }
+
+    #[napi(js_name = "toString")]
+    pub fn to_js_string(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_crypt() {
+        let options = CipherOptions {
+            padding: None,
+            omit: None,
+            grouping: Some(5),
+        };
+        let pfc = JsPlayFairKey::new(String::from("playfair example"), Some(options)).unwrap();
+        assert_eq!(
+            pfc.encrypt(String::from("hide the gold")).unwrap(),
+            "BMODZ BXDNA GE"
+        );
+        let options = CipherOptions {
+            padding: Some(String::from("XY")),
+            omit: None,
+            grouping: None,
+        };
+        let e = JsTwoSquare::new(
+            String::from("EXAMPLE"),
+            String::from("KEYWORD"),
+            Some(options),
+        );
+        assert_eq!(e.err().unwrap().status, Status::InvalidArg);
+    }
+
+    #[test]
+    fn test_file_task() {
+        let dir = std::env::temp_dir();
+        let path_in = dir.join(format!("node_task_in_{}.txt", std::process::id()));
+        let path_out = dir.join(format!("node_task_out_{}.txt", std::process::id()));
+        std::fs::write(&path_in, "joe").unwrap();
+        let mut task = FileTask {
+            cipher: FourSquare::new("EXAMPLE", "KEYWORD"),
+            path_in: path_in.to_string_lossy().into_owned(),
+            path_out: path_out.to_string_lossy().into_owned(),
+            encrypt: true,
+        };
+        task.compute().unwrap();
+        let crypt = task.cipher.encrypt("joe").unwrap();
+        assert_eq!(std::fs::read_to_string(&path_out).unwrap(), crypt);
+        task.path_in = String::from("/nonexistent/playfair");
+        assert_eq!(task.compute().err().unwrap().status, Status::GenericFailure);
+        let _ = std::fs::remove_file(path_in);
+        let _ = std::fs::remove_file(path_out);
+    }
+}
@@ -0,0 +1,504 @@
+//! Frequency analysis of texts, the first step to crack any of the ciphers.
+//! Only the letters A-Z are taken into account, any other character is
+//! skipped and lowercase letters are counted as uppercase ones.
+//!
+
+pub mod language;
+pub mod scoring;
+
+use std::collections::HashMap;
+
+use language::Language;
+
+/// Relative frequencies of the letters A-Z in English texts.
+pub const ENGLISH_MONOGRAMS: [f64; 26] = [
+    0.0804, 0.0148, 0.0334, 0.0382, 0.1249, 0.0240, 0.0187, 0.0505, 0.0757, 0.0016, 0.0054, 0.0407,
+    0.0251, 0.0723, 0.0764, 0.0214, 0.0012, 0.0628, 0.0651, 0.0928, 0.0273, 0.0105, 0.0168, 0.0023,
+    0.0166, 0.0009,
+];
+
+/// Relative frequencies of the 30 most frequent digrams in English texts,
+/// most frequent first.
+pub const ENGLISH_DIGRAMS: [([char; 2], f64); 30] = [
+    (['T', 'H'], 0.0356),
+    (['H', 'E'], 0.0307),
+    (['I', 'N'], 0.0243),
+    (['E', 'R'], 0.0205),
+    (['A', 'N'], 0.0199),
+    (['R', 'E'], 0.0185),
+    (['O', 'N'], 0.0176),
+    (['A', 'T'], 0.0149),
+    (['E', 'N'], 0.0145),
+    (['N', 'D'], 0.0135),
+    (['T', 'I'], 0.0134),
+    (['E', 'S'], 0.0134),
+    (['O', 'R'], 0.0128),
+    (['T', 'E'], 0.0120),
+    (['O', 'F'], 0.0117),
+    (['E', 'D'], 0.0117),
+    (['I', 'S'], 0.0113),
+    (['I', 'T'], 0.0112),
+    (['A', 'L'], 0.0109),
+    (['A', 'R'], 0.0107),
+    (['S', 'T'], 0.0105),
+    (['T', 'O'], 0.0104),
+    (['N', 'T'], 0.0104),
+    (['N', 'G'], 0.0095),
+    (['S', 'E'], 0.0093),
+    (['H', 'A'], 0.0093),
+    (['A', 'S'], 0.0087),
+    (['O', 'U'], 0.0087),
+    (['I', 'O'], 0.0083),
+    (['L', 'E'], 0.0083),
+];
+
+/// Observed relative frequency of a letter or digram compared to the
+/// expected one in English texts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyComparison<T> {
+    pub item: T,
+    pub observed: f64,
+    pub expected: f64,
+}
+
+impl<T> FrequencyComparison<T> {
+    /// Observed minus expected relative frequency.
+    pub fn deviation(&self) -> f64 {
+        self.observed - self.expected
+    }
+}
+
+pub(crate) fn letters(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars()
+        .map(|c| c.to_ascii_uppercase())
+        .filter(|c| c.is_ascii_uppercase())
+}
+
+/// Counts how often each letter occurs in the text.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::monogram_frequencies;
+///
+/// let frequencies = monogram_frequencies("Hide the gold!");
+/// assert_eq!(frequencies[&'D'], 2);
+/// assert_eq!(frequencies[&'E'], 2);
+/// assert_eq!(frequencies.get(&'Z'), None);
+/// ```
+pub fn monogram_frequencies(text: &str) -> HashMap<char, usize> {
+    let mut frequencies = HashMap::new();
+    for c in letters(text) {
+        *frequencies.entry(c).or_insert(0) += 1;
+    }
+    frequencies
+}
+
+/// Counts how often each digram occurs in the text. The letters are split
+/// into digrams the way the ciphers do it, one pair after the other, so
+/// the digrams don't overlap. A trailing single letter is not counted.
+/// Doubled letters are not split, so cipher texts can be analysed as
+/// they are.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::digram_frequencies;
+///
+/// let frequencies = digram_frequencies("BMODZ BXDNA GE BM");
+/// assert_eq!(frequencies[&['B', 'M']], 2);
+/// assert_eq!(frequencies[&['G', 'E']], 1);
+/// assert_eq!(frequencies.get(&['M', 'O']), None);
+/// ```
+pub fn digram_frequencies(text: &str) -> HashMap<[char; 2], usize> {
+    let mut frequencies = HashMap::new();
+    let mut letters = letters(text);
+    while let (Some(a), Some(b)) = (letters.next(), letters.next()) {
+        *frequencies.entry([a, b]).or_insert(0) += 1;
+    }
+    frequencies
+}
+
+/// Compares the relative frequency of each letter in the text with its
+/// frequency in English texts. The result holds all letters A-Z in
+/// alphabetical order.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::compare_monograms;
+///
+/// let comparison = compare_monograms("eeee tttt");
+/// assert_eq!(comparison[4].item, 'E');
+/// assert_eq!(comparison[4].observed, 0.5);
+/// assert_eq!(comparison[4].expected, 0.1249);
+/// ```
+pub fn compare_monograms(text: &str) -> Vec<FrequencyComparison<char>> {
+    compare_monograms_in(text, Language::English)
+}
+
+/// Same as compare_monograms for a text of the language given.
+pub fn compare_monograms_in(text: &str, language: Language) -> Vec<FrequencyComparison<char>> {
+    let frequencies = monogram_frequencies(text);
+    let total: usize = frequencies.values().sum();
+    ('A'..='Z')
+        .zip(language.monograms().iter().copied())
+        .map(|(item, expected)| FrequencyComparison {
+            item,
+            observed: relative(frequencies.get(&item), total),
+            expected,
+        })
+        .collect()
+}
+
+/// Compares the relative frequency of the most frequent English digrams
+/// (see ENGLISH_DIGRAMS) in the text with their frequency in English
+/// texts. The result is in the order of ENGLISH_DIGRAMS.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::compare_digrams;
+///
+/// let comparison = compare_digrams("then that");
+/// assert_eq!(comparison[0].item, ['T', 'H']);
+/// assert_eq!(comparison[0].observed, 0.5);
+/// ```
+pub fn compare_digrams(text: &str) -> Vec<FrequencyComparison<[char; 2]>> {
+    let frequencies = digram_frequencies(text);
+    let total: usize = frequencies.values().sum();
+    ENGLISH_DIGRAMS
+        .iter()
+        .map(|&(item, expected)| FrequencyComparison {
+            item,
+            observed: relative(frequencies.get(&item), total),
+            expected,
+        })
+        .collect()
+}
+
+/// Index of coincidence of the text, the probability two letters picked at
+/// random are the same. It's about 0.066 for English and 0.038 for random
+/// letters. Digram substitutions like the PlayFair cipher flatten the
+/// letter distribution, so their cipher texts are somewhere in between.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::index_of_coincidence;
+///
+/// assert_eq!(index_of_coincidence("aabb"), 1.0 / 3.0);
+/// assert_eq!(index_of_coincidence("a"), 0.0);
+/// ```
+pub fn index_of_coincidence(text: &str) -> f64 {
+    let frequencies = monogram_frequencies(text);
+    let total: usize = frequencies.values().sum();
+    if total < 2 {
+        return 0.0;
+    }
+    let coincidences: usize = frequencies.values().map(|n| n * (n - 1)).sum();
+    coincidences as f64 / (total * (total - 1)) as f64
+}
+
+/// Chi-squared statistic of the letter counts of the text against the
+/// counts expected in an English text of the same length. The lower, the
+/// more English the letter distribution is.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::chi_squared;
+///
+/// assert!(chi_squared("hide the gold in the tree stump") < chi_squared("xqzv jkwy"));
+/// ```
+pub fn chi_squared(text: &str) -> f64 {
+    chi_squared_in(text, Language::English)
+}
+
+/// Same as chi_squared against the letter frequencies of the language.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::{chi_squared_in, language::Language};
+///
+/// assert_eq!(chi_squared_in("hide the gold", Language::English),
+///     playfair_cipher::analysis::chi_squared("hide the gold"));
+/// ```
+pub fn chi_squared_in(text: &str, language: Language) -> f64 {
+    let total = letters(text).count() as f64;
+    if total == 0.0 {
+        return 0.0;
+    }
+    compare_monograms_in(text, language)
+        .iter()
+        .map(|comparison| {
+            let expected = comparison.expected * total;
+            let observed = comparison.observed * total;
+            (observed - expected).powi(2) / expected
+        })
+        .sum()
+}
+
+/// Shannon entropy of the letters of the text in bits per letter. It's at
+/// most log2(26), about 4.7, for evenly distributed letters and about 4.2
+/// for English.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::entropy;
+///
+/// assert_eq!(entropy("abab"), 1.0);
+/// assert_eq!(entropy("aaaa"), 0.0);
+/// ```
+pub fn entropy(text: &str) -> f64 {
+    let frequencies = monogram_frequencies(text);
+    let total: usize = frequencies.values().sum();
+    frequencies
+        .values()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Number of letters followed by the same letter, e.g. 2 for "balloon".
+pub fn doubled_letters(text: &str) -> usize {
+    let letters: Vec<char> = letters(text).collect();
+    letters.windows(2).filter(|pair| pair[0] == pair[1]).count()
+}
+
+/// Number of digrams, split as the ciphers do it, consisting of the same
+/// letter twice. The PlayFair cipher never yields such a digram, so a
+/// cipher text containing one wasn't encrypted by it.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::doubled_digrams;
+///
+/// assert_eq!(doubled_digrams("balloon"), 2);
+/// assert_eq!(doubled_digrams("BMODZBXDNAGE"), 0);
+/// ```
+pub fn doubled_digrams(text: &str) -> usize {
+    digram_frequencies(text)
+        .iter()
+        .filter(|(digram, _)| digram[0] == digram[1])
+        .map(|(_, count)| count)
+        .sum()
+}
+
+/// All statistics of a text at once, to characterize a cipher text before
+/// choosing an attack.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::Statistics;
+///
+/// let statistics = Statistics::of("BMODZ BXDNA BEKUD MUIXM MOUVI F");
+/// assert_eq!(statistics.letters, 26);
+/// assert_eq!(statistics.doubled_digrams, 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub letters: usize,
+    pub index_of_coincidence: f64,
+    pub chi_squared: f64,
+    pub entropy: f64,
+    pub doubled_letters: usize,
+    pub doubled_digrams: usize,
+}
+
+impl Statistics {
+    pub fn of(text: &str) -> Self {
+        Statistics {
+            letters: letters(text).count(),
+            index_of_coincidence: index_of_coincidence(text),
+            chi_squared: chi_squared(text),
+            entropy: entropy(text),
+            doubled_letters: doubled_letters(text),
+            doubled_digrams: doubled_digrams(text),
+        }
+    }
+}
+
+/// The ciphers identify can tell apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CipherKind {
+    PlayFair,
+    TwoSquare,
+    FourSquare,
+    /// Anything else, e.g. a plain text or a monoalphabetic substitution.
+    Other,
+}
+
+/// A guess of identify, the higher the score the more likely.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Guess {
+    pub cipher: CipherKind,
+    pub score: f64,
+}
+
+/// Estimates which cipher the cipher text was encrypted with. Returns a guess
+/// for each CipherKind, the most likely first. The scores add up to 1.
+///
+/// The estimation is based on statistical fingerprints, so it needs some
+/// hundred letters to be reliable:
+///
+/// - All three ciphers yield an even number of letters out of 25 at most.
+/// - Their digram substitution flattens the letter distribution, so the
+///   index of coincidence is well below English.
+/// - The PlayFair cipher never yields a digram of the same letter twice,
+///   the others do about once in 25 digrams.
+/// - The two square cipher leaves one in five digrams unchanged, so common
+///   English digrams show up in its cipher text.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::{identify, CipherKind};
+///
+/// let guesses = identify("It was the best of times, it was the worst of times.");
+/// assert_eq!(guesses[0].cipher, CipherKind::Other);
+/// ```
+pub fn identify(cipher_text: &str) -> Vec<Guess> {
+    let letters: Vec<char> = letters(cipher_text).collect();
+    let digrams = letters.len() / 2;
+    let mut distinct = letters.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let digram_cipher = digrams > 0 && letters.len().is_multiple_of(2) && distinct.len() <= 25;
+
+    let ic = index_of_coincidence(cipher_text);
+    // English is about 0.066, digram ciphers about 0.045 to 0.05
+    let flattened = ((0.062 - ic) / 0.012).clamp(0.05, 1.0);
+    let other = match digram_cipher {
+        true => 1.0 - flattened + 0.05,
+        false => 1.0,
+    };
+
+    // Probability none of the digrams is doubled, if one in 25 is
+    let doubled = doubled_digrams(cipher_text);
+    let doubled_likelihood = match doubled {
+        0 => (24.0f64 / 25.0).powi(digrams as i32),
+        _ => 1.0,
+    };
+    let playfair_likelihood = match doubled {
+        0 => 1.0,
+        _ => 0.0,
+    };
+
+    // Share of the most common English digrams, 0.048 for evenly
+    // distributed digrams and about 0.12 for a two square cipher text
+    let common: f64 = compare_digrams(cipher_text)
+        .iter()
+        .map(|comparison| comparison.observed)
+        .sum();
+    let transparent = ((common - 0.06) / 0.05).clamp(0.05, 0.95);
+
+    let structure = match digram_cipher {
+        true => flattened,
+        false => 0.0,
+    };
+    let mut guesses = vec![
+        Guess {
+            cipher: CipherKind::PlayFair,
+            score: structure * playfair_likelihood * (1.0 - transparent),
+        },
+        Guess {
+            cipher: CipherKind::TwoSquare,
+            score: structure * doubled_likelihood * transparent,
+        },
+        Guess {
+            cipher: CipherKind::FourSquare,
+            score: structure * doubled_likelihood * (1.0 - transparent),
+        },
+        Guess {
+            cipher: CipherKind::Other,
+            score: other,
+        },
+    ];
+    let total: f64 = guesses.iter().map(|guess| guess.score).sum();
+    for guess in guesses.iter_mut() {
+        guess.score /= total;
+    }
+    guesses.sort_by(|a, b| b.score.total_cmp(&a.score));
+    guesses
+}
+
+fn relative(count: Option<&usize>, total: usize) -> f64 {
+    match (count, total) {
+        (Some(&count), total) if total > 0 => count as f64 / total as f64,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_reference_tables() {
+        let sum: f64 = ENGLISH_MONOGRAMS.iter().sum();
+        assert!((sum - 1.0).abs() < 0.01);
+        assert!(ENGLISH_DIGRAMS.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_frequencies_skip_non_letters() {
+        let frequencies = monogram_frequencies("a-b a4ß");
+        assert_eq!(frequencies.len(), 2);
+        assert_eq!(frequencies[&'A'], 2);
+        let frequencies = digram_frequencies("ab-c");
+        assert_eq!(frequencies.len(), 1);
+        assert_eq!(frequencies[&['A', 'B']], 1);
+    }
+
+    #[test]
+    fn test_compare_empty() {
+        assert!(compare_monograms("").iter().all(|c| c.observed == 0.0));
+        let comparison = compare_digrams("1234");
+        assert!(comparison.iter().all(|c| c.deviation() == -c.expected));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let english = Statistics::of(
+            "It was the best of times, it was the worst of times, it was the age of wisdom, \
+             it was the age of foolishness, it was the epoch of belief",
+        );
+        let uniform = Statistics::of("abcdefghijklmnopqrstuvwxyz");
+        assert!(english.index_of_coincidence > uniform.index_of_coincidence);
+        assert!(english.chi_squared < uniform.chi_squared);
+        assert!(english.entropy < uniform.entropy);
+        assert!((uniform.entropy - 26f64.log2()).abs() < 1e-9);
+        assert_eq!(english.doubled_letters, 4);
+        assert_eq!(Statistics::of("").entropy, 0.0);
+        assert_eq!(Statistics::of("").chi_squared, 0.0);
+    }
+
+    #[test]
+    #[cfg(all(feature = "two-square", feature = "four-square"))]
+    fn test_identify() {
+        use crate::cryptable::Cypher;
+        use crate::{four_square::FourSquare, playfair::PlayFairKey, two_square::TwoSquare};
+
+        let plain = &include_str!("analysis/english.txt")[..3000];
+        let playfair = PlayFairKey::new("monarchy").encrypt(plain).unwrap();
+        let two_square = TwoSquare::new("EXAMPLE", "KEYWORD").encrypt(plain).unwrap();
+        let four_square = FourSquare::new("EXAMPLE", "KEYWORD")
+            .encrypt(plain)
+            .unwrap();
+        assert_eq!(identify(&playfair)[0].cipher, CipherKind::PlayFair);
+        assert_eq!(identify(&two_square)[0].cipher, CipherKind::TwoSquare);
+        assert_eq!(identify(&four_square)[0].cipher, CipherKind::FourSquare);
+        assert_eq!(identify(plain)[0].cipher, CipherKind::Other);
+        assert_eq!(identify("")[0].cipher, CipherKind::Other);
+        let total: f64 = identify(&playfair).iter().map(|g| g.score).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,172 @@
+//! Statistics of the languages a plain text may be written in. English is
+//! always available, German, French, Spanish and Italian with the features
+//! of the same name, as each of them embeds a corpus into the binary.
+//!
+//! The corpora are written without accents and with the German umlauts
+//! spelled out (AE, OE, UE, SS), as plain texts were prepared before being
+//! enciphered by hand.
+//!
+
+#[cfg(any(
+    feature = "german",
+    feature = "french",
+    feature = "spanish",
+    feature = "italian"
+))]
+use std::sync::OnceLock;
+
+use super::scoring::{FitnessScorer, Quadgrams};
+use super::ENGLISH_MONOGRAMS;
+
+/// Relative frequencies of the letters A-Z in German texts.
+#[cfg(feature = "german")]
+pub const GERMAN_MONOGRAMS: [f64; 26] = [
+    0.0652, 0.0189, 0.0273, 0.0508, 0.1640, 0.0166, 0.0301, 0.0458, 0.0655, 0.0027, 0.0142, 0.0344,
+    0.0253, 0.0978, 0.0259, 0.0067, 0.0002, 0.0700, 0.0727, 0.0615, 0.0417, 0.0085, 0.0192, 0.0003,
+    0.0004, 0.0113,
+];
+
+/// Relative frequencies of the letters A-Z in French texts.
+#[cfg(feature = "french")]
+pub const FRENCH_MONOGRAMS: [f64; 26] = [
+    0.0764, 0.0090, 0.0326, 0.0367, 0.1472, 0.0107, 0.0087, 0.0074, 0.0753, 0.0061, 0.0007, 0.0546,
+    0.0297, 0.0710, 0.0580, 0.0252, 0.0136, 0.0669, 0.0795, 0.0724, 0.0631, 0.0184, 0.0005, 0.0043,
+    0.0013, 0.0033,
+];
+
+/// Relative frequencies of the letters A-Z in Spanish texts.
+#[cfg(feature = "spanish")]
+pub const SPANISH_MONOGRAMS: [f64; 26] = [
+    0.1153, 0.0222, 0.0402, 0.0501, 0.1218, 0.0069, 0.0177, 0.0070, 0.0625, 0.0049, 0.0001, 0.0497,
+    0.0316, 0.0671, 0.0868, 0.0251, 0.0088, 0.0687, 0.0798, 0.0463, 0.0293, 0.0114, 0.0002, 0.0022,
+    0.0101, 0.0047,
+];
+
+/// Relative frequencies of the letters A-Z in Italian texts.
+#[cfg(feature = "italian")]
+pub const ITALIAN_MONOGRAMS: [f64; 26] = [
+    0.1175, 0.0093, 0.0450, 0.0374, 0.1179, 0.0115, 0.0164, 0.0064, 0.1014, 0.0001, 0.0001, 0.0651,
+    0.0251, 0.0688, 0.0983, 0.0306, 0.0051, 0.0637, 0.0498, 0.0562, 0.0301, 0.0210, 0.0003, 0.0001,
+    0.0002, 0.0118,
+];
+
+/// A language with statistics available.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::language::Language;
+/// use playfair_cipher::analysis::scoring::FitnessScorer;
+///
+/// let english = Language::English;
+/// assert_eq!(english.monograms()[4], 0.1249);
+/// assert!(english.score("attack at dawn") > english.score("xtrfsq mw hpsz"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    #[cfg(feature = "german")]
+    German,
+    #[cfg(feature = "french")]
+    French,
+    #[cfg(feature = "spanish")]
+    Spanish,
+    #[cfg(feature = "italian")]
+    Italian,
+}
+
+impl Language {
+    /// All languages available with the features enabled.
+    pub fn all() -> Vec<Language> {
+        vec![
+            Language::English,
+            #[cfg(feature = "german")]
+            Language::German,
+            #[cfg(feature = "french")]
+            Language::French,
+            #[cfg(feature = "spanish")]
+            Language::Spanish,
+            #[cfg(feature = "italian")]
+            Language::Italian,
+        ]
+    }
+
+    /// Relative frequencies of the letters A-Z in texts of the language.
+    pub fn monograms(&self) -> &'static [f64; 26] {
+        match self {
+            Language::English => &ENGLISH_MONOGRAMS,
+            #[cfg(feature = "german")]
+            Language::German => &GERMAN_MONOGRAMS,
+            #[cfg(feature = "french")]
+            Language::French => &FRENCH_MONOGRAMS,
+            #[cfg(feature = "spanish")]
+            Language::Spanish => &SPANISH_MONOGRAMS,
+            #[cfg(feature = "italian")]
+            Language::Italian => &ITALIAN_MONOGRAMS,
+        }
+    }
+
+    /// The quadgrams of the language, computed from the embedded corpus
+    /// the first time they are needed.
+    pub fn quadgrams(&self) -> &'static Quadgrams {
+        match self {
+            Language::English => Quadgrams::english(),
+            #[cfg(feature = "german")]
+            Language::German => {
+                static GERMAN: OnceLock<Quadgrams> = OnceLock::new();
+                GERMAN.get_or_init(|| Quadgrams::from_corpus(include_str!("german.txt")))
+            }
+            #[cfg(feature = "french")]
+            Language::French => {
+                static FRENCH: OnceLock<Quadgrams> = OnceLock::new();
+                FRENCH.get_or_init(|| Quadgrams::from_corpus(include_str!("french.txt")))
+            }
+            #[cfg(feature = "spanish")]
+            Language::Spanish => {
+                static SPANISH: OnceLock<Quadgrams> = OnceLock::new();
+                SPANISH.get_or_init(|| Quadgrams::from_corpus(include_str!("spanish.txt")))
+            }
+            #[cfg(feature = "italian")]
+            Language::Italian => {
+                static ITALIAN: OnceLock<Quadgrams> = OnceLock::new();
+                ITALIAN.get_or_init(|| Quadgrams::from_corpus(include_str!("italian.txt")))
+            }
+        }
+    }
+}
+
+/// Scores a text by the quadgrams of the language.
+impl FitnessScorer for Language {
+    fn score(&self, text: &str) -> f64 {
+        self.quadgrams().score(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_monograms_sum_up() {
+        for language in Language::all() {
+            let sum: f64 = language.monograms().iter().sum();
+            assert!(
+                (0.95..1.01).contains(&sum),
+                "{:?} sums up to {}",
+                language,
+                sum
+            );
+        }
+    }
+
+    #[cfg(all(feature = "german", feature = "french"))]
+    #[test]
+    fn test_languages_told_apart() {
+        let german = "der wolf ging in den wald und wartete auf das kind";
+        let french = "le loup alla dans la foret et attendit la petite fille";
+        assert!(Language::German.score(german) > Language::French.score(german));
+        assert!(Language::French.score(french) > Language::German.score(french));
+        assert!(Language::German.score(german) > Language::English.score(german));
+    }
+}
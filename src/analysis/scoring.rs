@@ -0,0 +1,181 @@
+//! Rates how much a text looks like English by the log probabilities of its
+//! quadgrams, the groups of four consecutive letters. The higher the score
+//! the more English the text is. It's the fitness function the solvers in
+//! the crack module need to tell a correct key from a wrong one.
+//!
+//! The solvers take any FitnessScorer, so the quadgrams can be replaced by
+//! a scoring of their own, e.g. by a word list or for another language.
+//!
+//! Instead of shipping the table of all 26^4 quadgrams, the crate embeds a
+//! small corpus of public domain English texts. The table is computed from
+//! it the first time it's needed.
+//!
+
+use std::sync::OnceLock;
+
+use super::letters;
+
+const LETTERS: usize = 26;
+const QUADGRAMS: usize = LETTERS * LETTERS * LETTERS * LETTERS;
+
+pub(crate) const ENGLISH_CORPUS: &str = include_str!("english.txt");
+
+/// Rates how much a plain text looks like the language expected, the higher
+/// the score the better. Implemented for Quadgrams and for any function or
+/// closure taking the text and returning the score. Scorers are shared by
+/// the threads of a solver, so they must be Sync.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::scoring::FitnessScorer;
+///
+/// // Counts the letters covered by the words of a word list
+/// struct Words(Vec<&'static str>);
+///
+/// impl FitnessScorer for Words {
+///     fn score(&self, text: &str) -> f64 {
+///         self.0.iter().map(|word| (text.matches(word).count() * word.len()) as f64).sum()
+///     }
+/// }
+///
+/// let words = Words(vec!["GOLD", "HIDE", "THE"]);
+/// assert_eq!(words.score("HIDETHEGOLDX"), 11.0);
+/// let closure = |text: &str| text.len() as f64;
+/// assert_eq!(closure.score("HIDETHEGOLDX"), 12.0);
+/// ```
+pub trait FitnessScorer: Sync {
+    fn score(&self, text: &str) -> f64;
+}
+
+impl<F: Fn(&str) -> f64 + Sync> FitnessScorer for F {
+    fn score(&self, text: &str) -> f64 {
+        self(text)
+    }
+}
+
+impl FitnessScorer for Quadgrams {
+    fn score(&self, text: &str) -> f64 {
+        Quadgrams::score(self, text)
+    }
+}
+
+impl FitnessScorer for &Quadgrams {
+    fn score(&self, text: &str) -> f64 {
+        Quadgrams::score(self, text)
+    }
+}
+
+/// Log10 probabilities of all quadgrams of a language.
+///
+#[derive(Debug, Clone)]
+pub struct Quadgrams {
+    log_probabilities: Vec<f32>,
+}
+
+impl Quadgrams {
+    /// Computes the quadgram log probabilities from a text of the language.
+    /// Quadgrams not found in the corpus get a probability far below the one
+    /// of the rarest quadgram found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::analysis::scoring::Quadgrams;
+    ///
+    /// let quadgrams = Quadgrams::from_corpus("the cat sat on the mat");
+    /// assert!(quadgrams.score("the mat") > quadgrams.score("xqz mkw"));
+    /// ```
+    pub fn from_corpus(corpus: &str) -> Self {
+        let mut counts = vec![0u32; QUADGRAMS];
+        let mut total: u32 = 0;
+        for quadgram in Self::quadgrams(corpus) {
+            counts[quadgram] += 1;
+            total += 1;
+        }
+        let total = total.max(1) as f64;
+        let floor = (0.01 / total).log10() as f32;
+        let log_probabilities = counts
+            .iter()
+            .map(|&count| match count {
+                0 => floor,
+                count => (count as f64 / total).log10() as f32,
+            })
+            .collect();
+        Quadgrams { log_probabilities }
+    }
+
+    /// The quadgrams of English, computed from the embedded corpus once.
+    pub fn english() -> &'static Quadgrams {
+        static ENGLISH: OnceLock<Quadgrams> = OnceLock::new();
+        ENGLISH.get_or_init(|| Quadgrams::from_corpus(ENGLISH_CORPUS))
+    }
+
+    /// Sum of the log probabilities of all quadgrams of the text. Only the
+    /// letters A-Z are taken into account. A text of less than four letters
+    /// scores 0.
+    pub fn score(&self, text: &str) -> f64 {
+        Self::quadgrams(text)
+            .map(|quadgram| self.log_probabilities[quadgram] as f64)
+            .sum()
+    }
+
+    // Index of any quadgram of the text, the letters taken as base 26 digits
+    fn quadgrams(text: &str) -> impl Iterator<Item = usize> + '_ {
+        let mut index: usize = 0;
+        letters(text).enumerate().filter_map(move |(seen, c)| {
+            index = (index * LETTERS + (c as u8 - b'A') as usize) % QUADGRAMS;
+            (seen >= 3).then_some(index)
+        })
+    }
+}
+
+/// Rates how much a text looks like English, see Quadgrams::score.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::analysis::scoring::score;
+///
+/// assert!(score("attack at dawn") > score("xtrfsq mw hpsz"));
+/// ```
+pub fn score(text: &str) -> f64 {
+    Quadgrams::english().score(text)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_quadgrams_index() {
+        let indices: Vec<usize> = Quadgrams::quadgrams("ab-cde").collect();
+        assert_eq!(
+            indices,
+            [
+                (LETTERS + 2) * LETTERS + 3,
+                ((LETTERS + 2) * LETTERS + 3) * LETTERS + 4
+            ]
+        );
+        assert_eq!(score("abc"), 0.0);
+    }
+
+    #[test]
+    fn test_scorers_agree() {
+        let text = "attack at dawn";
+        let english = Quadgrams::english();
+        assert_eq!(FitnessScorer::score(&english, text), score(text));
+        assert_eq!(FitnessScorer::score(english, text), score(text));
+        assert_eq!(FitnessScorer::score(&score, text), score(text));
+    }
+
+    #[test]
+    fn test_plain_text_beats_cipher_text() {
+        let plain = "the quick brown fox jumps over the lazy dog";
+        let crypt = PlayFairKey::new("playfair example").encrypt(plain).unwrap();
+        assert!(score(plain) > score(&crypt));
+    }
+}
@@ -0,0 +1,290 @@
+//! An ASCII armored text form of a key, so it can be pasted into an email,
+//! a wiki or a puzzle hand-out and read back unambiguously. The square is
+//! written row by row, the options as headers and a checksum guards against
+//! letters garbled on the way:
+//!
+//! ```text
+//! -----BEGIN PLAYFAIR KEY-----
+//! Padding: X
+//! Omit: J
+//!
+//! P L A Y F
+//! I R E X M
+//! B C D G H
+//! K N O Q S
+//! T U V W Z
+//! =306031
+//! -----END PLAYFAIR KEY-----
+//! ```
+//!
+//! The grouping and a final digram policy other than FinalDigram::Pad are
+//! written as headers `Grouping` and `Final-Digram` when set. Text around
+//! the armor, e.g. the rest of an email, is ignored as are headers not
+//! known, so comments can be added as `Comment: ...`.
+//!
+//! ```
+//! use playfair_cipher::armor;
+//! use playfair_cipher::playfair::PlayFairKey;
+//!
+//! let key = PlayFairKey::builder().key("playfair example").grouping(5).build().unwrap();
+//! let armored = armor::export(&key);
+//! assert!(armored.starts_with("-----BEGIN PLAYFAIR KEY-----\n"));
+//! let imported = armor::import(&format!("Today's key:\n\n{}\nRegards", armored)).unwrap();
+//! assert_eq!(imported.square(), key.square());
+//! assert_eq!(imported.options(), key.options());
+//! ```
+//!
+
+use std::fmt::Write;
+
+use crate::errors::{ArmorError, InvalidKeyError};
+use crate::options::{FinalDigram, PayloadOptions};
+use crate::playfair::PlayFairKey;
+
+/// First line of an armored key.
+pub const BEGIN: &str = "-----BEGIN PLAYFAIR KEY-----";
+/// Last line of an armored key.
+pub const END: &str = "-----END PLAYFAIR KEY-----";
+
+/// Returns the armored text form of the key, ending with a newline.
+pub fn export(key: &PlayFairKey) -> String {
+    let options = key.options();
+    let mut armored = format!("{}\n", BEGIN);
+    // Writing to a String never fails
+    let _ = writeln!(armored, "Padding: {}", options.padding());
+    let _ = writeln!(armored, "Omit: {}", options.omit());
+    if let Some(grouping) = options.grouping() {
+        let _ = writeln!(armored, "Grouping: {}", grouping);
+    }
+    if options.final_digram() != FinalDigram::Pad {
+        let _ = writeln!(armored, "Final-Digram: {}", options.final_digram().name());
+    }
+    armored.push('\n');
+    for row in key.square().chunks(5) {
+        let row: Vec<String> = row.iter().map(char::to_string).collect();
+        let _ = writeln!(armored, "{}", row.join(" "));
+    }
+    let _ = writeln!(armored, "={:06X}", checksum(key.square(), options));
+    armored.push_str(END);
+    armored.push('\n');
+    armored
+}
+
+/// Reads a key from its armored text form. Fails if the armor is
+/// incomplete, a header or the square is invalid or the checksum doesn't
+/// match.
+pub fn import(armored: &str) -> Result<PlayFairKey, ArmorError> {
+    let mut lines = armored
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != BEGIN);
+    if lines.next().is_none() {
+        return Err(ArmorError::MissingBegin);
+    }
+    let mut body: Vec<&str> = Vec::new();
+    loop {
+        match lines.next() {
+            Some(END) => break,
+            Some(line) => body.push(line),
+            None => return Err(ArmorError::MissingEnd),
+        }
+    }
+
+    let Headers {
+        padding,
+        omit,
+        grouping,
+        final_digram,
+        square_start,
+    } = headers(&body)?;
+    let checksum_line = match body.iter().rposition(|line| line.starts_with('=')) {
+        Some(idx) if idx >= square_start => idx,
+        _ => return Err(ArmorError::MissingChecksum),
+    };
+    let found = u32::from_str_radix(&body[checksum_line][1..], 16)
+        .map_err(|_| ArmorError::InvalidLine(String::from(body[checksum_line])))?;
+
+    let square = PlayFairKey::from_matrix_str(&body[square_start..checksum_line].join("\n"))?;
+    let omit = omit.unwrap_or(square.options().omit());
+    if omit != square.options().omit() {
+        return Err(ArmorError::InvalidKey(InvalidKeyError::new(format!(
+            "The square lacks '{}' but the omitted letter is '{}'",
+            square.options().omit(),
+            omit
+        ))));
+    }
+    let options = PayloadOptions::new(padding.unwrap_or('X'), omit, grouping)?
+        .with_final_digram(final_digram.unwrap_or_default());
+    let expected = checksum(square.square(), &options);
+    if expected != found {
+        return Err(ArmorError::ChecksumMismatch { expected, found });
+    }
+    Ok(PlayFairKey::from_square_with_options(
+        *square.square(),
+        options,
+    ))
+}
+
+// The headers read and the index of the first line of the square.
+#[derive(Default)]
+struct Headers {
+    padding: Option<char>,
+    omit: Option<char>,
+    grouping: Option<usize>,
+    final_digram: Option<FinalDigram>,
+    square_start: usize,
+}
+
+// Reads the headers up to the blank line separating them from the square.
+fn headers(body: &[&str]) -> Result<Headers, ArmorError> {
+    let mut headers = Headers::default();
+    let separator = match body.iter().position(|line| line.is_empty()) {
+        Some(idx) => idx,
+        // No headers at all
+        None => return Ok(headers),
+    };
+    for line in &body[..separator] {
+        let invalid = || ArmorError::InvalidLine(String::from(*line));
+        let (name, value) = line.split_once(':').ok_or_else(invalid)?;
+        let value = value.trim();
+        match name.trim() {
+            "Padding" => headers.padding = Some(single_char(value).ok_or_else(invalid)?),
+            "Omit" => headers.omit = Some(single_char(value).ok_or_else(invalid)?),
+            "Grouping" => headers.grouping = Some(value.parse().map_err(|_| invalid())?),
+            "Final-Digram" => {
+                headers.final_digram = Some(FinalDigram::of(value).ok_or_else(invalid)?)
+            }
+            _ => {}
+        }
+    }
+    headers.square_start = separator
+        + body[separator..]
+            .iter()
+            .take_while(|line| line.is_empty())
+            .count();
+    Ok(headers)
+}
+
+fn single_char(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+// CRC-24 as used by OpenPGP armor, over the letters of the square followed
+// by padding, omitted letter, grouping and a final digram policy other than
+// the default. Whitespace changes don't affect it, a garbled or swapped
+// letter does.
+fn checksum(square: &[char; 25], options: &PayloadOptions) -> u32 {
+    let mut data: String = square.iter().collect();
+    data.push(options.padding());
+    data.push(options.omit());
+    if let Some(grouping) = options.grouping() {
+        data.push_str(&grouping.to_string());
+    }
+    if options.final_digram() != FinalDigram::Pad {
+        data.push_str(options.final_digram().name());
+    }
+    let mut crc: u32 = 0xB7_04CE;
+    for byte in data.bytes() {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x100_0000 != 0 {
+                crc ^= 0x186_4CFB;
+            }
+        }
+    }
+    crc & 0xFF_FFFF
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+
+    #[test]
+    fn test_round_trip() {
+        let key = PlayFairKey::builder()
+            .key("monarchy")
+            .padding('Q')
+            .omit('K')
+            .build()
+            .unwrap();
+        let armored = export(&key);
+        assert_eq!(armored.lines().count(), 11);
+        assert!(armored.contains("Omit: K\n"));
+        assert!(!armored.contains("Grouping"));
+        let imported = import(&armored).unwrap();
+        assert_eq!(imported.square(), key.square());
+        assert_eq!(imported.options(), key.options());
+        // Indentation and comments don't matter
+        let indented: String = armored
+            .replace("Omit: K", "Omit: K\nComment: daily key")
+            .lines()
+            .map(|line| format!("    {}\n", line))
+            .collect();
+        assert_eq!(import(&indented).unwrap().square(), key.square());
+    }
+
+    #[test]
+    fn test_round_trip_final_digram() {
+        let key = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::Strict)
+            .build()
+            .unwrap();
+        let armored = export(&key);
+        assert!(armored.contains("Final-Digram: strict\n"));
+        let imported = import(&armored).unwrap();
+        assert_eq!(imported.options(), key.options());
+        assert!(imported.encrypt("abc").is_err());
+        assert!(matches!(
+            import(&armored.replace("Final-Digram: strict", "Final-Digram: pass_through")),
+            Err(ArmorError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            import(&armored.replace("Final-Digram: strict", "Final-Digram: drop")),
+            Err(ArmorError::InvalidLine(_))
+        ));
+        // Keys padding the last letter are written as before
+        assert!(!export(&PlayFairKey::new("playfair example")).contains("Final-Digram"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let armored = export(&PlayFairKey::new("playfair example"));
+        assert!(matches!(
+            import("no key here"),
+            Err(ArmorError::MissingBegin)
+        ));
+        assert!(matches!(
+            import(&armored.replace(END, "")),
+            Err(ArmorError::MissingEnd)
+        ));
+        assert!(matches!(
+            import(&armored.replace("P L A Y F", "L P A Y F")),
+            Err(ArmorError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            import(&armored.replace("Padding: X", "Padding: Z")),
+            Err(ArmorError::ChecksumMismatch { .. })
+        ));
+        assert!(matches!(
+            import(&armored.replace("Padding: X", "Padding: XX")),
+            Err(ArmorError::InvalidLine(_))
+        ));
+        assert!(matches!(
+            import(&armored.replace("Omit: J", "Omit: K")),
+            Err(ArmorError::InvalidKey(_))
+        ));
+        let lines: Vec<&str> = armored.lines().filter(|l| !l.starts_with('=')).collect();
+        assert!(matches!(
+            import(&lines.join("\n")),
+            Err(ArmorError::MissingChecksum)
+        ));
+    }
+}
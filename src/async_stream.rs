@@ -0,0 +1,402 @@
+//! En- and decrypting tokio streams, so services running on tokio can pipe
+//! data through a cipher without blocking a worker thread on large inputs.
+//! Like the chunked module the input is processed in chunks with constant
+//! memory and the result is the same as en- or decrypting the whole input
+//! at once.
+//!
+//! CryptReader and CryptWriter wrap a reader or a writer, so the cipher can
+//! be placed anywhere in an async pipeline. The functions en- or decrypt a
+//! whole stream or file with them.
+//!
+//! Only available with the feature `async`.
+//!
+//! ```
+//! use playfair_cipher::{async_stream, playfair::PlayFairKey};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let pfc = PlayFairKey::new("playfair example");
+//! let mut crypt: Vec<u8> = Vec::new();
+//! async_stream::encrypt_stream(&pfc, "hide the gold".as_bytes(), &mut crypt)
+//!     .await
+//!     .unwrap();
+//! assert_eq!(crypt, b"BMODZBXDNAGE");
+//! # });
+//! ```
+//!
+
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, ReadBuf};
+
+use crate::{
+    chunked::{ChunkedCrypt, CHUNK_SIZE},
+    cryptable::Cypher,
+    errors::StreamError,
+    structs::CryptModus,
+};
+
+/// Reader en- or decrypting everything read from the reader it wraps.
+/// Errors of the cipher are reported as io::Error of the kind InvalidData
+/// wrapping the CharNotInKeyError.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{async_stream::CryptReader, playfair::PlayFairKey};
+/// use tokio::io::AsyncReadExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut reader = CryptReader::encrypting(&pfc, "hide the gold".as_bytes());
+/// let mut crypt = String::new();
+/// reader.read_to_string(&mut crypt).await.unwrap();
+/// assert_eq!(crypt, "BMODZBXDNAGE");
+/// # });
+/// ```
+pub struct CryptReader<'a, C: Cypher, R> {
+    reader: R,
+    crypt: ChunkedCrypt<'a, C>,
+    // The part of the output already handed out
+    position: usize,
+    finished: bool,
+}
+
+impl<'a, C: Cypher, R: AsyncRead + Unpin> CryptReader<'a, C, R> {
+    /// Encrypts what is read from the reader.
+    pub fn encrypting(cipher: &'a C, reader: R) -> Self {
+        CryptReader::new(
+            ChunkedCrypt::new(cipher, CryptModus::Encrypt, CHUNK_SIZE),
+            reader,
+        )
+    }
+
+    /// Decrypts what is read from the reader.
+    pub fn decrypting(cipher: &'a C, reader: R) -> Self {
+        CryptReader::new(
+            ChunkedCrypt::new(cipher, CryptModus::Decrypt, CHUNK_SIZE),
+            reader,
+        )
+    }
+
+    fn new(crypt: ChunkedCrypt<'a, C>, reader: R) -> Self {
+        CryptReader {
+            reader,
+            crypt,
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<C: Cypher, R: AsyncRead + Unpin> AsyncRead for CryptReader<'_, C, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            let output = &this.crypt.output.as_bytes()[this.position..];
+            if !output.is_empty() {
+                let length = output.len().min(buf.remaining());
+                buf.put_slice(&output[..length]);
+                this.position += length;
+                return Poll::Ready(Ok(()));
+            }
+            if this.finished {
+                return Poll::Ready(Ok(()));
+            }
+            this.crypt.output.clear();
+            this.position = 0;
+            let mut chunk = ReadBuf::new(this.crypt.unfilled());
+            ready!(Pin::new(&mut this.reader).poll_read(cx, &mut chunk))?;
+            let read = chunk.filled().len();
+            this.finished = this.crypt.filled(read)?;
+        }
+    }
+}
+
+/// Writer en- or decrypting everything written to it into the writer it
+/// wraps. The end of the input is only known on shutdown, so a trailing
+/// letter without partner is written then. Errors of the cipher are
+/// reported as io::Error of the kind InvalidData wrapping the
+/// CharNotInKeyError.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{async_stream::CryptWriter, playfair::PlayFairKey};
+/// use tokio::io::AsyncWriteExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut writer = CryptWriter::encrypting(&pfc, Vec::new());
+/// writer.write_all(b"hide the gold").await.unwrap();
+/// writer.shutdown().await.unwrap();
+/// assert_eq!(writer.into_inner(), b"BMODZBXDNAGE");
+/// # });
+/// ```
+pub struct CryptWriter<'a, C: Cypher, W> {
+    writer: W,
+    crypt: ChunkedCrypt<'a, C>,
+    // The part of the output already written to the writer
+    position: usize,
+    finished: bool,
+}
+
+impl<'a, C: Cypher, W: AsyncWrite + Unpin> CryptWriter<'a, C, W> {
+    /// Encrypts what is written into the writer.
+    pub fn encrypting(cipher: &'a C, writer: W) -> Self {
+        CryptWriter::new(
+            ChunkedCrypt::new(cipher, CryptModus::Encrypt, CHUNK_SIZE),
+            writer,
+        )
+    }
+
+    /// Decrypts what is written into the writer.
+    pub fn decrypting(cipher: &'a C, writer: W) -> Self {
+        CryptWriter::new(
+            ChunkedCrypt::new(cipher, CryptModus::Decrypt, CHUNK_SIZE),
+            writer,
+        )
+    }
+
+    fn new(crypt: ChunkedCrypt<'a, C>, writer: W) -> Self {
+        CryptWriter {
+            writer,
+            crypt,
+            position: 0,
+            finished: false,
+        }
+    }
+
+    /// The wrapped writer. Without a shutdown before, the letters written
+    /// last may be missing.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    // Writes the output not written yet to the writer.
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.position < self.crypt.output.len() {
+            let output = &self.crypt.output.as_bytes()[self.position..];
+            let written = ready!(Pin::new(&mut self.writer).poll_write(cx, output))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.position += written;
+        }
+        self.crypt.output.clear();
+        self.position = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<C: Cypher, W: AsyncWrite + Unpin> AsyncWrite for CryptWriter<'_, C, W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let chunk = this.crypt.unfilled();
+        let length = chunk.len().min(buf.len());
+        chunk[..length].copy_from_slice(&buf[..length]);
+        this.crypt.filled(length)?;
+        Poll::Ready(Ok(length))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        Pin::new(&mut this.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        if !this.finished {
+            this.finished = this.crypt.filled(0)?;
+            ready!(this.poll_drain(cx))?;
+        }
+        Pin::new(&mut this.writer).poll_shutdown(cx)
+    }
+}
+
+/// Encrypts everything read from the reader and writes the result into the
+/// writer.
+pub async fn encrypt_stream<C: Cypher>(
+    cipher: &C,
+    reader: impl AsyncRead + Unpin,
+    writer: impl AsyncWrite + Unpin,
+) -> Result<(), StreamError> {
+    copy(CryptReader::encrypting(cipher, reader), writer).await
+}
+
+/// Decrypts everything read from the reader and writes the result into the
+/// writer.
+pub async fn decrypt_stream<C: Cypher>(
+    cipher: &C,
+    reader: impl AsyncRead + Unpin,
+    writer: impl AsyncWrite + Unpin,
+) -> Result<(), StreamError> {
+    copy(CryptReader::decrypting(cipher, reader), writer).await
+}
+
+/// Encrypts the file at path_in into the file at path_out, which is created
+/// or truncated. On an error path_out is left with the part encrypted so
+/// far.
+///
+/// # Example
+///
+/// ```no_run
+/// use playfair_cipher::{async_stream, playfair::PlayFairKey};
+///
+/// # async fn run() {
+/// let pfc = PlayFairKey::new("playfair example");
+/// async_stream::encrypt_file(&pfc, "message.txt", "message.crypt")
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+pub async fn encrypt_file<C: Cypher>(
+    cipher: &C,
+    path_in: impl AsRef<Path>,
+    path_out: impl AsRef<Path>,
+) -> Result<(), StreamError> {
+    let reader = BufReader::new(File::open(path_in).await?);
+    let writer = BufWriter::new(File::create(path_out).await?);
+    encrypt_stream(cipher, reader, writer).await
+}
+
+/// Decrypts the file at path_in into the file at path_out, see encrypt_file.
+pub async fn decrypt_file<C: Cypher>(
+    cipher: &C,
+    path_in: impl AsRef<Path>,
+    path_out: impl AsRef<Path>,
+) -> Result<(), StreamError> {
+    let reader = BufReader::new(File::open(path_in).await?);
+    let writer = BufWriter::new(File::create(path_out).await?);
+    decrypt_stream(cipher, reader, writer).await
+}
+
+async fn copy<C: Cypher>(
+    mut reader: CryptReader<'_, C, impl AsyncRead + Unpin>,
+    mut writer: impl AsyncWrite + Unpin,
+) -> Result<(), StreamError> {
+    tokio::io::copy(&mut reader, &mut writer).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::playfair::PlayFairKey;
+    use tokio::io::AsyncReadExt;
+
+    const TEXT: &str = "Thé quick red fox jumps over the lazy brown dog. Balloons aa see.";
+
+    #[tokio::test]
+    async fn test_chunks_match_whole() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let crypt = pfc.encrypt(TEXT).unwrap();
+        for chunk_size in [4, 5, 64] {
+            let mut reader = CryptReader::new(
+                ChunkedCrypt::new(&pfc, CryptModus::Encrypt, chunk_size),
+                TEXT.as_bytes(),
+            );
+            let mut chunked = String::new();
+            reader.read_to_string(&mut chunked).await.unwrap();
+            assert_eq!(chunked, crypt);
+
+            let mut writer = CryptWriter::new(
+                ChunkedCrypt::new(&pfc, CryptModus::Encrypt, chunk_size),
+                Vec::new(),
+            );
+            writer.write_all(TEXT.as_bytes()).await.unwrap();
+            writer.shutdown().await.unwrap();
+            assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), crypt);
+        }
+        let mut plain: Vec<u8> = Vec::new();
+        decrypt_stream(&pfc, crypt.as_bytes(), &mut plain)
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            pfc.decrypt(&crypt).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        let pfc = PlayFairKey::new("playfair example");
+        // Decrypting what is read from an encrypting reader
+        let mut writer = CryptWriter::decrypting(&pfc, Vec::new());
+        tokio::io::copy(
+            &mut CryptReader::encrypting(&pfc, "hide the gold".as_bytes()),
+            &mut writer,
+        )
+        .await
+        .unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(writer.into_inner(), b"HIDETHEGOLDX");
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let mut pfc = PlayFairKey::new("playfair example");
+        pfc.key_map.remove(&'T');
+        let mut crypt = String::new();
+        let error = CryptReader::encrypting(&pfc, "hide the gold".as_bytes())
+            .read_to_string(&mut crypt)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        match encrypt_stream(&pfc, "hide the gold".as_bytes(), Vec::new()).await {
+            Err(StreamError::CharNotInKey(e)) => {
+                assert_eq!(e.character(), 'T');
+                assert_eq!(e.original_position(), Some(5));
+            }
+            _ => panic!("T is not part of the key"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_files() {
+        let pfc = PlayFairKey::new("playfair example");
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let plain = dir.join(format!("playfair_async_plain_{}.txt", id));
+        let crypt = dir.join(format!("playfair_async_crypt_{}.txt", id));
+        std::fs::write(&plain, TEXT).unwrap();
+        encrypt_file(&pfc, &plain, &crypt).await.unwrap();
+        let crypt_text = std::fs::read_to_string(&crypt).unwrap();
+        for path in [&plain, &crypt] {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(crypt_text, pfc.encrypt(TEXT).unwrap());
+        assert!(matches!(
+            decrypt_file(&pfc, dir.join("playfair_async_missing"), &crypt).await,
+            Err(StreamError::Io(_))
+        ));
+    }
+}
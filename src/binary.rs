@@ -0,0 +1,154 @@
+//! Lossless encoding of arbitrary bytes into letters of the square, so
+//! small binary payloads can be run through the ciphers, see
+//! Cypher::encrypt_bytes.
+//!
+//! Each byte becomes a digram of two distinct letters: there are 25 * 24
+//! such digrams, enough for the 256 byte values. As no digram holds a
+//! doubled letter and the text is of even length, the ciphers never insert
+//! padding, so decoding the decrypted text yields the exact bytes. The
+//! letters are twice as many as the bytes.
+//!
+
+use crate::errors::BinaryError;
+use crate::options::PayloadOptions;
+
+// Number of letters a second letter can be: any but the first one.
+const SECONDS: usize = 24;
+
+/// Encodes bytes into letters of the square the options describe.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::binary::{decode, encode};
+/// use playfair_cipher::options::PayloadOptions;
+///
+/// let options = PayloadOptions::default();
+/// let letters = encode(&[0, 1, 255], &options);
+/// assert_eq!(letters, "ABACLR");
+/// assert_eq!(decode(&letters, &options).unwrap(), [0, 1, 255]);
+/// ```
+pub fn encode(data: &[u8], options: &PayloadOptions) -> String {
+    let alphabet: Vec<char> = options.alphabet().collect();
+    let mut letters = String::with_capacity(2 * data.len());
+    for byte in data {
+        let first = *byte as usize / SECONDS;
+        let mut second = *byte as usize % SECONDS;
+        // Skipping the first letter, so the digram is never doubled.
+        if second >= first {
+            second += 1;
+        }
+        letters.push(alphabet[first]);
+        letters.push(alphabet[second]);
+    }
+    letters
+}
+
+/// Decodes letters produced by encode back into bytes. Whitespace is
+/// ignored, so grouped text can be decoded.
+pub fn decode(letters: &str, options: &PayloadOptions) -> Result<Vec<u8>, BinaryError> {
+    let alphabet: Vec<char> = options.alphabet().collect();
+    let mut indices = Vec::with_capacity(letters.len());
+    for (position, character) in letters.chars().enumerate() {
+        match alphabet.iter().position(|c| *c == character) {
+            Some(idx) => indices.push(idx),
+            None if character.is_whitespace() => continue,
+            None => {
+                return Err(BinaryError::InvalidChar {
+                    character,
+                    position,
+                })
+            }
+        }
+    }
+    if !indices.len().is_multiple_of(2) {
+        return Err(BinaryError::OddLength {
+            length: indices.len(),
+        });
+    }
+    indices
+        .chunks(2)
+        .enumerate()
+        .map(|(position, digram)| {
+            let (first, second) = (digram[0], digram[1]);
+            let value = match second.cmp(&first) {
+                std::cmp::Ordering::Less => Some(first * SECONDS + second),
+                std::cmp::Ordering::Greater => Some(first * SECONDS + second - 1),
+                std::cmp::Ordering::Equal => None,
+            };
+            value
+                .and_then(|value| u8::try_from(value).ok())
+                .ok_or(BinaryError::InvalidDigram {
+                    digram: [alphabet[first], alphabet[second]],
+                    position,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_all_bytes() {
+        let options = PayloadOptions::new('Q', 'K', None).unwrap();
+        let data: Vec<u8> = (0..=255).collect();
+        let letters = encode(&data, &options);
+        assert!(!letters.contains('K'));
+        assert!(letters
+            .as_bytes()
+            .chunks(2)
+            .all(|digram| digram[0] != digram[1]));
+        assert_eq!(decode(&letters, &options).unwrap(), data);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        let options = PayloadOptions::default();
+        assert!(matches!(
+            decode("AB J", &options),
+            Err(BinaryError::InvalidChar {
+                character: 'J',
+                position: 3
+            })
+        ));
+        assert!(matches!(
+            decode("ABA", &options),
+            Err(BinaryError::OddLength { length: 3 })
+        ));
+        assert!(matches!(
+            decode("ABCC", &options),
+            Err(BinaryError::InvalidDigram { position: 1, .. })
+        ));
+        // Beyond the 256 byte values
+        assert!(decode("ZY", &options).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "two-square", feature = "four-square"))]
+    fn test_ciphers() {
+        use crate::cryptable::Cypher;
+        use crate::four_square::FourSquare;
+        use crate::two_square::TwoSquare;
+
+        let data: Vec<u8> = (0..=255).rev().collect();
+        let tsq = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .grouping(5)
+            .build()
+            .unwrap();
+        assert_eq!(
+            tsq.decrypt_bytes(&tsq.encrypt_bytes(&data).unwrap())
+                .unwrap(),
+            data
+        );
+        let fsq = FourSquare::new("EXAMPLE", "KEYWORD");
+        assert_eq!(
+            fsq.decrypt_bytes(&fsq.encrypt_bytes(&data).unwrap())
+                .unwrap(),
+            data
+        );
+    }
+}
@@ -0,0 +1,500 @@
+//! En- and decrypting arbitrarily large inputs with constant memory. The
+//! input is read in chunks, a trailing character without partner is
+//! carried over to the next chunk and only padded at the true end of the
+//! input. The result is the same as en- or decrypting the whole input at once.
+//!
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::{
+    cryptable::{engine, Crypt, Cypher},
+    errors::StreamError,
+    options::FinalDigram,
+    seriation,
+    structs::{unpaired, CryptModus, Payload},
+};
+
+pub(crate) const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Encrypts everything read from the reader and writes the result into the
+/// writer.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{chunked, playfair::PlayFairKey};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut crypt: Vec<u8> = Vec::new();
+/// chunked::encrypt_stream(&pfc, "hide the gold".as_bytes(), &mut crypt).unwrap();
+/// assert_eq!(crypt, b"BMODZBXDNAGE");
+/// ```
+pub fn encrypt_stream<C: Cypher>(
+    cipher: &C,
+    reader: impl Read,
+    writer: impl Write,
+) -> Result<(), StreamError> {
+    ChunkedCrypt::new(cipher, CryptModus::Encrypt, CHUNK_SIZE).run(reader, writer)
+}
+
+/// Decrypts everything read from the reader and writes the result into the
+/// writer.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{chunked, playfair::PlayFairKey};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut plain: Vec<u8> = Vec::new();
+/// chunked::decrypt_stream(&pfc, "BMODZBXDNAGE".as_bytes(), &mut plain).unwrap();
+/// assert_eq!(plain, b"HIDETHEGOLDX");
+/// ```
+pub fn decrypt_stream<C: Cypher>(
+    cipher: &C,
+    reader: impl Read,
+    writer: impl Write,
+) -> Result<(), StreamError> {
+    ChunkedCrypt::new(cipher, CryptModus::Decrypt, CHUNK_SIZE).run(reader, writer)
+}
+
+/// Encrypts the file at path_in into the file at path_out, which is created
+/// or truncated. Both files are buffered and streamed through the cipher,
+/// so the size of the file doesn't matter. On an error path_out is left
+/// with the part encrypted so far.
+///
+/// # Example
+///
+/// ```no_run
+/// use playfair_cipher::{chunked, playfair::PlayFairKey};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// chunked::encrypt_file(&pfc, "message.txt", "message.crypt").unwrap();
+/// ```
+pub fn encrypt_file<C: Cypher>(
+    cipher: &C,
+    path_in: impl AsRef<Path>,
+    path_out: impl AsRef<Path>,
+) -> Result<(), StreamError> {
+    let reader = BufReader::new(File::open(path_in)?);
+    let writer = BufWriter::new(File::create(path_out)?);
+    encrypt_stream(cipher, reader, writer)
+}
+
+/// Decrypts the file at path_in into the file at path_out, see encrypt_file.
+pub fn decrypt_file<C: Cypher>(
+    cipher: &C,
+    path_in: impl AsRef<Path>,
+    path_out: impl AsRef<Path>,
+) -> Result<(), StreamError> {
+    let reader = BufReader::new(File::open(path_in)?);
+    let writer = BufWriter::new(File::create(path_out)?);
+    decrypt_stream(cipher, reader, writer)
+}
+
+// A character of the normalized input together with its position in the
+// normalized input and in the original input.
+type Located = (char, usize, usize);
+
+// The state of en- or decrypting a stream chunk by chunk, shared by the
+// blocking functions of this module and the adapters of async_stream: the
+// input is read into the buffer, the result collected in the output.
+pub(crate) struct ChunkedCrypt<'a, C: Cypher> {
+    cipher: &'a C,
+    modus: CryptModus,
+    buffer: Vec<u8>,
+    // Bytes of a char split by the chunk boundary, kept at the start of the
+    // buffer
+    pending: usize,
+    payload: Payload<'static>,
+    // Character without partner at the end of the last chunk
+    carry: Option<Located>,
+    // The letters of a seriated payload not en- or decrypted yet: the last
+    // block is shorter, so a block is only complete once more letters follow.
+    block: Vec<Located>,
+    normalized_offset: usize,
+    original_offset: usize,
+    written: usize,
+    pub(crate) output: String,
+}
+
+impl<'a, C: Cypher> ChunkedCrypt<'a, C> {
+    pub(crate) fn new(cipher: &'a C, modus: CryptModus, chunk_size: usize) -> Self {
+        ChunkedCrypt {
+            cipher,
+            modus,
+            buffer: vec![0u8; chunk_size],
+            pending: 0,
+            payload: Payload::new("", engine(cipher).payload_options()),
+            carry: None,
+            block: Vec::new(),
+            normalized_offset: 0,
+            original_offset: 0,
+            written: 0,
+            output: String::new(),
+        }
+    }
+
+    fn run(mut self, mut reader: impl Read, mut writer: impl Write) -> Result<(), StreamError> {
+        loop {
+            let read = reader.read(self.unfilled())?;
+            let finished = self.filled(read)?;
+            writer.write_all(self.output.as_bytes())?;
+            self.output.clear();
+            if finished {
+                break;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    // The part of the buffer the next chunk is read into.
+    pub(crate) fn unfilled(&mut self) -> &mut [u8] {
+        &mut self.buffer[self.pending..]
+    }
+
+    // En- or decrypts the chunk of the length given read into unfilled, the
+    // result is appended to the output. A length of 0 marks the end of the
+    // input. Returns whether the input is finished.
+    pub(crate) fn filled(&mut self, read: usize) -> Result<bool, StreamError> {
+        if read == 0 {
+            // A char cut off by the end of the input is an invalid byte
+            // sequence, skipped like the ones within the input.
+            if self.pending > 0 {
+                self.original_offset += 1;
+                self.pending = 0;
+            }
+            self.finish()?;
+            return Ok(true);
+        }
+        let available = self.pending + read;
+        let mut buffer = std::mem::take(&mut self.buffer);
+        let pending = self.feed(&mut buffer, available);
+        self.buffer = buffer;
+        self.pending = pending?;
+        Ok(false)
+    }
+
+    // En- or decrypts the first bytes of the buffer, the result is appended
+    // to the output. The bytes of a char split by the chunk boundary are
+    // moved to the start of the buffer, returns their number.
+    fn feed(&mut self, buffer: &mut [u8], available: usize) -> Result<usize, StreamError> {
+        let mut start = 0;
+        let mut pending = 0;
+        while start < available {
+            match std::str::from_utf8(&buffer[start..available]) {
+                Ok(text) => {
+                    self.process(text)?;
+                    start = available;
+                }
+                Err(e) => {
+                    let valid = start + e.valid_up_to();
+                    self.process(std::str::from_utf8(&buffer[start..valid]).unwrap_or(""))?;
+                    match e.error_len() {
+                        // Invalid bytes are skipped like any other char
+                        // not within A-Z.
+                        Some(len) => {
+                            self.original_offset += 1;
+                            start = valid + len;
+                        }
+                        None => {
+                            pending = available - valid;
+                            buffer.copy_within(valid..available, 0);
+                            start = available;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(pending)
+    }
+
+    // Handles a trailing char without partner at the end of the input as
+    // the final digram policy demands.
+    fn finish(&mut self) -> Result<(), StreamError> {
+        if let Some(period) = self.payload.options.seriation {
+            return self.finish_seriated(period);
+        }
+        if let Some(first) = self.carry.take() {
+            let padding = self.payload.options.padding;
+            match self.payload.options.final_digram {
+                FinalDigram::Pad => self.crypt(first, (padding, first.1, first.2))?,
+                FinalDigram::Strict => return Err(unpaired(first.0).at(first.1, first.2).into()),
+                FinalDigram::PassThrough => self.write(first.0),
+            }
+        }
+        Ok(())
+    }
+
+    // En- or decrypts the letters left as last block, the last letter of an
+    // odd number is handled as the final digram policy demands.
+    fn finish_seriated(&mut self, period: usize) -> Result<(), StreamError> {
+        let mut block = std::mem::take(&mut self.block);
+        let mut lone = None;
+        if let Some(&last) = block.last().filter(|_| block.len() % 2 == 1) {
+            match self.payload.options.final_digram {
+                FinalDigram::Pad => block.push((self.payload.options.padding, last.1, last.2)),
+                FinalDigram::Strict => return Err(unpaired(last.0).at(last.1, last.2).into()),
+                FinalDigram::PassThrough => lone = block.pop(),
+            }
+        }
+        self.crypt_block(&block, period)?;
+        if let Some(last) = lone {
+            self.write(last.0);
+        }
+        Ok(())
+    }
+
+    // Normalizes the text and en- or decrypts all complete digrams.
+    fn process(&mut self, text: &str) -> Result<(), StreamError> {
+        self.payload.fill(text);
+        let padding = self.payload.options.padding;
+        let normalized = std::mem::take(&mut self.payload.payload);
+        let origins = std::mem::take(&mut self.payload.origins);
+        let (normalized_offset, original_offset) = (self.normalized_offset, self.original_offset);
+        let mut located =
+            normalized
+                .bytes()
+                .zip(origins.iter())
+                .enumerate()
+                .map(|(idx, (c, origin))| {
+                    (c as char, normalized_offset + idx, original_offset + origin)
+                });
+        if let Some(period) = self.payload.options.seriation {
+            for letter in located.by_ref() {
+                self.block.push(letter);
+                if self.block.len() > 2 * period {
+                    let block: Vec<Located> = self.block.drain(..2 * period).collect();
+                    self.crypt_block(&block, period)?;
+                }
+            }
+        }
+        while let Some(first) = self.carry.take().or_else(|| located.next()) {
+            let second = match located.next() {
+                Some(s) => s,
+                None => {
+                    // Its partner may be in the next chunk
+                    self.carry = Some(first);
+                    break;
+                }
+            };
+            if first.0 == second.0 && self.modus == CryptModus::Encrypt {
+                // first and second are the same, so stuff it
+                self.crypt(first, (padding, first.1, first.2))?;
+                self.carry = Some(second);
+            } else {
+                self.crypt(first, second)?;
+            }
+        }
+        self.normalized_offset += normalized.len();
+        self.original_offset += text.chars().count();
+        self.payload.payload = normalized;
+        self.payload.origins = origins;
+        Ok(())
+    }
+
+    fn crypt(&mut self, a: Located, b: Located) -> Result<(), StreamError> {
+        let [a, b] = self.crypt_digram(a, b)?;
+        self.write(a);
+        self.write(b);
+        Ok(())
+    }
+
+    // En- or decrypts a seriated block, see the seriation module.
+    fn crypt_block(&mut self, block: &[Located], period: usize) -> Result<(), StreamError> {
+        let mut crypt = Vec::with_capacity(block.len());
+        for pair in seriation::to_pairs(block, period).chunks(2) {
+            crypt.extend(self.crypt_digram(pair[0], pair[1])?);
+        }
+        for c in seriation::from_pairs(&crypt, period) {
+            self.write(c);
+        }
+        Ok(())
+    }
+
+    fn crypt_digram(&self, a: Located, b: Located) -> Result<[char; 2], StreamError> {
+        match engine(self.cipher).crypt(a.0, b.0, &self.modus) {
+            Ok(digram_crypt) => Ok([digram_crypt.a, digram_crypt.b]),
+            Err(e) => {
+                let (_, position, original_position) = if e.character == a.0 { a } else { b };
+                Err(e.at(position, original_position).into())
+            }
+        }
+    }
+
+    fn write(&mut self, c: char) {
+        let grouping = match self.modus {
+            CryptModus::Encrypt => self.payload.options.grouping,
+            CryptModus::Decrypt => None,
+        };
+        if let Some(g) = grouping {
+            if self.written > 0 && self.written.is_multiple_of(g) {
+                self.output.push(' ');
+            }
+        }
+        self.output.push(c);
+        self.written += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::playfair::PlayFairKey;
+
+    const TEXT: &str = "Thé quick red fox jumps over the lazy brown dog. Balloons aa see.";
+
+    #[test]
+    fn test_chunks_match_whole() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let crypt = pfc.encrypt(TEXT).unwrap();
+        for chunk_size in [4, 5, 7, 64] {
+            let mut chunked: Vec<u8> = Vec::new();
+            ChunkedCrypt::new(&pfc, CryptModus::Encrypt, chunk_size)
+                .run(TEXT.as_bytes(), &mut chunked)
+                .unwrap();
+            assert_eq!(
+                String::from_utf8(chunked).unwrap(),
+                crypt,
+                "chunk size {}",
+                chunk_size
+            );
+
+            let mut plain: Vec<u8> = Vec::new();
+            ChunkedCrypt::new(&pfc, CryptModus::Decrypt, chunk_size)
+                .run(crypt.as_bytes(), &mut plain)
+                .unwrap();
+            assert_eq!(
+                String::from_utf8(plain).unwrap(),
+                pfc.decrypt(&crypt).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunks_error_position() {
+        let mut pfc = PlayFairKey::new("playfair example");
+        pfc.key_map.remove(&'T');
+        match ChunkedCrypt::new(&pfc, CryptModus::Encrypt, 3)
+            .run("hide the gold".as_bytes(), Vec::new())
+        {
+            Ok(_) => panic!("T is not part of the key"),
+            Err(StreamError::CharNotInKey(e)) => {
+                assert_eq!(e.character(), 'T');
+                assert_eq!(e.position(), Some(4));
+                assert_eq!(e.original_position(), Some(5));
+            }
+            Err(e) => panic!("Unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    fn test_chunks_truncated_char() {
+        let pfc = PlayFairKey::new("playfair example");
+        // The input ends with the first byte of 'é'
+        let input = &"hide thé".as_bytes()[..8];
+        for chunk_size in [4, 64] {
+            let mut chunked = ChunkedCrypt::new(&pfc, CryptModus::Encrypt, chunk_size);
+            let mut reader = input;
+            loop {
+                let read = std::io::Read::read(&mut reader, chunked.unfilled()).unwrap();
+                if chunked.filled(read).unwrap() {
+                    break;
+                }
+            }
+            assert_eq!(chunked.output, pfc.encrypt("hide th").unwrap());
+            assert_eq!(chunked.pending, 0);
+            assert_eq!(chunked.original_offset, 8);
+        }
+    }
+
+    #[test]
+    fn test_chunks_final_digram() {
+        for final_digram in [FinalDigram::Strict, FinalDigram::PassThrough] {
+            let pfc = PlayFairKey::builder()
+                .key("playfair example")
+                .grouping(5)
+                .final_digram(final_digram)
+                .build()
+                .unwrap();
+            let mut chunked: Vec<u8> = Vec::new();
+            let result = ChunkedCrypt::new(&pfc, CryptModus::Encrypt, 4)
+                .run("hide the gol".as_bytes(), &mut chunked);
+            match pfc.encrypt("hide the gol") {
+                Ok(crypt) => assert_eq!(String::from_utf8(chunked).unwrap(), crypt),
+                Err(e) => match result {
+                    Err(StreamError::CharNotInKey(chunked_e)) => {
+                        assert_eq!(chunked_e.character(), e.character());
+                        assert_eq!(chunked_e.original_position(), e.original_position());
+                    }
+                    _ => panic!("Strict mode must reject the last L"),
+                },
+            }
+        }
+    }
+
+    #[cfg(feature = "two-square")]
+    #[test]
+    fn test_chunks_seriation() {
+        use crate::two_square::TwoSquare;
+
+        for final_digram in [FinalDigram::Pad, FinalDigram::PassThrough] {
+            let two_square = TwoSquare::builder()
+                .keys("EXAMPLE", "KEYWORD")
+                .seriation(4)
+                .grouping(5)
+                .final_digram(final_digram)
+                .build()
+                .unwrap();
+            let crypt = two_square.encrypt(TEXT).unwrap();
+            for chunk_size in [4, 9, 64] {
+                let mut chunked: Vec<u8> = Vec::new();
+                ChunkedCrypt::new(&two_square, CryptModus::Encrypt, chunk_size)
+                    .run(TEXT.as_bytes(), &mut chunked)
+                    .unwrap();
+                assert_eq!(String::from_utf8(chunked).unwrap(), crypt);
+
+                let mut plain: Vec<u8> = Vec::new();
+                ChunkedCrypt::new(&two_square, CryptModus::Decrypt, chunk_size)
+                    .run(crypt.as_bytes(), &mut plain)
+                    .unwrap();
+                assert_eq!(
+                    String::from_utf8(plain).unwrap(),
+                    two_square.decrypt(&crypt).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_files() {
+        let pfc = PlayFairKey::new("playfair example");
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        let plain = dir.join(format!("playfair_plain_{}.txt", id));
+        let crypt = dir.join(format!("playfair_crypt_{}.txt", id));
+        let decrypted = dir.join(format!("playfair_decrypted_{}.txt", id));
+        std::fs::write(&plain, TEXT).unwrap();
+        encrypt_file(&pfc, &plain, &crypt).unwrap();
+        decrypt_file(&pfc, &crypt, &decrypted).unwrap();
+        let crypt_text = std::fs::read_to_string(&crypt).unwrap();
+        let decrypted_text = std::fs::read_to_string(&decrypted).unwrap();
+        for path in [&plain, &crypt, &decrypted] {
+            std::fs::remove_file(path).unwrap();
+        }
+        assert_eq!(crypt_text, pfc.encrypt(TEXT).unwrap());
+        assert_eq!(decrypted_text, pfc.decrypt(&crypt_text).unwrap());
+        assert!(matches!(
+            encrypt_file(&pfc, dir.join("playfair_missing"), &crypt),
+            Err(StreamError::Io(_))
+        ));
+    }
+}
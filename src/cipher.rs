@@ -0,0 +1,259 @@
+//! A single value holding a whole cipher setup: the cipher, its keys, the
+//! payload options and how the cipher text is formatted. Applications
+//! configure it once and en- and decrypt with it, instead of wiring the
+//! options across the cipher, its builder and a formatter.
+//!
+//! ```
+//! use playfair_cipher::{cipher::Cipher, config::Algorithm, format::Case};
+//!
+//! let cipher = Cipher::builder()
+//!     .algorithm(Algorithm::PlayFair)
+//!     .keys(&["playfair example"])
+//!     .grouping(5)
+//!     .formatter(Case::Lower)
+//!     .build()
+//!     .unwrap();
+//! let crypt = cipher.encrypt("hide the gold").unwrap();
+//! assert_eq!(crypt, "bmodz bxdna ge");
+//! assert_eq!(cipher.decrypt(&crypt).unwrap(), "HIDETHEGOLDX");
+//! ```
+//!
+
+use std::fmt;
+
+use crate::config::{Algorithm, CipherConfig};
+use crate::cryptable::{engine, Cypher};
+use crate::errors::{CharNotInKeyError, InvalidOptionError};
+use crate::format::CiphertextFormatter;
+use crate::options::{FinalDigram, PayloadOptions};
+
+/// A cipher with its keys, options and formatter, see Cipher::builder.
+pub struct Cipher {
+    algorithm: Algorithm,
+    cypher: Box<dyn Cypher>,
+    formatter: Option<Box<dyn CiphertextFormatter>>,
+}
+
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cipher")
+            .field("algorithm", &self.algorithm)
+            .field("options", self.options())
+            .field("formatted", &self.formatter.is_some())
+            .finish()
+    }
+}
+
+/// Builder for a Cipher, see Cipher::builder.
+///
+pub struct CipherBuilder {
+    algorithm: Algorithm,
+    keys: Vec<String>,
+    padding: char,
+    omit: char,
+    grouping: Option<usize>,
+    final_digram: FinalDigram,
+    formatter: Option<Box<dyn CiphertextFormatter>>,
+}
+
+impl Default for CipherBuilder {
+    fn default() -> Self {
+        let options = PayloadOptions::default();
+        CipherBuilder {
+            algorithm: Algorithm::default(),
+            keys: Vec::new(),
+            padding: options.padding,
+            omit: options.omit,
+            grouping: options.grouping,
+            final_digram: options.final_digram,
+            formatter: None,
+        }
+    }
+}
+
+impl CipherBuilder {
+    /// The cipher, defaults to Algorithm::PlayFair.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// The keywords, as many as the cipher takes, see Algorithm::keys.
+    pub fn keys(mut self, keys: &[&str]) -> Self {
+        self.keys = keys.iter().map(|key| String::from(*key)).collect();
+        self
+    }
+
+    /// Takes all payload options at once, replacing the ones set before.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.padding = options.padding;
+        self.omit = options.omit;
+        self.grouping = options.grouping;
+        self.final_digram = options.final_digram;
+        self
+    }
+
+    /// Letter used to split doubled letters and to pad a payload of odd length.
+    pub fn padding(mut self, padding: char) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Letter left out of the square.
+    pub fn omit(mut self, omit: char) -> Self {
+        self.omit = omit;
+        self
+    }
+
+    /// Splits the cipher text into groups of this many letters.
+    pub fn grouping(mut self, grouping: usize) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// How the last letter of a payload of odd length is handled, defaults
+    /// to FinalDigram::Pad.
+    pub fn final_digram(mut self, final_digram: FinalDigram) -> Self {
+        self.final_digram = final_digram;
+        self
+    }
+
+    /// Formats the cipher text returned by Cipher::encrypt, see the format
+    /// module. Formatters are combined as tuple.
+    pub fn formatter(mut self, formatter: impl CiphertextFormatter + 'static) -> Self {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Constructs the cipher. Fails if the options are invalid, the number
+    /// of keys doesn't fit the cipher or the cipher was left out by the
+    /// features of the crate.
+    pub fn build(self) -> Result<Cipher, InvalidOptionError> {
+        let options = PayloadOptions::new(self.padding, self.omit, self.grouping)?
+            .with_final_digram(self.final_digram);
+        Ok(Cipher {
+            algorithm: self.algorithm,
+            cypher: self.algorithm.cypher(&self.keys, options, None, None)?,
+            formatter: self.formatter,
+        })
+    }
+}
+
+impl Cipher {
+    /// Returns a builder to construct a cipher setup.
+    pub fn builder() -> CipherBuilder {
+        CipherBuilder::default()
+    }
+
+    /// Constructs the cipher described by the config, without formatter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{cipher::Cipher, config::CipherConfig};
+    ///
+    /// let config = CipherConfig {
+    ///     keys: vec![String::from("monarchy")],
+    ///     ..CipherConfig::default()
+    /// };
+    /// let cipher = Cipher::from_config(&config).unwrap();
+    /// assert_eq!(cipher.encrypt("hide").unwrap(), "BFCK");
+    /// ```
+    pub fn from_config(config: &CipherConfig) -> Result<Self, InvalidOptionError> {
+        Ok(Cipher {
+            algorithm: config.algorithm,
+            cypher: config.build()?,
+            formatter: None,
+        })
+    }
+
+    /// Encrypts the payload and formats the result with the formatter.
+    pub fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        let crypt = self.cypher.encrypt(payload)?;
+        match &self.formatter {
+            Some(formatter) => formatter.format(self.cypher.as_ref(), &crypt),
+            None => Ok(crypt),
+        }
+    }
+
+    /// Decrypts the cipher text. Grouping and casing are ignored, any other
+    /// formatting, like Morse code, has to be undone first.
+    pub fn decrypt(&self, crypt: &str) -> Result<String, CharNotInKeyError> {
+        self.cypher.decrypt(crypt)
+    }
+
+    /// The cipher chosen.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The options the cipher was constructed with.
+    pub fn options(&self) -> &PayloadOptions {
+        engine(self.cypher.as_ref()).options()
+    }
+
+    /// The underlying cipher, for everything else the Cypher trait offers.
+    pub fn cypher(&self) -> &dyn Cypher {
+        self.cypher.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::format::{Case, Groups};
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_matches_cypher() {
+        let cipher = Cipher::builder()
+            .keys(&["playfair example"])
+            .padding('q')
+            .grouping(5)
+            .build()
+            .unwrap();
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .padding('q')
+            .grouping(5)
+            .build()
+            .unwrap();
+        assert_eq!(cipher.algorithm(), Algorithm::PlayFair);
+        assert_eq!(cipher.options(), pfc.options());
+        let crypt = cipher.encrypt("hide the gold").unwrap();
+        assert_eq!(crypt, pfc.encrypt("hide the gold").unwrap());
+        assert_eq!(
+            cipher.decrypt(&crypt).unwrap(),
+            pfc.decrypt(&crypt).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_formatter() {
+        let cipher = Cipher::builder()
+            .keys(&["playfair example"])
+            .options(PayloadOptions::default().with_final_digram(FinalDigram::PassThrough))
+            .formatter((Groups::new(3, "-"), Case::Lower))
+            .build()
+            .unwrap();
+        let crypt = cipher.encrypt("hide the go").unwrap();
+        assert_eq!(crypt, "bmo-dzb-xdo");
+        assert_eq!(cipher.decrypt(&crypt).unwrap(), "HIDETHEGO");
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(Cipher::builder().build().is_err());
+        assert!(Cipher::builder()
+            .keys(&["EXAMPLE"])
+            .omit('1')
+            .build()
+            .is_err());
+        assert!(Cipher::builder()
+            .algorithm(Algorithm::TwoSquare)
+            .keys(&["EXAMPLE"])
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,499 @@
+//! Description of a whole cipher setup, i.e. which cipher with which keys
+//! and options. With the feature `serde` a config can be serialized, so
+//! setups can be stored in files and shared between tools. The features
+//! `toml` and `json` add helpers reading a config from a string.
+//!
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cryptable::Cypher;
+#[cfg(any(feature = "toml", feature = "json"))]
+use crate::errors::ConfigError;
+use crate::errors::InvalidOptionError;
+#[cfg(feature = "four-square")]
+use crate::four_square::FourSquare;
+use crate::options::{FinalDigram, PayloadOptions, Transparency};
+use crate::playfair::PlayFairKey;
+#[cfg(feature = "two-square")]
+use crate::two_square::TwoSquare;
+
+/// The ciphers of the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Algorithm {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "playfair"))]
+    PlayFair,
+    TwoSquare,
+    FourSquare,
+}
+
+impl Algorithm {
+    /// Number of keywords the cipher takes.
+    pub fn keys(&self) -> usize {
+        match self {
+            Algorithm::PlayFair => 1,
+            Algorithm::TwoSquare | Algorithm::FourSquare => 2,
+        }
+    }
+
+    /// Whether the cipher is part of the build, see the features
+    /// `two-square` and `four-square`.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Algorithm::PlayFair => true,
+            Algorithm::TwoSquare => cfg!(feature = "two-square"),
+            Algorithm::FourSquare => cfg!(feature = "four-square"),
+        }
+    }
+
+    /// Constructs the cipher with the keys, options and settings, see
+    /// CipherConfig::build.
+    pub(crate) fn cypher(
+        &self,
+        keys: &[String],
+        options: PayloadOptions,
+        seriation: Option<usize>,
+        transparency: Option<Transparency>,
+    ) -> Result<Box<dyn Cypher>, InvalidOptionError> {
+        if keys.len() != self.keys() {
+            return Err(InvalidOptionError::new(format!(
+                "{:?} takes {} key(s) - got {}",
+                self,
+                self.keys(),
+                keys.len()
+            )));
+        }
+        if seriation.is_some() && *self == Algorithm::PlayFair {
+            return Err(InvalidOptionError::new(String::from(
+                "Only TwoSquare and FourSquare can be seriated",
+            )));
+        }
+        if transparency.is_some() && *self != Algorithm::TwoSquare {
+            return Err(InvalidOptionError::new(format!(
+                "Transparency only applies to TwoSquare - got {:?}",
+                self
+            )));
+        }
+        Ok(match self {
+            Algorithm::PlayFair => Box::new(PlayFairKey::with_options(&keys[0], options)),
+            #[cfg(feature = "two-square")]
+            Algorithm::TwoSquare => Box::new(
+                TwoSquare::with_options(&keys[0], &keys[1], options)
+                    .with_settings(transparency.unwrap_or_default(), seriation)?,
+            ),
+            #[cfg(feature = "four-square")]
+            Algorithm::FourSquare => Box::new(
+                FourSquare::with_options(&keys[0], &keys[1], options).with_seriation(seriation)?,
+            ),
+            #[allow(unreachable_patterns)]
+            algorithm => {
+                return Err(InvalidOptionError::new(format!(
+                    "{:?} is not available, the crate was built without its feature",
+                    algorithm
+                )))
+            }
+        })
+    }
+}
+
+/// Version of the serialized config written by this crate, see
+/// CipherConfig. It's only raised if a config can't be read correctly by
+/// older versions of the crate, options added later are simply left out by
+/// them.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// A cipher with its keys and options. Options not given take the
+/// defaults of PayloadOptions.
+///
+/// Serialized with serde, a config looks like this in TOML:
+///
+/// ```toml
+/// version = 1
+/// algorithm = "two_square"
+/// keys = ["EXAMPLE", "KEYWORD"]
+/// padding = "Q"
+/// grouping = 5
+/// final_digram = "strict"
+/// transparency = "swap"
+/// ```
+///
+/// The version is the one of the format, see CONFIG_VERSION. Configs
+/// lacking it were written before it was introduced and are read as
+/// version 1. Fields not known are ignored, while a config of a version
+/// newer than CONFIG_VERSION is rejected.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::config::{Algorithm, CipherConfig};
+///
+/// let config = CipherConfig {
+///     algorithm: Algorithm::PlayFair,
+///     keys: vec![String::from("playfair example")],
+///     grouping: Some(5),
+///     ..CipherConfig::default()
+/// };
+/// let cipher = config.build().unwrap();
+/// assert_eq!(
+///     cipher.encrypt("hide the gold in the tree stump").unwrap(),
+///     "BMODZ BXDNA BEKUD MUIXM MOUVI F"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "SerializedConfig", try_from = "SerializedConfig")
+)]
+pub struct CipherConfig {
+    pub algorithm: Algorithm,
+    pub keys: Vec<String>,
+    /// Letter used to split doubled letters and to pad a payload of odd length.
+    pub padding: Option<char>,
+    /// Letter left out of the square.
+    pub omit: Option<char>,
+    /// Splits the cipher text into groups of this many letters.
+    pub grouping: Option<usize>,
+    /// How the last letter of a payload of odd length is handled.
+    pub final_digram: Option<FinalDigram>,
+    /// Period of the seriation of the payload, TwoSquare and FourSquare
+    /// only.
+    pub seriation: Option<usize>,
+    /// The rule for letters in the same column, TwoSquare only.
+    pub transparency: Option<Transparency>,
+}
+
+// The serialized form of a CipherConfig, carrying the version of the format.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SerializedConfig {
+    #[serde(default = "first_version")]
+    version: u32,
+    algorithm: Algorithm,
+    keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    padding: Option<char>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    omit: Option<char>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grouping: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    final_digram: Option<FinalDigram>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seriation: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transparency: Option<Transparency>,
+}
+
+#[cfg(feature = "serde")]
+fn first_version() -> u32 {
+    1
+}
+
+#[cfg(feature = "serde")]
+impl From<CipherConfig> for SerializedConfig {
+    fn from(config: CipherConfig) -> Self {
+        SerializedConfig {
+            version: CONFIG_VERSION,
+            algorithm: config.algorithm,
+            keys: config.keys,
+            padding: config.padding,
+            omit: config.omit,
+            grouping: config.grouping,
+            final_digram: config.final_digram,
+            seriation: config.seriation,
+            transparency: config.transparency,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedConfig> for CipherConfig {
+    type Error = String;
+
+    fn try_from(config: SerializedConfig) -> Result<Self, Self::Error> {
+        if config.version > CONFIG_VERSION {
+            return Err(format!(
+                "Config of version {} written by a newer version of the crate - up to {} supported",
+                config.version, CONFIG_VERSION
+            ));
+        }
+        Ok(CipherConfig {
+            algorithm: config.algorithm,
+            keys: config.keys,
+            padding: config.padding,
+            omit: config.omit,
+            grouping: config.grouping,
+            final_digram: config.final_digram,
+            seriation: config.seriation,
+            transparency: config.transparency,
+        })
+    }
+}
+
+impl CipherConfig {
+    /// The options of the config, validated. Seriation and transparency
+    /// are settings of the cipher, see build.
+    pub fn options(&self) -> Result<PayloadOptions, InvalidOptionError> {
+        let defaults = PayloadOptions::default();
+        Ok(PayloadOptions::new(
+            self.padding.unwrap_or(defaults.padding),
+            self.omit.unwrap_or(defaults.omit),
+            self.grouping,
+        )?
+        .with_final_digram(self.final_digram.unwrap_or_default()))
+    }
+
+    /// Constructs the cipher described. Fails if the options are invalid,
+    /// the number of keys or a setting doesn't fit the cipher or the cipher
+    /// was left out by the features of the crate, e.g. `two-square`.
+    pub fn build(&self) -> Result<Box<dyn Cypher>, InvalidOptionError> {
+        self.algorithm.cypher(
+            &self.keys,
+            self.options()?,
+            self.seriation,
+            self.transparency,
+        )
+    }
+}
+
+#[cfg(feature = "toml")]
+impl CipherConfig {
+    /// Reads a config written in TOML.
+    ///
+    /// Only available with the feature `toml`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::config::CipherConfig;
+    ///
+    /// let config = CipherConfig::from_toml_str(
+    ///     r#"
+    ///     algorithm = "four_square"
+    ///     keys = ["EXAMPLE", "KEYWORD"]
+    ///     grouping = 4
+    ///     "#,
+    /// )
+    /// .unwrap();
+    /// let cipher = config.build().unwrap();
+    /// assert_eq!(cipher.encrypt("attack at dawn").unwrap(), "MMOW PAMM EWWG");
+    /// ```
+    pub fn from_toml_str(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str(toml).map_err(|e| ConfigError::new(format!("Invalid TOML config: {}", e)))
+    }
+}
+
+#[cfg(feature = "json")]
+impl CipherConfig {
+    /// Reads a config written in JSON.
+    ///
+    /// Only available with the feature `json`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::config::{Algorithm, CipherConfig};
+    ///
+    /// let config =
+    ///     CipherConfig::from_json_str(r#"{"algorithm": "playfair", "keys": ["monarchy"]}"#).unwrap();
+    /// assert_eq!(config.algorithm, Algorithm::PlayFair);
+    /// assert_eq!(config.build().unwrap().encrypt("hide").unwrap(), "BFCK");
+    /// ```
+    pub fn from_json_str(json: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(json)
+            .map_err(|e| ConfigError::new(format!("Invalid JSON config: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::engine;
+
+    #[test]
+    fn test_build() {
+        let config = CipherConfig {
+            keys: vec![String::from("playfair example")],
+            padding: Some('q'),
+            grouping: Some(5),
+            ..CipherConfig::default()
+        };
+        let cipher = config.build().unwrap();
+        assert_eq!(cipher.encrypt("hide the gold").unwrap(), "BMODZ BXDNA GO");
+        assert_eq!(engine(cipher.as_ref()).options().padding(), 'Q');
+    }
+
+    #[test]
+    fn test_build_final_digram() {
+        let config = CipherConfig {
+            keys: vec![String::from("playfair example")],
+            final_digram: Some(FinalDigram::PassThrough),
+            ..CipherConfig::default()
+        };
+        assert_eq!(
+            config.options().unwrap().final_digram(),
+            FinalDigram::PassThrough
+        );
+        assert_eq!(
+            config.build().unwrap().encrypt("hide the gold").unwrap(),
+            "BMODZBXDNAD"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_final_digram_round_trip() {
+        let config = CipherConfig {
+            keys: vec![String::from("monarchy")],
+            final_digram: Some(FinalDigram::Strict),
+            ..CipherConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"algorithm":"playfair","keys":["monarchy"],"final_digram":"strict"}"#
+        );
+        let read = CipherConfig::from_json_str(&json).unwrap();
+        assert_eq!(read, config);
+        assert_eq!(read.options().unwrap(), config.options().unwrap());
+    }
+
+    #[cfg(feature = "two-square")]
+    #[test]
+    fn test_build_settings() {
+        use crate::two_square::TwoSquare;
+
+        let config = CipherConfig {
+            algorithm: Algorithm::TwoSquare,
+            keys: vec![String::from("EXAMPLE"), String::from("KEYWORD")],
+            seriation: Some(4),
+            transparency: Some(Transparency::Shift),
+            ..CipherConfig::default()
+        };
+        let tsq = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .seriation(4)
+            .transparency(Transparency::Shift)
+            .build()
+            .unwrap();
+        let payload = "help me obi wan kenobi";
+        assert_eq!(
+            config.build().unwrap().encrypt(payload).unwrap(),
+            tsq.encrypt(payload).unwrap()
+        );
+        let invalid = [
+            CipherConfig {
+                seriation: Some(0),
+                ..config.clone()
+            },
+            CipherConfig {
+                algorithm: Algorithm::PlayFair,
+                keys: vec![String::from("EXAMPLE")],
+                seriation: Some(4),
+                transparency: None,
+                ..config.clone()
+            },
+            CipherConfig {
+                algorithm: Algorithm::FourSquare,
+                seriation: None,
+                ..config.clone()
+            },
+        ];
+        for config in invalid {
+            assert!(config.build().is_err());
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_settings_round_trip() {
+        let config = CipherConfig {
+            algorithm: Algorithm::TwoSquare,
+            keys: vec![String::from("EXAMPLE"), String::from("KEYWORD")],
+            seriation: Some(6),
+            transparency: Some(Transparency::Swap),
+            ..CipherConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"algorithm":"two_square","keys":["EXAMPLE","KEYWORD"],"seriation":6,"transparency":"swap"}"#
+        );
+        assert_eq!(CipherConfig::from_json_str(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let config = CipherConfig {
+            algorithm: Algorithm::FourSquare,
+            keys: vec![String::from("EXAMPLE")],
+            ..CipherConfig::default()
+        };
+        assert!(config.build().is_err());
+        let config = CipherConfig {
+            keys: vec![String::from("EXAMPLE")],
+            omit: Some('1'),
+            ..CipherConfig::default()
+        };
+        assert!(config.build().is_err());
+    }
+
+    #[cfg(all(feature = "toml", feature = "json"))]
+    #[test]
+    fn test_formats_agree() {
+        let toml = CipherConfig::from_toml_str(
+            "algorithm = \"two_square\"\nkeys = [\"EXAMPLE\", \"KEYWORD\"]\npadding = \"Q\"\n",
+        )
+        .unwrap();
+        let json = CipherConfig::from_json_str(
+            r#"{"algorithm": "two_square", "keys": ["EXAMPLE", "KEYWORD"], "padding": "Q"}"#,
+        )
+        .unwrap();
+        assert_eq!(toml, json);
+        assert_eq!(toml.padding, Some('Q'));
+        assert!(CipherConfig::from_toml_str("algorithm = \"enigma\"").is_err());
+        assert!(CipherConfig::from_json_str("{\"keys\": []}").is_err());
+    }
+
+    #[cfg(all(feature = "toml", feature = "json"))]
+    #[test]
+    fn test_versions() {
+        let config = CipherConfig {
+            keys: vec![String::from("monarchy")],
+            grouping: Some(5),
+            ..CipherConfig::default()
+        };
+        let toml = toml::to_string(&config).unwrap();
+        assert!(toml.starts_with("version = 1\n"));
+        assert_eq!(CipherConfig::from_toml_str(&toml).unwrap(), config);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"algorithm":"playfair","keys":["monarchy"],"grouping":5}"#
+        );
+        // Written before the version was introduced
+        let unversioned = CipherConfig::from_json_str(
+            r#"{"algorithm": "playfair", "keys": ["monarchy"], "grouping": 5}"#,
+        )
+        .unwrap();
+        assert_eq!(unversioned, config);
+        // Options added later are ignored
+        let extended = CipherConfig::from_toml_str(
+            "version = 1\nalgorithm = \"playfair\"\nkeys = [\"monarchy\"]\ngrouping = 5\nalphabet = \"6x6\"\n",
+        )
+        .unwrap();
+        assert_eq!(extended, config);
+        let newer = CipherConfig::from_json_str(
+            r#"{"version": 2, "algorithm": "playfair", "keys": ["monarchy"]}"#,
+        );
+        assert!(newer.unwrap_err().to_string().contains("version 2"));
+    }
+}
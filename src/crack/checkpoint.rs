@@ -0,0 +1,480 @@
+//! Checkpoints of a running crack, so a search over hours survives a
+//! restart of the program. A solver hands out a checkpoint whenever a
+//! restart or a generation is done, and resumes from it later on.
+//!
+//! A checkpoint is written as plain text, one record per line:
+//!
+//! ```text
+//! playfair_cipher checkpoint 1
+//! solver hill_climbing
+//! seed 4
+//! cipher_text BMODZBXDNAGE
+//! iterations 20000
+//! finished 0 1
+//! candidate -84.25 PLAYFIREXMBCDGHKNOQSTUVWZ
+//! ```
+//!
+//! Keys are written as their squares, a Two or Four Square key as both
+//! squares separated by a space.
+//!
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{solver_options, Candidate, Ranking, Rng};
+use crate::cryptable::Cypher;
+use crate::errors::CheckpointError;
+#[cfg(feature = "four-square")]
+use crate::four_square::FourSquare;
+use crate::options::PayloadOptions;
+use crate::playfair::PlayFairKey;
+#[cfg(feature = "two-square")]
+use crate::two_square::TwoSquare;
+
+const HEADER: &str = "playfair_cipher checkpoint 1";
+
+// Squares is public within a private module, so the crate is the only one
+// able to make a key checkpointable.
+mod private {
+    use super::*;
+
+    pub trait Squares: Sized {
+        fn squares(&self) -> Vec<&PlayFairKey>;
+        fn from_squares(squares: Vec<PlayFairKey>, options: PayloadOptions) -> Option<Self>;
+    }
+}
+
+/// A key a checkpoint can hold, i.e. the key of any solver.
+pub trait CheckpointKey: private::Squares + Cypher + Clone + Send + Sync {}
+
+impl<K: private::Squares + Cypher + Clone + Send + Sync> CheckpointKey for K {}
+
+impl private::Squares for PlayFairKey {
+    fn squares(&self) -> Vec<&PlayFairKey> {
+        vec![self]
+    }
+
+    fn from_squares(squares: Vec<PlayFairKey>, _: PayloadOptions) -> Option<Self> {
+        let [key] = <[PlayFairKey; 1]>::try_from(squares).ok()?;
+        Some(key)
+    }
+}
+
+#[cfg(feature = "two-square")]
+impl private::Squares for TwoSquare {
+    fn squares(&self) -> Vec<&PlayFairKey> {
+        vec![self.top(), self.bottom()]
+    }
+
+    fn from_squares(squares: Vec<PlayFairKey>, options: PayloadOptions) -> Option<Self> {
+        let [top, bottom] = <[PlayFairKey; 2]>::try_from(squares).ok()?;
+        Some(TwoSquare::from_keys(top, bottom, options))
+    }
+}
+
+#[cfg(feature = "four-square")]
+impl private::Squares for FourSquare {
+    fn squares(&self) -> Vec<&PlayFairKey> {
+        vec![self.top_right(), self.bottom_left()]
+    }
+
+    fn from_squares(squares: Vec<PlayFairKey>, options: PayloadOptions) -> Option<Self> {
+        let [top_right, bottom_left] = <[PlayFairKey; 2]>::try_from(squares).ok()?;
+        Some(FourSquare::from_keys(top_right, bottom_left, options))
+    }
+}
+
+/// State of a crack, see Solver::crack_resumable.
+///
+/// Solvers restarting their search record which restarts are finished,
+/// each restart has its own seed drawn from the seed of the solver, so the
+/// unfinished ones are searched as if the crack hadn't been interrupted.
+/// The genetic solver records the population and the state of its random
+/// generator after the last generation.
+///
+#[derive(Debug, Clone)]
+pub struct Checkpoint<K> {
+    pub(crate) solver: String,
+    pub(crate) seed: u64,
+    pub(crate) cipher_text: String,
+    pub(crate) iterations: usize,
+    pub(crate) finished: Vec<usize>,
+    pub(crate) generation: usize,
+    pub(crate) rng: Option<u64>,
+    pub(crate) population: Vec<Candidate<K>>,
+    pub(crate) candidates: Vec<Candidate<K>>,
+}
+
+impl<K: CheckpointKey> Checkpoint<K> {
+    pub(crate) fn new(solver: &str, seed: u64, cipher_text: &str) -> Self {
+        Checkpoint {
+            solver: String::from(solver),
+            seed,
+            cipher_text: String::from(cipher_text),
+            iterations: 0,
+            finished: Vec::new(),
+            generation: 0,
+            rng: None,
+            population: Vec::new(),
+            candidates: Vec::new(),
+        }
+    }
+
+    // The checkpoint to resume from, a fresh one if there is none. Fails if
+    // the checkpoint was taken by another solver or of another crack.
+    pub(crate) fn resume(
+        resume: Option<Self>,
+        solver: &str,
+        seed: u64,
+        cipher_text: &str,
+    ) -> Result<Self, CheckpointError> {
+        let Some(checkpoint) = resume else {
+            return Ok(Checkpoint::new(solver, seed, cipher_text));
+        };
+        if checkpoint.solver != solver {
+            return Err(CheckpointError::Mismatch(format!(
+                "Checkpoint was taken by solver {} - resumed with {}",
+                checkpoint.solver, solver
+            )));
+        }
+        if checkpoint.seed != seed || checkpoint.cipher_text != cipher_text {
+            return Err(CheckpointError::Mismatch(String::from(
+                "Checkpoint was taken with another seed or cipher text",
+            )));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Name of the solver which took the checkpoint, e.g. hill_climbing.
+    pub fn solver(&self) -> &str {
+        &self.solver
+    }
+
+    /// Number of keys tried so far.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// The best candidates found so far, the best first.
+    pub fn candidates(&self) -> &[Candidate<K>] {
+        &self.candidates
+    }
+
+    /// Reads a checkpoint written by Display. The options must be the ones
+    /// of the solver, the squares are built with them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::crack::{checkpoint::Checkpoint, hill_climbing::HillClimbing, Solver};
+    /// use playfair_cipher::{options::PayloadOptions, playfair::PlayFairKey};
+    /// use std::sync::Mutex;
+    ///
+    /// let solver = HillClimbing::builder().restarts(3).iterations(100).build().unwrap();
+    /// let fitness = |plain: &str| plain.matches("TH").count() as f64;
+    /// let saved = Mutex::new(String::new());
+    /// let ranked = solver
+    ///     .crack_resumable("BMODZBXDNAGE", fitness, |_: &_| {}, None, |c: &Checkpoint<PlayFairKey>| {
+    ///         *saved.lock().unwrap() = c.to_string()
+    ///     })
+    ///     .unwrap();
+    ///
+    /// // Resuming from the last checkpoint has nothing left to do
+    /// let saved = saved.into_inner().unwrap();
+    /// let checkpoint = Checkpoint::parse(&saved, PayloadOptions::default()).unwrap();
+    /// assert_eq!(checkpoint.iterations(), 300);
+    /// let resumed = solver
+    ///     .crack_resumable("BMODZBXDNAGE", fitness, |_: &_| {}, Some(checkpoint), |_: &_| {})
+    ///     .unwrap();
+    /// assert_eq!(resumed[0].plain_text(), ranked[0].plain_text());
+    /// ```
+    pub fn parse(text: &str, options: PayloadOptions) -> Result<Self, CheckpointError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(malformed(1, format!("Expected header '{}'", HEADER)));
+        }
+        let mut checkpoint = Checkpoint::new("", 0, "");
+        for (index, line) in lines {
+            let number = index + 1;
+            let mut fields = line.split_whitespace();
+            let Some(record) = fields.next() else {
+                continue;
+            };
+            let fields: Vec<&str> = fields.collect();
+            match record {
+                "solver" => checkpoint.solver = single(&fields, number)?.to_string(),
+                "seed" => checkpoint.seed = number_of(single(&fields, number)?, number)?,
+                "cipher_text" => {
+                    checkpoint.cipher_text = fields.concat();
+                }
+                "iterations" => {
+                    checkpoint.iterations = number_of(single(&fields, number)?, number)?
+                }
+                "finished" => {
+                    checkpoint.finished = fields
+                        .iter()
+                        .map(|field| number_of(field, number))
+                        .collect::<Result<_, _>>()?
+                }
+                "generation" => {
+                    checkpoint.generation = number_of(single(&fields, number)?, number)?
+                }
+                "rng" => checkpoint.rng = Some(number_of(single(&fields, number)?, number)?),
+                "candidate" | "member" => {
+                    let candidate =
+                        candidate_of(&fields, &checkpoint.cipher_text, options, number)?;
+                    match record {
+                        "candidate" => checkpoint.candidates.push(candidate),
+                        _ => checkpoint.population.push(candidate),
+                    }
+                }
+                _ => return Err(malformed(number, format!("Unknown record '{}'", record))),
+            }
+        }
+        if checkpoint.solver.is_empty() {
+            return Err(malformed(1, String::from("Solver is missing")));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Writes the checkpoint to the file. The file is replaced at once, so
+    /// a crash while saving leaves the previous checkpoint in place.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        fs::write(&partial, self.to_string())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Reads a checkpoint written by save, see parse.
+    pub fn load(path: impl AsRef<Path>, options: PayloadOptions) -> Result<Self, CheckpointError> {
+        Checkpoint::parse(&fs::read_to_string(path)?, options)
+    }
+}
+
+impl<K: CheckpointKey> fmt::Display for Checkpoint<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "solver {}", self.solver)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "cipher_text {}", self.cipher_text)?;
+        writeln!(f, "iterations {}", self.iterations)?;
+        write!(f, "finished")?;
+        for run in &self.finished {
+            write!(f, " {}", run)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "generation {}", self.generation)?;
+        if let Some(rng) = self.rng {
+            writeln!(f, "rng {}", rng)?;
+        }
+        for (record, candidates) in [
+            ("candidate", &self.candidates),
+            ("member", &self.population),
+        ] {
+            for candidate in candidates.iter() {
+                write!(f, "{} {}", record, candidate.score)?;
+                for square in private::Squares::squares(&candidate.key) {
+                    write!(f, " {}", square.square().iter().collect::<String>())?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Records the runs of a restarting solver as they finish and hands out
+// the checkpoint after each of them.
+pub(crate) struct Recorder<'a, K, S: Fn(&Checkpoint<K>) + Sync> {
+    checkpoint: Mutex<Checkpoint<K>>,
+    resumed: Vec<Candidate<K>>,
+    seed: u64,
+    limit: usize,
+    save: &'a S,
+}
+
+impl<'a, K: CheckpointKey, S: Fn(&Checkpoint<K>) + Sync> Recorder<'a, K, S> {
+    pub(crate) fn new(checkpoint: Checkpoint<K>, limit: usize, save: &'a S) -> Self {
+        Recorder {
+            resumed: checkpoint.candidates.clone(),
+            seed: checkpoint.seed,
+            checkpoint: Mutex::new(checkpoint),
+            limit,
+            save,
+        }
+    }
+
+    // The best candidates found before the checkpoint resumed from.
+    pub(crate) fn resumed(&self) -> &[Candidate<K>] {
+        &self.resumed
+    }
+
+    pub(crate) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub(crate) fn finished(&self, run: usize) -> bool {
+        let checkpoint = self.checkpoint.lock().unwrap_or_else(|e| e.into_inner());
+        checkpoint.finished.contains(&run)
+    }
+
+    // Takes in the keys tried by the run, the run counts as finished unless
+    // it was stopped.
+    pub(crate) fn record(&self, run: usize, ranking: &Ranking<K>, tried: usize, finished: bool) {
+        let mut checkpoint = self.checkpoint.lock().unwrap_or_else(|e| e.into_inner());
+        checkpoint.iterations += tried;
+        if finished {
+            checkpoint.finished.push(run);
+            checkpoint.finished.sort_unstable();
+        }
+        let mut merged = Ranking::new(self.limit);
+        for candidate in checkpoint.candidates.iter().chain(ranking.candidates()) {
+            merged.insert(candidate);
+        }
+        checkpoint.candidates = merged.into_vec();
+        (self.save)(&checkpoint);
+    }
+}
+
+// Generator state of the genetic solver.
+impl Rng {
+    pub(crate) fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub(crate) fn from_state(state: u64) -> Self {
+        Rng { state }
+    }
+}
+
+fn malformed(line: usize, error: String) -> CheckpointError {
+    CheckpointError::Malformed { line, error }
+}
+
+fn single<'t>(fields: &[&'t str], line: usize) -> Result<&'t str, CheckpointError> {
+    match fields {
+        [field] => Ok(field),
+        _ => Err(malformed(
+            line,
+            format!("Expected a single value - got {}", fields.len()),
+        )),
+    }
+}
+
+fn number_of<T: std::str::FromStr>(field: &str, line: usize) -> Result<T, CheckpointError> {
+    field
+        .parse()
+        .map_err(|_| malformed(line, format!("'{}' is not a valid number", field)))
+}
+
+fn candidate_of<K: CheckpointKey>(
+    fields: &[&str],
+    cipher_text: &str,
+    options: PayloadOptions,
+    line: usize,
+) -> Result<Candidate<K>, CheckpointError> {
+    let Some((score, squares)) = fields.split_first() else {
+        return Err(malformed(line, String::from("Score is missing")));
+    };
+    let options = solver_options(options);
+    let squares = squares
+        .iter()
+        .map(|square| square_of(square, options, line))
+        .collect::<Result<_, _>>()?;
+    let key = <K as private::Squares>::from_squares(squares, options)
+        .ok_or_else(|| malformed(line, String::from("Wrong number of squares")))?;
+    let plain_text = key
+        .decrypt(cipher_text)
+        .map_err(|e| malformed(line, e.to_string()))?;
+    Ok(Candidate {
+        key,
+        plain_text,
+        score: number_of(score, line)?,
+    })
+}
+
+fn square_of(
+    field: &str,
+    options: PayloadOptions,
+    line: usize,
+) -> Result<PlayFairKey, CheckpointError> {
+    let square: Vec<char> = field.chars().collect();
+    let mut alphabet: Vec<char> = options.alphabet().collect();
+    let mut sorted = square.clone();
+    sorted.sort_unstable();
+    alphabet.sort_unstable();
+    if sorted != alphabet {
+        return Err(malformed(
+            line,
+            format!(
+                "'{}' is not a square of the letters {}",
+                field,
+                alphabet.iter().collect::<String>()
+            ),
+        ));
+    }
+    let square = square.try_into().expect("as many letters as the alphabet");
+    Ok(PlayFairKey::from_square_with_options(square, options))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "two-square")]
+    fn test_round_trip() {
+        let options = PayloadOptions::default();
+        let key = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let cipher_text = key.encrypt("help me obi wan kenobi").unwrap();
+        let mut checkpoint = Checkpoint::new("two_square", 7, &cipher_text);
+        checkpoint.iterations = 1200;
+        checkpoint.finished = vec![0, 3];
+        checkpoint.candidates.push(Candidate {
+            plain_text: key.decrypt(&cipher_text).unwrap(),
+            key,
+            score: -12.5,
+        });
+        let parsed: Checkpoint<TwoSquare> =
+            Checkpoint::parse(&checkpoint.to_string(), options).unwrap();
+        assert_eq!(parsed.to_string(), checkpoint.to_string());
+        assert_eq!(parsed.finished, [0, 3]);
+        assert_eq!(parsed.candidates()[0].plain_text(), "HELPMEOBIWANKENOBI");
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let options = PayloadOptions::default();
+        let parse = |text: &str| Checkpoint::<PlayFairKey>::parse(text, options);
+        assert!(parse("solver hill_climbing").is_err());
+        let text = format!("{}\nsolver hill_climbing\nseed x\n", HEADER);
+        assert!(matches!(
+            parse(&text),
+            Err(CheckpointError::Malformed { line: 3, .. })
+        ));
+        let text = format!("{}\nsolver hill_climbing\ncandidate 1.0 ABC\n", HEADER);
+        assert!(parse(&text).is_err());
+        let text = format!("{}\nsolver hill_climbing\nunknown 1\n", HEADER);
+        assert!(parse(&text).is_err());
+    }
+
+    #[test]
+    fn test_resume_mismatch() {
+        let checkpoint: Checkpoint<PlayFairKey> = Checkpoint::new("genetic", 1, "ABCD");
+        let resume =
+            |solver, seed| Checkpoint::resume(Some(checkpoint.clone()), solver, seed, "ABCD");
+        assert!(resume("genetic", 1).is_ok());
+        assert!(resume("hill_climbing", 1).is_err());
+        assert!(resume("genetic", 2).is_err());
+    }
+}
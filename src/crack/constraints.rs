@@ -0,0 +1,364 @@
+//! Constraints on the key square known beforehand, e.g. from a historical
+//! record or from a puzzle's hints. Handed to a solver, only squares
+//! satisfying all of them are searched, which narrows the key space a lot.
+//!
+
+use super::{solver_options, Rng};
+use crate::errors::InvalidOptionError;
+use crate::options::PayloadOptions;
+use crate::playfair::{PlayFairKey, ROW_LENGTH};
+
+const LENGTH: usize = ROW_LENGTH as usize;
+
+/// Known facts about a key square, see KeyConstraints::builder.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConstraints {
+    cells: [Option<char>; 25],
+    // Words shorter than a row, the row given but not the column
+    row_words: Vec<(Vec<char>, usize)>,
+    options: PayloadOptions,
+}
+
+/// Builder for KeyConstraints.
+///
+#[derive(Debug, Clone, Default)]
+pub struct KeyConstraintsBuilder {
+    letters: Vec<(char, usize, usize)>,
+    prefixes: Vec<String>,
+    row_words: Vec<(String, usize)>,
+    options: PayloadOptions,
+}
+
+impl KeyConstraintsBuilder {
+    /// The letter is at the given row and column, both counted from 0.
+    pub fn letter_at(mut self, letter: char, row: usize, col: usize) -> Self {
+        self.letters.push((letter, row, col));
+        self
+    }
+
+    /// The keyword the square was built from starts with the prefix, so
+    /// the letters of the prefix fill the first cells of the square.
+    pub fn keyword_prefix(mut self, prefix: &str) -> Self {
+        self.prefixes.push(String::from(prefix));
+        self
+    }
+
+    /// The word is read in the row, counted from 0, from left to right. A
+    /// word shorter than a row may start at any column.
+    pub fn word_in_row(mut self, word: &str, row: usize) -> Self {
+        self.row_words.push((String::from(word), row));
+        self
+    }
+
+    /// Options of the square, above all the omitted letter. Must be the
+    /// same as the ones of the solver.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Checks the constraints, fails if they are out of the square or
+    /// contradict each other.
+    pub fn build(self) -> Result<KeyConstraints, InvalidOptionError> {
+        let options = self.options;
+        let mut constraints = KeyConstraints::none(options);
+        for (letter, row, col) in self.letters {
+            let letters = letters_of(&letter.to_string(), &options)?;
+            check_line(row)?;
+            check_line(col)?;
+            constraints.fix(row * LENGTH + col, letters[0])?;
+        }
+        for prefix in self.prefixes {
+            let mut letters = letters_of(&prefix, &options)?;
+            dedup_keeping_order(&mut letters);
+            for (cell, letter) in letters.into_iter().take(25).enumerate() {
+                constraints.fix(cell, letter)?;
+            }
+        }
+        for (word, row) in self.row_words {
+            let letters = letters_of(&word, &options)?;
+            check_line(row)?;
+            let mut distinct = letters.clone();
+            dedup_keeping_order(&mut distinct);
+            if letters.len() > LENGTH || distinct.len() != letters.len() {
+                return Err(InvalidOptionError::new(format!(
+                    "Word '{}' must consist of at most {} distinct letters to fit in a row",
+                    word, LENGTH
+                )));
+            }
+            match letters.len() {
+                LENGTH => {
+                    for (col, letter) in letters.into_iter().enumerate() {
+                        constraints.fix(row * LENGTH + col, letter)?;
+                    }
+                }
+                _ => constraints.row_words.push((letters, row)),
+            }
+        }
+        if constraints.place_row_words(&mut None).is_none() {
+            return Err(InvalidOptionError::new(String::from(
+                "The words can't be placed in their rows along with the other constraints",
+            )));
+        }
+        Ok(constraints)
+    }
+}
+
+impl KeyConstraints {
+    /// Returns a builder to collect the constraints.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::constraints::KeyConstraints, playfair::PlayFairKey};
+    ///
+    /// let constraints = KeyConstraints::builder()
+    ///     .keyword_prefix("play")
+    ///     .letter_at('E', 1, 2)
+    ///     .word_in_row("BCD", 2)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(constraints.satisfied_by(&PlayFairKey::new("playfair example")));
+    /// assert!(!constraints.satisfied_by(&PlayFairKey::new("monarchy")));
+    /// assert!(KeyConstraints::builder().letter_at('E', 0, 0).letter_at('E', 0, 1).build().is_err());
+    /// ```
+    pub fn builder() -> KeyConstraintsBuilder {
+        KeyConstraintsBuilder::default()
+    }
+
+    pub fn options(&self) -> &PayloadOptions {
+        &self.options
+    }
+
+    // No constraints at all, any square satisfies them.
+    pub(crate) fn none(options: PayloadOptions) -> Self {
+        KeyConstraints {
+            cells: [None; 25],
+            row_words: Vec::new(),
+            options,
+        }
+    }
+
+    // The constraints handed to a solver builder, which must have the same
+    // options as the solver. They're taken with the options of the solver's
+    // keys then.
+    pub(crate) fn for_solver(
+        constraints: Option<KeyConstraints>,
+        options: PayloadOptions,
+    ) -> Result<Self, InvalidOptionError> {
+        match constraints {
+            Some(constraints) if constraints.options != options => Err(InvalidOptionError::new(
+                String::from("Constraints must have the same options as the solver"),
+            )),
+            Some(constraints) => Ok(KeyConstraints {
+                options: solver_options(options),
+                ..constraints
+            }),
+            None => Ok(KeyConstraints::none(solver_options(options))),
+        }
+    }
+
+    /// Whether the square of the key satisfies all constraints.
+    pub fn satisfied_by(&self, key: &PlayFairKey) -> bool {
+        let square = key.square();
+        self.cells
+            .iter()
+            .zip(square)
+            .all(|(cell, c)| cell.is_none_or(|letter| letter == *c))
+            && self.row_words.iter().all(|(word, row)| {
+                square[row * LENGTH..(row + 1) * LENGTH]
+                    .windows(word.len())
+                    .any(|window| window == word.as_slice())
+            })
+    }
+
+    // A random square satisfying all constraints.
+    pub(crate) fn random_key(&self, rng: &mut Rng) -> PlayFairKey {
+        let mut square = self
+            .place_row_words(&mut Some(&mut *rng))
+            // The words can be placed, see build
+            .expect("row words can be placed");
+        let mut rest: Vec<char> = self
+            .options
+            .alphabet()
+            .filter(|c| !square.contains(&Some(*c)))
+            .collect();
+        rng.shuffle(&mut rest);
+        for (cell, c) in square.iter_mut().filter(|c| c.is_none()).zip(rest) {
+            *cell = Some(c);
+        }
+        PlayFairKey::from_square_with_options(square.map(|c| c.unwrap_or('*')), self.options)
+    }
+
+    fn fix(&mut self, cell: usize, letter: char) -> Result<(), InvalidOptionError> {
+        match (
+            self.cells[cell],
+            self.cells.iter().position(|c| *c == Some(letter)),
+        ) {
+            (Some(fixed), _) if fixed != letter => Err(InvalidOptionError::new(format!(
+                "Cell {} can't hold both '{}' and '{}'",
+                cell, fixed, letter
+            ))),
+            (_, Some(other)) if other != cell => Err(InvalidOptionError::new(format!(
+                "'{}' can't be in both cell {} and cell {}",
+                letter, other, cell
+            ))),
+            _ => {
+                self.cells[cell] = Some(letter);
+                Ok(())
+            }
+        }
+    }
+
+    // The fixed cells along with the row words placed, None if they don't
+    // fit. Given a random generator the columns of the words are tried in
+    // random order, in ascending order otherwise.
+    fn place_row_words(&self, rng: &mut Option<&mut Rng>) -> Option<[Option<char>; 25]> {
+        let mut square = self.cells;
+        self.place(0, &mut square, rng).then_some(square)
+    }
+
+    fn place(
+        &self,
+        index: usize,
+        square: &mut [Option<char>; 25],
+        rng: &mut Option<&mut Rng>,
+    ) -> bool {
+        let Some((word, row)) = self.row_words.get(index) else {
+            return true;
+        };
+        let mut columns: Vec<usize> = (0..=LENGTH - word.len()).collect();
+        if let Some(rng) = rng {
+            rng.shuffle(&mut columns);
+        }
+        for col in columns {
+            let start = row * LENGTH + col;
+            let fits = word.iter().enumerate().all(|(offset, letter)| {
+                match (
+                    square[start + offset],
+                    square.iter().position(|c| *c == Some(*letter)),
+                ) {
+                    (Some(c), _) => c == *letter,
+                    (None, Some(_)) => false,
+                    (None, None) => true,
+                }
+            });
+            if !fits {
+                continue;
+            }
+            let before = *square;
+            for (offset, letter) in word.iter().enumerate() {
+                square[start + offset] = Some(*letter);
+            }
+            if self.place(index + 1, square, rng) {
+                return true;
+            }
+            *square = before;
+        }
+        false
+    }
+}
+
+// The letters of the text as they are in the square, fails if there are none.
+fn letters_of(text: &str, options: &PayloadOptions) -> Result<Vec<char>, InvalidOptionError> {
+    let letters: Vec<char> = crate::normalize(text, options).chars().collect();
+    match letters.is_empty() {
+        true => Err(InvalidOptionError::new(format!(
+            "'{}' contains no letter of the square",
+            text
+        ))),
+        false => Ok(letters),
+    }
+}
+
+fn check_line(line: usize) -> Result<(), InvalidOptionError> {
+    match line < LENGTH {
+        true => Ok(()),
+        false => Err(InvalidOptionError::new(format!(
+            "Rows and columns are counted from 0 to {} - got {}",
+            LENGTH - 1,
+            line
+        ))),
+    }
+}
+
+fn dedup_keeping_order(letters: &mut Vec<char>) {
+    let mut seen = Vec::with_capacity(letters.len());
+    letters.retain(|c| match seen.contains(c) {
+        true => false,
+        false => {
+            seen.push(*c);
+            true
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(KeyConstraints::builder()
+            .letter_at('A', 5, 0)
+            .build()
+            .is_err());
+        assert!(KeyConstraints::builder()
+            .letter_at('1', 0, 0)
+            .build()
+            .is_err());
+        assert!(KeyConstraints::builder()
+            .word_in_row("ROOT", 0)
+            .build()
+            .is_err());
+        assert!(KeyConstraints::builder()
+            .word_in_row("ROYALS", 0)
+            .build()
+            .is_err());
+        // The prefix fixes M at cell 0
+        assert!(KeyConstraints::builder()
+            .keyword_prefix("MON")
+            .letter_at('A', 0, 0)
+            .build()
+            .is_err());
+        // Two words of three letters don't fit in the same row
+        assert!(KeyConstraints::builder()
+            .word_in_row("ABC", 1)
+            .word_in_row("XYZ", 1)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_omitted_letter_substituted() {
+        let constraints = KeyConstraints::builder()
+            .word_in_row("jam", 0)
+            .build()
+            .unwrap();
+        assert!(constraints.satisfied_by(&PlayFairKey::new("iam")));
+    }
+
+    #[test]
+    fn test_random_key_satisfies() {
+        let constraints = KeyConstraints::builder()
+            .keyword_prefix("king")
+            .word_in_row("ROYAL", 3)
+            .word_in_row("EF", 2)
+            .letter_at('Z', 4, 4)
+            .build()
+            .unwrap();
+        let mut rng = Rng::new(11);
+        for _ in 0..50 {
+            let key = constraints.random_key(&mut rng);
+            let mut letters = key.square().to_vec();
+            letters.sort();
+            assert_eq!(
+                letters,
+                PayloadOptions::default().alphabet().collect::<Vec<char>>()
+            );
+            assert!(constraints.satisfied_by(&key));
+        }
+    }
+}
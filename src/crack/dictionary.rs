@@ -0,0 +1,374 @@
+//! Dictionary attack on a PlayFair cipher text. Many keys are built from a
+//! plain word, so trying every word of a word list as keyword is often way
+//! faster than searching the whole key space. Optionally common prefixes
+//! and suffixes are added to each word as well.
+//!
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use super::{rate, solver_options, CancellationToken, Candidate, Stop};
+use crate::analysis::scoring::FitnessScorer;
+use crate::errors::InvalidOptionError;
+use crate::options::PayloadOptions;
+use crate::playfair::PlayFairKey;
+
+const PREFIXES: [&str; 3] = ["THE", "MY", "A"];
+const SUFFIXES: [&str; 5] = ["S", "ES", "ED", "ER", "ING"];
+
+/// A keyword of the word list together with what it decrypts the cipher text to.
+///
+#[derive(Debug, Clone)]
+pub struct DictionaryHit {
+    pub(crate) keyword: String,
+    pub(crate) candidate: Candidate<PlayFairKey>,
+}
+
+impl DictionaryHit {
+    /// The keyword as tried, including an added prefix or suffix.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn candidate(&self) -> &Candidate<PlayFairKey> {
+        &self.candidate
+    }
+}
+
+/// A keyword of the word list generating a given square, see
+/// DictionaryAttack::reconstruct_keyword.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordMatch {
+    pub(crate) keyword: String,
+    pub(crate) row_shift: usize,
+    pub(crate) column_shift: usize,
+}
+
+impl KeywordMatch {
+    /// The keyword as tried, including an added prefix or suffix.
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    /// Number of rows the square of the keyword is rotated up to get the
+    /// square given.
+    pub fn row_shift(&self) -> usize {
+        self.row_shift
+    }
+
+    /// Number of columns the square of the keyword is rotated left to get
+    /// the square given.
+    pub fn column_shift(&self) -> usize {
+        self.column_shift
+    }
+}
+
+/// Dictionary attack on PlayFair cipher texts, see DictionaryAttack::builder.
+///
+#[derive(Debug, Clone)]
+pub struct DictionaryAttack {
+    hits: usize,
+    affixes: bool,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+/// Builder for a DictionaryAttack.
+///
+#[derive(Debug, Clone)]
+pub struct DictionaryAttackBuilder {
+    hits: usize,
+    affixes: bool,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+impl Default for DictionaryAttackBuilder {
+    fn default() -> Self {
+        DictionaryAttackBuilder {
+            hits: 10,
+            affixes: false,
+            options: PayloadOptions::default(),
+            cancellation: CancellationToken::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl DictionaryAttackBuilder {
+    /// Number of best keywords returned. Defaults to 10.
+    pub fn hits(mut self, hits: usize) -> Self {
+        self.hits = hits;
+        self
+    }
+
+    /// Tries each word with common prefixes (THE, MY, A) and suffixes (S,
+    /// ES, ED, ER, ING) too. Off by default.
+    pub fn affixes(mut self, affixes: bool) -> Self {
+        self.affixes = affixes;
+        self
+    }
+
+    /// Options of the keys tried, above all the omitted letter.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Token to stop the attack early from another thread. None by default.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Time after which the attack stops and returns the best hits found so
+    /// far. Unlimited by default.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn build(self) -> Result<DictionaryAttack, InvalidOptionError> {
+        if self.hits == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Hits must be greater than 0",
+            )));
+        }
+        Ok(DictionaryAttack {
+            hits: self.hits,
+            affixes: self.affixes,
+            options: solver_options(self.options),
+            cancellation: self.cancellation,
+            time_limit: self.time_limit,
+        })
+    }
+}
+
+impl DictionaryAttack {
+    /// Returns a builder to configure the attack.
+    pub fn builder() -> DictionaryAttackBuilder {
+        DictionaryAttackBuilder::default()
+    }
+
+    /// Tries every word as keyword and returns the best hits, the one with
+    /// the highest fitness first. Words yielding the same square as a word
+    /// tried before are skipped. A cancelled attack returns the best hits of
+    /// the words tried so far.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::dictionary::DictionaryAttack, playfair::PlayFairKey};
+    /// use playfair_cipher::{analysis::scoring::score, cryptable::Cypher};
+    ///
+    /// let crypt = PlayFairKey::new("examples").encrypt("meet me at the old bridge").unwrap();
+    /// let attack = DictionaryAttack::builder().hits(3).affixes(true).build().unwrap();
+    /// let hits = attack.crack(&crypt, ["monarchy", "example", "playfair"], score);
+    /// assert_eq!(hits[0].keyword(), "EXAMPLES");
+    /// assert_eq!(hits[0].candidate().plain_text(), "MEETMEATTHEOLDBRIDGE");
+    /// ```
+    pub fn crack<S: AsRef<str>>(
+        &self,
+        cipher_text: &str,
+        words: impl IntoIterator<Item = S>,
+        fitness: impl FitnessScorer,
+    ) -> Vec<DictionaryHit> {
+        let cipher_text = crate::normalize(cipher_text, &self.options);
+        let mut tried: HashSet<[char; 25]> = HashSet::new();
+        let mut hits: Vec<DictionaryHit> = Vec::new();
+        let stop = Stop::new(&self.cancellation, self.time_limit);
+        for word in words {
+            if stop.reached() {
+                break;
+            }
+            let word = word.as_ref().trim().to_uppercase();
+            if word.is_empty() {
+                continue;
+            }
+            for keyword in self.keywords(&word) {
+                let key = PlayFairKey::with_options(&keyword, self.options);
+                if !tried.insert(key.key) {
+                    continue;
+                }
+                hits.push(DictionaryHit {
+                    keyword,
+                    candidate: rate(key, &cipher_text, &fitness),
+                });
+                // Keeps the memory bounded for long word lists
+                if hits.len() >= 2 * self.hits {
+                    self.rank(&mut hits);
+                }
+            }
+        }
+        self.rank(&mut hits);
+        hits
+    }
+
+    /// Searches the words for keywords generating the square, e.g. one
+    /// recovered by a solver, so the keyword can be reported instead of the
+    /// bare square. The squares are compared with the options of the key.
+    ///
+    /// Rotating the rows or columns of a square doesn't change the cipher,
+    /// so a solver may find any of the 25 rotations of the square built
+    /// from the keyword. A match tells the rotation as well.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::dictionary::DictionaryAttack, playfair::PlayFairKey};
+    ///
+    /// // The square of "monarchy" with the first row moved to the bottom
+    /// let found = PlayFairKey::from_matrix_str(
+    ///     "C H Y B D
+    ///      E F G I K
+    ///      L P Q S T
+    ///      U V W X Z
+    ///      M O N A R",
+    /// )
+    /// .unwrap();
+    /// let attack = DictionaryAttack::builder().build().unwrap();
+    /// let matches = attack.reconstruct_keyword(&found, ["playfair", "monarchy", "rust"]);
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].keyword(), "MONARCHY");
+    /// assert_eq!((matches[0].row_shift(), matches[0].column_shift()), (1, 0));
+    /// ```
+    pub fn reconstruct_keyword<S: AsRef<str>>(
+        &self,
+        square: &PlayFairKey,
+        words: impl IntoIterator<Item = S>,
+    ) -> Vec<KeywordMatch> {
+        let mut rotations: HashMap<[char; 25], (usize, usize)> = HashMap::new();
+        for row_shift in 0..5 {
+            for column_shift in 0..5 {
+                let mut rotated = ['*'; 25];
+                for (idx, cell) in rotated.iter_mut().enumerate() {
+                    let (row, column) = (idx / 5, idx % 5);
+                    // The cell of the keyword square which ends up here
+                    *cell = square.key
+                        [((row + 5 - row_shift) % 5) * 5 + (column + 5 - column_shift) % 5];
+                }
+                rotations
+                    .entry(rotated)
+                    .or_insert((row_shift, column_shift));
+            }
+        }
+        let mut tried: HashSet<String> = HashSet::new();
+        let mut matches = Vec::new();
+        for word in words {
+            let word = word.as_ref().trim().to_uppercase();
+            if word.is_empty() {
+                continue;
+            }
+            for keyword in self.keywords(&word) {
+                if !tried.insert(keyword.clone()) {
+                    continue;
+                }
+                let key = PlayFairKey::with_options(&keyword, *square.options());
+                if let Some((row_shift, column_shift)) = rotations.get(&key.key) {
+                    matches.push(KeywordMatch {
+                        keyword,
+                        row_shift: *row_shift,
+                        column_shift: *column_shift,
+                    });
+                }
+            }
+        }
+        matches
+    }
+
+    fn keywords(&self, word: &str) -> Vec<String> {
+        let mut keywords = vec![String::from(word)];
+        if self.affixes {
+            keywords.extend(PREFIXES.iter().map(|prefix| format!("{}{}", prefix, word)));
+            keywords.extend(SUFFIXES.iter().map(|suffix| format!("{}{}", word, suffix)));
+        }
+        keywords
+    }
+
+    fn rank(&self, hits: &mut Vec<DictionaryHit>) {
+        hits.sort_by(|a, b| b.candidate.score.total_cmp(&a.candidate.score));
+        hits.truncate(self.hits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::scoring::score;
+    use crate::cryptable::Cypher;
+    use crate::options::FinalDigram;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(DictionaryAttack::builder().hits(0).build().is_err());
+    }
+
+    #[test]
+    fn test_crack_ranked() {
+        let crypt = PlayFairKey::new("monarchy")
+            .encrypt("we shall meet at the bridge at midnight")
+            .unwrap();
+        let attack = DictionaryAttack::builder().hits(2).build().unwrap();
+        let words = "playfair\n\nexample\nmonarchy\nmonarch\nrust\n";
+        let hits = attack.crack(&crypt, words.lines(), score);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].keyword(), "MONARCHY");
+        assert!(hits[0].candidate().score() >= hits[1].candidate().score());
+    }
+
+    #[test]
+    fn test_crack_odd_length_strict() {
+        let options = PayloadOptions::default().with_final_digram(FinalDigram::Strict);
+        let attack = DictionaryAttack::builder()
+            .options(options)
+            .build()
+            .unwrap();
+        let hits = attack.crack("BMODZBXDNAG", ["playfair", "monarchy"], score);
+        assert!(hits
+            .iter()
+            .all(|hit| hit.candidate().plain_text().ends_with('G')));
+    }
+
+    #[test]
+    fn test_crack_stopped() {
+        let crypt = PlayFairKey::new("monarchy")
+            .encrypt("attack at dawn")
+            .unwrap();
+        let attack = DictionaryAttack::builder()
+            .time_limit(Duration::ZERO)
+            .build()
+            .unwrap();
+        assert!(attack.crack(&crypt, ["monarchy"], score).is_empty());
+    }
+
+    #[test]
+    fn test_reconstruct_keyword() {
+        let attack = DictionaryAttack::builder().affixes(true).build().unwrap();
+        let square =
+            PlayFairKey::new("examples").mutated(crate::playfair::KeyMutation::SwapColumns(0, 4));
+        // Swapping columns is no rotation
+        assert!(attack.reconstruct_keyword(&square, ["example"]).is_empty());
+        let square = PlayFairKey::new("examples");
+        let matches = attack.reconstruct_keyword(&square, ["example", "examples"]);
+        let keywords: Vec<&str> = matches.iter().map(KeywordMatch::keyword).collect();
+        // Repeated letters don't change the square
+        assert_eq!(
+            keywords,
+            ["EXAMPLES", "EXAMPLEES", "EXAMPLESS", "EXAMPLESES"]
+        );
+        assert_eq!(matches[0].row_shift(), 0);
+    }
+
+    #[test]
+    fn test_crack_skips_same_square() {
+        let attack = DictionaryAttack::builder().build().unwrap();
+        // ROOT and ROT yield the same square
+        let hits = attack.crack("ABCD", ["root", "rot"], score);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].keyword(), "ROOT");
+    }
+}
@@ -0,0 +1,275 @@
+//! Recovery of the two keyed squares of a Four Square cipher. Each plain
+//! letter takes its row from one keyed square and its column from the
+//! other, so changing one square only pays off together with the other.
+//! Mutating both at random performs poorly, instead the solver optimizes
+//! them in turns: climbing on the top right square while the bottom left
+//! one is fixed, then the other way round, for several rounds.
+//!
+
+use std::time::Duration;
+
+use super::checkpoint::{Checkpoint, Recorder};
+use super::{
+    best_of, random_key, random_mutation, rate, solver_options, CancellationToken, Candidate,
+    Progress, Ranking, Rng, Solver, Stop, Tracker,
+};
+use crate::analysis::scoring::FitnessScorer;
+use crate::errors::{CheckpointError, InvalidOptionError};
+use crate::four_square::FourSquare;
+use crate::options::PayloadOptions;
+
+/// Four Square solver, see FourSquareSolver::builder.
+///
+#[derive(Debug, Clone)]
+pub struct FourSquareSolver {
+    restarts: usize,
+    rounds: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+/// Builder for a FourSquareSolver.
+///
+#[derive(Debug, Clone)]
+pub struct FourSquareSolverBuilder {
+    restarts: usize,
+    rounds: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+impl Default for FourSquareSolverBuilder {
+    fn default() -> Self {
+        FourSquareSolverBuilder {
+            restarts: 10,
+            rounds: 6,
+            iterations: 3_000,
+            seed: 0,
+            candidates: 5,
+            options: PayloadOptions::default(),
+            cancellation: CancellationToken::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl FourSquareSolverBuilder {
+    /// Number of searches, each starting from fresh random squares. Defaults to 10.
+    pub fn restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Number of turns each square is optimized per search. Defaults to 6.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Number of mutations tried per square and round. Defaults to 3000.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Seed of the random squares and mutations. The same seed yields the
+    /// same result. Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of best candidates with distinct plain texts returned. Defaults to 5.
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Options of the squares tried, above all the omitted letter.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Token to stop the solver early from another thread. None by default.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Time after which a crack stops and returns the best candidates found
+    /// so far. Unlimited by default.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn build(self) -> Result<FourSquareSolver, InvalidOptionError> {
+        if self.restarts == 0 || self.rounds == 0 || self.iterations == 0 || self.candidates == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Restarts, rounds, iterations and candidates must be greater than 0",
+            )));
+        }
+        Ok(FourSquareSolver {
+            restarts: self.restarts,
+            rounds: self.rounds,
+            iterations: self.iterations,
+            seed: self.seed,
+            candidates: self.candidates,
+            options: solver_options(self.options),
+            cancellation: self.cancellation,
+            time_limit: self.time_limit,
+        })
+    }
+}
+
+impl FourSquareSolver {
+    /// Returns a builder to configure the solver.
+    pub fn builder() -> FourSquareSolverBuilder {
+        FourSquareSolverBuilder::default()
+    }
+
+    // Returns the best squares of the search along with the number of
+    // squares tried.
+    fn search(
+        &self,
+        cipher_text: &str,
+        fitness: &impl FitnessScorer,
+        rng: &mut Rng,
+        stop: &Stop,
+    ) -> (Ranking<FourSquare>, usize) {
+        let top_right = random_key(rng, self.options);
+        let bottom_left = random_key(rng, self.options);
+        let mut current = rate(
+            FourSquare::from_keys(top_right, bottom_left, self.options),
+            cipher_text,
+            fitness,
+        );
+        let mut ranking = Ranking::new(self.candidates);
+        ranking.insert(&current);
+        let mut tried = 0;
+        for round in 0..2 * self.rounds {
+            let top = round % 2 == 0;
+            for _ in 0..self.iterations {
+                if stop.reached() {
+                    return (ranking, tried);
+                }
+                tried += 1;
+                let mutation = random_mutation(rng);
+                let (top_right, bottom_left) = match top {
+                    true => (
+                        current.key.top_right().mutated(mutation),
+                        current.key.bottom_left().clone(),
+                    ),
+                    false => (
+                        current.key.top_right().clone(),
+                        current.key.bottom_left().mutated(mutation),
+                    ),
+                };
+                let candidate = rate(
+                    FourSquare::from_keys(top_right, bottom_left, self.options),
+                    cipher_text,
+                    fitness,
+                );
+                // Accepting equal scores lets the search move along plateaus
+                if candidate.score >= current.score {
+                    current = candidate;
+                    ranking.insert(&current);
+                }
+            }
+        }
+        (ranking, tried)
+    }
+}
+
+impl Solver for FourSquareSolver {
+    type Key = FourSquare;
+
+    /// Searches the squares which decrypt the cipher text to the plain
+    /// texts with the highest fitness.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::four_square::FourSquareSolver, four_square::FourSquare};
+    /// use playfair_cipher::{crack::Solver, cryptable::Cypher};
+    ///
+    /// let crypt = FourSquare::new("EXAMPLE", "KEYWORD").encrypt("help me obi wan kenobi").unwrap();
+    /// let solver = FourSquareSolver::builder().restarts(2).build().unwrap();
+    /// let ranked = solver.crack(&crypt, |plain: &str| {
+    ///     plain.chars().zip("HELPMEOBIWANKENOBI".chars()).filter(|(a, b)| a == b).count() as f64
+    /// });
+    /// let best = &ranked[0];
+    /// assert_eq!(best.plain_text(), "HELPMEOBIWANKENOBI");
+    /// ```
+    fn crack_resumable(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+        resume: Option<Checkpoint<FourSquare>>,
+        checkpoint: impl Fn(&Checkpoint<FourSquare>) + Sync,
+    ) -> Result<Vec<Candidate<FourSquare>>, CheckpointError> {
+        let cipher_text = crate::normalize(cipher_text, &self.options);
+        let resume = Checkpoint::resume(resume, "four_square", self.seed, &cipher_text)?;
+        let tracker = Tracker::new(self.restarts * 2 * self.rounds * self.iterations, &progress)
+            .resumed(&resume);
+        let recorder = Recorder::new(resume, self.candidates, &checkpoint);
+        let stop = Stop::new(&self.cancellation, self.time_limit);
+        Ok(best_of(
+            self.restarts,
+            2 * self.rounds * self.iterations,
+            &recorder,
+            |rng| {
+                let (searched, tried) = self.search(&cipher_text, &fitness, rng, &stop);
+                tracker.advance(tried, searched.best());
+                (searched, tried)
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(FourSquareSolver::builder().rounds(0).build().is_err());
+        assert!(FourSquareSolver::builder().restarts(0).build().is_err());
+        assert!(FourSquareSolver::builder().iterations(0).build().is_err());
+    }
+
+    #[test]
+    fn test_crack_known_plain_text() {
+        let fsq = FourSquare::new("EXAMPLE", "KEYWORD");
+        let crypt = fsq
+            .encrypt("the quick brown fox jumps over the lazy dog")
+            .unwrap();
+        let plain = fsq.decrypt(&crypt).unwrap();
+        let solver = FourSquareSolver::builder()
+            .restarts(2)
+            .seed(3)
+            .build()
+            .unwrap();
+        let ranked = solver.crack(&crypt, |text: &str| {
+            text.chars()
+                .zip(plain.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        });
+        let best = &ranked[0];
+        assert_eq!(best.plain_text(), plain);
+        assert_eq!(best.key().encrypt(&plain).unwrap(), crypt);
+    }
+}
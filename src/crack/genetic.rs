@@ -0,0 +1,441 @@
+//! Genetic search for the key of a PlayFair cipher text. A population of
+//! squares is bred over generations: the fittest squares are kept as they
+//! are, the others are replaced by children of squares picked in
+//! tournaments. A child takes whole rows or columns of one parent, the
+//! remaining letters in the order of the other parent, and is mutated a
+//! little. Rows and columns are the blocks a PlayFair square encrypts with,
+//! so they are worth passing on as a whole.
+//!
+
+use std::time::Duration;
+
+use super::checkpoint::Checkpoint;
+use super::constraints::KeyConstraints;
+use super::{
+    random_mutation, rate_all, solver_options, CancellationToken, Candidate, Progress, Ranking,
+    Rng, Solver, Stop, Tracker,
+};
+use crate::analysis::scoring::FitnessScorer;
+use crate::errors::{CheckpointError, InvalidOptionError};
+use crate::options::PayloadOptions;
+use crate::playfair::{PlayFairKey, ROW_LENGTH};
+
+// Number of squares competing for being a parent
+const TOURNAMENT_SIZE: usize = 3;
+
+/// Genetic key search, see GeneticSolver::builder.
+///
+#[derive(Debug, Clone)]
+pub struct GeneticSolver {
+    population: usize,
+    generations: usize,
+    elite: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    constraints: KeyConstraints,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+/// Builder for a GeneticSolver.
+///
+#[derive(Debug, Clone)]
+pub struct GeneticSolverBuilder {
+    population: usize,
+    generations: usize,
+    elite: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    constraints: Option<KeyConstraints>,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+impl Default for GeneticSolverBuilder {
+    fn default() -> Self {
+        GeneticSolverBuilder {
+            population: 200,
+            generations: 300,
+            elite: 10,
+            seed: 0,
+            candidates: 5,
+            options: PayloadOptions::default(),
+            constraints: None,
+            cancellation: CancellationToken::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl GeneticSolverBuilder {
+    /// Number of squares per generation. Defaults to 200.
+    pub fn population(mut self, population: usize) -> Self {
+        self.population = population;
+        self
+    }
+
+    /// Number of generations bred. Defaults to 300.
+    pub fn generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// Number of the fittest squares passed on to the next generation
+    /// unchanged. Must be less than the population. Defaults to 10.
+    pub fn elite(mut self, elite: usize) -> Self {
+        self.elite = elite;
+        self
+    }
+
+    /// Seed of the random squares, selections and mutations. The same seed
+    /// yields the same result. Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of best candidates with distinct plain texts returned. Defaults to 5.
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Options of the squares bred, above all the omitted letter.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Searches only keys satisfying the constraints, which must have the
+    /// same options as the solver. None by default.
+    pub fn constraints(mut self, constraints: KeyConstraints) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    /// Token to stop the solver early from another thread. None by default.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Time after which a crack stops and returns the best candidates found
+    /// so far. Unlimited by default.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn build(self) -> Result<GeneticSolver, InvalidOptionError> {
+        if self.population == 0 || self.generations == 0 || self.candidates == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Population, generations and candidates must be greater than 0",
+            )));
+        }
+        if self.elite >= self.population {
+            return Err(InvalidOptionError::new(format!(
+                "Elite {} must be less than the population {}",
+                self.elite, self.population
+            )));
+        }
+        let constraints = KeyConstraints::for_solver(self.constraints, self.options)?;
+        Ok(GeneticSolver {
+            population: self.population,
+            generations: self.generations,
+            elite: self.elite,
+            seed: self.seed,
+            candidates: self.candidates,
+            options: solver_options(self.options),
+            constraints,
+            cancellation: self.cancellation,
+            time_limit: self.time_limit,
+        })
+    }
+}
+
+impl GeneticSolver {
+    /// Returns a builder to configure the solver.
+    pub fn builder() -> GeneticSolverBuilder {
+        GeneticSolverBuilder::default()
+    }
+
+    // The fittest of a few randomly picked squares
+    fn select<'a>(
+        &self,
+        population: &'a [Candidate<PlayFairKey>],
+        rng: &mut Rng,
+    ) -> &'a PlayFairKey {
+        let fittest = (0..TOURNAMENT_SIZE)
+            .map(|_| &population[rng.below(population.len())])
+            .max_by(|a, b| a.score.total_cmp(&b.score))
+            .expect("tournament is not empty");
+        &fittest.key
+    }
+
+    // Takes the population bred into the checkpoint.
+    fn record(
+        &self,
+        state: &mut Checkpoint<PlayFairKey>,
+        population: Vec<Candidate<PlayFairKey>>,
+        rng: &Rng,
+        tried: usize,
+    ) {
+        let mut ranking = Ranking::new(self.candidates);
+        for candidate in &population {
+            ranking.insert(candidate);
+        }
+        state.candidates = ranking.into_vec();
+        state.population = population;
+        state.rng = Some(rng.state());
+        state.iterations += tried;
+    }
+}
+
+impl Solver for GeneticSolver {
+    type Key = PlayFairKey;
+
+    /// Breeds the squares which decrypt the cipher text to the plain texts
+    /// with the highest fitness.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::genetic::GeneticSolver, playfair::PlayFairKey};
+    /// use playfair_cipher::{crack::Solver, cryptable::Cypher};
+    ///
+    /// let crypt = PlayFairKey::new("playfair example").encrypt("hide the gold").unwrap();
+    /// let solver = GeneticSolver::builder().seed(2).build().unwrap();
+    /// let ranked = solver.crack(&crypt, |plain: &str| {
+    ///     plain.chars().zip("HIDETHEGOLDX".chars()).filter(|(a, b)| a == b).count() as f64
+    /// });
+    /// let best = &ranked[0];
+    /// assert_eq!(best.plain_text(), "HIDETHEGOLDX");
+    /// ```
+    fn crack_resumable(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+        resume: Option<Checkpoint<PlayFairKey>>,
+        checkpoint: impl Fn(&Checkpoint<PlayFairKey>) + Sync,
+    ) -> Result<Vec<Candidate<PlayFairKey>>, CheckpointError> {
+        let cipher_text = crate::normalize(cipher_text, &self.options);
+        let mut state = Checkpoint::resume(resume, "genetic", self.seed, &cipher_text)?;
+        let children = self.population - self.elite;
+        let tracker =
+            Tracker::new(self.population + self.generations * children, &progress).resumed(&state);
+        let stop = Stop::new(&self.cancellation, self.time_limit);
+        let mut rng = match state.rng {
+            Some(rng) if state.population.len() == self.population => Rng::from_state(rng),
+            Some(_) => {
+                return Err(CheckpointError::Mismatch(format!(
+                    "Checkpoint was taken with a population of {} - resumed with {}",
+                    state.population.len(),
+                    self.population
+                )))
+            }
+            None => {
+                let mut rng = Rng::new(self.seed);
+                let keys = (0..self.population)
+                    .map(|_| self.constraints.random_key(&mut rng))
+                    .collect();
+                let mut population = rate_all(keys, &cipher_text, &fitness);
+                population.sort_by(|a, b| b.score.total_cmp(&a.score));
+                tracker.advance(self.population, &population[0]);
+                self.record(&mut state, population, &rng, self.population);
+                checkpoint(&state);
+                rng
+            }
+        };
+        while state.generation < self.generations {
+            if stop.reached() {
+                break;
+            }
+            let population = &state.population;
+            // Breeding is sequential, so the result only depends on the
+            // seed, rating the children may run in parallel
+            let keys = (0..children)
+                .map(|_| {
+                    let first = self.select(population, &mut rng);
+                    let second = self.select(population, &mut rng);
+                    let child = crossover(first, second, &mut rng, self.options)
+                        .mutated(random_mutation(&mut rng));
+                    // Constraints broken by breeding are kept by passing on
+                    // the first parent instead
+                    match self.constraints.satisfied_by(&child) {
+                        true => child,
+                        false => first.clone(),
+                    }
+                })
+                .collect();
+            let mut population = std::mem::take(&mut state.population);
+            population.truncate(self.elite);
+            population.extend(rate_all(keys, &cipher_text, &fitness));
+            population.sort_by(|a, b| b.score.total_cmp(&a.score));
+            tracker.advance(children, &population[0]);
+            state.generation += 1;
+            self.record(&mut state, population, &rng, children);
+            checkpoint(&state);
+        }
+        Ok(state.candidates)
+    }
+}
+
+// A child square with random rows or columns of the first parent in place.
+// The other cells get the letters still missing, in the order they appear
+// in the second parent.
+fn crossover(
+    first: &PlayFairKey,
+    second: &PlayFairKey,
+    rng: &mut Rng,
+    options: PayloadOptions,
+) -> PlayFairKey {
+    let length = ROW_LENGTH as usize;
+    let by_rows = rng.below(2) == 0;
+    let lines = rng.next_u64();
+    let mut square = ['*'; 25];
+    for (cell, c) in square.iter_mut().enumerate() {
+        let line = match by_rows {
+            true => cell / length,
+            false => cell % length,
+        };
+        if (lines >> line) & 1 == 1 {
+            *c = first.key[cell];
+        }
+    }
+    let rest: Vec<char> = second
+        .key
+        .iter()
+        .filter(|c| !square.contains(c))
+        .copied()
+        .collect();
+    for (cell, c) in square.iter_mut().filter(|c| **c == '*').zip(rest) {
+        *cell = c;
+    }
+    PlayFairKey::from_square_with_options(square, options)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::scoring::score;
+    use crate::cryptable::Cypher;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(GeneticSolver::builder().population(0).build().is_err());
+        assert!(GeneticSolver::builder().generations(0).build().is_err());
+        assert!(GeneticSolver::builder()
+            .population(10)
+            .elite(10)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_crossover_complete() {
+        let mut rng = Rng::new(3);
+        let options = PayloadOptions::default();
+        let first = PlayFairKey::new("playfair example");
+        let second = PlayFairKey::new("monarchy");
+        for _ in 0..20 {
+            let child = crossover(&first, &second, &mut rng, options);
+            let mut letters = child.square().to_vec();
+            letters.sort();
+            assert_eq!(letters, options.alphabet().collect::<Vec<char>>());
+        }
+        // Parents alike have a child alike
+        let child = crossover(&first, &first, &mut rng, options);
+        assert_eq!(child.square(), first.square());
+    }
+
+    #[test]
+    fn test_crack_known_plain_text() {
+        let key = PlayFairKey::new("monarchy");
+        let crypt = key
+            .encrypt("we shall meet at the bridge at midnight")
+            .unwrap();
+        let plain = key.decrypt(&crypt).unwrap();
+        let solver = GeneticSolver::builder().seed(2).build().unwrap();
+        let ranked = solver.crack(&crypt, |text: &str| {
+            text.chars()
+                .zip(plain.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        });
+        let best = &ranked[0];
+        assert_eq!(best.plain_text(), plain);
+    }
+
+    #[test]
+    fn test_crack_cancelled() {
+        let token = CancellationToken::new();
+        let solver = GeneticSolver::builder()
+            .population(20)
+            .elite(2)
+            .cancellation(token.clone())
+            .build()
+            .unwrap();
+        let reported = std::sync::Mutex::new(Vec::new());
+        // Cancelled while rating the initial population, so no generation is bred
+        let ranked = solver.crack_with_progress("BMODZBXDNAGE", score, |progress: &Progress| {
+            token.cancel();
+            reported.lock().unwrap().push(progress.iterations());
+        });
+        assert_eq!(ranked.len(), 5);
+        assert_eq!(reported.into_inner().unwrap(), [20]);
+    }
+
+    #[test]
+    fn test_crack_resumed() {
+        let crypt = PlayFairKey::new("monarchy")
+            .encrypt("we shall meet at the bridge at midnight")
+            .unwrap();
+        let builder = GeneticSolver::builder()
+            .population(30)
+            .elite(3)
+            .generations(8);
+        let token = CancellationToken::new();
+        let interrupted = builder.clone().cancellation(token.clone()).build().unwrap();
+        let saved = std::sync::Mutex::new(String::new());
+        interrupted
+            .crack_resumable(
+                &crypt,
+                score,
+                |_: &Progress| {},
+                None,
+                |state: &Checkpoint<_>| {
+                    if state.generation == 3 {
+                        token.cancel();
+                    }
+                    *saved.lock().unwrap() = state.to_string();
+                },
+            )
+            .unwrap();
+        let saved = saved.into_inner().unwrap();
+        let resume = Checkpoint::parse(&saved, PayloadOptions::default()).unwrap();
+        assert_eq!(resume.generation, 3);
+
+        let solver = builder.build().unwrap();
+        let resumed = solver
+            .crack_resumable(&crypt, score, |_: &Progress| {}, Some(resume), |_: &_| {})
+            .unwrap();
+        let uninterrupted = solver.crack(&crypt, score);
+        let texts = |ranked: &[Candidate<PlayFairKey>]| {
+            ranked
+                .iter()
+                .map(|c| (c.plain_text.clone(), c.score))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&resumed), texts(&uninterrupted));
+
+        let other = GeneticSolver::builder().population(20).build().unwrap();
+        let resume = Checkpoint::parse(&saved, PayloadOptions::default()).unwrap();
+        assert!(other
+            .crack_resumable(&crypt, score, |_: &Progress| {}, Some(resume), |_: &_| {})
+            .is_err());
+    }
+}
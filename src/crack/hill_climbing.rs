@@ -0,0 +1,396 @@
+//! Hill climbing recovery of a PlayFair key. Starting from a random key,
+//! small mutations of the key are kept as long as they don't lower the
+//! score of the decrypted text. As the climb easily gets stuck on a local
+//! maximum, it's restarted several times from a fresh random key and the
+//! best key of all climbs is returned.
+//!
+
+use std::time::Duration;
+
+use super::checkpoint::{Checkpoint, Recorder};
+use super::constraints::KeyConstraints;
+use super::{
+    best_of, random_mutation, rate, solver_options, CancellationToken, Candidate, Progress,
+    Ranking, Rng, Solver, Stop, Tracker,
+};
+use crate::analysis::scoring::FitnessScorer;
+use crate::errors::{CheckpointError, InvalidOptionError};
+use crate::options::PayloadOptions;
+use crate::playfair::PlayFairKey;
+
+/// Hill climbing solver for PlayFair cipher texts, see HillClimbing::builder.
+///
+#[derive(Debug, Clone)]
+pub struct HillClimbing {
+    restarts: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    constraints: KeyConstraints,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+/// Builder for a HillClimbing solver.
+///
+#[derive(Debug, Clone)]
+pub struct HillClimbingBuilder {
+    restarts: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    options: PayloadOptions,
+    constraints: Option<KeyConstraints>,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+impl Default for HillClimbingBuilder {
+    fn default() -> Self {
+        HillClimbingBuilder {
+            restarts: 20,
+            iterations: 10_000,
+            seed: 0,
+            candidates: 5,
+            options: PayloadOptions::default(),
+            constraints: None,
+            cancellation: CancellationToken::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl HillClimbingBuilder {
+    /// Number of climbs, each starting from a fresh random key. Defaults to 20.
+    pub fn restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Number of mutations tried within each climb. Defaults to 10000.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Seed of the random keys and mutations. The same seed yields the same
+    /// result. Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of best candidates with distinct plain texts returned. Defaults to 5.
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Options of the keys tried, above all the omitted letter.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Searches only keys satisfying the constraints, which must have the
+    /// same options as the solver. None by default.
+    pub fn constraints(mut self, constraints: KeyConstraints) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    /// Token to stop the solver early from another thread. None by default.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Time after which a crack stops and returns the best candidates found
+    /// so far. Unlimited by default.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn build(self) -> Result<HillClimbing, InvalidOptionError> {
+        if self.restarts == 0 || self.iterations == 0 || self.candidates == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Restarts, iterations and candidates must be greater than 0",
+            )));
+        }
+        let constraints = KeyConstraints::for_solver(self.constraints, self.options)?;
+        Ok(HillClimbing {
+            restarts: self.restarts,
+            iterations: self.iterations,
+            seed: self.seed,
+            candidates: self.candidates,
+            options: solver_options(self.options),
+            constraints,
+            cancellation: self.cancellation,
+            time_limit: self.time_limit,
+        })
+    }
+}
+
+impl HillClimbing {
+    /// Returns a builder to configure the solver.
+    pub fn builder() -> HillClimbingBuilder {
+        HillClimbingBuilder::default()
+    }
+
+    // Returns the best keys of the climb along with the number of keys tried.
+    fn climb(
+        &self,
+        cipher_text: &str,
+        fitness: &impl FitnessScorer,
+        rng: &mut Rng,
+        stop: &Stop,
+    ) -> (Ranking<PlayFairKey>, usize) {
+        let key = self.constraints.random_key(rng);
+        let mut current = rate(key, cipher_text, fitness);
+        let mut ranking = Ranking::new(self.candidates);
+        ranking.insert(&current);
+        for tried in 0..self.iterations {
+            if stop.reached() {
+                return (ranking, tried);
+            }
+            let key = current.key.mutated(random_mutation(rng));
+            if !self.constraints.satisfied_by(&key) {
+                continue;
+            }
+            let candidate = rate(key, cipher_text, fitness);
+            // Accepting equal scores lets the climb move along plateaus
+            if candidate.score >= current.score {
+                current = candidate;
+                ranking.insert(&current);
+            }
+        }
+        (ranking, self.iterations)
+    }
+}
+
+impl Solver for HillClimbing {
+    type Key = PlayFairKey;
+
+    /// Searches the keys which decrypt the cipher text to the plain texts
+    /// with the highest fitness.
+    ///
+    /// # Example
+    ///
+    /// Knowing part of the plain text is enough to make up a fitness
+    /// function.
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::hill_climbing::HillClimbing, playfair::PlayFairKey};
+    /// use playfair_cipher::{crack::Solver, cryptable::Cypher};
+    ///
+    /// let crypt = PlayFairKey::new("playfair example").encrypt("hide the gold").unwrap();
+    /// let solver = HillClimbing::builder().seed(4).build().unwrap();
+    /// let ranked = solver.crack(&crypt, |plain: &str| {
+    ///     plain.chars().zip("HIDETHEGOLD".chars()).filter(|(a, b)| a == b).count() as f64
+    /// });
+    /// let best = &ranked[0];
+    /// assert_eq!(best.plain_text(), "HIDETHEGOLDX");
+    /// ```
+    fn crack_resumable(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+        resume: Option<Checkpoint<PlayFairKey>>,
+        checkpoint: impl Fn(&Checkpoint<PlayFairKey>) + Sync,
+    ) -> Result<Vec<Candidate<PlayFairKey>>, CheckpointError> {
+        let cipher_text = crate::normalize(cipher_text, &self.options);
+        let resume = Checkpoint::resume(resume, "hill_climbing", self.seed, &cipher_text)?;
+        let tracker = Tracker::new(self.restarts * self.iterations, &progress).resumed(&resume);
+        let recorder = Recorder::new(resume, self.candidates, &checkpoint);
+        let stop = Stop::new(&self.cancellation, self.time_limit);
+        Ok(best_of(self.restarts, self.iterations, &recorder, |rng| {
+            let (climbed, tried) = self.climb(&cipher_text, &fitness, rng, &stop);
+            tracker.advance(tried, climbed.best());
+            (climbed, tried)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::analysis::scoring::score;
+    use crate::cryptable::Cypher;
+    use crate::options::FinalDigram;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(HillClimbing::builder().restarts(0).build().is_err());
+        assert!(HillClimbing::builder().iterations(0).build().is_err());
+        assert!(HillClimbing::builder().candidates(0).build().is_err());
+        let constraints = KeyConstraints::builder().build().unwrap();
+        let options = PayloadOptions::new('Q', 'J', None).unwrap();
+        assert!(HillClimbing::builder()
+            .options(options)
+            .constraints(constraints)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_crack_with_progress() {
+        let solver = HillClimbing::builder()
+            .restarts(3)
+            .iterations(50)
+            .build()
+            .unwrap();
+        let reported = std::sync::Mutex::new(Vec::new());
+        let ranked = solver.crack_with_progress("BMODZBXDNAGE", score, |progress: &Progress| {
+            reported.lock().unwrap().push(progress.clone());
+        });
+        let best = &ranked[0];
+        let reported = reported.into_inner().unwrap();
+        let iterations: Vec<usize> = reported.iter().map(|p| p.iterations()).collect();
+        assert_eq!(iterations, [50, 100, 150]);
+        assert!(reported.iter().all(|p| p.total() == 150));
+        let last = reported.last().unwrap();
+        assert_eq!(last.best_score(), best.score());
+        assert_eq!(last.preview(), best.plain_text());
+    }
+
+    #[test]
+    fn test_crack_odd_length_strict() {
+        let options = PayloadOptions::default().with_final_digram(FinalDigram::Strict);
+        let solver = HillClimbing::builder()
+            .restarts(2)
+            .iterations(50)
+            .options(options)
+            .build()
+            .unwrap();
+        let ranked = solver.crack("BMODZBXDNAG", score);
+        assert!(ranked.iter().all(|c| c.plain_text().ends_with('G')));
+        let ranked = solver.crack_with_progress("BMODZBXDNAG", score, |_: &Progress| {});
+        assert!(ranked.iter().all(|c| c.plain_text().ends_with('G')));
+    }
+
+    #[test]
+    fn test_crack_constrained() {
+        let key = PlayFairKey::new("monarchy");
+        let crypt = key.encrypt("we shall meet at the bridge").unwrap();
+        let plain = key.decrypt(&crypt).unwrap();
+        let constraints = KeyConstraints::builder()
+            .keyword_prefix("monarchy")
+            .word_in_row("VWXZ", 4)
+            .build()
+            .unwrap();
+        let solver = HillClimbing::builder()
+            .restarts(2)
+            .iterations(2_000)
+            .seed(4)
+            .constraints(constraints.clone())
+            .build()
+            .unwrap();
+        let ranked = solver.crack(&crypt, |text: &str| {
+            text.chars()
+                .zip(plain.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        });
+        let best = &ranked[0];
+        assert!(constraints.satisfied_by(best.key()));
+        assert_eq!(best.plain_text(), plain);
+    }
+
+    #[test]
+    fn test_crack_stopped() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let cancelled = HillClimbing::builder()
+            .restarts(3)
+            .cancellation(token)
+            .build()
+            .unwrap();
+        let timed_out = HillClimbing::builder()
+            .restarts(3)
+            .time_limit(Duration::ZERO)
+            .build()
+            .unwrap();
+        for solver in [cancelled, timed_out] {
+            let last = std::sync::Mutex::new(None);
+            let ranked =
+                solver.crack_with_progress("BMODZBXDNAGE", score, |progress: &Progress| {
+                    *last.lock().unwrap() = Some(progress.iterations());
+                });
+            // Each run still returns the key it started from
+            assert!(!ranked.is_empty());
+            assert_eq!(last.into_inner().unwrap(), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_crack_resumed() {
+        let crypt = PlayFairKey::new("monarchy")
+            .encrypt("attack at dawn")
+            .unwrap();
+        let builder = HillClimbing::builder().restarts(4).iterations(300);
+        let token = CancellationToken::new();
+        let interrupted = builder.clone().cancellation(token.clone()).build().unwrap();
+        let saved = std::sync::Mutex::new(String::new());
+        interrupted
+            .crack_resumable(
+                &crypt,
+                score,
+                |_: &Progress| {},
+                None,
+                |state: &Checkpoint<_>| {
+                    token.cancel();
+                    *saved.lock().unwrap() = state.to_string();
+                },
+            )
+            .unwrap();
+        let resume =
+            Checkpoint::parse(&saved.into_inner().unwrap(), PayloadOptions::default()).unwrap();
+        assert!(!resume.finished.is_empty());
+
+        let solver = builder.build().unwrap();
+        let resumed = solver
+            .crack_resumable(&crypt, score, |_: &Progress| {}, Some(resume), |_: &_| {})
+            .unwrap();
+        let uninterrupted = solver.crack(&crypt, score);
+        let scores =
+            |ranked: &[Candidate<PlayFairKey>]| ranked.iter().map(|c| c.score).collect::<Vec<_>>();
+        assert_eq!(scores(&resumed), scores(&uninterrupted));
+        assert_eq!(resumed[0].plain_text(), uninterrupted[0].plain_text());
+    }
+
+    #[test]
+    fn test_crack_known_plain_text() {
+        let key = PlayFairKey::new("monarchy");
+        let crypt = key
+            .encrypt("THEQUICKBROWNFOXIUMPSOVERTHELAZYDOGANDRUNSAWAYINTOTHEWOODS")
+            .unwrap();
+        let plain = key.decrypt(&crypt).unwrap();
+        let solver = HillClimbing::builder()
+            .restarts(5)
+            .iterations(5_000)
+            .seed(1)
+            .build()
+            .unwrap();
+        let fitness = |text: &str| {
+            text.chars()
+                .zip(plain.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        };
+        let ranked = solver.crack(&crypt, fitness);
+        let best = &ranked[0];
+        let again = solver.crack(&crypt, fitness);
+        assert_eq!(best.plain_text(), again[0].plain_text());
+        assert_eq!(ranked.len(), 5);
+        assert!(ranked.windows(2).all(|w| w[0].score() >= w[1].score()));
+        assert!(ranked[1..]
+            .iter()
+            .all(|c| c.plain_text() != best.plain_text()));
+        assert_eq!(best.plain_text(), plain);
+        assert_eq!(best.key().encrypt(&plain).unwrap(), crypt);
+    }
+}
@@ -0,0 +1,327 @@
+//! Systematic enumeration of PlayFair key squares, for brute force loops
+//! and for searches of their own. The iterators are lazy, only the square
+//! yielded is held in memory.
+//!
+//! - KeywordSquares yields the squares of all keywords up to a length.
+//! - PatternSquares yields all squares matching a pattern of known cells.
+//!
+
+use super::known_plaintext::PartialSquare;
+use crate::errors::InvalidOptionError;
+use crate::options::PayloadOptions;
+use crate::playfair::PlayFairKey;
+
+const CELLS: usize = 25;
+
+/// Iterator over the squares of all keywords of at most the given length,
+/// shorter keywords first. Letters repeated within a keyword don't change
+/// the square, so only keywords of distinct letters are tried. Keywords
+/// yielding the same square as a shorter keyword are skipped, e.g. "AB"
+/// yields the same square as the empty keyword. Each square is yielded
+/// once, along with its keyword.
+///
+/// The number of keywords grows fast: there are 25 * 24 * 23 = 13800
+/// keywords of length 3, about 6.4 million of length 5.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{crack::key_space::KeywordSquares, cryptable::Cypher};
+/// use playfair_cipher::{options::PayloadOptions, playfair::PlayFairKey};
+///
+/// let mut squares = KeywordSquares::new(2, PayloadOptions::default());
+/// let (keyword, key) = squares.next().unwrap();
+/// assert_eq!(keyword, "");
+/// assert_eq!(key.to_string().lines().next(), Some("A B C D E"));
+/// // Besides the empty keyword, 24 of length 1 (A is the same as the empty
+/// // one) and 25 * 24 of length 2 but the 25 ending with the letter that
+/// // comes next anyway, e.g. AB and BA
+/// assert_eq!(squares.count(), 24 + 25 * 24 - 25);
+///
+/// let crypt = PlayFairKey::new("zy").encrypt("the rust ship sails at noon").unwrap();
+/// let (keyword, _) = KeywordSquares::new(2, PayloadOptions::default())
+///     .find(|(_, key)| key.decrypt(&crypt).unwrap() == "THERUSTSHIPSAILSATNOON")
+///     .unwrap();
+/// assert_eq!(keyword, "ZY");
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeywordSquares {
+    alphabet: Vec<char>,
+    max_length: usize,
+    // Indices of the keyword letters in the alphabet, None before the first
+    keyword: Option<Vec<usize>>,
+    options: PayloadOptions,
+}
+
+impl KeywordSquares {
+    pub fn new(max_length: usize, options: PayloadOptions) -> Self {
+        KeywordSquares {
+            alphabet: options.alphabet().collect(),
+            max_length: max_length.min(CELLS),
+            keyword: None,
+            options,
+        }
+    }
+
+    // Moves on to the next keyword, false if there is none.
+    fn advance(&mut self) -> bool {
+        let n = self.alphabet.len();
+        match &mut self.keyword {
+            None => {
+                self.keyword = Some(Vec::new());
+                true
+            }
+            Some(keyword) => {
+                if next_arrangement(keyword, n) {
+                    return true;
+                }
+                // The first arrangement of the next length
+                let length = keyword.len() + 1;
+                if length > self.max_length {
+                    return false;
+                }
+                *keyword = (0..length).collect();
+                true
+            }
+        }
+    }
+
+    // Whether the keyword yields the same square as the keyword without its
+    // last letter: the letter is the one filled in next anyway.
+    fn redundant(keyword: &[usize]) -> bool {
+        let Some((last, rest)) = keyword.split_last() else {
+            return false;
+        };
+        (0..).find(|index| !rest.contains(index)) == Some(*last)
+    }
+}
+
+impl Iterator for KeywordSquares {
+    type Item = (String, PlayFairKey);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.advance() {
+            let keyword = self.keyword.as_ref().expect("advanced to a keyword");
+            if Self::redundant(keyword) {
+                continue;
+            }
+            let keyword: String = keyword.iter().map(|index| self.alphabet[*index]).collect();
+            let key = PlayFairKey::with_options(&keyword, self.options);
+            return Some((keyword, key));
+        }
+        None
+    }
+}
+
+/// Iterator over all squares matching a pattern, i.e. with the known cells
+/// as given and the other letters in any order. There are n! squares for n
+/// unknown cells, so it's only feasible for a few of them.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{crack::key_space::PatternSquares, options::PayloadOptions};
+///
+/// let pattern = "
+///     P L A Y F
+///     I R E X M
+///     B C D G H
+///     K N O Q S
+///     T U . . .
+/// ";
+/// let squares = PatternSquares::new(pattern, PayloadOptions::default()).unwrap();
+/// let rows: Vec<String> = squares
+///     .map(|key| key.to_string().lines().last().unwrap().to_string())
+///     .collect();
+/// assert_eq!(rows, ["T U V W Z", "T U V Z W", "T U W V Z", "T U W Z V", "T U Z V W", "T U Z W V"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PatternSquares {
+    cells: [Option<char>; CELLS],
+    // The letters missing in the pattern, in the order of the next square
+    missing: Vec<char>,
+    started: bool,
+    options: PayloadOptions,
+}
+
+impl PatternSquares {
+    /// Parses the pattern, the 25 cells row by row. A letter is a known
+    /// cell, '.' or '?' an unknown one, whitespace is skipped. That's the
+    /// way a PartialSquare is displayed.
+    pub fn new(pattern: &str, options: PayloadOptions) -> Result<Self, InvalidOptionError> {
+        let mut cells = Vec::with_capacity(CELLS);
+        for c in pattern.chars().filter(|c| !c.is_whitespace()) {
+            let cell = match (
+                c.to_ascii_uppercase(),
+                options.substitute(c.to_ascii_uppercase()),
+            ) {
+                ('.' | '?', _) => None,
+                (upper, Some(letter)) if upper.is_ascii_uppercase() => Some(letter),
+                _ => {
+                    return Err(InvalidOptionError::new(format!(
+                        "Pattern may only contain letters of the square, '.' and '?' - got '{}'",
+                        c
+                    )))
+                }
+            };
+            if cell.is_some() && cells.contains(&cell) {
+                return Err(InvalidOptionError::new(format!(
+                    "'{}' appears twice in the pattern",
+                    c
+                )));
+            }
+            cells.push(cell);
+        }
+        let cells: [Option<char>; CELLS] = cells.try_into().map_err(|cells: Vec<_>| {
+            InvalidOptionError::new(format!(
+                "Pattern must have {} cells - got {}",
+                CELLS,
+                cells.len()
+            ))
+        })?;
+        Ok(PatternSquares::from_cells(cells, options))
+    }
+
+    fn from_cells(cells: [Option<char>; CELLS], options: PayloadOptions) -> Self {
+        let missing = options
+            .alphabet()
+            .filter(|c| !cells.contains(&Some(*c)))
+            .collect();
+        PatternSquares {
+            cells,
+            missing,
+            started: false,
+            options,
+        }
+    }
+}
+
+impl From<&PartialSquare> for PatternSquares {
+    /// All completions of the cells known so far.
+    fn from(partial: &PartialSquare) -> Self {
+        PatternSquares::from_cells(*partial.cells(), partial.options)
+    }
+}
+
+impl Iterator for PatternSquares {
+    type Item = PlayFairKey;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started && !next_permutation(&mut self.missing) {
+            return None;
+        }
+        self.started = true;
+        let mut missing = self.missing.iter();
+        let square = self.cells.map(|cell| {
+            cell.or_else(|| missing.next().copied())
+                .expect("as many letters missing as unknown cells")
+        });
+        Some(PlayFairKey::from_square_with_options(square, self.options))
+    }
+}
+
+// Rearranges the items to the lexicographically next permutation, false if
+// they were in the last one already.
+fn next_permutation<T: Ord>(items: &mut [T]) -> bool {
+    let Some(pivot) = (1..items.len()).rev().find(|i| items[i - 1] < items[*i]) else {
+        return false;
+    };
+    let successor = (pivot..items.len())
+        .rev()
+        .find(|i| items[*i] > items[pivot - 1])
+        .expect("the item at the pivot is greater");
+    items.swap(pivot - 1, successor);
+    items[pivot..].reverse();
+    true
+}
+
+// Moves to the lexicographically next arrangement of distinct numbers below
+// n, false if it was the last one of its length.
+fn next_arrangement(arrangement: &mut [usize], n: usize) -> bool {
+    for position in (0..arrangement.len()).rev() {
+        let used = &arrangement[..position];
+        let Some(next) = (arrangement[position] + 1..n).find(|c| !used.contains(c)) else {
+            continue;
+        };
+        arrangement[position] = next;
+        let free: Vec<usize> = (0..n)
+            .filter(|c| !arrangement[..=position].contains(c))
+            .collect();
+        for (rest, c) in arrangement[position + 1..].iter_mut().zip(free) {
+            *rest = c;
+        }
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_next_arrangement() {
+        let mut arrangement = vec![0, 1];
+        let mut all = vec![arrangement.clone()];
+        while next_arrangement(&mut arrangement, 3) {
+            all.push(arrangement.clone());
+        }
+        assert_eq!(
+            all,
+            [[0, 1], [0, 2], [1, 0], [1, 2], [2, 0], [2, 1]].map(|a| a.to_vec())
+        );
+    }
+
+    #[test]
+    fn test_keyword_squares_distinct() {
+        let squares: Vec<(String, PlayFairKey)> =
+            KeywordSquares::new(2, PayloadOptions::default()).collect();
+        let distinct: HashSet<[char; 25]> = squares.iter().map(|(_, key)| key.key).collect();
+        assert_eq!(distinct.len(), squares.len());
+        let keywords: Vec<&str> = squares
+            .iter()
+            .map(|(keyword, _)| keyword.as_str())
+            .collect();
+        assert!(keywords.contains(&"ZB"));
+        assert!(!keywords.contains(&"ZA"));
+        assert!(!keywords.contains(&"A"));
+        assert!(!keywords.contains(&"AB"));
+        assert!(!keywords.contains(&"BA"));
+    }
+
+    #[test]
+    fn test_pattern_invalid() {
+        let options = PayloadOptions::default();
+        assert!(PatternSquares::new("ABC", options).is_err());
+        assert!(PatternSquares::new("AA.......................", options).is_err());
+        assert!(PatternSquares::new("A1.......................", options).is_err());
+    }
+
+    #[test]
+    fn test_pattern_count() {
+        let options = PayloadOptions::default();
+        let complete: String = options.alphabet().collect();
+        assert_eq!(PatternSquares::new(&complete, options).unwrap().count(), 1);
+        let pattern = format!("{}....", &complete[..21]);
+        assert_eq!(PatternSquares::new(&pattern, options).unwrap().count(), 24);
+    }
+
+    #[test]
+    fn test_pattern_from_partial() {
+        let key = PlayFairKey::new("monarchy");
+        let mut cells = key.key.map(Some);
+        cells[3] = None;
+        cells[24] = None;
+        let partial = PartialSquare {
+            cells,
+            options: PayloadOptions::default(),
+        };
+        let keys: Vec<PlayFairKey> = PatternSquares::from(&partial).collect();
+        assert_eq!(keys.len(), 2);
+        assert!(keys.iter().any(|k| k.key == key.key));
+        let shown = PatternSquares::new(&partial.to_string(), PayloadOptions::default()).unwrap();
+        assert_eq!(shown.count(), 2);
+    }
+}
@@ -0,0 +1,431 @@
+//! Known plain text attack on the PlayFair cipher. Every digram of a plain
+//! text and the digram it's encrypted to constrain where the four letters
+//! can be within the square. Placing some letters determines the cells of
+//! others, so a backtracking search over the cells of the constrained
+//! letters quickly finds all squares consistent with the texts.
+//!
+//! A square whose rows and columns are rotated encrypts exactly like the
+//! original one, so the first letter of the first digram is always put
+//! into the top left cell.
+//!
+
+use std::fmt;
+
+use crate::errors::InvalidOptionError;
+use crate::options::PayloadOptions;
+use crate::playfair::{crypt_cells, PlayFairKey, ROW_LENGTH};
+use crate::structs::{CryptModus, Payload};
+
+const CELLS: usize = 25;
+const NONE: u8 = u8::MAX;
+
+/// A square where only some cells are known.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSquare {
+    pub(crate) cells: [Option<char>; CELLS],
+    pub(crate) options: PayloadOptions,
+}
+
+impl PartialSquare {
+    /// Letter in the cell, None if it's unknown.
+    pub fn get(&self, row: usize, column: usize) -> Option<char> {
+        self.cells[row * ROW_LENGTH as usize + column]
+    }
+
+    /// The cells row by row.
+    pub fn cells(&self) -> &[Option<char>; CELLS] {
+        &self.cells
+    }
+
+    /// Number of known cells.
+    pub fn known(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_some()).count()
+    }
+
+    /// The key if all cells are known.
+    pub fn to_key(&self) -> Option<PlayFairKey> {
+        let mut square = ['*'; CELLS];
+        for (cell, c) in square.iter_mut().zip(self.cells) {
+            *cell = c?;
+        }
+        Some(PlayFairKey::from_square_with_options(square, self.options))
+    }
+}
+
+impl fmt::Display for PartialSquare {
+    /// Prints the square row by row, unknown cells as '.'.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(ROW_LENGTH as usize) {
+            let row: Vec<String> = row.iter().map(|c| c.unwrap_or('.').to_string()).collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of a known plain text attack.
+///
+#[derive(Debug, Clone)]
+pub struct Reconstruction {
+    pub(crate) partial: PartialSquare,
+    pub(crate) completions: Vec<PartialSquare>,
+    pub(crate) exhausted: bool,
+}
+
+impl Reconstruction {
+    /// The cells all consistent squares agree on.
+    pub fn partial(&self) -> &PartialSquare {
+        &self.partial
+    }
+
+    /// All squares found consistent with the texts. Only the constrained
+    /// letters are placed, any other letter may be in any free cell.
+    pub fn completions(&self) -> &[PartialSquare] {
+        &self.completions
+    }
+
+    /// False if the search stopped at the limit of completions, so there
+    /// are more consistent squares than returned.
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+}
+
+/// Known plain text attack, see KnownPlaintextAttack::builder.
+///
+#[derive(Debug, Clone)]
+pub struct KnownPlaintextAttack {
+    limit: usize,
+    options: PayloadOptions,
+}
+
+/// Builder for a KnownPlaintextAttack.
+///
+#[derive(Debug, Clone)]
+pub struct KnownPlaintextAttackBuilder {
+    limit: usize,
+    options: PayloadOptions,
+}
+
+impl Default for KnownPlaintextAttackBuilder {
+    fn default() -> Self {
+        KnownPlaintextAttackBuilder {
+            limit: 1000,
+            options: PayloadOptions::default(),
+        }
+    }
+}
+
+impl KnownPlaintextAttackBuilder {
+    /// Maximum number of consistent squares searched. Defaults to 1000.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Options the plain texts were encrypted with. Padding and omitted
+    /// letter are needed to split the plain texts into the same digrams.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn build(self) -> Result<KnownPlaintextAttack, InvalidOptionError> {
+        if self.limit == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Limit must be greater than 0",
+            )));
+        }
+        Ok(KnownPlaintextAttack {
+            limit: self.limit,
+            options: self.options,
+        })
+    }
+}
+
+impl KnownPlaintextAttack {
+    /// Returns a builder to configure the attack.
+    pub fn builder() -> KnownPlaintextAttackBuilder {
+        KnownPlaintextAttackBuilder::default()
+    }
+
+    /// Reconstructs the square from pairs of plain text and the cipher
+    /// text it's encrypted to.
+    ///
+    /// # Errors
+    ///
+    /// If a plain text doesn't yield as many letters as the cipher text or
+    /// a cipher digram consists of the same letter twice.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::crack::known_plaintext::KnownPlaintextAttack;
+    ///
+    /// let attack = KnownPlaintextAttack::builder().build().unwrap();
+    /// let reconstruction = attack
+    ///     .crack(&[("hide the gold in the tree stump", "BMODZBXDNABEKUDMUIXMMOUVIF")])
+    ///     .unwrap();
+    /// assert!(reconstruction.exhausted());
+    /// assert!(reconstruction.partial().known() > 0);
+    /// ```
+    pub fn crack(&self, pairs: &[(&str, &str)]) -> Result<Reconstruction, InvalidOptionError> {
+        let constraints = self.constraints(pairs)?;
+        let mut search = Search {
+            constraints: &constraints,
+            cell_of: [NONE; 26],
+            letter_at: [NONE; CELLS],
+            trail: Vec::new(),
+            completions: Vec::new(),
+            limit: self.limit,
+            options: self.options,
+        };
+        if let Some(&[first, ..]) = constraints.first() {
+            // Rotations are equivalent, see module documentation
+            if search.assign(first, 0) {
+                search.solve();
+            }
+        } else {
+            search.completions.push(search.square());
+        }
+        let exhausted = search.completions.len() < self.limit;
+        let completions = search.completions;
+        let mut cells = [None; CELLS];
+        if let Some(first) = completions.first() {
+            for (cell, c) in cells.iter_mut().enumerate() {
+                *c = first.cells[cell].filter(|c| {
+                    completions
+                        .iter()
+                        .all(|completion| completion.cells[cell] == Some(*c))
+                });
+            }
+        }
+        Ok(Reconstruction {
+            partial: PartialSquare {
+                cells,
+                options: self.options,
+            },
+            completions,
+            exhausted,
+        })
+    }
+
+    // Letter indices (plain a, plain b, crypt a, crypt b) of all digrams.
+    fn constraints(&self, pairs: &[(&str, &str)]) -> Result<Vec<[u8; 4]>, InvalidOptionError> {
+        let mut constraints: Vec<[u8; 4]> = Vec::new();
+        for (plain, crypt) in pairs {
+            let plain: Vec<[char; 2]> = Payload::new(plain, &self.options).collect();
+            let crypt: Vec<char> = crate::normalize(crypt, &self.options).chars().collect();
+            if plain.len() * 2 != crypt.len() {
+                return Err(InvalidOptionError::new(format!(
+                    "Plain text of {} digrams doesn't match cipher text of {} letters",
+                    plain.len(),
+                    crypt.len()
+                )));
+            }
+            for (p, c) in plain.iter().zip(crypt.chunks(2)) {
+                if c[0] == c[1] {
+                    return Err(InvalidOptionError::new(format!(
+                        "Cipher digram {}{} can't be the result of an encryption",
+                        c[0], c[1]
+                    )));
+                }
+                // A doubled padding letter isn't split and can't be encrypted
+                // consistently, so it doesn't tell anything.
+                if p[0] == p[1] {
+                    continue;
+                }
+                let constraint = [p[0], p[1], c[0], c[1]].map(|c| c as u8 - b'A');
+                if !constraints.contains(&constraint) {
+                    constraints.push(constraint);
+                }
+            }
+        }
+        Ok(constraints)
+    }
+}
+
+struct Search<'a> {
+    constraints: &'a [[u8; 4]],
+    cell_of: [u8; 26],
+    letter_at: [u8; CELLS],
+    // Letters in the order they were placed, to undo the placements
+    trail: Vec<u8>,
+    completions: Vec<PartialSquare>,
+    limit: usize,
+    options: PayloadOptions,
+}
+
+impl Search<'_> {
+    fn solve(&mut self) {
+        if self.completions.len() >= self.limit {
+            return;
+        }
+        let mark = self.trail.len();
+        if !self.propagate() {
+            self.undo(mark);
+            return;
+        }
+        match self.unplaced() {
+            None => self.completions.push(self.square()),
+            Some(letter) => {
+                for cell in 0..CELLS as u8 {
+                    if self.letter_at[cell as usize] != NONE {
+                        continue;
+                    }
+                    let branch = self.trail.len();
+                    self.assign(letter, cell);
+                    self.solve();
+                    self.undo(branch);
+                }
+            }
+        }
+        self.undo(mark);
+    }
+
+    fn assign(&mut self, letter: u8, cell: u8) -> bool {
+        let placed = self.cell_of[letter as usize];
+        if placed != NONE {
+            return placed == cell;
+        }
+        if self.letter_at[cell as usize] != NONE {
+            return false;
+        }
+        self.cell_of[letter as usize] = cell;
+        self.letter_at[cell as usize] = letter;
+        self.trail.push(letter);
+        true
+    }
+
+    fn undo(&mut self, mark: usize) {
+        for letter in self.trail.drain(mark..) {
+            self.letter_at[self.cell_of[letter as usize] as usize] = NONE;
+            self.cell_of[letter as usize] = NONE;
+        }
+    }
+
+    // Places all letters determined by the placed ones. False if the placed
+    // letters contradict a constraint.
+    fn propagate(&mut self) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &[pa, pb, ca, cb] in self.constraints {
+                let before = self.trail.len();
+                let [pa_cell, pb_cell, ca_cell, cb_cell] =
+                    [pa, pb, ca, cb].map(|l| self.cell_of[l as usize]);
+                if pa_cell != NONE && pb_cell != NONE {
+                    let (a, b) = crypt_cells(pa_cell, pb_cell, ROW_LENGTH, &CryptModus::Encrypt);
+                    if !self.assign(ca, a) || !self.assign(cb, b) {
+                        return false;
+                    }
+                } else if ca_cell != NONE && cb_cell != NONE {
+                    let (a, b) = crypt_cells(ca_cell, cb_cell, ROW_LENGTH, &CryptModus::Decrypt);
+                    if !self.assign(pa, a) || !self.assign(pb, b) {
+                        return false;
+                    }
+                } else if !(may_encrypt_to(pa_cell, ca_cell) && may_encrypt_to(pb_cell, cb_cell)) {
+                    return false;
+                }
+                changed |= self.trail.len() > before;
+            }
+        }
+        true
+    }
+
+    // An unplaced letter of the constraint with the most letters placed.
+    fn unplaced(&self) -> Option<u8> {
+        self.constraints
+            .iter()
+            .filter_map(|constraint| {
+                let unplaced = constraint
+                    .iter()
+                    .filter(|l| self.cell_of[**l as usize] == NONE);
+                let count = unplaced.clone().count();
+                unplaced.copied().next().map(|l| (count, l))
+            })
+            .min_by_key(|(count, _)| *count)
+            .map(|(_, l)| l)
+    }
+
+    fn square(&self) -> PartialSquare {
+        PartialSquare {
+            cells: self.letter_at.map(|l| match l {
+                NONE => None,
+                l => Some((b'A' + l) as char),
+            }),
+            options: self.options,
+        }
+    }
+}
+
+// A letter is encrypted to a letter in the same row or to the one below it.
+fn may_encrypt_to(plain: u8, crypt: u8) -> bool {
+    if plain == NONE || crypt == NONE {
+        return true;
+    }
+    plain / ROW_LENGTH == crypt / ROW_LENGTH || (plain + ROW_LENGTH) % CELLS as u8 == crypt
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(KnownPlaintextAttack::builder().limit(0).build().is_err());
+    }
+
+    #[test]
+    fn test_crack_misaligned() {
+        let attack = KnownPlaintextAttack::builder().build().unwrap();
+        assert!(attack.crack(&[("hide the gold", "BMODZ")]).is_err());
+        assert!(attack.crack(&[("hide", "BBOD")]).is_err());
+    }
+
+    #[test]
+    fn test_crack_consistent() {
+        let key = PlayFairKey::new("playfair example");
+        let plain = "the quick brown fox jumps over the lazy dog";
+        let crypt = key.encrypt(plain).unwrap();
+        let attack = KnownPlaintextAttack::builder().build().unwrap();
+        let reconstruction = attack.crack(&[(plain, &crypt)]).unwrap();
+        assert!(reconstruction.exhausted());
+        assert!(!reconstruction.completions().is_empty());
+        for completion in reconstruction.completions() {
+            // The search fixes T, the first letter, in the top left cell
+            assert_eq!(completion.get(0, 0), Some('T'));
+        }
+        // The original square rotated so T is top left
+        let rotated = key
+            .mutated(crate::playfair::KeyMutation::SwapRows(0, 4))
+            .mutated(crate::playfair::KeyMutation::SwapRows(1, 4))
+            .mutated(crate::playfair::KeyMutation::SwapRows(2, 4))
+            .mutated(crate::playfair::KeyMutation::SwapRows(3, 4));
+        assert_eq!(rotated.square()[0], 'T');
+        assert!(reconstruction.completions().iter().any(|completion| {
+            completion
+                .cells()
+                .iter()
+                .zip(rotated.square())
+                .all(|(c, r)| c.is_none_or(|c| c == *r))
+        }));
+        let partial = reconstruction.partial();
+        assert!(partial.known() >= 1);
+        assert_eq!(partial.to_string().lines().count(), 5);
+    }
+
+    #[test]
+    fn test_crack_complete_square() {
+        let key = PlayFairKey::new("playfair example");
+        let square: Vec<Option<char>> = key.square().iter().map(|c| Some(*c)).collect();
+        let partial = PartialSquare {
+            cells: square.try_into().unwrap(),
+            options: PayloadOptions::default(),
+        };
+        assert_eq!(partial.to_key().unwrap().square(), key.square());
+    }
+}
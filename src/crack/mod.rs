@@ -0,0 +1,551 @@
+//! Solvers recovering the key of a cipher text. A solver tries keys and
+//! rates the resulting plain texts with a fitness function, the higher the
+//! score the more the plain text looks like language.
+//!
+pub mod checkpoint;
+pub mod constraints;
+pub mod dictionary;
+#[cfg(feature = "four-square")]
+pub mod four_square;
+pub mod genetic;
+pub mod hill_climbing;
+pub mod key_space;
+pub mod known_plaintext;
+#[cfg(feature = "two-square")]
+pub mod two_square;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::analysis::scoring::FitnessScorer;
+use crate::crack::checkpoint::{Checkpoint, CheckpointKey, Recorder};
+use crate::cryptable::Cypher;
+use crate::errors::CheckpointError;
+use crate::options::{FinalDigram, PayloadOptions};
+use crate::playfair::KeyMutation;
+#[cfg(any(feature = "two-square", feature = "four-square"))]
+use crate::playfair::PlayFairKey;
+
+/// The best key found by a solver together with the plain text it yields
+/// and the score of the plain text.
+///
+#[derive(Debug, Clone)]
+pub struct Candidate<K> {
+    pub(crate) key: K,
+    pub(crate) plain_text: String,
+    pub(crate) score: f64,
+}
+
+impl<K> Candidate<K> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn plain_text(&self) -> &str {
+        &self.plain_text
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+}
+
+/// Common interface of the solvers searching the key space, so the search
+/// strategies are interchangeable.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::crack::{genetic::GeneticSolver, hill_climbing::HillClimbing, Solver};
+/// use playfair_cipher::{cryptable::Cypher, playfair::PlayFairKey};
+///
+/// fn recover(solver: &impl Solver<Key = PlayFairKey>, crypt: &str, plain: &str) -> String {
+///     let ranked = solver.crack(crypt, |text: &str| {
+///         text.chars().zip(plain.chars()).filter(|(a, b)| a == b).count() as f64
+///     });
+///     ranked[0].plain_text().to_string()
+/// }
+///
+/// let crypt = PlayFairKey::new("playfair example").encrypt("hide the gold").unwrap();
+/// let hill_climbing = HillClimbing::builder().seed(4).build().unwrap();
+/// assert_eq!(recover(&hill_climbing, &crypt, "HIDETHEGOLDX"), "HIDETHEGOLDX");
+/// let genetic = GeneticSolver::builder().generations(5).build().unwrap();
+/// println!("{}", recover(&genetic, &crypt, "HIDETHEGOLDX"));
+/// ```
+pub trait Solver {
+    /// The key the solver recovers, e.g. PlayFairKey.
+    type Key: CheckpointKey;
+
+    /// Searches the keys which decrypt the cipher text to the plain texts
+    /// with the highest fitness. Returns the best candidates with distinct
+    /// plain texts, the best one first. A short cipher text often has
+    /// several plausible decryptions, so it's worth looking at more than
+    /// the first one.
+    fn crack(&self, cipher_text: &str, fitness: impl FitnessScorer) -> Vec<Candidate<Self::Key>> {
+        self.crack_with_progress(cipher_text, fitness, |_: &Progress| {})
+    }
+
+    /// Same as crack, calling back with the progress whenever a restart or
+    /// a generation is done. With the feature `rayon` the restarts run in
+    /// parallel and the callback is called from the threads of the rayon
+    /// thread pool, one call at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::crack::{hill_climbing::HillClimbing, Progress, Solver};
+    ///
+    /// let solver = HillClimbing::builder().restarts(4).iterations(100).build().unwrap();
+    /// let fitness = |plain: &str| plain.matches("TH").count() as f64;
+    /// solver.crack_with_progress("BMODZBXDNAGE", fitness, |progress: &Progress| {
+    ///     println!(
+    ///         "{}/{} best {} {}",
+    ///         progress.iterations(),
+    ///         progress.total(),
+    ///         progress.best_score(),
+    ///         progress.preview()
+    ///     );
+    /// });
+    /// ```
+    fn crack_with_progress(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+    ) -> Vec<Candidate<Self::Key>> {
+        self.crack_resumable(cipher_text, fitness, progress, None, |_: &_| {})
+            .expect("a crack without checkpoint to resume from can't mismatch")
+    }
+
+    /// Same as crack_with_progress, resuming from a checkpoint if one is
+    /// given and calling back with a checkpoint whenever a restart or a
+    /// generation is done. Saving the checkpoints, e.g. with
+    /// Checkpoint::save, lets a crack taking hours survive a restart of the
+    /// program. A crack stopped by cancellation or time limit can be
+    /// resumed from its last checkpoint as well.
+    ///
+    /// Fails if the checkpoint was taken by another solver, with another
+    /// seed or of another cipher text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use playfair_cipher::crack::{checkpoint::Checkpoint, hill_climbing::HillClimbing};
+    /// use playfair_cipher::crack::{Progress, Solver};
+    /// use playfair_cipher::{analysis::scoring::score, options::PayloadOptions};
+    ///
+    /// let path = "crack.checkpoint";
+    /// let resume = Checkpoint::load(path, PayloadOptions::default()).ok();
+    /// let solver = HillClimbing::builder().restarts(500).build().unwrap();
+    /// let ranked = solver
+    ///     .crack_resumable("BMODZBXDNAGE", score, |_: &Progress| {}, resume, |checkpoint: &_| {
+    ///         checkpoint.save(path).unwrap()
+    ///     })
+    ///     .unwrap();
+    /// ```
+    fn crack_resumable(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+        resume: Option<Checkpoint<Self::Key>>,
+        checkpoint: impl Fn(&Checkpoint<Self::Key>) + Sync,
+    ) -> Result<Vec<Candidate<Self::Key>>, CheckpointError>;
+}
+
+// Number of plain text chars shown in the progress
+const PREVIEW_LENGTH: usize = 40;
+
+/// How far a solver got, handed to the progress callback.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub(crate) iterations: usize,
+    pub(crate) total: usize,
+    pub(crate) best_score: f64,
+    pub(crate) preview: String,
+}
+
+impl Progress {
+    /// Number of keys tried so far.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Number of keys the solver is going to try at all.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Score of the best key found so far.
+    pub fn best_score(&self) -> f64 {
+        self.best_score
+    }
+
+    /// The first chars of the plain text of the best key found so far.
+    pub fn preview(&self) -> &str {
+        &self.preview
+    }
+}
+
+/// Token to abort a running solver from another thread, e.g. when the user
+/// of a GUI gives up waiting. A cancelled solver stops trying keys and
+/// returns the best candidates found so far. Clones share the token.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::crack::{hill_climbing::HillClimbing, CancellationToken, Solver};
+/// use playfair_cipher::{cryptable::Cypher, playfair::PlayFairKey};
+///
+/// let token = CancellationToken::new();
+/// let solver = HillClimbing::builder()
+///     .cancellation(token.clone())
+///     .build()
+///     .unwrap();
+/// token.cancel();
+/// let crypt = PlayFairKey::new("playfair example").encrypt("hide the gold").unwrap();
+/// let ranked = solver.crack(&crypt, |plain: &str| plain.len() as f64);
+/// assert!(!ranked.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Asks the solvers holding the token to stop.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+// When a crack has to stop, either cancelled or out of time.
+#[derive(Debug)]
+pub(crate) struct Stop<'a> {
+    token: &'a CancellationToken,
+    deadline: Option<Instant>,
+}
+
+impl<'a> Stop<'a> {
+    // The time limit starts to run now.
+    pub(crate) fn new(token: &'a CancellationToken, time_limit: Option<Duration>) -> Self {
+        Stop {
+            token,
+            deadline: time_limit.map(|limit| Instant::now() + limit),
+        }
+    }
+
+    pub(crate) fn reached(&self) -> bool {
+        self.token.is_cancelled()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+// Tracks the progress of the runs of a solver, which may run in parallel.
+pub(crate) struct Tracker<'a, P: Fn(&Progress) + Sync> {
+    progress: Mutex<Progress>,
+    callback: &'a P,
+}
+
+impl<'a, P: Fn(&Progress) + Sync> Tracker<'a, P> {
+    pub(crate) fn new(total: usize, callback: &'a P) -> Self {
+        Tracker {
+            progress: Mutex::new(Progress {
+                iterations: 0,
+                total,
+                best_score: f64::NEG_INFINITY,
+                preview: String::new(),
+            }),
+            callback,
+        }
+    }
+
+    // Continues from the keys tried before the checkpoint.
+    pub(crate) fn resumed<K>(self, checkpoint: &Checkpoint<K>) -> Self {
+        {
+            let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+            progress.iterations = checkpoint.iterations;
+            if let Some(best) = checkpoint.candidates.first() {
+                progress.best_score = best.score;
+                progress.preview = best.plain_text.chars().take(PREVIEW_LENGTH).collect();
+            }
+        }
+        self
+    }
+
+    // Adds the keys tried along with the best key found by them.
+    pub(crate) fn advance<K>(&self, iterations: usize, best: &Candidate<K>) {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        progress.iterations += iterations;
+        if best.score > progress.best_score {
+            progress.best_score = best.score;
+            progress.preview = best.plain_text.chars().take(PREVIEW_LENGTH).collect();
+        }
+        // Called while locked, so the callback isn't called concurrently
+        (self.callback)(&progress);
+    }
+}
+
+// The best candidates seen, at most one per plain text, the best first.
+#[derive(Debug, Clone)]
+pub(crate) struct Ranking<K> {
+    limit: usize,
+    candidates: Vec<Candidate<K>>,
+}
+
+impl<K: Clone> Ranking<K> {
+    pub(crate) fn new(limit: usize) -> Self {
+        Ranking {
+            limit,
+            candidates: Vec::with_capacity(limit + 1),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, candidate: &Candidate<K>) {
+        if self.candidates.len() == self.limit
+            && self
+                .candidates
+                .last()
+                .is_some_and(|worst| candidate.score <= worst.score)
+        {
+            return;
+        }
+        match self
+            .candidates
+            .iter()
+            .position(|c| c.plain_text == candidate.plain_text)
+        {
+            Some(same) if self.candidates[same].score >= candidate.score => return,
+            Some(same) => self.candidates[same] = candidate.clone(),
+            None => self.candidates.push(candidate.clone()),
+        }
+        // Stable, so of equal scores the one seen first stays ahead
+        self.candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        self.candidates.truncate(self.limit);
+    }
+
+    pub(crate) fn best(&self) -> &Candidate<K> {
+        // Every search rates at least one key
+        &self.candidates[0]
+    }
+
+    pub(crate) fn candidates(&self) -> &[Candidate<K>] {
+        &self.candidates
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<Candidate<K>> {
+        self.candidates
+    }
+}
+
+// Runs a search per seed drawn from the seed given and returns the best
+// candidates of all. With the feature rayon the searches run in parallel.
+// Either way each search has its own generator, so the result only depends
+// on the seed. Searches finished before the checkpoint of the recorder are
+// skipped, a search counts as finished if it tried all keys of a run.
+pub(crate) fn best_of<K: CheckpointKey, S: Fn(&Checkpoint<K>) + Sync>(
+    runs: usize,
+    per_run: usize,
+    recorder: &Recorder<K, S>,
+    search: impl Fn(&mut Rng) -> (Ranking<K>, usize) + Sync,
+) -> Vec<Candidate<K>> {
+    let mut rng = Rng::new(recorder.seed());
+    let seeds: Vec<(usize, u64)> = (0..runs)
+        .map(|run| (run, rng.next_u64()))
+        .filter(|(run, _)| !recorder.finished(*run))
+        .collect();
+    let run = |(run, seed): &(usize, u64)| {
+        let (ranking, tried) = search(&mut Rng::new(*seed));
+        recorder.record(*run, &ranking, tried, tried == per_run);
+        ranking
+    };
+    #[cfg(feature = "rayon")]
+    let searched: Vec<Ranking<K>> = {
+        use rayon::prelude::*;
+        seeds.par_iter().map(run).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let searched: Vec<Ranking<K>> = seeds.iter().map(run).collect();
+    // In the order of the runs, not the one they finished in
+    let mut ranking = Ranking::new(recorder.limit());
+    for candidate in recorder
+        .resumed()
+        .iter()
+        .chain(searched.iter().flat_map(Ranking::candidates))
+    {
+        ranking.insert(candidate);
+    }
+    ranking.into_vec()
+}
+
+// Rates the keys, in parallel with the feature rayon.
+pub(crate) fn rate_all<C: Cypher + Send>(
+    keys: Vec<C>,
+    cipher_text: &str,
+    fitness: &impl FitnessScorer,
+) -> Vec<Candidate<C>> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        keys.into_par_iter()
+            .map(|key| rate(key, cipher_text, fitness))
+            .collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    keys.into_iter()
+        .map(|key| rate(key, cipher_text, fitness))
+        .collect()
+}
+
+// Small xorshift generator, so the solvers are reproducible by seed without
+// pulling in a dependency. Not suitable for anything but exploring keys.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // splitmix64 of the seed, so seeds close to each other don't start
+        // with similar states and the state is never 0
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Rng {
+            state: (z ^ (z >> 31)) | 1,
+        }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    // Uniformly distributed number in 0..bound
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (((self.next_u64() >> 32) * bound as u64) >> 32) as usize
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+// The options the keys of a solver are built with. The last letter of a
+// cipher text of odd length is passed through, whatever the final digram
+// policy of the options given: no candidate should fail on it.
+pub(crate) fn solver_options(options: PayloadOptions) -> PayloadOptions {
+    options.with_final_digram(FinalDigram::PassThrough)
+}
+
+// Decrypts the cipher text with the key and rates the plain text.
+pub(crate) fn rate<C: Cypher>(
+    key: C,
+    cipher_text: &str,
+    fitness: &impl FitnessScorer,
+) -> Candidate<C> {
+    // The cipher text is normalized with the options of the key, so any
+    // letter is part of the key, and a last letter without partner is
+    // passed through, see solver_options
+    let plain_text = key
+        .decrypt(cipher_text)
+        .expect("normalized cipher text only consists of letters of the key");
+    let score = fitness.score(&plain_text);
+    Candidate {
+        key,
+        plain_text,
+        score,
+    }
+}
+
+// A key with the letters of the alphabet in random order.
+#[cfg(any(feature = "two-square", feature = "four-square"))]
+pub(crate) fn random_key(rng: &mut Rng, options: PayloadOptions) -> PlayFairKey {
+    let mut square = ['*'; 25];
+    for (cell, c) in square.iter_mut().zip(options.alphabet()) {
+        *cell = c;
+    }
+    rng.shuffle(&mut square);
+    PlayFairKey::from_square_with_options(square, options)
+}
+
+// A random small change of a key. Mostly two letters are swapped, now and
+// then rows, columns or the whole square are rearranged to escape from
+// local maxima.
+pub(crate) fn random_mutation(rng: &mut Rng) -> KeyMutation {
+    match rng.below(50) {
+        0 => KeyMutation::SwapRows(rng.below(5) as u8, rng.below(5) as u8),
+        1 => KeyMutation::SwapColumns(rng.below(5) as u8, rng.below(5) as u8),
+        2 => KeyMutation::Transpose,
+        3 => KeyMutation::FlipRows,
+        4 => KeyMutation::FlipColumns,
+        5 => KeyMutation::Reverse,
+        _ => KeyMutation::SwapCells(rng.below(25) as u8, rng.below(25) as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_rng_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        assert!((0..1000).all(|_| a.below(25) < 25));
+    }
+
+    #[test]
+    fn test_ranking_distinct() {
+        let candidate = |plain_text: &str, score: f64| Candidate {
+            key: (),
+            plain_text: String::from(plain_text),
+            score,
+        };
+        let mut ranking = Ranking::new(2);
+        ranking.insert(&candidate("A", 1.0));
+        ranking.insert(&candidate("B", 3.0));
+        ranking.insert(&candidate("A", 2.0));
+        ranking.insert(&candidate("B", 0.0));
+        ranking.insert(&candidate("C", 2.0));
+        let ranked: Vec<(String, f64)> = ranking
+            .into_vec()
+            .into_iter()
+            .map(|c| (c.plain_text, c.score))
+            .collect();
+        assert_eq!(ranked, [(String::from("B"), 3.0), (String::from("A"), 2.0)]);
+    }
+
+    #[test]
+    #[cfg(any(feature = "two-square", feature = "four-square"))]
+    fn test_random_key_complete() {
+        let mut rng = Rng::new(7);
+        let options = PayloadOptions::default();
+        let key = random_key(&mut rng, options);
+        let mut letters: Vec<char> = key.square().to_vec();
+        letters.sort();
+        assert_eq!(letters, options.alphabet().collect::<Vec<char>>());
+    }
+}
@@ -0,0 +1,379 @@
+//! Recovery of both squares of a Two Square cipher. The cipher has two
+//! weaknesses the solver makes use of:
+//!
+//! - A digram whose first letter is in the same column of the top square
+//!   as its second letter in the bottom square is left unchanged. About one
+//!   in five digrams is transparent, so the most common English digrams
+//!   show up in the cipher text as they are. Assuming they are transparent
+//!   tells which letters share a column, which seeds the search with
+//!   squares way closer to the solution than random ones.
+//! - Encryption and decryption are the same, so a square which is right
+//!   for the plain text is right for the cipher text as well.
+//!
+//! As with the Four Square cipher, the squares are optimized in turns.
+//!
+
+use std::time::Duration;
+
+use super::checkpoint::{Checkpoint, Recorder};
+use super::{
+    best_of, random_key, random_mutation, rate, solver_options, CancellationToken, Candidate,
+    Progress, Ranking, Rng, Solver, Stop, Tracker,
+};
+use crate::analysis::scoring::FitnessScorer;
+use crate::analysis::{digram_frequencies, ENGLISH_DIGRAMS};
+use crate::errors::{CheckpointError, InvalidOptionError};
+use crate::options::PayloadOptions;
+use crate::playfair::{PlayFairKey, ROW_LENGTH};
+use crate::two_square::TwoSquare;
+
+// Number of the most common English digrams assumed to be transparent
+const TRANSPARENT_DIGRAMS: usize = 10;
+
+/// Two Square solver, see TwoSquareSolver::builder.
+///
+#[derive(Debug, Clone)]
+pub struct TwoSquareSolver {
+    restarts: usize,
+    rounds: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    transparency: bool,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+/// Builder for a TwoSquareSolver.
+///
+#[derive(Debug, Clone)]
+pub struct TwoSquareSolverBuilder {
+    restarts: usize,
+    rounds: usize,
+    iterations: usize,
+    seed: u64,
+    candidates: usize,
+    transparency: bool,
+    options: PayloadOptions,
+    cancellation: CancellationToken,
+    time_limit: Option<Duration>,
+}
+
+impl Default for TwoSquareSolverBuilder {
+    fn default() -> Self {
+        TwoSquareSolverBuilder {
+            restarts: 10,
+            rounds: 6,
+            iterations: 3_000,
+            seed: 0,
+            candidates: 5,
+            transparency: true,
+            options: PayloadOptions::default(),
+            cancellation: CancellationToken::default(),
+            time_limit: None,
+        }
+    }
+}
+
+impl TwoSquareSolverBuilder {
+    /// Number of searches, each starting from fresh squares. Defaults to 10.
+    pub fn restarts(mut self, restarts: usize) -> Self {
+        self.restarts = restarts;
+        self
+    }
+
+    /// Number of turns each square is optimized per search. Defaults to 6.
+    pub fn rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Number of mutations tried per square and round. Defaults to 3000.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Seed of the random squares and mutations. The same seed yields the
+    /// same result. Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Number of best candidates with distinct plain texts returned. Defaults to 5.
+    pub fn candidates(mut self, candidates: usize) -> Self {
+        self.candidates = candidates;
+        self
+    }
+
+    /// Seeds the squares by assuming common English digrams in the cipher
+    /// text are transparent. On by default, turn it off for cipher texts
+    /// not written in English.
+    pub fn transparency(mut self, transparency: bool) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Options of the squares tried, above all the omitted letter.
+    pub fn options(mut self, options: PayloadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Token to stop the solver early from another thread. None by default.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Time after which a crack stops and returns the best candidates found
+    /// so far. Unlimited by default.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn build(self) -> Result<TwoSquareSolver, InvalidOptionError> {
+        if self.restarts == 0 || self.rounds == 0 || self.iterations == 0 || self.candidates == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Restarts, rounds, iterations and candidates must be greater than 0",
+            )));
+        }
+        Ok(TwoSquareSolver {
+            restarts: self.restarts,
+            rounds: self.rounds,
+            iterations: self.iterations,
+            seed: self.seed,
+            candidates: self.candidates,
+            transparency: self.transparency,
+            options: solver_options(self.options),
+            cancellation: self.cancellation,
+            time_limit: self.time_limit,
+        })
+    }
+}
+
+impl TwoSquareSolver {
+    /// Returns a builder to configure the solver.
+    pub fn builder() -> TwoSquareSolverBuilder {
+        TwoSquareSolverBuilder::default()
+    }
+
+    // Returns the best squares of the search along with the number of
+    // squares tried.
+    fn search(
+        &self,
+        cipher_text: &str,
+        transparent: &[[char; 2]],
+        fitness: &impl FitnessScorer,
+        rng: &mut Rng,
+        stop: &Stop,
+    ) -> (Ranking<TwoSquare>, usize) {
+        let top = random_key(rng, self.options);
+        let bottom = seeded_bottom(&top, transparent, rng, self.options);
+        let mut current = rate(
+            TwoSquare::from_keys(top, bottom, self.options),
+            cipher_text,
+            fitness,
+        );
+        let mut ranking = Ranking::new(self.candidates);
+        ranking.insert(&current);
+        let mut tried = 0;
+        for round in 0..2 * self.rounds {
+            let top = round % 2 == 0;
+            for _ in 0..self.iterations {
+                if stop.reached() {
+                    return (ranking, tried);
+                }
+                tried += 1;
+                let mutation = random_mutation(rng);
+                // A letter is taken from one square by the position of the
+                // cipher letters in both, so now and then both squares are
+                // changed at once to get out of a local maximum
+                let (top_square, bottom_square) = match (top, rng.below(10)) {
+                    (_, 0) => (
+                        current.key.top().mutated(mutation),
+                        current.key.bottom().mutated(random_mutation(rng)),
+                    ),
+                    (true, _) => (
+                        current.key.top().mutated(mutation),
+                        current.key.bottom().clone(),
+                    ),
+                    (false, _) => (
+                        current.key.top().clone(),
+                        current.key.bottom().mutated(mutation),
+                    ),
+                };
+                let candidate = rate(
+                    TwoSquare::from_keys(top_square, bottom_square, self.options),
+                    cipher_text,
+                    fitness,
+                );
+                // Accepting equal scores lets the search move along plateaus
+                if candidate.score >= current.score {
+                    current = candidate;
+                    ranking.insert(&current);
+                }
+            }
+        }
+        (ranking, tried)
+    }
+}
+
+impl Solver for TwoSquareSolver {
+    type Key = TwoSquare;
+
+    /// Searches the squares which decrypt the cipher text to the plain
+    /// texts with the highest fitness. The squares are available through
+    /// top and bottom of the key of the candidate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{crack::two_square::TwoSquareSolver, two_square::TwoSquare};
+    /// use playfair_cipher::{crack::Solver, cryptable::Cypher};
+    ///
+    /// let crypt = TwoSquare::new("EXAMPLE", "KEYWORD").encrypt("help me obi wan kenobi").unwrap();
+    /// let solver = TwoSquareSolver::builder().restarts(2).build().unwrap();
+    /// let ranked = solver.crack(&crypt, |plain: &str| {
+    ///     plain.chars().zip("HELPMEOBIWANKENOBI".chars()).filter(|(a, b)| a == b).count() as f64
+    /// });
+    /// let best = &ranked[0];
+    /// assert_eq!(best.plain_text(), "HELPMEOBIWANKENOBI");
+    /// println!("{}\n{}", best.key().top(), best.key().bottom());
+    /// ```
+    fn crack_resumable(
+        &self,
+        cipher_text: &str,
+        fitness: impl FitnessScorer,
+        progress: impl Fn(&Progress) + Sync,
+        resume: Option<Checkpoint<TwoSquare>>,
+        checkpoint: impl Fn(&Checkpoint<TwoSquare>) + Sync,
+    ) -> Result<Vec<Candidate<TwoSquare>>, CheckpointError> {
+        let cipher_text = crate::normalize(cipher_text, &self.options);
+        let transparent = match self.transparency {
+            true => transparent_digrams(&cipher_text),
+            false => Vec::new(),
+        };
+        let resume = Checkpoint::resume(resume, "two_square", self.seed, &cipher_text)?;
+        let tracker = Tracker::new(self.restarts * 2 * self.rounds * self.iterations, &progress)
+            .resumed(&resume);
+        let recorder = Recorder::new(resume, self.candidates, &checkpoint);
+        let stop = Stop::new(&self.cancellation, self.time_limit);
+        Ok(best_of(
+            self.restarts,
+            2 * self.rounds * self.iterations,
+            &recorder,
+            |rng| {
+                let (searched, tried) =
+                    self.search(&cipher_text, &transparent, &fitness, rng, &stop);
+                tracker.advance(tried, searched.best());
+                (searched, tried)
+            },
+        ))
+    }
+}
+
+// The most common English digrams occurring in the cipher text at least twice.
+fn transparent_digrams(cipher_text: &str) -> Vec<[char; 2]> {
+    let frequencies = digram_frequencies(cipher_text);
+    ENGLISH_DIGRAMS
+        .iter()
+        .take(TRANSPARENT_DIGRAMS)
+        .map(|(digram, _)| *digram)
+        .filter(|digram| frequencies.get(digram).is_some_and(|count| *count >= 2))
+        .collect()
+}
+
+// A random bottom square with the second letter of every transparent digram
+// in the column of its first letter in the top square, as far as possible.
+fn seeded_bottom(
+    top: &PlayFairKey,
+    transparent: &[[char; 2]],
+    rng: &mut Rng,
+    options: PayloadOptions,
+) -> PlayFairKey {
+    let length = ROW_LENGTH as usize;
+    let mut square = ['*'; 25];
+    for [a, b] in transparent {
+        let (Some(column), false) = (
+            top.key
+                .iter()
+                .position(|c| c == a)
+                .map(|cell| cell % length),
+            square.contains(b),
+        ) else {
+            continue;
+        };
+        let free: Vec<usize> = (0..length)
+            .map(|row| row * length + column)
+            .filter(|cell| square[*cell] == '*')
+            .collect();
+        if !free.is_empty() {
+            square[free[rng.below(free.len())]] = *b;
+        }
+    }
+    let mut rest: Vec<char> = options.alphabet().filter(|c| !square.contains(c)).collect();
+    rng.shuffle(&mut rest);
+    for (cell, c) in square.iter_mut().filter(|c| **c == '*').zip(rest) {
+        *cell = c;
+    }
+    PlayFairKey::from_square_with_options(square, options)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(TwoSquareSolver::builder().rounds(0).build().is_err());
+    }
+
+    #[test]
+    fn test_seeded_bottom() {
+        let mut rng = Rng::new(5);
+        let options = PayloadOptions::default();
+        let top = PlayFairKey::new("EXAMPLE");
+        let bottom = seeded_bottom(
+            &top,
+            &[['T', 'H'], ['H', 'E'], ['T', 'E']],
+            &mut rng,
+            options,
+        );
+        let mut letters = bottom.square().to_vec();
+        letters.sort();
+        assert_eq!(letters, options.alphabet().collect::<Vec<char>>());
+        // T is in column 4 of the top square, H in column 1
+        assert_eq!(bottom.cell('H').unwrap() % 5, 4);
+        assert_eq!(bottom.cell('E').unwrap() % 5, 1);
+        // Transparent digrams are left as they are
+        let tsq = TwoSquare::from_keys(top, bottom, options);
+        assert_eq!(tsq.encrypt("TH").unwrap(), "TH");
+    }
+
+    #[test]
+    fn test_crack_known_plain_text() {
+        let tsq = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let crypt = tsq.encrypt("attack the north gate at dawn").unwrap();
+        let plain = tsq.decrypt(&crypt).unwrap();
+        let solver = TwoSquareSolver::builder()
+            .restarts(10)
+            .seed(0)
+            .build()
+            .unwrap();
+        let ranked = solver.crack(&crypt, |text: &str| {
+            text.chars()
+                .zip(plain.chars())
+                .filter(|(a, b)| a == b)
+                .count() as f64
+        });
+        let best = &ranked[0];
+        assert_eq!(best.plain_text(), plain);
+        assert_eq!(best.key().encrypt(&plain).unwrap(), crypt);
+    }
+}
@@ -1,18 +1,1215 @@
 //! Traits indicating the cryptablilty of a modul  
 
+use std::fmt;
+
 use crate::{
-    errors::CharNotInKeyError,
-    structs::{CryptModus, CryptResult},
+    binary,
+    digram::{self, Digram, Position, PositionedDigram, Rule, TraceStep},
+    errors::{BinaryError, CharNotInKeyError, CoordinateError, DecryptError, WriteError},
+    format::CiphertextFormatter,
+    options::PayloadOptions,
+    playfair::{PlayFairKey, ROW_LENGTH},
+    segment::{self, Segmentation},
+    seriation,
+    structs::{CryptChars, CryptModus, CryptResult, Payload, Step},
+    text::{CipherText, Plaintext},
 };
 
+/// Sample encrypted by Cypher::verify if none is given. Contains every
+/// letter.
+pub const PANGRAM: &str = "The quick brown fox jumps over the lazy dog";
+
+/// Result of Cypher::verify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// The sample as normalized for encryption.
+    pub normalized: String,
+    pub crypt: String,
+    pub decrypted: String,
+    /// Whether the decrypted text matches the sample, padding aside.
+    pub passed: bool,
+}
+
+/// How normalization changes a char of the input, see ChangedChar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CharChange {
+    /// The char is dropped, e.g. whitespace, digits or the omitted letter
+    /// if it has no substitute.
+    Dropped,
+    /// The char is replaced by the letters given, e.g. J by I or ß by SS.
+    Substituted(String),
+}
+
+/// A char of the input changed by normalization other than by uppercasing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedChar {
+    /// Index of the char in the input, counted in chars.
+    pub position: usize,
+    pub character: char,
+    pub change: CharChange,
+}
+
+/// Result of Cypher::validate_input.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    /// The chars dropped or substituted, ordered by position.
+    pub changes: Vec<ChangedChar>,
+    /// Number of letters of the normalized payload.
+    pub normalized_length: usize,
+    /// Number of padding letters inserted to split doubled letters.
+    pub fillers: usize,
+    /// Whether a padding letter is appended to complete the last digram.
+    pub padded: bool,
+    /// The last letter of a payload of odd length if the final digram
+    /// policy doesn't pad it: it's passed through or, in strict mode,
+    /// rejected.
+    pub unpaired: Option<char>,
+}
+
+impl ValidationReport {
+    /// Number of letters of the cipher text, grouping aside.
+    pub fn crypt_length(&self) -> usize {
+        self.normalized_length + self.fillers + usize::from(self.padded)
+    }
+
+    /// Whether every char of the input makes it into the cipher text as is.
+    pub fn is_lossless(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Result of Cypher::encrypt_with_outcome: the cipher text and what the
+/// encryption did to the payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptOutcome {
+    pub ciphertext: String,
+    /// Number of chars of the payload dropped by normalization.
+    pub dropped: usize,
+    /// Number of padding letters inserted to split doubled letters.
+    pub fillers: usize,
+    /// Whether a padding letter was appended to complete the last digram.
+    pub padded: bool,
+    /// Number of digrams encrypted.
+    pub digrams: usize,
+}
+
+// The squares of a cipher as laid out on paper, row by row, and for the
+// letters of a plain and a cipher digram the index of their square.
+pub(crate) struct Layout<'a> {
+    pub squares: Vec<&'a PlayFairKey>,
+    pub columns: usize,
+    pub plain: [usize; 2],
+    pub crypt: [usize; 2],
+}
+
+// The workings of the ciphers of the crate, which the default methods of
+// Cypher are built on.
 pub(crate) trait Crypt {
-    fn crypt_payload(&self, payload: &str, modus: &CryptModus)
-        -> Result<String, CharNotInKeyError>;
     fn crypt(&self, a: char, b: char, modus: &CryptModus)
         -> Result<CryptResult, CharNotInKeyError>;
+    fn payload_options(&self) -> &PayloadOptions;
+    // The squares of the cipher, None if they are unknown.
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt>;
+
+    fn crypt_payload(&self, payload: &str, modus: &CryptModus) -> Result<String, CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        let mut payload_iter = Payload::new(payload, self.payload_options());
+
+        payload_iter.crypt_payload(self, modus)
+    }
+}
+
+// Where the letters of a cipher of the crate are found in its squares,
+// which the methods of SquareCypher are built on.
+pub(crate) trait SquareCrypt {
+    // The squares the first and the second letter of a cipher text
+    // digram are taken from.
+    fn cipher_squares(&self) -> [&PlayFairKey; 2];
+    // The squares the first and the second letter of a plain text
+    // digram are taken from.
+    fn plain_squares(&self) -> [&PlayFairKey; 2];
+    fn layout(&self) -> Layout<'_>;
+    // The rule a digram is encrypted by.
+    fn rule(&self, a: char, b: char) -> Result<Rule, CharNotInKeyError>;
+}
+
+// Internals and SquareInternals are public within a private module, so
+// Cypher and SquareCypher can hand them out while nobody outside the crate
+// is able to name or construct them.
+mod private {
+    pub struct Internals<'a>(pub(crate) &'a dyn super::Crypt);
+
+    pub struct SquareInternals<'a>(pub(crate) &'a dyn super::SquareCrypt);
 }
 
+pub(crate) use private::{Internals, SquareInternals};
+
+// The Crypt of any cipher: the one of a cipher of the crate or, for a
+// cipher implemented elsewhere, one built on its digram methods and the
+// default options. The squares of such a cipher are unknown.
+pub(crate) enum Engine<'a, C: ?Sized> {
+    Native(&'a dyn Crypt),
+    Foreign(&'a C),
+}
+
+impl<C: ?Sized> Clone for Engine<'_, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: ?Sized> Copy for Engine<'_, C> {}
+
+pub(crate) fn engine<C: Cypher + ?Sized>(cipher: &C) -> Engine<'_, C> {
+    match cipher.internals() {
+        Some(internals) => Engine::Native(internals.0),
+        None => Engine::Foreign(cipher),
+    }
+}
+
+impl<'a, C: Cypher + ?Sized> Engine<'a, C> {
+    // The payload options, borrowed for as long as the cipher.
+    pub(crate) fn options(&self) -> &'a PayloadOptions {
+        match self {
+            Engine::Native(crypt) => crypt.payload_options(),
+            Engine::Foreign(_) => &PayloadOptions::DEFAULT,
+        }
+    }
+}
+
+impl<C: Cypher + ?Sized> Crypt for Engine<'_, C> {
+    fn crypt(
+        &self,
+        a: char,
+        b: char,
+        modus: &CryptModus,
+    ) -> Result<CryptResult, CharNotInKeyError> {
+        match self {
+            Engine::Native(crypt) => crypt.crypt(a, b, modus),
+            Engine::Foreign(cipher) => {
+                let [a, b] = match modus {
+                    CryptModus::Encrypt => cipher.encrypt_digram(a, b)?,
+                    CryptModus::Decrypt => cipher.decrypt_digram(a, b)?,
+                };
+                Ok(CryptResult { a, b })
+            }
+        }
+    }
+
+    fn payload_options(&self) -> &PayloadOptions {
+        self.options()
+    }
+
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt> {
+        match self {
+            Engine::Native(crypt) => crypt.square_crypt(),
+            Engine::Foreign(_) => None,
+        }
+    }
+}
+
+/// En- and decryption of strings. All other methods have a default, so a
+/// cipher implemented outside the crate gets them as well: they en- and
+/// decrypt digram by digram with its encrypt_digram and decrypt_digram and
+/// the default options. Those two en- and decrypt the digram as payload by
+/// default, a cipher building encrypt and decrypt on the other methods has
+/// to implement them. The methods telling where letters are found in the
+/// squares are provided by SquareCypher, which only the ciphers of the
+/// crate implement.
 pub trait Cypher {
     fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError>;
     fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError>;
+
+    // Hands the ciphers of the crate to the default methods, others have
+    // none.
+    #[doc(hidden)]
+    fn internals(&self) -> Option<Internals<'_>> {
+        None
+    }
+
+    /// Encrypts a single digram without any normalization of the payload, so
+    /// the characters must already be uppercase and part of the key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(pfc.encrypt_digram('H', 'I').unwrap(), ['B', 'M']);
+    /// ```
+    fn encrypt_digram(&self, a: char, b: char) -> Result<[char; 2], CharNotInKeyError> {
+        crypt_digram(self, a, b, &CryptModus::Encrypt)
+    }
+
+    /// Decrypts a single digram without any normalization of the payload, so
+    /// the characters must already be uppercase and part of the key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(pfc.decrypt_digram('B', 'M').unwrap(), ['H', 'I']);
+    /// ```
+    fn decrypt_digram(&self, a: char, b: char) -> Result<[char; 2], CharNotInKeyError> {
+        crypt_digram(self, a, b, &CryptModus::Decrypt)
+    }
+
+    /// Encrypts a string and writes the result into any `fmt::Write` sink,
+    /// without building an intermediate String.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let mut line = String::from("crypt: ");
+    /// pfc.encrypt_to_writer("hide the gold", &mut line).unwrap();
+    /// assert_eq!(line, "crypt: BMODZBXDNAGE");
+    /// ```
+    fn encrypt_to_writer(
+        &self,
+        payload: &str,
+        writer: &mut dyn fmt::Write,
+    ) -> Result<(), WriteError>
+    where
+        Self: Sized,
+    {
+        let engine = engine(self);
+        Payload::new(payload, engine.payload_options()).crypt_payload_to_writer(
+            &engine,
+            &CryptModus::Encrypt,
+            writer,
+        )
+    }
+
+    /// Decrypts a string and writes the result into any `fmt::Write` sink,
+    /// without building an intermediate String.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let mut line = String::from("plain: ");
+    /// pfc.decrypt_to_writer("BMODZBXDNAGE", &mut line).unwrap();
+    /// assert_eq!(line, "plain: HIDETHEGOLDX");
+    /// ```
+    fn decrypt_to_writer(
+        &self,
+        payload: &str,
+        writer: &mut dyn fmt::Write,
+    ) -> Result<(), WriteError>
+    where
+        Self: Sized,
+    {
+        let engine = engine(self);
+        Payload::new(payload, engine.payload_options()).crypt_payload_to_writer(
+            &engine,
+            &CryptModus::Decrypt,
+            writer,
+        )
+    }
+
+    /// Encrypts a string and hands the cipher text to the callback digram by
+    /// digram as it's produced, so e.g. a GUI or a sender can start before
+    /// the whole message is done. A chunk holds the two letters of a digram
+    /// and the space starting a new group, if any. On an error the chunks
+    /// delivered so far are the cipher text up to the offending digram.
+    /// A seriated cipher delivers the letters of a block once it's complete,
+    /// still two at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::builder().key("playfair example").grouping(5).build().unwrap();
+    /// let mut chunks = Vec::new();
+    /// pfc.encrypt_with("hide the gold", |chunk| chunks.push(chunk.to_string()))
+    ///     .unwrap();
+    /// assert_eq!(chunks, ["BM", "OD", "Z B", "XD", "NA", " GE"]);
+    /// ```
+    fn encrypt_with(
+        &self,
+        payload: &str,
+        mut callback: impl FnMut(&str),
+    ) -> Result<(), CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        let engine = engine(self);
+        Payload::new(payload, engine.payload_options()).crypt_payload_with(
+            &engine,
+            &CryptModus::Encrypt,
+            |chunk| -> Result<(), CharNotInKeyError> {
+                callback(chunk);
+                Ok(())
+            },
+        )
+    }
+
+    /// Decrypts a string and hands the plain text to the callback digram by
+    /// digram, see encrypt_with.
+    fn decrypt_with(
+        &self,
+        payload: &str,
+        mut callback: impl FnMut(&str),
+    ) -> Result<(), CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        let engine = engine(self);
+        Payload::new(payload, engine.payload_options()).crypt_payload_with(
+            &engine,
+            &CryptModus::Decrypt,
+            |chunk| -> Result<(), CharNotInKeyError> {
+                callback(chunk);
+                Ok(())
+            },
+        )
+    }
+
+    /// Decrypts a string after verifying it could be the result of an
+    /// encryption with this cipher: it must consist of an even number of
+    /// letters present in the key square. Whitespace is ignored, so grouped
+    /// cipher text is fine. Anything else is reported instead of being
+    /// silently dropped or substituted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    /// use playfair_cipher::errors::DecryptError;
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(pfc.decrypt_strict("BMODZ BXDNA GE").unwrap(), "HIDETHEGOLDX");
+    /// assert!(matches!(
+    ///     pfc.decrypt_strict("BMODZ BXDNA G"),
+    ///     Err(DecryptError::OddLength { length: 11 })
+    /// ));
+    /// assert!(matches!(
+    ///     pfc.decrypt_strict("BMODZ-BXDNA-GE"),
+    ///     Err(DecryptError::DroppedChar { character: '-', position: 5 })
+    /// ));
+    /// ```
+    fn decrypt_strict(&self, payload: &str) -> Result<String, DecryptError>
+    where
+        Self: Sized,
+    {
+        let engine = engine(self);
+        let options = engine.payload_options();
+        let mut length: usize = 0;
+        for (position, character) in payload.chars().enumerate() {
+            if character.is_whitespace() {
+                continue;
+            }
+            let upper = character.to_ascii_uppercase();
+            if !upper.is_ascii_uppercase() {
+                return Err(DecryptError::DroppedChar {
+                    character,
+                    position,
+                });
+            }
+            if upper == options.omit() {
+                return Err(CharNotInKeyError::new(
+                    format!(
+                        "Only chars A-Z possible - '{}' is not part of the key",
+                        upper
+                    ),
+                    upper,
+                )
+                .at(length, position)
+                .into());
+            }
+            length += 1;
+        }
+        if !length.is_multiple_of(2) {
+            return Err(DecryptError::OddLength { length });
+        }
+        Ok(self.decrypt(payload)?)
+    }
+
+    /// Checks a payload before encrypting it: reports the chars which would
+    /// be dropped or substituted, the length of the normalized payload and
+    /// the padding letters which would be inserted - without encrypting
+    /// anything. Handy for a UI to warn the user up front.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::{CharChange, Cypher}};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let report = pfc.validate_input("Jill's 2 ball");
+    /// assert_eq!(report.changes[0].change, CharChange::Substituted(String::from("I")));
+    /// assert_eq!(report.changes[1].character, '\'');
+    /// assert_eq!(report.changes[2].change, CharChange::Dropped);
+    /// // IILLSBALL - II and the final LL are split, the last L is padded
+    /// assert_eq!(report.normalized_length, 9);
+    /// assert_eq!(report.fillers, 2);
+    /// assert!(report.padded);
+    /// assert_eq!(report.crypt_length(), 12);
+    /// ```
+    fn validate_input(&self, payload: &str) -> ValidationReport {
+        let engine = engine(self);
+        let options = engine.payload_options();
+        let changes = changed_chars(payload, options).collect();
+        let mut payload_iter = Payload::new(payload, options);
+        let mut unpaired = None;
+        while let Some(step) = payload_iter.next_digram(&CryptModus::Encrypt) {
+            if let Step::Lone(c) = step {
+                unpaired = Some(c);
+            }
+        }
+        ValidationReport {
+            changes,
+            normalized_length: payload_iter.payload.len(),
+            fillers: payload_iter.fillers,
+            padded: payload_iter.padded,
+            unpaired,
+        }
+    }
+
+    /// Encrypts a string like encrypt and returns the cipher text along
+    /// with its grouping, see the text module.
+    fn encrypt_text(&self, payload: &str) -> Result<CipherText, CharNotInKeyError> {
+        Ok(CipherText::new(
+            &self.encrypt(payload)?,
+            engine(self).payload_options().grouping(),
+        ))
+    }
+
+    /// Encrypts plain text, see the text module. Same as encrypt_text, but
+    /// the type makes sure it's no cipher text.
+    fn encrypt_typed(&self, plain: &Plaintext) -> Result<CipherText, CharNotInKeyError> {
+        self.encrypt_text(plain.as_str())
+    }
+
+    /// Decrypts cipher text into plain text, see the text module. Grouping
+    /// and casing of the cipher text are ignored.
+    fn decrypt_typed(&self, crypt: &CipherText) -> Result<Plaintext, CharNotInKeyError> {
+        Ok(Plaintext::new(self.decrypt(crypt.as_str())?))
+    }
+
+    /// Encrypts a string lazily, yielding the cipher text char by char as
+    /// encrypt returns it, including the spaces between groups. The result
+    /// is never held as a whole, so the iterator composes with other
+    /// adapters in constant memory. It ends after the first error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::builder()
+    ///     .key("playfair example")
+    ///     .grouping(5)
+    ///     .build()
+    ///     .unwrap();
+    /// let lower: String = pfc
+    ///     .encrypt_iter("hide the gold")
+    ///     .map(|c| c.map(|c| c.to_ascii_lowercase()))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(lower, "bmodz bxdna ge");
+    /// ```
+    fn encrypt_iter<'a>(
+        &'a self,
+        payload: &'a str,
+    ) -> impl Iterator<Item = Result<char, CharNotInKeyError>> + 'a
+    where
+        Self: Sized,
+    {
+        CryptChars::new(engine(self), payload, CryptModus::Encrypt)
+    }
+
+    /// Encrypts a string like encrypt and counts what happened on the way,
+    /// for monitoring or batch jobs which want the numbers without
+    /// comparing strings. See validate_input for the details on the changes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let outcome = pfc.encrypt_with_outcome("Hide the gold!").unwrap();
+    /// assert_eq!(outcome.ciphertext, "BMODZBXDNAGE");
+    /// assert_eq!(outcome.dropped, 3);
+    /// assert_eq!((outcome.fillers, outcome.padded), (0, true));
+    /// assert_eq!(outcome.digrams, 6);
+    /// ```
+    fn encrypt_with_outcome(&self, payload: &str) -> Result<EncryptOutcome, CharNotInKeyError> {
+        let engine = engine(self);
+        let mut payload_iter = Payload::new(payload, engine.payload_options());
+        let ciphertext = payload_iter.crypt_payload(&engine, &CryptModus::Encrypt)?;
+        let letters = payload_iter.payload.len() + payload_iter.fillers;
+        Ok(EncryptOutcome {
+            ciphertext,
+            dropped: payload.chars().count() - payload_iter.derived(),
+            fillers: payload_iter.fillers,
+            padded: payload_iter.padded,
+            digrams: (letters + usize::from(payload_iter.padded)) / 2,
+        })
+    }
+
+    /// Encrypts a string like encrypt, handing every char normalization
+    /// drops or substitutes to on_drop first, with its position and what
+    /// happens to it. The hook may log or warn - or abort the encryption by
+    /// returning an error, which is returned as is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::{CharChange, Cypher}};
+    /// use playfair_cipher::errors::CharNotInKeyError;
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let mut dropped = Vec::new();
+    /// let crypt = pfc
+    ///     .encrypt_on_drop("hide 2 gold", |changed| {
+    ///         if changed.change == CharChange::Dropped {
+    ///             dropped.push(changed.position);
+    ///         }
+    ///         Ok::<(), CharNotInKeyError>(())
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(crypt, pfc.encrypt("hide 2 gold").unwrap());
+    /// assert_eq!(dropped, [4, 5, 6]);
+    /// ```
+    fn encrypt_on_drop<E: From<CharNotInKeyError>>(
+        &self,
+        payload: &str,
+        mut on_drop: impl FnMut(&ChangedChar) -> Result<(), E>,
+    ) -> Result<String, E>
+    where
+        Self: Sized,
+    {
+        for changed in changed_chars(payload, engine(self).payload_options()) {
+            on_drop(&changed)?;
+        }
+        Ok(self.encrypt(payload)?)
+    }
+
+    /// Encrypts a string and returns the result digram by digram together
+    /// with where it is found in the cipher text and where its letters come
+    /// from in the normalized and in the original payload. Handy to map
+    /// cipher text back to the input, e.g. to highlight it or to place a
+    /// crib. A last letter passed through without partner is no digram and
+    /// left out, see FinalDigram.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::builder().key("playfair example").grouping(5).build().unwrap();
+    /// let digrams = pfc.encrypt_positioned("Hide the gold!").unwrap();
+    /// // TH of "the" is encrypted to ZB, found at index 4 of "BMODZ BXDNA GE"
+    /// assert_eq!(digrams[2].plain, ['T', 'H']);
+    /// assert_eq!(digrams[2].crypt_position, 4);
+    /// assert_eq!(digrams[2].positions, [Some(4), Some(5)]);
+    /// assert_eq!(digrams[2].original_positions, [Some(5), Some(6)]);
+    /// // The last D is padded
+    /// assert_eq!(digrams[5].original_positions, [Some(12), None]);
+    /// ```
+    fn encrypt_positioned(
+        &self,
+        payload: &str,
+    ) -> Result<Vec<PositionedDigram>, CharNotInKeyError> {
+        let engine = engine(self);
+        let mut payload_iter = Payload::new(payload, engine.payload_options());
+        let grouping = payload_iter.options.grouping;
+        // The letters of a seriated payload are written back line by line
+        let (period, paired) = (payload_iter.options.seriation, payload_iter.order.len());
+        let mut digrams =
+            Vec::with_capacity(payload_iter.output_capacity(&CryptModus::Encrypt) / 2);
+        payload_iter.crypt_digrams_with(
+            &engine,
+            &CryptModus::Encrypt,
+            |plain, origins, digram_crypt| -> Result<(), CharNotInKeyError> {
+                let letters = match period {
+                    Some(period) => seriation::unpaired_index(2 * digrams.len(), period, paired),
+                    None => 2 * digrams.len(),
+                };
+                digrams.push(PositionedDigram {
+                    plain,
+                    crypt: [digram_crypt.a, digram_crypt.b],
+                    crypt_position: letters + grouping.map_or(0, |g| letters / g),
+                    positions: origins.map(|origin| origin.map(|(position, _)| position)),
+                    original_positions: origins.map(|origin| origin.map(|(_, original)| original)),
+                });
+                Ok(())
+            },
+        )?;
+        Ok(digrams)
+    }
+
+    /// Encrypts a string and formats the cipher text with the formatter,
+    /// see the module format. Formatters can be combined as tuple, they
+    /// are applied from left to right.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    /// use playfair_cipher::format::{Case, Groups};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let formatter = (Groups::new(4, "-"), Case::Lower);
+    /// assert_eq!(
+    ///     pfc.encrypt_formatted("hide the gold", &formatter).unwrap(),
+    ///     "bmod-zbxd-nage"
+    /// );
+    /// ```
+    fn encrypt_formatted(
+        &self,
+        payload: &str,
+        formatter: &impl CiphertextFormatter,
+    ) -> Result<String, CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        formatter.format(self, &self.encrypt(payload)?)
+    }
+
+    /// Self test of the cipher: encrypts the sample, or PANGRAM if there's
+    /// none, decrypts the result and checks the round trip. The decrypted
+    /// text has to match the normalized sample after dropping the padding
+    /// letter from both. Handy as sanity check after loading a key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let verification = pfc.verify(Some("hide the gold")).unwrap();
+    /// assert!(verification.passed);
+    /// assert_eq!(verification.decrypted, "HIDETHEGOLDX");
+    /// assert!(pfc.verify(None).unwrap().passed);
+    /// ```
+    fn verify(&self, sample: Option<&str>) -> Result<Verification, CharNotInKeyError> {
+        let sample = sample.unwrap_or(PANGRAM);
+        let engine = engine(self);
+        let options = engine.payload_options();
+        let normalized = Payload::new(sample, options).payload.into_owned();
+        let crypt = self.encrypt(sample)?;
+        let decrypted = self.decrypt(&crypt)?;
+        let without_padding =
+            |text: &str| -> String { text.chars().filter(|c| *c != options.padding).collect() };
+        Ok(Verification {
+            passed: without_padding(&decrypted) == without_padding(&normalized),
+            normalized,
+            crypt,
+            decrypted,
+        })
+    }
+
+    /// Encrypts arbitrary bytes, e.g. a small image. The bytes are encoded
+    /// into letters of the square first, two per byte, see the binary
+    /// module.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let data = b"\x89PNG\r\n";
+    /// let crypt = pfc.encrypt_bytes(data).unwrap();
+    /// assert_eq!(crypt.len(), 2 * data.len());
+    /// assert_eq!(pfc.decrypt_bytes(&crypt).unwrap(), data);
+    /// ```
+    fn encrypt_bytes(&self, data: &[u8]) -> Result<String, CharNotInKeyError> {
+        self.encrypt(&binary::encode(data, engine(self).payload_options()))
+    }
+
+    /// Decrypts cipher text produced by encrypt_bytes back into the bytes.
+    fn decrypt_bytes(&self, crypt: &str) -> Result<Vec<u8>, BinaryError> {
+        binary::decode(&self.decrypt(crypt)?, engine(self).payload_options())
+    }
+
+    /// Splits the payload into segments and encrypts each of them on its
+    /// own, keeping the text between them, see the segment module.
+    fn encrypt_segmented(
+        &self,
+        payload: &str,
+        segmentation: Segmentation,
+    ) -> Result<String, CharNotInKeyError> {
+        segment::crypt_segmented(self, payload, segmentation, &CryptModus::Encrypt)
+    }
+
+    /// Decrypts cipher text produced by encrypt_segmented with the same
+    /// segmentation.
+    fn decrypt_segmented(
+        &self,
+        crypt: &str,
+        segmentation: Segmentation,
+    ) -> Result<String, CharNotInKeyError> {
+        segment::crypt_segmented(self, crypt, segmentation, &CryptModus::Decrypt)
+    }
+
+    /// Same as encrypt but accepts anything which can be borrowed as a str,
+    /// e.g. `String`, `&String` or `Cow<str>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let payload = String::from("hide the gold");
+    /// assert_eq!(pfc.encrypt_from(&payload).unwrap(), "BMODZBXDNAGE");
+    /// assert_eq!(pfc.encrypt_from(payload).unwrap(), "BMODZBXDNAGE");
+    /// assert_eq!(pfc.encrypt_from(Cow::from("hide the gold")).unwrap(), "BMODZBXDNAGE");
+    /// ```
+    fn encrypt_from(&self, payload: impl AsRef<str>) -> Result<String, CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        self.encrypt(payload.as_ref())
+    }
+
+    /// Same as decrypt but accepts anything which can be borrowed as a str,
+    /// e.g. `String`, `&String` or `Cow<str>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let crypt = pfc.encrypt_from(String::from("hide the gold")).unwrap();
+    /// assert_eq!(pfc.decrypt_from(crypt).unwrap(), "HIDETHEGOLDX");
+    /// ```
+    fn decrypt_from(&self, payload: impl AsRef<str>) -> Result<String, CharNotInKeyError>
+    where
+        Self: Sized,
+    {
+        self.decrypt(payload.as_ref())
+    }
+
+    /// Encrypts many payloads in parallel. The key is immutable, so it's
+    /// shared across all threads of the rayon thread pool. The results are
+    /// in the same order as the payloads.
+    ///
+    /// Only available with the feature `rayon`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let crypts = pfc.encrypt_all(&["hide the gold", "in the tree stump"]);
+    /// assert_eq!(crypts[0].as_deref().unwrap(), "BMODZBXDNAGE");
+    /// assert_eq!(crypts[1].as_deref().unwrap(), "RKZBIVEXMOUVIF");
+    /// ```
+    #[cfg(feature = "rayon")]
+    fn encrypt_all(&self, payloads: &[&str]) -> Vec<Result<String, CharNotInKeyError>>
+    where
+        Self: Sync + Sized,
+    {
+        use rayon::prelude::*;
+        payloads.par_iter().map(|p| self.encrypt(p)).collect()
+    }
+
+    /// Decrypts many payloads in parallel, see encrypt_all.
+    ///
+    /// Only available with the feature `rayon`.
+    #[cfg(feature = "rayon")]
+    fn decrypt_all(&self, payloads: &[&str]) -> Vec<Result<String, CharNotInKeyError>>
+    where
+        Self: Sync + Sized,
+    {
+        use rayon::prelude::*;
+        payloads.par_iter().map(|p| self.decrypt(p)).collect()
+    }
+}
+
+/// The methods of the ciphers of the crate telling where letters are found
+/// in their squares. The squares of a cipher implemented elsewhere are
+/// unknown, so only the ciphers of the crate implement it.
+pub trait SquareCypher: Cypher {
+    // Hands the squares to the default methods
+    #[doc(hidden)]
+    fn square_internals(&self) -> SquareInternals<'_>;
+
+    /// Encrypts a string and returns the result digram by digram: the
+    /// digram of the normalized payload, the digram it was encrypted to and
+    /// the rule applied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::SquareCypher};
+    /// use playfair_cipher::digram::Rule;
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let digrams = pfc.encrypt_digrams("hide the gold").unwrap();
+    /// assert_eq!(digrams.len(), 6);
+    /// assert_eq!(digrams[0].plain, ['H', 'I']);
+    /// assert_eq!(digrams[0].crypt, ['B', 'M']);
+    /// assert_eq!(digrams[0].rule, Rule::Rectangle);
+    /// assert_eq!(digrams[1].rule, Rule::Column);
+    /// ```
+    fn encrypt_digrams(&self, payload: &str) -> Result<Vec<Digram>, CharNotInKeyError> {
+        let squares = self.square_internals().0;
+        let engine = engine(self);
+        let mut payload_iter = Payload::new(payload, engine.payload_options());
+        let mut digrams =
+            Vec::with_capacity(payload_iter.output_capacity(&CryptModus::Encrypt) / 2);
+        payload_iter.crypt_digrams_with(
+            &engine,
+            &CryptModus::Encrypt,
+            |[a, b], _, digram_crypt| {
+                digrams.push(Digram {
+                    plain: [a, b],
+                    crypt: [digram_crypt.a, digram_crypt.b],
+                    rule: squares.rule(a, b)?,
+                });
+                Ok::<(), CharNotInKeyError>(())
+            },
+        )?;
+        Ok(digrams)
+    }
+
+    /// Encrypts a string step by step: for each digram the rule applied and
+    /// where its letters and the ones it was encrypted to are found in the
+    /// squares, see TraceStep.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::SquareCypher};
+    /// use playfair_cipher::digram::{Position, Rule};
+    ///
+    /// // P L A Y F
+    /// // I R E X M
+    /// // B C D G H
+    /// // K N O Q S
+    /// // T U V W Z
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let steps = pfc.encrypt_traced("hide").unwrap();
+    /// assert_eq!(steps[1].plain, ['D', 'E']);
+    /// assert_eq!(steps[1].rule, Rule::Column);
+    /// assert_eq!(steps[1].plain_positions[0], Position { row: 2, column: 2 });
+    /// assert_eq!(steps[1].crypt, ['O', 'D']);
+    /// assert_eq!(steps[1].crypt_positions[0], Position { row: 3, column: 2 });
+    /// ```
+    fn encrypt_traced(&self, payload: &str) -> Result<Vec<TraceStep>, CharNotInKeyError> {
+        let locate = |squares: [&PlayFairKey; 2], [a, b]: [char; 2]| {
+            let cells = [squares[0].cell(a)?, squares[1].cell(b)?];
+            Ok::<_, CharNotInKeyError>(cells.map(|cell| Position {
+                row: cell / ROW_LENGTH,
+                column: cell % ROW_LENGTH,
+            }))
+        };
+        let squares = self.square_internals().0;
+        self.encrypt_digrams(payload)?
+            .into_iter()
+            .map(|digram| {
+                Ok(TraceStep {
+                    plain: digram.plain,
+                    crypt: digram.crypt,
+                    rule: digram.rule,
+                    plain_positions: locate(squares.plain_squares(), digram.plain)?,
+                    crypt_positions: locate(squares.cipher_squares(), digram.crypt)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Explains an encryption step by step in plain text, e.g. for a
+    /// tutorial in the terminal. For each digram the squares are drawn with
+    /// the plain letters marked as [A], the cipher letters as (A) and a
+    /// letter which is both as <A>.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::SquareCypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(
+    ///     pfc.explain("hi").unwrap(),
+    ///     concat!(
+    ///         "HI -> BM (rectangle)\n",
+    ///         " P  L  A  Y  F\n",
+    ///         "[I] R  E  X (M)\n",
+    ///         "(B) C  D  G [H]\n",
+    ///         " K  N  O  Q  S\n",
+    ///         " T  U  V  W  Z\n",
+    ///     )
+    /// );
+    /// ```
+    fn explain(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        let layout = self.square_internals().0.layout();
+        Ok(self
+            .encrypt_traced(payload)?
+            .iter()
+            .map(|step| digram::render_step(&layout, step))
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    /// Encrypts a string and emits the cipher text as coordinates within
+    /// the key square instead of letters: for each letter its row and
+    /// column, both counted from 1. The first letter of a digram is looked
+    /// up in the square it was taken from, e.g. the top square of the Two
+    /// Square cipher, the second one in the other square.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::SquareCypher};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(
+    ///     pfc.encrypt_coordinates("hide the gold").unwrap(),
+    ///     "31 25 43 33 55 31 24 33 42 13 34 23"
+    /// );
+    /// ```
+    fn encrypt_coordinates(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        to_coordinates(
+            self.square_internals().0.cipher_squares(),
+            seriation_period(self),
+            &self.encrypt(payload)?,
+        )
+    }
+
+    /// Decrypts cipher text given as coordinates, see encrypt_coordinates.
+    /// Whitespace is ignored, so "3125" is the same as "31 25".
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::SquareCypher};
+    /// use playfair_cipher::errors::CoordinateError;
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(
+    ///     pfc.decrypt_coordinates("31 25 43 33 55 31 24 33 42 13 34 23").unwrap(),
+    ///     "HIDETHEGOLDX"
+    /// );
+    /// assert!(matches!(
+    ///     pfc.decrypt_coordinates("31 26"),
+    ///     Err(CoordinateError::InvalidChar { character: '6', position: 4 })
+    /// ));
+    /// ```
+    fn decrypt_coordinates(&self, coordinates: &str) -> Result<String, CoordinateError> {
+        let squares = self.square_internals().0.cipher_squares();
+        let mut digits: Vec<u8> = Vec::with_capacity(coordinates.len());
+        for (position, character) in coordinates.chars().enumerate() {
+            match character {
+                '1'..='5' => digits.push(character as u8 - b'1'),
+                c if c.is_whitespace() => continue,
+                _ => {
+                    return Err(CoordinateError::InvalidChar {
+                        character,
+                        position,
+                    })
+                }
+            }
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err(CoordinateError::OddLength {
+                length: digits.len(),
+            });
+        }
+        let (period, length) = (seriation_period(self), digits.len() / 2);
+        let crypt: String = digits
+            .chunks(2)
+            .enumerate()
+            .map(|(idx, cell)| {
+                squares[seriation::member(idx, period, length)].key
+                    [(cell[0] * ROW_LENGTH + cell[1]) as usize]
+            })
+            .collect();
+        Ok(self.decrypt(&crypt)?)
+    }
+}
+
+// The seriation period of a cipher of the crate.
+pub(crate) fn seriation_period(cipher: &(impl Cypher + ?Sized)) -> Option<usize> {
+    cipher
+        .internals()
+        .and_then(|internals| internals.0.payload_options().seriation)
+}
+
+// Converts cipher text to coordinates within the squares its letters are
+// taken from. Whitespace is ignored. The letters of seriated cipher text
+// are written line by line, so which square a letter is taken from depends
+// on the line and not on the position.
+pub(crate) fn to_coordinates(
+    squares: [&PlayFairKey; 2],
+    period: Option<usize>,
+    crypt: &str,
+) -> Result<String, CharNotInKeyError> {
+    let letters: Vec<char> = crypt.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut coordinates = String::with_capacity(letters.len() * 3);
+    for (idx, c) in letters.iter().copied().enumerate() {
+        let cell = squares[seriation::member(idx, period, letters.len())].cell(c)?;
+        if idx > 0 {
+            coordinates.push(' ');
+        }
+        coordinates.push((b'1' + cell / ROW_LENGTH) as char);
+        coordinates.push((b'1' + cell % ROW_LENGTH) as char);
+    }
+    Ok(coordinates)
+}
+
+// The chars of the payload normalization drops or substitutes, in order.
+fn changed_chars<'a>(
+    payload: &'a str,
+    options: &'a PayloadOptions,
+) -> impl Iterator<Item = ChangedChar> + 'a {
+    payload
+        .chars()
+        .enumerate()
+        .filter_map(|(position, character)| {
+            let normalized: String = character
+                .to_uppercase()
+                .filter(char::is_ascii_uppercase)
+                .filter_map(|c| options.substitute(c))
+                .collect();
+            let change = if normalized.is_empty() {
+                CharChange::Dropped
+            } else if normalized.len() == 1
+                && normalized.starts_with(character.to_ascii_uppercase())
+            {
+                return None;
+            } else {
+                CharChange::Substituted(normalized)
+            };
+            Some(ChangedChar {
+                position,
+                character,
+                change,
+            })
+        })
+}
+
+// Runs a digram of a cipher of the crate through its Crypt, any other
+// cipher en- or decrypts it as payload.
+fn crypt_digram<C: Cypher + ?Sized>(
+    cipher: &C,
+    a: char,
+    b: char,
+    modus: &CryptModus,
+) -> Result<[char; 2], CharNotInKeyError> {
+    if let Some(internals) = cipher.internals() {
+        let digram_crypt = internals.0.crypt(a, b, modus)?;
+        return Ok([digram_crypt.a, digram_crypt.b]);
+    }
+    let digram = String::from_iter([a, b]);
+    let crypt = match modus {
+        CryptModus::Encrypt => cipher.encrypt(&digram)?,
+        CryptModus::Decrypt => cipher.decrypt(&digram)?,
+    };
+    let mut letters = crypt.chars().filter(|c| !c.is_whitespace());
+    match (letters.next(), letters.next(), letters.next()) {
+        (Some(a), Some(b), None) => Ok([a, b]),
+        _ => Err(CharNotInKeyError::new(
+            format!("'{}' isn't en- or decrypted as a single digram", digram),
+            a,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Swaps the letters of each digram
+    struct Swap;
+
+    impl Cypher for Swap {
+        fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+            let mut crypt = String::new();
+            self.encrypt_with(payload, |chunk| crypt.push_str(chunk))?;
+            Ok(crypt)
+        }
+
+        fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+            let mut plain = String::new();
+            self.decrypt_with(payload, |chunk| plain.push_str(chunk))?;
+            Ok(plain)
+        }
+
+        fn encrypt_digram(&self, a: char, b: char) -> Result<[char; 2], CharNotInKeyError> {
+            Ok([b, a])
+        }
+
+        fn decrypt_digram(&self, a: char, b: char) -> Result<[char; 2], CharNotInKeyError> {
+            Ok([b, a])
+        }
+    }
+
+    // Reverses the letters of the payload
+    struct Reverse;
+
+    impl Cypher for Reverse {
+        fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+            Ok(payload
+                .chars()
+                .rev()
+                .map(|c| c.to_ascii_uppercase())
+                .collect())
+        }
+
+        fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+            self.encrypt(payload)
+        }
+    }
+
+    #[test]
+    fn test_foreign_digram() {
+        assert_eq!(Reverse.encrypt_digram('H', 'I').unwrap(), ['I', 'H']);
+        assert_eq!(Reverse.decrypt_digram('I', 'H').unwrap(), ['H', 'I']);
+        assert!(Reverse.encrypt_digram('H', ' ').is_err());
+        let lazy: String = Reverse
+            .encrypt_iter("hide")
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lazy, "IHED");
+    }
+
+    #[test]
+    fn test_foreign_cypher() {
+        assert_eq!(Swap.encrypt("hide the gold").unwrap(), "IHEDHTGELOXD");
+        assert_eq!(Swap.decrypt("IHEDHTGELOXD").unwrap(), "HIDETHEGOLDX");
+        let lazy: String = Swap.encrypt_iter("jam").collect::<Result<_, _>>().unwrap();
+        assert_eq!(lazy, "AIXM");
+        let mut line = String::new();
+        Swap.encrypt_to_writer("hide", &mut line).unwrap();
+        assert_eq!(line, "IHED");
+        let outcome = Swap.encrypt_with_outcome("hide!").unwrap();
+        assert_eq!((outcome.ciphertext.as_str(), outcome.dropped), ("IHED", 1));
+        assert_eq!(
+            Swap.decrypt_bytes(&Swap.encrypt_bytes(b"ok").unwrap())
+                .unwrap(),
+            b"ok"
+        );
+    }
+
+    #[test]
+    fn test_foreign_coordinates() {
+        use crate::format::Coordinates;
+
+        // The squares of a foreign cipher are unknown
+        let error = Swap.encrypt_formatted("hide", &Coordinates).unwrap_err();
+        assert_eq!(error.character, 'I');
+        assert_eq!(Swap.encrypt_formatted("", &Coordinates).unwrap(), "");
+    }
 }
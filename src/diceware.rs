@@ -0,0 +1,211 @@
+//! Generator of random keywords which are still easy to remember: like
+//! diceware passphrases they are made of words drawn from a list, by the
+//! generator or by rolling three dice per word. Available with the feature
+//! `diceware`.
+//!
+//! ```
+//! use playfair_cipher::cryptable::Cypher;
+//! use playfair_cipher::diceware::KeywordGenerator;
+//!
+//! let mut generator = KeywordGenerator::builder().words(3).seed(7).build().unwrap();
+//! let (phrase, key) = generator.key();
+//! assert_eq!(phrase.split(' ').count(), 3);
+//! assert_eq!(key.decrypt(&key.encrypt("hide the gold").unwrap()).unwrap(), "HIDETHEGOLDX");
+//! ```
+//!
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::crack::Rng;
+use crate::errors::InvalidOptionError;
+use crate::playfair::PlayFairKey;
+
+/// The built-in list of 6 * 6 * 6 words, so a word can be chosen by
+/// rolling three dice, see roll.
+pub const WORDLIST: [&str; 216] = [
+    "ACORN", "ACTOR", "ADOBE", "AGENT", "ALARM", "ALBUM", "ALLEY", "AMBER", "ANGEL", "ANKLE",
+    "ANVIL", "APPLE", "APRON", "ARENA", "ARROW", "ASPEN", "ATLAS", "ATTIC", "AWARD", "BACON",
+    "BADGE", "BAGEL", "BAKER", "BAMBOO", "BANJO", "BARGE", "BASIL", "BEACH", "BEARD", "BERRY",
+    "BISON", "BLADE", "BLAZE", "BLOOM", "BOARD", "BONUS", "BOOTS", "BREAD", "BRICK", "BRIDE",
+    "BROOK", "BROOM", "BRUSH", "BUGLE", "CABIN", "CABLE", "CACTUS", "CAMEL", "CANDY", "CANOE",
+    "CANYON", "CARGO", "CAROL", "CEDAR", "CHALK", "CHARM", "CHEEK", "CHESS", "CHIEF", "CHIME",
+    "CIDER", "CIGAR", "CLAMP", "CLIFF", "CLOAK", "CLOCK", "CLOUD", "CLOWN", "COBRA", "COCOA",
+    "COMET", "CORAL", "COUCH", "CRANE", "CRATE", "CROWN", "DAISY", "DANCE", "DELTA", "DENIM",
+    "DIARY", "DINGO", "DIVER", "DOUGH", "DRAMA", "DREAM", "DRIFT", "DRUM", "EAGLE", "EASEL",
+    "ELBOW", "ELDER", "EMBER", "FABLE", "FAIRY", "FALCON", "FENCE", "FERRY", "FIELD", "FLAME",
+    "FLASK", "FLEET", "FLUTE", "FORGE", "FOSSIL", "FROST", "FUDGE", "GECKO", "GHOST", "GIANT",
+    "GINGER", "GLADE", "GLOVE", "GOOSE", "GRAPE", "GRAVY", "GUAVA", "HARBOR", "HAZEL", "HERON",
+    "HONEY", "HORNET", "IGLOO", "IRIS", "IVORY", "JACKET", "JAGUAR", "JELLY", "JEWEL", "JOCKEY",
+    "JUICE", "KAYAK", "KERNEL", "KETTLE", "KIOSK", "KOALA", "LADDER", "LAGOON", "LEMON", "LILAC",
+    "LINEN", "LLAMA", "LOCKET", "LOTUS", "LUNAR", "MAGNET", "MANGO", "MAPLE", "MARBLE", "MEADOW",
+    "MELON", "MINT", "MOCHA", "MOOSE", "MOSAIC", "NECTAR", "NEEDLE", "NOBLE", "NOVEL", "NUTMEG",
+    "OASIS", "OCEAN", "OLIVE", "ONION", "OPERA", "ORBIT", "OTTER", "OYSTER", "PADDLE", "PANDA",
+    "PARROT", "PASTRY", "PEACH", "PEARL", "PEBBLE", "PEPPER", "PIANO", "PILOT", "PIXEL", "PLAZA",
+    "PLUM", "POPPY", "PRISM", "QUAIL", "QUARTZ", "QUILL", "RADAR", "RAVEN", "RELIC", "RIBBON",
+    "RIVER", "ROBIN", "ROCKET", "SADDLE", "SALMON", "SATIN", "SCARF", "SHELL", "SLATE", "SPICE",
+    "SQUID", "STORK", "SUGAR", "SWAMP", "TANGO", "TIGER", "TOAST", "TOPAZ", "TULIP", "TUNDRA",
+    "VELVET", "VIOLA", "WALNUT", "WHALE", "WILLOW", "ZEBRA",
+];
+
+/// The word of the built-in list three dice point to, each showing 1 to 6.
+/// Returns None if a die shows anything else.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::diceware::roll;
+///
+/// assert_eq!(roll([1, 1, 1]), Some("ACORN"));
+/// assert_eq!(roll([6, 6, 6]), Some("ZEBRA"));
+/// assert_eq!(roll([0, 1, 7]), None);
+/// ```
+pub fn roll(dice: [u8; 3]) -> Option<&'static str> {
+    if dice.iter().any(|die| !(1..=6).contains(die)) {
+        return None;
+    }
+    let idx = dice
+        .iter()
+        .fold(0, |idx, die| idx * 6 + (*die - 1) as usize);
+    Some(WORDLIST[idx])
+}
+
+/// Draws keywords of random words, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct KeywordGenerator {
+    list: Vec<String>,
+    words: usize,
+    rng: Rng,
+}
+
+/// Builder for a KeywordGenerator.
+#[derive(Debug, Clone, Default)]
+pub struct KeywordGeneratorBuilder {
+    list: Option<Vec<String>>,
+    words: Option<usize>,
+    seed: Option<u64>,
+}
+
+impl KeywordGeneratorBuilder {
+    /// Words the keywords are made of. Defaults to WORDLIST.
+    pub fn list<S: AsRef<str>>(mut self, list: &[S]) -> Self {
+        self.list = Some(list.iter().map(|w| w.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Number of words per keyword. Defaults to 3.
+    pub fn words(mut self, words: usize) -> Self {
+        self.words = Some(words);
+        self
+    }
+
+    /// Seed of the random choices. Defaults to the current time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Fails if the list holds no word with letters A-Z or the number of
+    /// words is 0.
+    pub fn build(self) -> Result<KeywordGenerator, InvalidOptionError> {
+        let list: Vec<String> = match self.list {
+            Some(list) => list
+                .iter()
+                .map(|w| w.trim().to_uppercase())
+                .filter(|w| w.chars().any(|c| c.is_ascii_uppercase()))
+                .collect(),
+            None => WORDLIST.iter().map(|w| w.to_string()).collect(),
+        };
+        if list.is_empty() {
+            return Err(InvalidOptionError::new(String::from(
+                "At least one word containing letters A-Z is needed",
+            )));
+        }
+        let words = self.words.unwrap_or(3);
+        if words == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Number of words must be greater than 0",
+            )));
+        }
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Ok(KeywordGenerator {
+            list,
+            words,
+            rng: Rng::new(seed),
+        })
+    }
+}
+
+impl KeywordGenerator {
+    /// Returns a builder to configure the generator.
+    pub fn builder() -> KeywordGeneratorBuilder {
+        KeywordGeneratorBuilder::default()
+    }
+
+    /// The next phrase, the words separated by spaces.
+    pub fn phrase(&mut self) -> String {
+        (0..self.words)
+            .map(|_| self.list[self.rng.below(self.list.len())].as_str())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// The next phrase together with the PlayFair key built of it.
+    pub fn key(&mut self) -> (String, PlayFairKey) {
+        let phrase = self.phrase();
+        let key = PlayFairKey::new(&phrase);
+        (phrase, key)
+    }
+
+    /// Entropy of a phrase in bits, assuming the words are drawn uniformly
+    /// from a list without duplicates.
+    pub fn entropy(&self) -> f64 {
+        self.words as f64 * (self.list.len() as f64).log2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_wordlist() {
+        let mut sorted = WORDLIST.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), WORDLIST.len());
+        assert!(WORDLIST
+            .iter()
+            .all(|w| w.chars().all(|c| c.is_ascii_uppercase())));
+    }
+
+    #[test]
+    fn test_generator() {
+        let phrase = |seed| {
+            KeywordGenerator::builder()
+                .seed(seed)
+                .build()
+                .unwrap()
+                .phrase()
+        };
+        assert_eq!(phrase(1), phrase(1));
+        assert_ne!(phrase(1), phrase(2));
+        let mut generator = KeywordGenerator::builder()
+            .list(&["north", " ", "south"])
+            .words(4)
+            .build()
+            .unwrap();
+        assert!(generator
+            .phrase()
+            .split(' ')
+            .all(|w| w == "NORTH" || w == "SOUTH"));
+        assert_eq!(generator.entropy(), 4.0);
+        assert!(KeywordGenerator::builder().list(&["42"]).build().is_err());
+        assert!(KeywordGenerator::builder().words(0).build().is_err());
+    }
+}
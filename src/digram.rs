@@ -0,0 +1,214 @@
+//! Digram by digram results of an encryption, for tools analyzing or
+//! visualizing the cipher text rather than just printing it. A trace
+//! additionally tells where the letters are found in the squares, which
+//! shows step by step how the cipher works.
+//!
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cryptable::Layout;
+use crate::playfair::ROW_LENGTH;
+
+/// The rule a digram was en- or decrypted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Rule {
+    /// Both letters are in the same row of the square, each is replaced by
+    /// its right neighbour.
+    Row,
+    /// Both letters are in the same column of the square, each is replaced
+    /// by the letter below. With the Two Square cipher, letters in the same
+    /// column of the two squares are left as they are.
+    Column,
+    /// The letters span a rectangle, each is replaced by the letter in its
+    /// row at the other corner.
+    Rectangle,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Row => write!(f, "row"),
+            Rule::Column => write!(f, "column"),
+            Rule::Rectangle => write!(f, "rectangle"),
+        }
+    }
+}
+
+/// A digram of the payload, as split for encryption, with the digram it
+/// was encrypted to.
+///
+/// With the feature `serde` it's serialized as e.g.
+/// `{"in":"HI","out":"BM","rule":"rectangle"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Digram {
+    #[cfg_attr(feature = "serde", serde(rename = "in", with = "letters"))]
+    pub plain: [char; 2],
+    #[cfg_attr(feature = "serde", serde(rename = "out", with = "letters"))]
+    pub crypt: [char; 2],
+    pub rule: Rule,
+}
+
+/// Row and column of a letter within a square, both counted from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub row: u8,
+    pub column: u8,
+}
+
+/// One step of a traced encryption: a digram with the positions of its
+/// letters. The plain letters are looked up in the squares the cipher
+/// takes plain text from, the cipher letters in the ones it takes cipher
+/// text from. With the PlayFair cipher this is the one square, with Two
+/// Square the first letter is in the top square and the second one in the
+/// bottom square. With Four Square the plain letters are in the top left
+/// and bottom right squares, the cipher letters in the top right and
+/// bottom left squares.
+///
+/// With the feature `serde` a trace can be exported e.g. as JSON to drive
+/// an animation:
+///
+/// ```json
+/// {
+///   "in": "HI", "out": "BM", "rule": "rectangle",
+///   "in_positions": [{"row": 2, "column": 4}, {"row": 1, "column": 0}],
+///   "out_positions": [{"row": 2, "column": 0}, {"row": 1, "column": 4}]
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TraceStep {
+    #[cfg_attr(feature = "serde", serde(rename = "in", with = "letters"))]
+    pub plain: [char; 2],
+    #[cfg_attr(feature = "serde", serde(rename = "out", with = "letters"))]
+    pub crypt: [char; 2],
+    pub rule: Rule,
+    #[cfg_attr(feature = "serde", serde(rename = "in_positions"))]
+    pub plain_positions: [Position; 2],
+    #[cfg_attr(feature = "serde", serde(rename = "out_positions"))]
+    pub crypt_positions: [Position; 2],
+}
+
+/// A digram of the cipher text with the positions of its letters, see
+/// Cypher::encrypt_positioned. Positions are counted in chars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PositionedDigram {
+    #[cfg_attr(feature = "serde", serde(rename = "in", with = "letters"))]
+    pub plain: [char; 2],
+    #[cfg_attr(feature = "serde", serde(rename = "out", with = "letters"))]
+    pub crypt: [char; 2],
+    /// Index of the first letter of the cipher digram within the cipher
+    /// text, spaces between groups included. The second letter follows it,
+    /// unless the payload is seriated: it's found in the second line of the
+    /// block then.
+    pub crypt_position: usize,
+    /// Index of each letter of the plain digram within the normalized
+    /// payload. None for a padding letter inserted.
+    pub positions: [Option<usize>; 2],
+    /// Index of the char of the original payload each letter of the plain
+    /// digram was derived from. None for a padding letter inserted.
+    pub original_positions: [Option<usize>; 2],
+}
+
+// Serializes a digram as a string of its two letters.
+#[cfg(feature = "serde")]
+mod letters {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        digram: &[char; 2],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&digram.iter().collect::<String>())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[char; 2], D::Error> {
+        let letters = String::deserialize(deserializer)?;
+        let mut chars = letters.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(a), Some(b), None) => Ok([a, b]),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&letters),
+                &"a digram of two letters",
+            )),
+        }
+    }
+}
+
+// Draws the squares with the letters of the step marked: plain letters as
+// [A], cipher letters as (A) and a letter which is both as <A>.
+pub(crate) fn render_step(layout: &Layout, step: &TraceStep) -> String {
+    let is_at = |squares: [usize; 2], positions: [Position; 2], square: usize, at: Position| {
+        (0..2).any(|idx| squares[idx] == square && positions[idx] == at)
+    };
+    let mut rendered = format!(
+        "{}{} -> {}{} ({})\n",
+        step.plain[0], step.plain[1], step.crypt[0], step.crypt[1], step.rule
+    );
+    for (layout_row, squares) in layout.squares.chunks(layout.columns).enumerate() {
+        if layout_row > 0 {
+            rendered.push('\n');
+        }
+        for row in 0..ROW_LENGTH {
+            let mut line = String::new();
+            for (layout_column, square) in squares.iter().enumerate() {
+                let square_idx = layout_row * layout.columns + layout_column;
+                if layout_column > 0 {
+                    line.push_str("   ");
+                }
+                for column in 0..ROW_LENGTH {
+                    let at = Position { row, column };
+                    let letter = square.key[(row * ROW_LENGTH + column) as usize];
+                    let (open, close) = match (
+                        is_at(layout.plain, step.plain_positions, square_idx, at),
+                        is_at(layout.crypt, step.crypt_positions, square_idx, at),
+                    ) {
+                        (true, true) => ('<', '>'),
+                        (true, false) => ('[', ']'),
+                        (false, true) => ('(', ')'),
+                        (false, false) => (' ', ' '),
+                    };
+                    line.push(open);
+                    line.push(letter);
+                    line.push(close);
+                }
+            }
+            rendered.push_str(line.trim_end());
+            rendered.push('\n');
+        }
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_trace_json() {
+        use super::*;
+        use crate::{cryptable::SquareCypher, playfair::PlayFairKey};
+
+        let pfc = PlayFairKey::new("playfair example");
+        let trace = pfc.encrypt_traced("hide").unwrap();
+        let json = serde_json::to_string(&trace).unwrap();
+        assert!(json.starts_with(
+            r#"[{"in":"HI","out":"BM","rule":"rectangle","in_positions":[{"row":2,"column":4}"#
+        ));
+        let read: Vec<TraceStep> = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, trace);
+        assert!(serde_json::from_str::<Digram>(r#"{"in":"HIX","out":"BM","rule":"row"}"#).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! A cipher wrapper with all digrams precomputed. For each of the 25 * 25
+//! possible digrams the encrypted and decrypted digram is computed once,
+//! so en- and decrypting becomes a table lookup per digram. Worth it for
+//! bulk workloads using the same key, not for keys used only once.
+//!
+
+use crate::{
+    cryptable::{engine, Crypt, Cypher, Internals, SquareCrypt, SquareCypher, SquareInternals},
+    errors::CharNotInKeyError,
+    options::PayloadOptions,
+    structs::{CryptModus, CryptResult},
+};
+
+const ALPHABET_LENGTH: usize = 25;
+
+/// Wraps any cipher and precomputes its digram substitutions.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{digram_table::DigramTable, playfair::PlayFairKey, cryptable::Cypher};
+///
+/// let pfc = DigramTable::new(PlayFairKey::new("playfair example"));
+/// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAGE");
+/// assert_eq!(pfc.decrypt("BMODZBXDNAGE").unwrap(), "HIDETHEGOLDX");
+/// ```
+pub struct DigramTable<C: Cypher> {
+    cipher: C,
+    // Index of any letter A-Z in the alphabet of the square
+    index: [Option<u8>; 26],
+    // None if the cipher refused the digram, the lookup then falls back to
+    // the cipher to report the error.
+    encrypt: Vec<Option<[char; 2]>>,
+    decrypt: Vec<Option<[char; 2]>>,
+}
+
+impl<C: Cypher> DigramTable<C> {
+    pub fn new(cipher: C) -> Self {
+        let mut index = [None; 26];
+        let alphabet: Vec<char> = engine(&cipher).payload_options().alphabet().collect();
+        for (idx, c) in alphabet.iter().enumerate() {
+            index[(*c as u8 - b'A') as usize] = Some(idx as u8);
+        }
+        let mut encrypt = Vec::with_capacity(ALPHABET_LENGTH * ALPHABET_LENGTH);
+        let mut decrypt = Vec::with_capacity(ALPHABET_LENGTH * ALPHABET_LENGTH);
+        for a in &alphabet {
+            for b in &alphabet {
+                encrypt.push(
+                    engine(&cipher)
+                        .crypt(*a, *b, &CryptModus::Encrypt)
+                        .ok()
+                        .map(|r| [r.a, r.b]),
+                );
+                decrypt.push(
+                    engine(&cipher)
+                        .crypt(*a, *b, &CryptModus::Decrypt)
+                        .ok()
+                        .map(|r| [r.a, r.b]),
+                );
+            }
+        }
+        DigramTable {
+            cipher,
+            index,
+            encrypt,
+            decrypt,
+        }
+    }
+
+    /// The wrapped cipher.
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    fn lookup(&self, c: char) -> Option<usize> {
+        match c.is_ascii_uppercase() {
+            true => self.index[(c as u8 - b'A') as usize].map(|idx| idx as usize),
+            false => None,
+        }
+    }
+}
+
+impl<C: Cypher> Crypt for DigramTable<C> {
+    fn crypt(
+        &self,
+        a: char,
+        b: char,
+        modus: &CryptModus,
+    ) -> Result<CryptResult, CharNotInKeyError> {
+        let table = match modus {
+            CryptModus::Encrypt => &self.encrypt,
+            CryptModus::Decrypt => &self.decrypt,
+        };
+        if let (Some(a_idx), Some(b_idx)) = (self.lookup(a), self.lookup(b)) {
+            if let Some([a_crypted, b_crypted]) = table[a_idx * ALPHABET_LENGTH + b_idx] {
+                return Ok(CryptResult {
+                    a: a_crypted,
+                    b: b_crypted,
+                });
+            }
+        }
+        engine(&self.cipher).crypt(a, b, modus)
+    }
+
+    fn payload_options(&self) -> &PayloadOptions {
+        engine(&self.cipher).options()
+    }
+
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt> {
+        self.cipher.internals()?.0.square_crypt()
+    }
+}
+
+impl<C: Cypher> Cypher for DigramTable<C> {
+    fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        self.crypt_payload(payload, &CryptModus::Encrypt)
+    }
+
+    fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        self.crypt_payload(payload, &CryptModus::Decrypt)
+    }
+
+    fn internals(&self) -> Option<Internals<'_>> {
+        Some(Internals(self))
+    }
+}
+
+impl<C: SquareCypher> SquareCypher for DigramTable<C> {
+    fn square_internals(&self) -> SquareInternals<'_> {
+        self.cipher.square_internals()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::playfair::PlayFairKey;
+
+    fn assert_same<C: Cypher>(cipher: &C, table: &DigramTable<C>) {
+        for a in engine(cipher).payload_options().alphabet() {
+            for b in engine(cipher).payload_options().alphabet() {
+                assert_eq!(
+                    cipher.encrypt_digram(a, b).unwrap(),
+                    table.encrypt_digram(a, b).unwrap()
+                );
+                assert_eq!(
+                    cipher.decrypt_digram(a, b).unwrap(),
+                    table.decrypt_digram(a, b).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_table_matches_cipher() {
+        assert_same(
+            &PlayFairKey::new("playfair example"),
+            &DigramTable::new(PlayFairKey::new("playfair example")),
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "two-square", feature = "four-square"))]
+    fn test_table_matches_square_ciphers() {
+        use crate::{four_square::FourSquare, two_square::TwoSquare};
+
+        assert_same(
+            &TwoSquare::new("EXAMPLE", "KEYWORD"),
+            &DigramTable::new(TwoSquare::new("EXAMPLE", "KEYWORD")),
+        );
+        let fsq = || {
+            FourSquare::builder()
+                .keys("EXAMPLE", "KEYWORD")
+                .omit('Q')
+                .build()
+                .unwrap()
+        };
+        assert_same(&fsq(), &DigramTable::new(fsq()));
+    }
+
+    #[test]
+    fn test_table_char_not_in_key() {
+        let table = DigramTable::new(PlayFairKey::new("playfair example"));
+        match table.encrypt_digram('J', 'A') {
+            Ok(d) => panic!("J is not part of the key - got {:?}", d),
+            Err(e) => assert_eq!(e.character(), 'J'),
+        }
+        assert_eq!(table.cipher().options().padding(), 'X');
+    }
+}
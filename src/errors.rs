@@ -1,25 +1,777 @@
 use std::error::Error;
 
 use std::fmt;
+use std::io;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Error indicating a character in the given string could not be looked up in the
 /// PlayFairKey. If this occours any operation is stopped.
 ///
+/// When the error was raised while processing a payload, it carries the
+/// index of the offending character both in the normalized payload and in
+/// the original input, so the character can be highlighted.
+///
+/// The same error reports the last letter of a payload of odd length left
+/// without partner by FinalDigram::Strict, that letter is in the key. Tell
+/// both apart by kind.
+///
+/// With the feature `serde` this error and the others not wrapping an I/O
+/// error can be serialized, e.g. to return them as structured payload of a
+/// web service.
+///
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CharNotInKeyError {
     pub(crate) error: String,
+    pub(crate) character: char,
+    pub(crate) position: Option<usize>,
+    pub(crate) original_position: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) kind: CharErrorKind,
+}
+
+/// Why a character couldn't be en- or decrypted, see CharNotInKeyError.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CharErrorKind {
+    /// The character isn't part of the key.
+    #[default]
+    NotInKey,
+    /// The character is the last one of a payload of odd length and left
+    /// without partner, see FinalDigram::Strict.
+    Unpaired,
 }
 
 impl fmt::Display for CharNotInKeyError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.error)
+        write!(f, "{}", self.error)?;
+        if let Some(position) = self.original_position {
+            write!(f, " at position {}", position)?;
+        }
+        Ok(())
     }
 }
 
 impl Error for CharNotInKeyError {}
 
 impl CharNotInKeyError {
+    pub(crate) fn new(error: String, character: char) -> Self {
+        CharNotInKeyError {
+            error,
+            character,
+            position: None,
+            original_position: None,
+            kind: CharErrorKind::NotInKey,
+        }
+    }
+
+    pub(crate) fn unpaired(error: String, character: char) -> Self {
+        CharNotInKeyError {
+            kind: CharErrorKind::Unpaired,
+            ..CharNotInKeyError::new(error, character)
+        }
+    }
+
+    /// Attaches the position of the offending character within the normalized
+    /// payload and within the original input.
+    pub(crate) fn at(mut self, position: usize, original_position: usize) -> Self {
+        self.position = Some(position);
+        self.original_position = Some(original_position);
+        self
+    }
+
+    /// The character which could not be found in the key, or the one left
+    /// without partner.
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    /// Whether the character isn't part of the key or left without partner.
+    pub fn kind(&self) -> CharErrorKind {
+        self.kind
+    }
+
+    /// Index of the offending character in the normalized payload, e.g. in
+    /// "IWOULDLIKETINSOFIAM" for the input "I would like 4 tins of jam.".
+    /// `None` if the error was not raised while processing a payload.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Index (counted in chars) of the offending character in the original
+    /// input as handed to encrypt or decrypt. `None` if the error was not
+    /// raised while processing a payload.
+    pub fn original_position(&self) -> Option<usize> {
+        self.original_position
+    }
+}
+
+/// Error indicating an invalid option was given when building a cipher,
+/// e.g. a padding character which is not part of the key square.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvalidOptionError {
+    pub(crate) error: String,
+}
+
+impl fmt::Display for InvalidOptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for InvalidOptionError {}
+
+impl InvalidOptionError {
+    pub(crate) fn new(error: String) -> Self {
+        InvalidOptionError { error }
+    }
+}
+
+/// Error indicating a key square could not be constructed, e.g. because a
+/// letter occours twice.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InvalidKeyError {
+    pub(crate) error: String,
+}
+
+impl fmt::Display for InvalidKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for InvalidKeyError {}
+
+impl InvalidKeyError {
+    pub(crate) fn new(error: String) -> Self {
+        InvalidKeyError { error }
+    }
+}
+
+/// Error indicating a cipher config could not be read, e.g. because of a
+/// syntax error or a missing field.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConfigError {
+    pub(crate) error: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for ConfigError {}
+
+impl ConfigError {
+    #[cfg(any(feature = "toml", feature = "json"))]
     pub(crate) fn new(error: String) -> Self {
-        CharNotInKeyError { error }
+        ConfigError { error }
+    }
+}
+
+/// Error indicating Morse code could not be converted to letters, e.g.
+/// because of an unknown code.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MorseError {
+    pub(crate) error: String,
+}
+
+impl fmt::Display for MorseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for MorseError {}
+
+impl MorseError {
+    pub(crate) fn new(error: String) -> Self {
+        MorseError { error }
+    }
+}
+
+/// Error indicating a text can't be encoded with the figure shift
+/// convention, as it contains the figures indicator.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FigureShiftError {
+    pub(crate) error: String,
+}
+
+impl fmt::Display for FigureShiftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl Error for FigureShiftError {}
+
+impl FigureShiftError {
+    pub(crate) fn new(error: String) -> Self {
+        FigureShiftError { error }
+    }
+}
+
+/// Error returned when writing the result of an en- or decryption into a
+/// `fmt::Write` sink.
+///
+#[derive(Debug, Clone)]
+pub enum WriteError {
+    /// A character could not be found in the key.
+    CharNotInKey(CharNotInKeyError),
+    /// The sink refused to take the result.
+    Fmt(fmt::Error),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriteError::CharNotInKey(e) => write!(f, "{}", e),
+            WriteError::Fmt(e) => write!(f, "Writing the result failed: {}", e),
+        }
+    }
+}
+
+impl Error for WriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WriteError::CharNotInKey(e) => Some(e),
+            WriteError::Fmt(e) => Some(e),
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for WriteError {
+    fn from(e: CharNotInKeyError) -> Self {
+        WriteError::CharNotInKey(e)
+    }
+}
+
+impl From<fmt::Error> for WriteError {
+    fn from(e: fmt::Error) -> Self {
+        WriteError::Fmt(e)
+    }
+}
+
+/// Error returned by a strict decryption if the cipher text can't be the
+/// result of an encryption with the cipher.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DecryptError {
+    /// The cipher text consists of an odd number of letters.
+    OddLength { length: usize },
+    /// A character which is neither a letter nor whitespace and would be
+    /// dropped. The position is counted in chars of the cipher text.
+    DroppedChar { character: char, position: usize },
+    /// A letter which is not part of the key square, e.g. the omitted one.
+    CharNotInKey(CharNotInKeyError),
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecryptError::OddLength { length } => write!(
+                f,
+                "Cipher text must consist of an even number of letters - got {}",
+                length
+            ),
+            DecryptError::DroppedChar {
+                character,
+                position,
+            } => write!(
+                f,
+                "Only chars A-Z possible - '{}' at position {} would be dropped",
+                character, position
+            ),
+            DecryptError::CharNotInKey(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for DecryptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecryptError::CharNotInKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for DecryptError {
+    fn from(e: CharNotInKeyError) -> Self {
+        DecryptError::CharNotInKey(e)
+    }
+}
+
+/// Error returned when decrypting cipher text given as coordinates within
+/// the key square.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CoordinateError {
+    /// A character which is neither a digit 1-5 nor whitespace. The
+    /// position is counted in chars of the coordinates.
+    InvalidChar { character: char, position: usize },
+    /// The coordinates consist of an odd number of digits, so a letter
+    /// lacks its column.
+    OddLength { length: usize },
+    /// A letter could not be decrypted.
+    CharNotInKey(CharNotInKeyError),
+}
+
+impl fmt::Display for CoordinateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CoordinateError::InvalidChar {
+                character,
+                position,
+            } => write!(
+                f,
+                "Only digits 1-5 possible - got '{}' at position {}",
+                character, position
+            ),
+            CoordinateError::OddLength { length } => write!(
+                f,
+                "Coordinates must consist of an even number of digits - got {}",
+                length
+            ),
+            CoordinateError::CharNotInKey(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for CoordinateError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CoordinateError::CharNotInKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for CoordinateError {
+    fn from(e: CharNotInKeyError) -> Self {
+        CoordinateError::CharNotInKey(e)
+    }
+}
+
+/// Error returned when decoding letters back into bytes, see
+/// Cypher::decrypt_bytes.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BinaryError {
+    /// A character which is not a letter of the square. The position is
+    /// counted in chars of the decrypted text.
+    InvalidChar { character: char, position: usize },
+    /// The text consists of an odd number of letters, so a byte lacks its
+    /// second letter.
+    OddLength { length: usize },
+    /// A digram not produced by the encoding, e.g. a doubled letter. The
+    /// position is the index of the byte it would encode.
+    InvalidDigram { digram: [char; 2], position: usize },
+    /// The cipher text could not be decrypted.
+    CharNotInKey(CharNotInKeyError),
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinaryError::InvalidChar {
+                character,
+                position,
+            } => write!(
+                f,
+                "Only letters of the square possible - got '{}' at position {}",
+                character, position
+            ),
+            BinaryError::OddLength { length } => write!(
+                f,
+                "Encoded bytes must consist of an even number of letters - got {}",
+                length
+            ),
+            BinaryError::InvalidDigram { digram, position } => write!(
+                f,
+                "Digram {}{} of byte {} does not encode a byte",
+                digram[0], digram[1], position
+            ),
+            BinaryError::CharNotInKey(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for BinaryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BinaryError::CharNotInKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for BinaryError {
+    fn from(e: CharNotInKeyError) -> Self {
+        BinaryError::CharNotInKey(e)
+    }
+}
+
+/// Error returned when decrypting a message with an indicator group, see
+/// IndicatorSystem::decrypt.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IndicatorError {
+    /// The message ended before the indicator was complete.
+    MissingIndicator { expected: usize, found: usize },
+    /// A character of the indicator which is not a letter of the square.
+    /// The position is counted in chars of the message.
+    InvalidChar { character: char, position: usize },
+    /// The cipher text could not be decrypted.
+    CharNotInKey(CharNotInKeyError),
+}
+
+impl fmt::Display for IndicatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndicatorError::MissingIndicator { expected, found } => write!(
+                f,
+                "Indicator of {} letters expected - message holds {}",
+                expected, found
+            ),
+            IndicatorError::InvalidChar {
+                character,
+                position,
+            } => write!(
+                f,
+                "Only letters of the square possible in the indicator - got '{}' at position {}",
+                character, position
+            ),
+            IndicatorError::CharNotInKey(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for IndicatorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            IndicatorError::CharNotInKey(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for IndicatorError {
+    fn from(e: CharNotInKeyError) -> Self {
+        IndicatorError::CharNotInKey(e)
+    }
+}
+
+/// Error returned when decoding digits of the VIC cipher.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum VicError {
+    /// A character which is neither a digit nor whitespace. The position is
+    /// counted in chars of the input.
+    InvalidChar { character: char, position: usize },
+    /// The digits end with the first digit of a two digit code.
+    Truncated { position: usize },
+}
+
+impl fmt::Display for VicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VicError::InvalidChar {
+                character,
+                position,
+            } => write!(
+                f,
+                "Only digits 0-9 possible - got '{}' at position {}",
+                character, position
+            ),
+            VicError::Truncated { position } => write!(
+                f,
+                "The digit at position {} lacks the second digit of its code",
+                position
+            ),
+        }
+    }
+}
+
+impl Error for VicError {}
+
+/// Error returned when en- or decrypting a stream.
+///
+#[derive(Debug)]
+pub enum StreamError {
+    /// A character could not be found in the key.
+    CharNotInKey(CharNotInKeyError),
+    /// Reading the input or writing the result failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::CharNotInKey(e) => write!(f, "{}", e),
+            StreamError::Io(e) => write!(f, "Reading or writing the stream failed: {}", e),
+        }
+    }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StreamError::CharNotInKey(e) => Some(e),
+            StreamError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<CharNotInKeyError> for StreamError {
+    fn from(e: CharNotInKeyError) -> Self {
+        StreamError::CharNotInKey(e)
+    }
+}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        // A character not in the key reported through io::Error, as by the
+        // adapters of async_stream
+        match e
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<CharNotInKeyError>())
+        {
+            Some(inner) => StreamError::CharNotInKey(inner.clone()),
+            None => StreamError::Io(e),
+        }
+    }
+}
+
+impl From<StreamError> for io::Error {
+    fn from(e: StreamError) -> Self {
+        match e {
+            StreamError::CharNotInKey(e) => io::Error::new(io::ErrorKind::InvalidData, e),
+            StreamError::Io(e) => e,
+        }
+    }
+}
+
+/// Error returned when reading, writing or resuming from a checkpoint of a
+/// crack.
+///
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// Reading or writing the checkpoint file failed.
+    Io(io::Error),
+    /// A line of the checkpoint could not be read, counted from 1.
+    Malformed { line: usize, error: String },
+    /// The checkpoint was taken by another solver or of another crack.
+    Mismatch(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "Reading or writing the checkpoint failed: {}", e),
+            CheckpointError::Malformed { line, error } => {
+                write!(f, "Malformed checkpoint at line {}: {}", line, error)
+            }
+            CheckpointError::Mismatch(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckpointError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+/// Error returned when reading an armored key, see armor::import.
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ArmorError {
+    /// The text holds no BEGIN line.
+    MissingBegin,
+    /// The END line is missing.
+    MissingEnd,
+    /// The line with the checksum following the square is missing.
+    MissingChecksum,
+    /// A header or the checksum line could not be read.
+    InvalidLine(String),
+    /// The square is not a valid key square.
+    InvalidKey(InvalidKeyError),
+    /// The headers don't make up valid options.
+    InvalidOption(InvalidOptionError),
+    /// The checksum doesn't match the square and the options, so something
+    /// got garbled.
+    ChecksumMismatch { expected: u32, found: u32 },
+}
+
+impl fmt::Display for ArmorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArmorError::MissingBegin => write!(f, "No armored key found"),
+            ArmorError::MissingEnd => write!(f, "The armored key is not terminated"),
+            ArmorError::MissingChecksum => write!(f, "The armored key lacks its checksum"),
+            ArmorError::InvalidLine(line) => write!(f, "Invalid line in armored key: '{}'", line),
+            ArmorError::InvalidKey(e) => write!(f, "{}", e),
+            ArmorError::InvalidOption(e) => write!(f, "{}", e),
+            ArmorError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Checksum {:06X} does not match the key - expected {:06X}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl Error for ArmorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ArmorError::InvalidKey(e) => Some(e),
+            ArmorError::InvalidOption(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidKeyError> for ArmorError {
+    fn from(e: InvalidKeyError) -> Self {
+        ArmorError::InvalidKey(e)
+    }
+}
+
+impl From<InvalidOptionError> for ArmorError {
+    fn from(e: InvalidOptionError) -> Self {
+        ArmorError::InvalidOption(e)
+    }
+}
+
+/// Error returned when using, reading or writing a keyring.
+///
+#[derive(Debug)]
+pub enum KeyringError {
+    /// No entry of this name.
+    UnknownEntry(String),
+    /// The config of the entry doesn't describe a valid cipher.
+    InvalidOption(InvalidOptionError),
+    /// A character could not be found in the key.
+    CharNotInKey(CharNotInKeyError),
+    /// Reading or writing the keyring file failed.
+    Io(io::Error),
+    /// A line of the keyring could not be read, counted from 1.
+    Malformed { line: usize, error: String },
+    /// An encrypted keyring could not be sealed or opened, e.g. because of
+    /// a wrong passphrase. Only returned with the feature
+    /// `encrypted-keyring`.
+    Sealing(String),
+}
+
+impl fmt::Display for KeyringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyringError::UnknownEntry(name) => write!(f, "No keyring entry '{}'", name),
+            KeyringError::InvalidOption(e) => write!(f, "{}", e),
+            KeyringError::CharNotInKey(e) => write!(f, "{}", e),
+            KeyringError::Io(e) => write!(f, "Reading or writing the keyring failed: {}", e),
+            KeyringError::Malformed { line, error } => {
+                write!(f, "Malformed keyring at line {}: {}", line, error)
+            }
+            KeyringError::Sealing(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for KeyringError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KeyringError::InvalidOption(e) => Some(e),
+            KeyringError::CharNotInKey(e) => Some(e),
+            KeyringError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvalidOptionError> for KeyringError {
+    fn from(e: InvalidOptionError) -> Self {
+        KeyringError::InvalidOption(e)
+    }
+}
+
+impl From<CharNotInKeyError> for KeyringError {
+    fn from(e: CharNotInKeyError) -> Self {
+        KeyringError::CharNotInKey(e)
+    }
+}
+
+impl From<io::Error> for KeyringError {
+    fn from(e: io::Error) -> Self {
+        KeyringError::Io(e)
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+    use crate::options::FinalDigram;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_serialize() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::Strict)
+            .build()
+            .unwrap();
+        let error = pfc.encrypt("hide the gold!").unwrap_err();
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(
+            json,
+            r#"{"error":"'D' is left without partner, the payload must consist of whole digrams","character":"D","position":10,"original_position":12,"kind":"Unpaired"}"#
+        );
+        let read: CharNotInKeyError = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.to_string(), error.to_string());
+        assert_eq!(read.original_position(), Some(12));
+        assert_eq!(read.kind(), CharErrorKind::Unpaired);
+        // Written before the kind was introduced
+        let read: CharNotInKeyError = serde_json::from_str(
+            r#"{"error":"'J' not in key","character":"J","position":0,"original_position":0}"#,
+        )
+        .unwrap();
+        assert_eq!(read.kind(), CharErrorKind::NotInKey);
+
+        let error = pfc.decrypt_strict("BMODZ1").unwrap_err();
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(json, r#"{"DroppedChar":{"character":"1","position":5}}"#);
+        let read: DecryptError = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.to_string(), error.to_string());
     }
 }
@@ -0,0 +1,362 @@
+//! The PlayFair cipher on an 8*8 square holding the letters A-Z, the digits
+//! 0-9 and 28 punctuation symbols including the space. Unlike the classic
+//! 5*5 square no letter has to be merged or dropped, so text with digits,
+//! spaces and punctuation survives en- and decryption - apart from the case
+//! and the padding inserted between doubled characters.
+//!
+//! ```
+//! use playfair_cipher::extended::ExtendedPlayFair;
+//!
+//! let epf = ExtendedPlayFair::new("playfair example");
+//! let crypt = epf.encrypt("Meet me at 10:30, gate 4!").unwrap();
+//! assert_eq!(epf.decrypt(&crypt).unwrap(), "MEET ME AT 10:30, GATE 4!X");
+//! ```
+//!
+//! The extended square is not a Cypher, as the features built on top of the
+//! 5*5 square - the solvers, the rendering of squares - don't apply to it.
+//!
+
+use std::fmt;
+
+use crate::errors::{CharNotInKeyError, InvalidOptionError};
+use crate::playfair::{crypt_cells, KeywordReport};
+use crate::structs::CryptModus;
+
+const ROW_LENGTH: u8 = 8;
+const KEY_LENGTH: usize = 64;
+const LETTERS_AND_DIGITS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Number of symbols besides letters and digits in the square.
+pub const SYMBOL_COUNT: usize = KEY_LENGTH - 36;
+
+/// The symbols of the square if none are configured.
+pub const DEFAULT_SYMBOLS: &str = " .,;:!?'\"-()/&+=*@#%$<>_[]{}";
+
+const NOT_IN_KEY: u8 = u8::MAX;
+
+/// PlayFair cipher on an 8*8 square, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct ExtendedPlayFair {
+    key: [char; KEY_LENGTH],
+    // Cell of each ASCII char, the square only holds ASCII chars.
+    cells: [u8; 128],
+    padding: char,
+}
+
+/// Builder for an ExtendedPlayFair, see ExtendedPlayFair::builder.
+#[derive(Debug, Clone)]
+pub struct ExtendedPlayFairBuilder {
+    key: String,
+    symbols: String,
+    padding: char,
+}
+
+impl Default for ExtendedPlayFairBuilder {
+    fn default() -> Self {
+        ExtendedPlayFairBuilder {
+            key: String::new(),
+            symbols: String::new(),
+            padding: 'X',
+        }
+    }
+}
+
+impl ExtendedPlayFairBuilder {
+    /// The keyword the square is built of. It may contain digits and
+    /// symbols of the square too.
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = String::from(key);
+        self
+    }
+
+    /// Symbols the square holds besides letters and digits, at most 28
+    /// distinct ASCII chars which are neither letters nor digits. The
+    /// remaining cells are filled with the DEFAULT_SYMBOLS not given.
+    pub fn symbols(mut self, symbols: &str) -> Self {
+        self.symbols = String::from(symbols);
+        self
+    }
+
+    /// Char used to split doubled chars and to pad a payload of odd length.
+    pub fn padding(mut self, padding: char) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn build(self) -> Result<ExtendedPlayFair, InvalidOptionError> {
+        let mut alphabet: Vec<char> = LETTERS_AND_DIGITS.chars().collect();
+        for symbol in self.symbols.chars() {
+            if !symbol.is_ascii() || symbol.is_ascii_alphanumeric() || symbol.is_ascii_control() {
+                return Err(InvalidOptionError::new(format!(
+                    "Symbols must be ASCII chars other than letters and digits - got '{}'",
+                    symbol
+                )));
+            }
+            if alphabet.contains(&symbol) {
+                return Err(InvalidOptionError::new(format!(
+                    "Symbol '{}' is given more than once",
+                    symbol
+                )));
+            }
+            alphabet.push(symbol);
+        }
+        if alphabet.len() > KEY_LENGTH {
+            return Err(InvalidOptionError::new(format!(
+                "At most {} symbols fit into the square - got {}",
+                SYMBOL_COUNT,
+                alphabet.len() - LETTERS_AND_DIGITS.len()
+            )));
+        }
+        for symbol in DEFAULT_SYMBOLS.chars() {
+            if alphabet.len() == KEY_LENGTH {
+                break;
+            }
+            if !alphabet.contains(&symbol) {
+                alphabet.push(symbol);
+            }
+        }
+        let padding = self.padding.to_ascii_uppercase();
+        if !alphabet.contains(&padding) {
+            return Err(InvalidOptionError::new(format!(
+                "Padding must be a char of the square - got '{}'",
+                self.padding
+            )));
+        }
+        Ok(ExtendedPlayFair::with_alphabet(
+            &self.key, &alphabet, padding,
+        ))
+    }
+}
+
+impl ExtendedPlayFair {
+    /// Constructs the cipher with the default symbols and X as padding.
+    pub fn new(key: &str) -> Self {
+        ExtendedPlayFair::builder()
+            .key(key)
+            .build()
+            .expect("the default options are valid")
+    }
+
+    /// Returns a builder to construct the cipher with options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::extended::ExtendedPlayFair;
+    ///
+    /// let epf = ExtendedPlayFair::builder()
+    ///     .key("key 2024")
+    ///     .symbols("€")
+    ///     .build();
+    /// assert!(epf.is_err());
+    /// let epf = ExtendedPlayFair::builder()
+    ///     .key("key 2024")
+    ///     .symbols("|\\")
+    ///     .padding('|')
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(epf.decrypt(&epf.encrypt("a|b\\c").unwrap()).unwrap(), "A|B\\C|");
+    /// ```
+    pub fn builder() -> ExtendedPlayFairBuilder {
+        ExtendedPlayFairBuilder::default()
+    }
+
+    fn with_alphabet(key: &str, alphabet: &[char], padding: char) -> Self {
+        let mut square = ['*'; KEY_LENGTH];
+        let mut cells = [NOT_IN_KEY; 128];
+        let mut key_length = 0;
+        let raw_key = key
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            // Chars not in the square would take the cell of another one.
+            .filter(|c| alphabet.contains(c))
+            .chain(alphabet.iter().copied());
+        for c in raw_key {
+            if key_length == KEY_LENGTH {
+                break;
+            }
+            if cells[c as usize] != NOT_IN_KEY {
+                continue;
+            }
+            square[key_length] = c;
+            cells[c as usize] = key_length as u8;
+            key_length += 1;
+        }
+        ExtendedPlayFair {
+            key: square,
+            cells,
+            padding,
+        }
+    }
+
+    /// The 8*8 square row by row.
+    pub fn square(&self) -> &[char; KEY_LENGTH] {
+        &self.key
+    }
+
+    pub fn padding(&self) -> char {
+        self.padding
+    }
+
+    fn cell(&self, c: char) -> Option<u8> {
+        match c.is_ascii() {
+            true => Some(self.cells[c as usize]).filter(|cell| *cell != NOT_IN_KEY),
+            false => None,
+        }
+    }
+
+    /// Reports which chars of the keyword are in the square, see
+    /// PlayFairKey::keyword_report. Unlike in a 5*5 square digits are
+    /// placed in the square, as is the space if it's one of the symbols.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::extended::ExtendedPlayFair;
+    ///
+    /// let epf = ExtendedPlayFair::new("key 2024");
+    /// let report = epf.keyword_report("key 2024");
+    /// assert_eq!(report.used, ['K', 'E', 'Y', ' ', '2', '0', '4']);
+    /// assert_eq!(report.repeated, ['2']);
+    /// assert!(report.is_lossless());
+    /// ```
+    pub fn keyword_report(&self, keyword: &str) -> KeywordReport {
+        let mut report = KeywordReport::default();
+        for c in keyword.chars().map(|c| c.to_ascii_uppercase()) {
+            match self.cell(c) {
+                Some(_) => report.record(c, Some(c)),
+                None if c.is_whitespace() => {}
+                None => report.record(c, None),
+            }
+        }
+        report
+    }
+
+    /// Converts the payload to uppercase and removes any char not in the
+    /// square.
+    pub fn normalize(&self, payload: &str) -> String {
+        payload
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(|c| self.cell(*c).is_some())
+            .collect()
+    }
+
+    /// Encrypts a string. Doubled chars are split by the padding, as is a
+    /// payload of odd length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::extended::ExtendedPlayFair;
+    ///
+    /// let epf = ExtendedPlayFair::new("playfair example");
+    /// assert_eq!(epf.encrypt("Room 101").unwrap(), "YTNBF412");
+    /// ```
+    pub fn encrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        let payload = self.normalize(payload);
+        let mut chars = payload.chars().peekable();
+        let mut crypt = String::with_capacity(payload.len() + 2);
+        while let Some(a) = chars.next() {
+            let b = match chars.peek() {
+                Some(b) if *b != a => chars.next().unwrap_or(self.padding),
+                _ => self.padding,
+            };
+            self.crypt_digram(a, b, &CryptModus::Encrypt, &mut crypt)?;
+        }
+        Ok(crypt)
+    }
+
+    /// Decrypts a string. The padding is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::extended::ExtendedPlayFair;
+    ///
+    /// let epf = ExtendedPlayFair::new("playfair example");
+    /// assert_eq!(epf.decrypt("YTNBF412").unwrap(), "ROOM 101");
+    /// ```
+    pub fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
+        let payload = self.normalize(payload);
+        let mut chars = payload.chars();
+        let mut plain = String::with_capacity(payload.len());
+        while let Some(a) = chars.next() {
+            let b = chars.next().unwrap_or(self.padding);
+            self.crypt_digram(a, b, &CryptModus::Decrypt, &mut plain)?;
+        }
+        Ok(plain)
+    }
+
+    fn crypt_digram(
+        &self,
+        a: char,
+        b: char,
+        modus: &CryptModus,
+        out: &mut String,
+    ) -> Result<(), CharNotInKeyError> {
+        let not_in_key = |c: char| {
+            CharNotInKeyError::new(format!("'{}' was not found in the extended square", c), c)
+        };
+        let a_cell = self.cell(a).ok_or_else(|| not_in_key(a))?;
+        let b_cell = self.cell(b).ok_or_else(|| not_in_key(b))?;
+        let (a_cell, b_cell) = crypt_cells(a_cell, b_cell, ROW_LENGTH, modus);
+        out.push(self.key[a_cell as usize]);
+        out.push(self.key[b_cell as usize]);
+        Ok(())
+    }
+}
+
+impl fmt::Display for ExtendedPlayFair {
+    /// Prints the 8*8 square row by row, the chars separated by spaces.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.key.chunks(ROW_LENGTH as usize) {
+            let row: Vec<String> = row.iter().map(char::to_string).collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let epf = ExtendedPlayFair::new("Agent 007, go!");
+        let square: String = epf.square().iter().collect();
+        assert!(square.starts_with("AGENT 07,O!BCDF"));
+        let mut sorted = epf.square().to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), KEY_LENGTH);
+        assert_eq!(epf.to_string().lines().count(), ROW_LENGTH as usize);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let epf = ExtendedPlayFair::new("Agent 007, go!");
+        let payload = "PAY $1,250.00 (50% NOW) TO ACCT #4471-B; OK?";
+        let crypt = epf.encrypt(payload).unwrap();
+        let plain = epf.decrypt(&crypt).unwrap();
+        // The padding splits the doubled chars and fills up the odd length
+        assert_eq!(plain.replace('X', ""), payload);
+    }
+
+    #[test]
+    fn test_symbols() {
+        assert!(ExtendedPlayFair::builder().symbols("A").build().is_err());
+        assert!(ExtendedPlayFair::builder().symbols("||").build().is_err());
+        assert!(ExtendedPlayFair::builder().padding('~').build().is_err());
+        let epf = ExtendedPlayFair::builder()
+            .symbols("~")
+            .padding('~')
+            .build()
+            .unwrap();
+        assert!(epf.square().contains(&'~'));
+        // The last default symbol makes way for the one given
+        assert!(!epf.square().contains(&'}'));
+    }
+}
@@ -0,0 +1,214 @@
+//! The figure shift convention of the telegraph, so numbers survive a 5*5
+//! square: a run of digits is introduced by the figures indicator, each
+//! digit is written as the letter sharing its key on a teleprinter - Q for
+//! 1, W for 2 up to P for 0 - and the letters indicator switches back. The
+//! indicators default to FIGS and LTRS, named after the shift keys of the
+//! ITA2 code.
+//!
+//! Unlike spelling the digits out it's reversible: decoding turns the runs
+//! back into digits and drops padding letters inserted into them by the
+//! encryption.
+//!
+//! ```
+//! use playfair_cipher::{cryptable::Cypher, figure_shift::FigureShift, playfair::PlayFairKey};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let shift = FigureShift::default();
+//! let encoded = shift.encode("meet at 1100 at gate 42").unwrap();
+//! assert_eq!(encoded, "meet at FIGSQQPPLTRS at gate FIGSRWLTRS");
+//! let crypt = pfc.encrypt(&encoded).unwrap();
+//! let plain = pfc.decrypt(&crypt).unwrap();
+//! assert_eq!(shift.decode(&plain), "MEETAT1100ATGATE42X");
+//! ```
+//!
+
+use crate::errors::{FigureShiftError, InvalidOptionError};
+
+/// The letters standing for the digits 0 to 9 in the figures case of ITA2.
+pub const FIGURES: [char; 10] = ['P', 'Q', 'W', 'E', 'R', 'T', 'Y', 'U', 'I', 'O'];
+
+/// Encodes digits as letters and back, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FigureShift {
+    figures: Vec<char>,
+    letters: Vec<char>,
+}
+
+impl Default for FigureShift {
+    fn default() -> Self {
+        FigureShift {
+            figures: "FIGS".chars().collect(),
+            letters: "LTRS".chars().collect(),
+        }
+    }
+}
+
+impl FigureShift {
+    /// Constructs the convention with the indicators given. They have to
+    /// consist of the letters A-Z, differ and must not contain a doubled
+    /// letter, which would be split by the encryption. The letters
+    /// indicator must not start with a letter standing for a digit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::figure_shift::FigureShift;
+    ///
+    /// let shift = FigureShift::new("zf", "zl").unwrap();
+    /// assert_eq!(shift.encode("12 monkeys").unwrap(), "ZFQWZL monkeys");
+    /// assert!(FigureShift::new("FIGS", "TLRS").is_err());
+    /// ```
+    pub fn new(figures: &str, letters: &str) -> Result<Self, InvalidOptionError> {
+        let figures: Vec<char> = figures.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let letters: Vec<char> = letters.chars().map(|c| c.to_ascii_uppercase()).collect();
+        for indicator in [&figures, &letters] {
+            let text: String = indicator.iter().collect();
+            if indicator.is_empty() || !indicator.iter().all(char::is_ascii_uppercase) {
+                return Err(InvalidOptionError::new(format!(
+                    "Indicators must consist of the letters A-Z - got '{}'",
+                    text
+                )));
+            }
+            if indicator.windows(2).any(|pair| pair[0] == pair[1]) {
+                return Err(InvalidOptionError::new(format!(
+                    "Indicators must not contain doubled letters - got '{}'",
+                    text
+                )));
+            }
+        }
+        if figures == letters {
+            return Err(InvalidOptionError::new(String::from(
+                "The figures and the letters indicator must differ",
+            )));
+        }
+        if FIGURES.contains(&letters[0]) {
+            return Err(InvalidOptionError::new(format!(
+                "The letters indicator must not start with '{}', it stands for a digit",
+                letters[0]
+            )));
+        }
+        Ok(FigureShift { figures, letters })
+    }
+
+    /// Replaces each run of digits by the figures indicator, the letters
+    /// of the digits and the letters indicator. Chars between the digits
+    /// of a run, like the comma of "1,000", are kept within it. Fails if
+    /// the letters of the text contain the figures indicator, decoding
+    /// would take it for a shift.
+    pub fn encode(&self, text: &str) -> Result<String, FigureShiftError> {
+        let letters: String = text
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        let figures: String = self.figures.iter().collect();
+        if letters.contains(&figures) {
+            return Err(FigureShiftError::new(format!(
+                "The text contains the figures indicator '{}'",
+                figures
+            )));
+        }
+        let mut encoded = String::with_capacity(text.len() + 2 * figures.len());
+        let mut shifted = false;
+        // Chars following a digit, as long as it's unknown whether the run
+        // of digits goes on
+        let mut pending = String::new();
+        for c in text.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                if !shifted {
+                    encoded.extend(&self.figures);
+                    shifted = true;
+                }
+                encoded.push_str(&pending);
+                pending.clear();
+                encoded.push(FIGURES[digit as usize]);
+            } else if shifted && !c.is_alphabetic() {
+                pending.push(c);
+            } else {
+                if shifted {
+                    encoded.extend(&self.letters);
+                    shifted = false;
+                }
+                encoded.push_str(&pending);
+                pending.clear();
+                encoded.push(c);
+            }
+        }
+        if shifted {
+            encoded.extend(&self.letters);
+        }
+        encoded.push_str(&pending);
+        Ok(encoded)
+    }
+
+    /// Turns the runs between the indicators back into digits, e.g. in
+    /// decrypted text. Within a run any letter not standing for a digit is
+    /// dropped, like the padding splitting doubled digits. Letters outside
+    /// the runs are uppercased.
+    pub fn decode(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().map(|c| c.to_ascii_uppercase()).collect();
+        let mut decoded = String::with_capacity(chars.len());
+        let mut shifted = false;
+        let mut idx = 0;
+        while idx < chars.len() {
+            let indicator = match shifted {
+                true => &self.letters,
+                false => &self.figures,
+            };
+            if chars[idx..].starts_with(indicator) {
+                shifted = !shifted;
+                idx += indicator.len();
+                continue;
+            }
+            match shifted {
+                true => {
+                    if let Some(digit) = FIGURES.iter().position(|f| *f == chars[idx]) {
+                        decoded.push((b'0' + digit as u8) as char);
+                    }
+                }
+                false => decoded.push(chars[idx]),
+            }
+            idx += 1;
+        }
+        decoded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "four-square")]
+    fn test_round_trip() {
+        use crate::cryptable::Cypher;
+        use crate::four_square::FourSquare;
+
+        let shift = FigureShift::default();
+        let encoded = shift.encode("Dial 555-0199, ask for 7").unwrap();
+        assert_eq!(encoded, "Dial FIGSTTT-PQOOLTRS, ask for FIGSULTRS");
+        let fsq = FourSquare::new("EXAMPLE", "KEYWORD");
+        let plain = fsq.decrypt(&fsq.encrypt(&encoded).unwrap()).unwrap();
+        assert_eq!(shift.decode(&plain), "DIAL5550199ASKFOR7");
+        // Doubled digits split by padding when encrypting with Playfair
+        assert_eq!(shift.decode("FIGSQXQWXWLTRSX"), "1122X");
+    }
+
+    #[test]
+    fn test_indicator_in_text() {
+        let shift = FigureShift::default();
+        assert!(shift.encode("Fresh figs, 2 kg").is_err());
+        assert!(shift.encode("of igs").is_err());
+        assert!(shift.encode("LTRS 2").is_ok());
+    }
+
+    #[test]
+    fn test_new_invalid() {
+        assert!(FigureShift::new("", "LTRS").is_err());
+        assert!(FigureShift::new("FIG5", "LTRS").is_err());
+        assert!(FigureShift::new("FIGGS", "LTRS").is_err());
+        assert!(FigureShift::new("FIGS", "figs").is_err());
+        assert!(FigureShift::new("FIGS", "QL").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Formatting of cipher text for transmission. Any formatter implements
+//! CiphertextFormatter, so it can be handed to Cypher::encrypt_formatted.
+//! Formatters are combined as tuple, e.g. `(Groups::new(5, " "), Case::Lower)`.
+//!
+
+use crate::cryptable::{seriation_period, to_coordinates, Cypher};
+use crate::errors::CharNotInKeyError;
+
+/// Turns cipher text as returned by encrypt into the form it's handed on,
+/// e.g. grouped, in lowercase or as Morse code.
+pub trait CiphertextFormatter {
+    /// Formats the cipher text produced by the cipher. Fails if the cipher
+    /// text contains a letter not found in the key, which can only happen
+    /// if it was not produced by the cipher.
+    fn format(&self, cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError>;
+}
+
+impl<A: CiphertextFormatter, B: CiphertextFormatter> CiphertextFormatter for (A, B) {
+    fn format(&self, cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        self.1.format(cipher, &self.0.format(cipher, crypt)?)
+    }
+}
+
+impl<A: CiphertextFormatter, B: CiphertextFormatter, C: CiphertextFormatter> CiphertextFormatter
+    for (A, B, C)
+{
+    fn format(&self, cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        self.2.format(
+            cipher,
+            &self.1.format(cipher, &self.0.format(cipher, crypt)?)?,
+        )
+    }
+}
+
+/// Splits the letters of the cipher text into groups of the given length,
+/// joined by the separator. Whitespace in the cipher text is ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Groups {
+    pub length: usize,
+    pub separator: String,
+}
+
+impl Groups {
+    pub fn new(length: usize, separator: &str) -> Self {
+        Groups {
+            length,
+            separator: String::from(separator),
+        }
+    }
+}
+
+impl CiphertextFormatter for Groups {
+    fn format(&self, _cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        let letters: Vec<char> = crypt.chars().filter(|c| !c.is_whitespace()).collect();
+        Ok(letters
+            .chunks(self.length.max(1))
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join(&self.separator))
+    }
+}
+
+/// Casing of the cipher text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+impl CiphertextFormatter for Case {
+    fn format(&self, _cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        Ok(match self {
+            Case::Upper => crypt.to_uppercase(),
+            Case::Lower => crypt.to_lowercase(),
+        })
+    }
+}
+
+/// Emits the cipher text as coordinates within the key square, see
+/// SquareCypher::encrypt_coordinates. The squares of a cipher implemented
+/// outside the crate are unknown, so its letters can't be located.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Coordinates;
+
+impl CiphertextFormatter for Coordinates {
+    fn format(&self, cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        match cipher
+            .internals()
+            .and_then(|internals| internals.0.square_crypt())
+        {
+            Some(squares) => {
+                to_coordinates(squares.cipher_squares(), seriation_period(cipher), crypt)
+            }
+            None => match crypt.chars().find(|c| !c.is_whitespace()) {
+                Some(c) => Err(CharNotInKeyError::new(
+                    format!(
+                        "'{}' can't be located, the squares of the cipher are unknown",
+                        c
+                    ),
+                    c,
+                )),
+                None => Ok(String::new()),
+            },
+        }
+    }
+}
+
+/// Layout of a classic message blank: the cipher text in groups of five
+/// letters, ten groups per line. Optionally headed by the number of groups,
+/// e.g. "GR 12", so the receiver can check nothing got lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageBlank {
+    /// Letters per group.
+    pub group_length: usize,
+    /// Groups per line.
+    pub groups_per_line: usize,
+    /// Whether the first line states the number of groups.
+    pub group_count_header: bool,
+}
+
+impl Default for MessageBlank {
+    fn default() -> Self {
+        MessageBlank {
+            group_length: 5,
+            groups_per_line: 10,
+            group_count_header: false,
+        }
+    }
+}
+
+/// Lays out cipher text on a message blank. Whitespace in the cipher text is
+/// ignored, so grouped cipher text may be formatted again. A group or line
+/// length of 0 is taken as 1.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::format::{format_ciphertext, MessageBlank};
+///
+/// let blank = MessageBlank {
+///     groups_per_line: 2,
+///     group_count_header: true,
+///     ..MessageBlank::default()
+/// };
+/// assert_eq!(
+///     format_ciphertext("BMODZ BXDNA GE", &blank),
+///     "GR 3\nBMODZ BXDNA\nGE"
+/// );
+/// ```
+pub fn format_ciphertext(ciphertext: &str, blank: &MessageBlank) -> String {
+    let group_length = blank.group_length.max(1);
+    let groups_per_line = blank.groups_per_line.max(1);
+    let letters: Vec<char> = ciphertext.chars().filter(|c| !c.is_whitespace()).collect();
+    let groups = letters.len().div_ceil(group_length);
+
+    let mut formatted = String::with_capacity(letters.len() * 2 + 8);
+    if blank.group_count_header {
+        formatted.push_str(&format!("GR {}", groups));
+    }
+    for (idx, group) in letters.chunks(group_length).enumerate() {
+        if idx % groups_per_line == 0 {
+            if !formatted.is_empty() {
+                formatted.push('\n');
+            }
+        } else {
+            formatted.push(' ');
+        }
+        formatted.extend(group);
+    }
+    formatted
+}
+
+impl CiphertextFormatter for MessageBlank {
+    fn format(&self, _cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        Ok(format_ciphertext(crypt, self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::morse::Morse;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_formatters() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        assert_eq!(
+            pfc.encrypt_formatted("hide the gold", &Groups::new(3, " "))
+                .unwrap(),
+            "BMO DZB XDN AGE"
+        );
+        assert_eq!(
+            pfc.encrypt_formatted("hide", &Coordinates).unwrap(),
+            "31 25 43 33"
+        );
+        assert_eq!(
+            pfc.encrypt_formatted("hide", &(Groups::new(2, " "), Morse))
+                .unwrap(),
+            "-... -- / --- -.."
+        );
+        assert_eq!(
+            pfc.encrypt_formatted("hide", &(Case::Lower, Groups::new(1, "."), Case::Upper))
+                .unwrap(),
+            "B.M.O.D"
+        );
+        assert!(Coordinates.format(&pfc, "JA").is_err());
+    }
+
+    #[test]
+    fn test_format_default() {
+        let crypt = "A".repeat(52);
+        let formatted = format_ciphertext(&crypt, &MessageBlank::default());
+        let lines: Vec<&str> = formatted.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].split(' ').count(), 10);
+        assert_eq!(lines[1], "AA");
+        assert_eq!(format_ciphertext("", &MessageBlank::default()), "");
+    }
+
+    #[test]
+    fn test_format_header_only() {
+        let blank = MessageBlank {
+            group_count_header: true,
+            ..MessageBlank::default()
+        };
+        assert_eq!(format_ciphertext("", &blank), "GR 0");
+        assert_eq!(format_ciphertext("ABCDEF", &blank), "GR 2\nABCDE F");
+    }
+}
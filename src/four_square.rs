@@ -3,13 +3,17 @@
 //!
 
 use crate::{
-    cryptable::{Crypt, Cypher},
-    errors::CharNotInKeyError,
-    playfair::{EMPTY_SQ_POS, ROW_LENGTH},
-    structs::{CryptModus, CryptResult, Payload},
+    cryptable::{Crypt, Cypher, Internals, Layout, SquareCrypt, SquareCypher, SquareInternals},
+    digram::Rule,
+    errors::{CharNotInKeyError, InvalidOptionError},
+    options::{FinalDigram, PayloadOptions},
+    playfair::ROW_LENGTH,
+    seriation,
+    structs::{CryptModus, CryptResult},
 };
 
 use super::playfair::PlayFairKey;
+use std::fmt;
 
 /// Four square cipher works as its name suggests with those 4 squares.
 /// E.g. having this key matrix
@@ -27,22 +31,261 @@ use super::playfair::PlayFairKey;
 /// TUVXZ vwxyz
 ///
 ///
+#[derive(Debug, Clone)]
 pub struct FourSquare {
     // Within the struct, top left and bottom right square are represented by the standard
     // as they are the same
     top_right: PlayFairKey,
     bottom_left: PlayFairKey,
     standard_key: PlayFairKey,
+    options: PayloadOptions,
+}
+
+/// The cipher text square of a Four Square cipher with a single keyed
+/// square, see FourSquare::single_keyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyedSquare {
+    TopRight,
+    BottomLeft,
 }
 
 impl FourSquare {
     pub fn new(key0: &str, key1: &str) -> Self {
+        FourSquare::with_options(key0, key1, PayloadOptions::default())
+    }
+
+    /// Constructs a Four Square cipher of which only one cipher text square
+    /// is keyed, the other one is the standard alphabetical square as the
+    /// plain text squares are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::four_square::{FourSquare, KeyedSquare};
+    /// use playfair_cipher::cryptable::Cypher;
+    ///
+    /// let fsq = FourSquare::single_keyed("EXAMPLE", KeyedSquare::BottomLeft);
+    /// assert_eq!(fsq.top_right().to_string(), FourSquare::new("", "").top_right().to_string());
+    /// assert_eq!(fsq.decrypt(&fsq.encrypt("joe").unwrap()).unwrap(), "IOEX");
+    /// ```
+    pub fn single_keyed(key: &str, keyed: KeyedSquare) -> Self {
+        let (key0, key1) = single_keys(key, keyed);
+        FourSquare::new(key0, key1)
+    }
+
+    /// Returns a builder to construct a Four Square cipher with options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::four_square::FourSquare;
+    /// use playfair_cipher::cryptable::Cypher;
+    ///
+    /// let fsq = FourSquare::builder()
+    ///     .keys("EXAMPLE", "KEYWORD")
+    ///     .padding('Q')
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(fsq.encrypt("joe").unwrap(), "DIES");
+    /// ```
+    pub fn builder() -> FourSquareBuilder {
+        FourSquareBuilder::default()
+    }
+
+    /// The options the cipher was constructed with.
+    pub fn options(&self) -> &PayloadOptions {
+        &self.options
+    }
+
+    /// The keyed square top right.
+    pub fn top_right(&self) -> &PlayFairKey {
+        &self.top_right
+    }
+
+    /// The keyed square bottom left.
+    pub fn bottom_left(&self) -> &PlayFairKey {
+        &self.bottom_left
+    }
+
+    /// The period of the seriation, if the payload is seriated.
+    pub fn seriation(&self) -> Option<usize> {
+        self.options.seriation
+    }
+
+    pub(crate) fn with_options(key0: &str, key1: &str, options: PayloadOptions) -> Self {
+        FourSquare::from_keys(
+            PlayFairKey::with_options(key0, options),
+            PlayFairKey::with_options(key1, options),
+            options,
+        )
+    }
+
+    pub(crate) fn from_keys(
+        top_right: PlayFairKey,
+        bottom_left: PlayFairKey,
+        options: PayloadOptions,
+    ) -> Self {
         FourSquare {
-            top_right: PlayFairKey::new(key0),
-            bottom_left: PlayFairKey::new(key1),
-            standard_key: PlayFairKey::new(""),
+            top_right,
+            bottom_left,
+            standard_key: PlayFairKey::with_options("", options),
+            options,
         }
     }
+
+    // The cipher seriating the payload with the period. The squares are
+    // taken without seriation, they may be used on their own.
+    pub(crate) fn with_seriation(
+        self,
+        seriation: Option<usize>,
+    ) -> Result<Self, InvalidOptionError> {
+        seriation::validate_period(seriation)?;
+        Ok(FourSquare {
+            options: self.options.with_seriation(seriation),
+            ..self
+        })
+    }
+}
+
+/// Builder for a FourSquare, see FourSquare::builder.
+///
+#[derive(Debug, Clone)]
+pub struct FourSquareBuilder {
+    key0: String,
+    key1: String,
+    padding: char,
+    omit: char,
+    grouping: Option<usize>,
+    final_digram: FinalDigram,
+    seriation: Option<usize>,
+}
+
+impl Default for FourSquareBuilder {
+    fn default() -> Self {
+        let options = PayloadOptions::default();
+        FourSquareBuilder {
+            key0: String::new(),
+            key1: String::new(),
+            padding: options.padding,
+            omit: options.omit,
+            grouping: options.grouping,
+            final_digram: options.final_digram,
+            seriation: None,
+        }
+    }
+}
+
+impl FourSquareBuilder {
+    /// The keywords the squares are built of.
+    pub fn keys(mut self, key0: &str, key1: &str) -> Self {
+        self.key0 = String::from(key0);
+        self.key1 = String::from(key1);
+        self
+    }
+
+    /// Letter used to split doubled letters and to pad a payload of odd length.
+    pub fn padding(mut self, padding: char) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Letter left out of the squares. J is replaced by I, any other letter
+    /// is dropped.
+    pub fn omit(mut self, omit: char) -> Self {
+        self.omit = omit;
+        self
+    }
+
+    /// Splits the cipher text into groups of this many letters.
+    pub fn grouping(mut self, grouping: usize) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// How the last letter of a payload of odd length is handled, defaults
+    /// to FinalDigram::Pad.
+    pub fn final_digram(mut self, final_digram: FinalDigram) -> Self {
+        self.final_digram = final_digram;
+        self
+    }
+
+    /// The keyword of the single keyed square, the other cipher text square
+    /// is the standard one. Replaces the keywords given by keys.
+    pub fn single_key(mut self, key: &str, keyed: KeyedSquare) -> Self {
+        let (key0, key1) = single_keys(key, keyed);
+        self.key0 = String::from(key0);
+        self.key1 = String::from(key1);
+        self
+    }
+
+    /// Seriates the payload with the period: it's written in blocks of two
+    /// lines of this many letters and the digrams are taken vertically.
+    /// Doubled letters are not split then. Applies to all the ways the
+    /// cipher en- and decrypts, streams included.
+    pub fn seriation(mut self, period: usize) -> Self {
+        self.seriation = Some(period);
+        self
+    }
+
+    pub fn build(self) -> Result<FourSquare, InvalidOptionError> {
+        let options = PayloadOptions::new(self.padding, self.omit, self.grouping)?
+            .with_final_digram(self.final_digram);
+        FourSquare::with_options(&self.key0, &self.key1, options).with_seriation(self.seriation)
+    }
+}
+
+// The keywords of the top right and the bottom left square. The empty
+// keyword yields the standard square.
+fn single_keys(key: &str, keyed: KeyedSquare) -> (&str, &str) {
+    match keyed {
+        KeyedSquare::TopRight => (key, ""),
+        KeyedSquare::BottomLeft => ("", key),
+    }
+}
+
+impl fmt::Display for FourSquare {
+    /// Prints the four squares in their spatial arrangement, the keyed ones
+    /// top right and bottom left.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::four_square::FourSquare;
+    ///
+    /// let fsq = FourSquare::new("EXAMPLE", "KEYWORD");
+    /// print!("{}", fsq);
+    /// // A B C D E   E X A M P
+    /// // F G H I K   L B C D F
+    /// // L M N O P   G H I K N
+    /// // Q R S T U   O Q R S T
+    /// // V W X Y Z   U V W Y Z
+    /// //
+    /// // K E Y W O   A B C D E
+    /// // R D A B C   F G H I K
+    /// // F G H I L   L M N O P
+    /// // M N P Q S   Q R S T U
+    /// // T U V X Z   V W X Y Z
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..ROW_LENGTH as usize {
+            writeln!(
+                f,
+                "{}   {}",
+                self.standard_key.row(row),
+                self.top_right.row(row)
+            )?;
+        }
+        writeln!(f)?;
+        for row in 0..ROW_LENGTH as usize {
+            writeln!(
+                f,
+                "{}   {}",
+                self.bottom_left.row(row),
+                self.standard_key.row(row)
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl Crypt for FourSquare {
@@ -69,65 +312,67 @@ impl Crypt for FourSquare {
         // a.D -> row 1, col 3  decrypt a.I.row 1, b.O.col 3 -> 1 * 5 + 3 =  8 (I)
         // b.I -> row 2, col 3  decrypt b.O.row 2, a.J.col 3 -> 2 * 5 + 3 = 13 (O)
         //
-        let (top_right_hash_map, bottom_left_hash_map, top_left_key, bottom_right_key) = match modus
-        {
+        let (top_right_square, bottom_left_square, top_left_key, bottom_right_key) = match modus {
             CryptModus::Encrypt => (
-                &self.standard_key.key_map,
-                &self.standard_key.key_map,
+                &self.standard_key,
+                &self.standard_key,
                 &self.top_right.key,
                 &self.bottom_left.key,
             ),
             CryptModus::Decrypt => (
-                &self.top_right.key_map,
-                &self.bottom_left.key_map,
+                &self.top_right,
+                &self.bottom_left,
                 &self.standard_key.key,
                 &self.standard_key.key,
             ),
         };
 
-        let a_sq_pos = match top_right_hash_map.get(&a) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        let b_sq_pos = match bottom_left_hash_map.get(&b) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        if a_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                a, &top_right_hash_map
-            )));
-        } else if b_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                b, &self.bottom_left.key
-            )));
-        }
-        let a_crypted_idx: u8 = a_sq_pos.row * ROW_LENGTH + b_sq_pos.column;
-        let b_crypted_idx: u8 = b_sq_pos.row * ROW_LENGTH + a_sq_pos.column;
-        let a_crypted = match top_left_key.get(a_crypted_idx as usize) {
-            Some(s) => *s,
-            None => '*',
-        };
-        let b_crypted = match bottom_right_key.get(b_crypted_idx as usize) {
-            Some(s) => *s,
-            None => '*',
-        };
+        let a_cell = top_right_square.cell(a)?;
+        let b_cell = bottom_left_square.cell(b)?;
+        let (a_row, a_column) = (a_cell / ROW_LENGTH, a_cell % ROW_LENGTH);
+        let (b_row, b_column) = (b_cell / ROW_LENGTH, b_cell % ROW_LENGTH);
         Ok(CryptResult {
-            a: a_crypted,
-            b: b_crypted,
+            a: top_left_key[(a_row * ROW_LENGTH + b_column) as usize],
+            b: bottom_right_key[(b_row * ROW_LENGTH + a_column) as usize],
         })
     }
 
-    fn crypt_payload(
-        &self,
-        payload: &str,
-        modus: &crate::structs::CryptModus,
-    ) -> Result<String, crate::errors::CharNotInKeyError> {
-        let mut payload_iter = Payload::new(payload);
+    fn payload_options(&self) -> &PayloadOptions {
+        &self.options
+    }
+
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt> {
+        Some(self)
+    }
+}
+
+impl SquareCrypt for FourSquare {
+    fn cipher_squares(&self) -> [&PlayFairKey; 2] {
+        [&self.top_right, &self.bottom_left]
+    }
+
+    fn plain_squares(&self) -> [&PlayFairKey; 2] {
+        [&self.standard_key, &self.standard_key]
+    }
+
+    fn layout(&self) -> Layout<'_> {
+        Layout {
+            squares: vec![
+                &self.standard_key,
+                &self.top_right,
+                &self.bottom_left,
+                &self.standard_key,
+            ],
+            columns: 2,
+            plain: [0, 3],
+            crypt: [1, 2],
+        }
+    }
 
-        payload_iter.crypt_payload(self, modus)
+    fn rule(&self, a: char, b: char) -> Result<Rule, CharNotInKeyError> {
+        self.standard_key.cell(a)?;
+        self.standard_key.cell(b)?;
+        Ok(Rule::Rectangle)
     }
 }
 
@@ -176,12 +421,23 @@ impl Cypher for FourSquare {
     fn decrypt(&self, payload: &str) -> Result<String, crate::errors::CharNotInKeyError> {
         self.crypt_payload(payload, &CryptModus::Decrypt)
     }
+
+    fn internals(&self) -> Option<Internals<'_>> {
+        Some(Internals(self))
+    }
+}
+
+impl SquareCypher for FourSquare {
+    fn square_internals(&self) -> SquareInternals<'_> {
+        SquareInternals(self)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::errors::DecryptError;
 
     // Working with this key matrix:
     // abcde EXAMP
@@ -204,7 +460,7 @@ mod tests {
         let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
         assert!(
             four_square.standard_key.key
-                == vec![
+                == [
                     'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
                     'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'
                 ]
@@ -212,20 +468,68 @@ mod tests {
 
         assert!(
             four_square.top_right.key
-                == vec![
+                == [
                     'E', 'X', 'A', 'M', 'P', 'L', 'B', 'C', 'D', 'F', 'G', 'H', 'I', 'K', 'N', 'O',
                     'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'Y', 'Z'
                 ]
         );
         assert!(
             four_square.bottom_left.key
-                == vec![
+                == [
                     'K', 'E', 'Y', 'W', 'O', 'R', 'D', 'A', 'B', 'C', 'F', 'G', 'H', 'I', 'L', 'M',
                     'N', 'P', 'Q', 'S', 'T', 'U', 'V', 'X', 'Z'
                 ]
         );
     }
 
+    #[test]
+    fn test_four_square_single_keyed() {
+        let fsq = FourSquare::single_keyed("EXAMPLE", KeyedSquare::TopRight);
+        assert_eq!(
+            fsq.top_right().square(),
+            FourSquare::new("EXAMPLE", "").top_right().square()
+        );
+        assert_eq!(fsq.bottom_left().square(), fsq.standard_key.square());
+        let built = FourSquare::builder()
+            .single_key("EXAMPLE", KeyedSquare::TopRight)
+            .grouping(4)
+            .build()
+            .unwrap();
+        assert_eq!(built.bottom_left().square(), fsq.standard_key.square());
+        let crypt = fsq.encrypt("attack at dawn").unwrap();
+        assert_eq!(
+            built.encrypt("attack at dawn").unwrap().replace(' ', ""),
+            crypt
+        );
+        assert_eq!(fsq.decrypt(&crypt).unwrap(), "ATTACKATDAWN");
+    }
+
+    #[test]
+    fn test_four_square_seriation() {
+        let four_square = FourSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .seriation(5)
+            .grouping(5)
+            .build()
+            .unwrap();
+        // Doubled vertical pairs are not split, the odd length is padded
+        let crypt = four_square.encrypt("attack at dawn, attacks").unwrap();
+        assert_eq!(crypt.len(), 20 + 3);
+        assert_eq!(four_square.decrypt(&crypt).unwrap(), "ATTACKATDAWNATTACKSX");
+    }
+
+    #[test]
+    fn test_four_square_display() {
+        let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
+        let display = four_square.to_string();
+        let lines: Vec<&str> = display.lines().collect();
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "A B C D E   E X A M P");
+        assert_eq!(lines[5], "");
+        assert_eq!(lines[10], "T U V X Z   V W X Y Z");
+        assert!(format!("{:?}", four_square).starts_with("FourSquare"));
+    }
+
     #[test]
     fn test_four_square_encrypt() {
         let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
@@ -235,6 +539,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_four_square_decrypt_strict() {
+        let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
+        match four_square.decrypt_strict("DIAJ") {
+            Ok(s) => panic!("J is not part of the key - got {}", s),
+            Err(DecryptError::CharNotInKey(e)) => {
+                assert_eq!(e.character(), 'J');
+                assert_eq!(e.position(), Some(3));
+                assert_eq!(e.original_position(), Some(3));
+            }
+            Err(e) => panic!("Unexpected error {}", e),
+        }
+        assert!(matches!(
+            four_square.decrypt_strict("DIAZD"),
+            Err(DecryptError::OddLength { length: 5 })
+        ));
+        match four_square.decrypt_strict("diaz") {
+            Ok(s) => assert_eq!(s, "IOEX"),
+            Err(e) => panic!("DecryptError {}", e),
+        }
+    }
+
     #[test]
     fn test_four_square_decrypt() {
         let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
@@ -243,4 +569,57 @@ mod tests {
             Err(e) => panic!("CharNotInKeyError {}", e),
         }
     }
+
+    #[test]
+    fn test_four_square_coordinates() {
+        let cipher = FourSquare::new("EXAMPLE", "KEYWORD");
+        let coordinates = cipher.encrypt_coordinates("joe").unwrap();
+        assert_eq!(coordinates, "24 34 13 55");
+        assert_eq!(cipher.decrypt_coordinates(&coordinates).unwrap(), "IOEX");
+        assert!(matches!(
+            cipher.decrypt_coordinates("123"),
+            Err(crate::errors::CoordinateError::OddLength { length: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_four_square_traced() {
+        let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
+        let steps = four_square.encrypt_traced("joe").unwrap();
+        for step in steps {
+            for (positions, letters, squares) in [
+                (
+                    step.plain_positions,
+                    step.plain,
+                    four_square.plain_squares(),
+                ),
+                (
+                    step.crypt_positions,
+                    step.crypt,
+                    four_square.cipher_squares(),
+                ),
+            ] {
+                for idx in 0..2 {
+                    let cell = positions[idx].row * ROW_LENGTH + positions[idx].column;
+                    assert_eq!(squares[idx].key[cell as usize], letters[idx]);
+                }
+            }
+            // The rectangle: same row as the plain letter, column of the other one
+            assert_eq!(step.crypt_positions[0].row, step.plain_positions[0].row);
+            assert_eq!(
+                step.crypt_positions[0].column,
+                step.plain_positions[1].column
+            );
+        }
+    }
+
+    #[test]
+    fn test_four_square_explain() {
+        let four_square = FourSquare::new("EXAMPLE", "KEYWORD");
+        let explained = four_square.explain("de").unwrap();
+        let lines: Vec<&str> = explained.lines().collect();
+        assert_eq!(lines[0], "DE -> PW (rectangle)");
+        assert_eq!(lines[1], " A  B  C [D] E     E  X  A  M (P)");
+        assert_eq!(lines[7], " K  E  Y (W) O     A  B  C  D [E]");
+    }
 }
@@ -0,0 +1,282 @@
+//! Simulation of operator errors, so trainees of cryptanalysis get garbled
+//! traffic like it came off the air: letters of a digram swapped, a letter
+//! taken from the wrong row of the square and whole groups lost. The errors
+//! are injected at random with the rates configured, the seed makes a run
+//! reproducible.
+//!
+//! ```
+//! use playfair_cipher::{cryptable::Cypher, garble::ErrorSimulator, playfair::PlayFairKey};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let crypt = pfc.encrypt("we shall meet at the bridge at midnight").unwrap();
+//! let mut simulator = ErrorSimulator::builder()
+//!     .wrong_row(0.2)
+//!     .dropped_group(0.1)
+//!     .seed(7)
+//!     .build()
+//!     .unwrap();
+//! let garbled = simulator.garble(&pfc, &crypt).unwrap();
+//! assert!(!garbled.errors.is_empty());
+//! assert_ne!(garbled.text.replace(' ', ""), crypt);
+//! ```
+//!
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::crack::Rng;
+use crate::cryptable::{seriation_period, SquareCypher};
+use crate::errors::{CharNotInKeyError, InvalidOptionError};
+use crate::grid::Grid;
+use crate::seriation;
+
+/// An error injected into the cipher text. Positions are counted in
+/// letters of the cipher text handed in, whitespace ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorError {
+    /// The two letters of the digram starting at the position were sent
+    /// in reverse order.
+    TransposedDigram { position: usize },
+    /// The letter was taken from the row above or below the right one in
+    /// its square.
+    WrongRow {
+        position: usize,
+        expected: char,
+        found: char,
+    },
+    /// The group of letters starting at the position got lost.
+    DroppedGroup { position: usize, length: usize },
+}
+
+/// Cipher text with errors injected, see ErrorSimulator::garble.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Garbled {
+    /// The garbled cipher text in groups.
+    pub text: String,
+    /// The errors injected, the ones within digrams first, each ordered
+    /// by position.
+    pub errors: Vec<OperatorError>,
+}
+
+/// Injects operator errors into cipher text, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct ErrorSimulator {
+    transposed_digram: f64,
+    wrong_row: f64,
+    dropped_group: f64,
+    group_length: usize,
+    rng: Rng,
+}
+
+/// Builder for an ErrorSimulator. All rates default to 0.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorSimulatorBuilder {
+    transposed_digram: f64,
+    wrong_row: f64,
+    dropped_group: f64,
+    group_length: Option<usize>,
+    seed: Option<u64>,
+}
+
+impl ErrorSimulatorBuilder {
+    /// Share of digrams sent in reverse order, from 0 to 1.
+    pub fn transposed_digram(mut self, rate: f64) -> Self {
+        self.transposed_digram = rate;
+        self
+    }
+
+    /// Share of digrams with a letter taken from the wrong row, from 0 to 1.
+    pub fn wrong_row(mut self, rate: f64) -> Self {
+        self.wrong_row = rate;
+        self
+    }
+
+    /// Share of groups lost, from 0 to 1.
+    pub fn dropped_group(mut self, rate: f64) -> Self {
+        self.dropped_group = rate;
+        self
+    }
+
+    /// Number of letters of a group the cipher text is sent in. Defaults
+    /// to 5.
+    pub fn group_length(mut self, group_length: usize) -> Self {
+        self.group_length = Some(group_length);
+        self
+    }
+
+    /// Seed of the random errors. Defaults to the current time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Fails if a rate is not within 0 and 1 or the group length is 0.
+    pub fn build(self) -> Result<ErrorSimulator, InvalidOptionError> {
+        for (name, rate) in [
+            ("transposed digrams", self.transposed_digram),
+            ("wrong rows", self.wrong_row),
+            ("dropped groups", self.dropped_group),
+        ] {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(InvalidOptionError::new(format!(
+                    "Rate of {} must be within 0 and 1 - got {}",
+                    name, rate
+                )));
+            }
+        }
+        let group_length = self.group_length.unwrap_or(5);
+        if group_length == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Group length must be greater than 0",
+            )));
+        }
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Ok(ErrorSimulator {
+            transposed_digram: self.transposed_digram,
+            wrong_row: self.wrong_row,
+            dropped_group: self.dropped_group,
+            group_length,
+            rng: Rng::new(seed),
+        })
+    }
+}
+
+impl ErrorSimulator {
+    /// Returns a builder to configure the simulator.
+    pub fn builder() -> ErrorSimulatorBuilder {
+        ErrorSimulatorBuilder::default()
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        // 53 random bits make a uniformly distributed f64 in 0..1
+        ((self.rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < rate
+    }
+
+    /// Injects errors into cipher text produced by the cipher. The letters
+    /// taken from a wrong row are looked up in the squares of the cipher.
+    /// Fails if the cipher text holds a letter not in those squares.
+    pub fn garble(
+        &mut self,
+        cipher: &(impl SquareCypher + ?Sized),
+        crypt: &str,
+    ) -> Result<Garbled, CharNotInKeyError> {
+        let squares = cipher.square_internals().0.cipher_squares();
+        let period = seriation_period(cipher);
+        let grid = Grid::square(5);
+        let mut letters: Vec<char> = crypt.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut errors = Vec::new();
+        for position in (0..letters.len().saturating_sub(1)).step_by(2) {
+            if self.chance(self.wrong_row) {
+                let position = position + self.rng.below(2);
+                let square = squares[seriation::member(position, period, letters.len())];
+                let cell = square.cell(letters[position])? as usize;
+                let steps = if self.rng.below(2) == 0 { -1 } else { 1 };
+                let found = square.key[grid.shift_down(cell, steps)];
+                errors.push(OperatorError::WrongRow {
+                    position,
+                    expected: letters[position],
+                    found,
+                });
+                letters[position] = found;
+            }
+            if self.chance(self.transposed_digram) {
+                letters.swap(position, position + 1);
+                errors.push(OperatorError::TransposedDigram { position });
+            }
+        }
+        let mut groups = Vec::new();
+        for (idx, group) in letters.chunks(self.group_length).enumerate() {
+            if self.chance(self.dropped_group) {
+                errors.push(OperatorError::DroppedGroup {
+                    position: idx * self.group_length,
+                    length: group.len(),
+                });
+            } else {
+                groups.push(group.iter().collect::<String>());
+            }
+        }
+        Ok(Garbled {
+            text: groups.join(" "),
+            errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_no_errors() {
+        let pfc = PlayFairKey::new("playfair example");
+        let mut simulator = ErrorSimulator::builder().seed(1).build().unwrap();
+        let garbled = simulator.garble(&pfc, "BMODZ BXDNAGE").unwrap();
+        assert_eq!(garbled.text, "BMODZ BXDNA GE");
+        assert!(garbled.errors.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "four-square")]
+    fn test_every_digram() {
+        use crate::cryptable::{Cypher, SquareCrypt};
+        use crate::four_square::FourSquare;
+
+        let fsq = FourSquare::new("EXAMPLE", "KEYWORD");
+        let crypt = fsq.encrypt("attack at dawn").unwrap();
+        let mut simulator = ErrorSimulator::builder()
+            .wrong_row(1.0)
+            .transposed_digram(1.0)
+            .dropped_group(1.0)
+            .seed(3)
+            .build()
+            .unwrap();
+        let garbled = simulator.garble(&fsq, &crypt).unwrap();
+        assert!(garbled.text.is_empty());
+        assert_eq!(garbled.errors.len(), 6 + 6 + 3);
+        for error in garbled.errors {
+            if let OperatorError::WrongRow {
+                position,
+                expected,
+                found,
+            } = error
+            {
+                let square = fsq.cipher_squares()[position % 2];
+                let (expected, found) =
+                    (square.cell(expected).unwrap(), square.cell(found).unwrap());
+                assert_eq!(expected % 5, found % 5);
+                assert_ne!(expected, found);
+            }
+        }
+        // Same seed, same errors
+        let mut other = ErrorSimulator::builder()
+            .wrong_row(0.5)
+            .seed(3)
+            .build()
+            .unwrap();
+        let mut again = ErrorSimulator::builder()
+            .wrong_row(0.5)
+            .seed(3)
+            .build()
+            .unwrap();
+        assert_eq!(
+            other.garble(&fsq, &crypt).unwrap(),
+            again.garble(&fsq, &crypt).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(ErrorSimulator::builder().wrong_row(1.5).build().is_err());
+        assert!(ErrorSimulator::builder()
+            .dropped_group(-0.1)
+            .build()
+            .is_err());
+        assert!(ErrorSimulator::builder().group_length(0).build().is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! Arithmetic on the cells of a Polybius square or any other grid: the
+//! cells are numbered row by row from 0, so in a 5*5 square the cell in
+//! row 2, column 3 is cell 13. The ciphers of the crate are built on these
+//! helpers, so variants with other dimensions don't need to repeat them.
+//!
+//! ```
+//! use playfair_cipher::grid::Grid;
+//!
+//! let grid = Grid::square(5);
+//! assert_eq!(grid.index(2, 3), 13);
+//! assert_eq!(grid.position(13), (2, 3));
+//! // The PlayFair rules: shifting within a row or column, wrapping around,
+//! // and swapping the columns of a rectangle's corners
+//! assert_eq!(grid.shift_right(14, 1), 10);
+//! assert_eq!(grid.shift_down(23, -1), 18);
+//! assert_eq!(grid.rectangle_corners(1, 13), (3, 11));
+//! ```
+//!
+
+/// A grid of rows * columns cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid {
+    rows: usize,
+    columns: usize,
+}
+
+impl Grid {
+    /// A grid of the dimensions given.
+    ///
+    /// # Panics
+    ///
+    /// If rows or columns is 0.
+    pub const fn new(rows: usize, columns: usize) -> Self {
+        assert!(rows > 0 && columns > 0, "a grid needs at least one cell");
+        Grid { rows, columns }
+    }
+
+    /// A grid of length * length cells.
+    pub const fn square(length: usize) -> Self {
+        Grid::new(length, length)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of cells.
+    pub fn cells(&self) -> usize {
+        self.rows * self.columns
+    }
+
+    /// Index of the cell in the row and column given.
+    pub fn index(&self, row: usize, column: usize) -> usize {
+        row * self.columns + column
+    }
+
+    /// Row and column of the cell.
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        (index / self.columns, index % self.columns)
+    }
+
+    pub fn row(&self, index: usize) -> usize {
+        index / self.columns
+    }
+
+    pub fn column(&self, index: usize) -> usize {
+        index % self.columns
+    }
+
+    pub fn same_row(&self, a: usize, b: usize) -> bool {
+        self.row(a) == self.row(b)
+    }
+
+    pub fn same_column(&self, a: usize, b: usize) -> bool {
+        self.column(a) == self.column(b)
+    }
+
+    /// The cell steps to the right within the same row, wrapping around
+    /// from the last column to the first. Negative steps move left.
+    pub fn shift_right(&self, index: usize, steps: isize) -> usize {
+        let (row, column) = self.position(index);
+        self.index(row, wrap(column, steps, self.columns))
+    }
+
+    /// The cell steps down within the same column, wrapping around from the
+    /// last row to the first. Negative steps move up.
+    pub fn shift_down(&self, index: usize, steps: isize) -> usize {
+        let (row, column) = self.position(index);
+        self.index(wrap(row, steps, self.rows), column)
+    }
+
+    /// The other two corners of the rectangle spanned by the cells a and b:
+    /// the one in the row of a and the column of b, and the one in the row
+    /// of b and the column of a.
+    pub fn rectangle_corners(&self, a: usize, b: usize) -> (usize, usize) {
+        let (a_row, a_column) = self.position(a);
+        let (b_row, b_column) = self.position(b);
+        (self.index(a_row, b_column), self.index(b_row, a_column))
+    }
+}
+
+fn wrap(value: usize, steps: isize, length: usize) -> usize {
+    (value as isize + steps).rem_euclid(length as isize) as usize
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_rectangular() {
+        let grid = Grid::new(2, 3);
+        assert_eq!(grid.cells(), 6);
+        assert_eq!(grid.position(5), (1, 2));
+        assert_eq!(grid.shift_right(5, 7), 3);
+        assert_eq!(grid.shift_down(5, -3), 2);
+        assert!(grid.same_column(1, 4));
+        assert!(!grid.same_row(2, 3));
+        assert_eq!(grid.rectangle_corners(0, 5), (2, 3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty() {
+        Grid::new(0, 5);
+    }
+}
@@ -0,0 +1,105 @@
+//! Setups as used historically, so messages can be en- and decrypted by the
+//! conventions of the time without researching padding letter, grouping or
+//! the handling of I and J first.
+//!
+
+use crate::config::{Algorithm, CipherConfig};
+use crate::cryptable::Cypher;
+use crate::errors::InvalidOptionError;
+
+/// A historical setup of one of the ciphers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// PlayFair as used by the British Army in World War I: J is written
+    /// as I, doubled letters and an odd length are padded with X, the
+    /// cipher text is sent in groups of five.
+    BritishArmyWw1,
+    /// PlayFair as used by the Australian coastwatchers in the Pacific in
+    /// World War II, e.g. for the message reporting the crew of PT-109:
+    /// same conventions as the British Army.
+    Coastwatchers,
+    /// Two Square with the conventions of the German Doppelkasten of World
+    /// War II: J is written as I, X pads and the cipher text is sent in
+    /// groups of five. Note the squares of the Doppelkasten were placed
+    /// side by side and each digram was enciphered twice, this preset only
+    /// covers the conventions on the letters.
+    GermanDoubleBox,
+}
+
+impl Preset {
+    /// All presets.
+    pub const ALL: [Preset; 3] = [
+        Preset::BritishArmyWw1,
+        Preset::Coastwatchers,
+        Preset::GermanDoubleBox,
+    ];
+
+    /// Short description of the setup.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Preset::BritishArmyWw1 => "PlayFair, British Army WWI: I=J, X pads, groups of 5",
+            Preset::Coastwatchers => {
+                "PlayFair, Australian coastwatchers WWII: I=J, X pads, groups of 5"
+            }
+            Preset::GermanDoubleBox => {
+                "Two Square, German Doppelkasten WWII: I=J, X pads, groups of 5"
+            }
+        }
+    }
+
+    /// The setup as config with the keys given.
+    pub fn config(&self, keys: &[&str]) -> CipherConfig {
+        let algorithm = match self {
+            Preset::BritishArmyWw1 | Preset::Coastwatchers => Algorithm::PlayFair,
+            Preset::GermanDoubleBox => Algorithm::TwoSquare,
+        };
+        CipherConfig {
+            algorithm,
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            padding: Some('X'),
+            omit: Some('J'),
+            grouping: Some(5),
+            final_digram: None,
+            seriation: None,
+            transparency: None,
+        }
+    }
+
+    /// Constructs the cipher of the setup. Fails if the number of keys
+    /// doesn't fit the cipher.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::historical::Preset;
+    ///
+    /// // The start of the PT-109 message. The original writes the cell of
+    /// // I and J as J, so it reads KXJEY UREBE ZWEHE WRYTU.
+    /// let cipher = Preset::Coastwatchers.build(&["royal new zealand navy"]).unwrap();
+    /// assert_eq!(
+    ///     cipher.encrypt("PT boat one owe nine lost").unwrap(),
+    ///     "KXIEY UREBE ZWEHE WRYTU"
+    /// );
+    /// ```
+    pub fn build(&self, keys: &[&str]) -> Result<Box<dyn Cypher>, InvalidOptionError> {
+        self.config(keys).build()
+    }
+}
+
+#[cfg(all(test, feature = "two-square"))]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in Preset::ALL {
+            let keys = vec!["EXAMPLE"; preset.config(&[]).algorithm.keys()];
+            let cipher = preset.build(&keys).unwrap();
+            let crypt = cipher.encrypt("jump over the hill").unwrap();
+            assert_eq!(crypt.split(' ').next().unwrap().len(), 5);
+            assert!(cipher.decrypt(&crypt).unwrap().starts_with("IUMP"));
+        }
+        assert!(Preset::GermanDoubleBox.build(&["EXAMPLE"]).is_err());
+    }
+}
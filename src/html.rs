@@ -0,0 +1,161 @@
+//! Generator of worked examples as standalone HTML pages: the key squares,
+//! the table of digrams and the encryption step by step, e.g. for teachers
+//! handing out examples. Only available with the feature `html`.
+//!
+
+use std::fmt::Write;
+
+use crate::cryptable::{Layout, SquareCypher};
+use crate::digram::{Position, TraceStep};
+use crate::errors::CharNotInKeyError;
+use crate::playfair::ROW_LENGTH;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table.square { border-collapse: collapse; font-family: monospace; font-size: 1.2em; }
+table.square td { border: 1px solid #444; width: 1.8em; height: 1.8em; text-align: center; }
+table.layout td { padding: 0 1em 1em 0; vertical-align: top; }
+table.digrams { border-collapse: collapse; }
+table.digrams th, table.digrams td { border: 1px solid #444; padding: 0.2em 0.8em; font-family: monospace; }
+td.plain { background: #90caf9; }
+td.crypt { background: #ffd54f; }
+td.both { background: #a5d6a7; }";
+
+/// Generates a page explaining the encryption of the plain text with the
+/// cipher. In each step the plain letters are highlighted blue, the cipher
+/// letters yellow and a letter which is both green.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{html::lesson, playfair::PlayFairKey};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let page = lesson(&pfc, "hide the gold").unwrap();
+/// assert!(page.starts_with("<!DOCTYPE html>"));
+/// assert!(page.contains("BMODZBXDNAGE"));
+/// ```
+pub fn lesson(cipher: &impl SquareCypher, plaintext: &str) -> Result<String, CharNotInKeyError> {
+    let layout = cipher.square_internals().0.layout();
+    let steps = cipher.encrypt_traced(plaintext)?;
+    let crypt: String = steps.iter().flat_map(|step| step.crypt).collect();
+
+    let mut page = String::new();
+    // Writing into a String can't fail
+    let _ = write!(
+        page,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Worked example</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+        STYLE
+    );
+    let _ = writeln!(page, "<h1>Worked example</h1>");
+    let _ = writeln!(
+        page,
+        "<p>Plain text: <code>{}</code></p>",
+        escape(plaintext)
+    );
+    let _ = writeln!(page, "<h2>Key</h2>");
+    page.push_str(&layout_html(&layout, None));
+
+    let _ = writeln!(page, "<h2>Digrams</h2>");
+    let _ = writeln!(
+        page,
+        "<table class=\"digrams\">\n<tr><th>Plain</th><th>Rule</th><th>Cipher</th></tr>"
+    );
+    for step in &steps {
+        let _ = writeln!(
+            page,
+            "<tr><td>{}{}</td><td>{}</td><td>{}{}</td></tr>",
+            step.plain[0], step.plain[1], step.rule, step.crypt[0], step.crypt[1]
+        );
+    }
+    let _ = writeln!(page, "</table>");
+
+    let _ = writeln!(page, "<h2>Step by step</h2>");
+    for (idx, step) in steps.iter().enumerate() {
+        let _ = writeln!(
+            page,
+            "<h3>Step {}: {}{} &rarr; {}{} ({})</h3>",
+            idx + 1,
+            step.plain[0],
+            step.plain[1],
+            step.crypt[0],
+            step.crypt[1],
+            step.rule
+        );
+        page.push_str(&layout_html(&layout, Some(step)));
+    }
+
+    let _ = writeln!(page, "<h2>Cipher text</h2>");
+    let _ = writeln!(page, "<p><code>{}</code></p>", crypt);
+    page.push_str("</body>\n</html>\n");
+    Ok(page)
+}
+
+// The squares as laid out on paper, with the letters of the step marked.
+fn layout_html(layout: &Layout, step: Option<&TraceStep>) -> String {
+    let is_at = |squares: [usize; 2], positions: [Position; 2], square: usize, at: Position| {
+        (0..2).any(|idx| squares[idx] == square && positions[idx] == at)
+    };
+    let mut html = String::from("<table class=\"layout\">\n");
+    for (layout_row, squares) in layout.squares.chunks(layout.columns).enumerate() {
+        html.push_str("<tr>\n");
+        for (layout_column, square) in squares.iter().enumerate() {
+            let square_idx = layout_row * layout.columns + layout_column;
+            html.push_str("<td><table class=\"square\">\n");
+            for row in 0..ROW_LENGTH {
+                html.push_str("<tr>");
+                for column in 0..ROW_LENGTH {
+                    let at = Position { row, column };
+                    let class = match step.map(|step| {
+                        (
+                            is_at(layout.plain, step.plain_positions, square_idx, at),
+                            is_at(layout.crypt, step.crypt_positions, square_idx, at),
+                        )
+                    }) {
+                        Some((true, true)) => " class=\"both\"",
+                        Some((true, false)) => " class=\"plain\"",
+                        Some((false, true)) => " class=\"crypt\"",
+                        _ => "",
+                    };
+                    let _ = write!(
+                        html,
+                        "<td{}>{}</td>",
+                        class,
+                        square.key[(row * ROW_LENGTH + column) as usize]
+                    );
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table></td>\n");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+// Escapes the chars with a meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(all(test, feature = "two-square"))]
+mod tests {
+
+    use super::*;
+    use crate::two_square::TwoSquare;
+
+    #[test]
+    fn test_lesson() {
+        let tsq = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let page = lesson(&tsq, "help <me>").unwrap();
+        assert!(page.contains("<code>help &lt;me&gt;</code>"));
+        // The key plus a layout for each of the 3 steps, each with both squares
+        assert_eq!(page.matches("<table class=\"square\">").count(), 4 * 2);
+        assert_eq!(page.matches("class=\"both\"").count(), 2);
+        assert_eq!(page.matches("class=\"plain\"").count(), 4);
+        assert!(page.contains("<tr><td>HE</td><td>column</td><td>HE</td></tr>"));
+    }
+}
@@ -0,0 +1,347 @@
+//! Indicator groups as sent by operators: for each message a random group
+//! of letters is chosen, the message is encrypted with a setup derived from
+//! it and the group is sent in the clear in front of the cipher text. The
+//! receiver reads the indicator off the message, derives the same setup
+//! and decrypts the rest.
+//!
+//! The setup is derived by mixing the indicator into each keyword, see
+//! KeyDerivation, so every message is encrypted with different squares
+//! although the operators share one set of keywords.
+//!
+//! ```
+//! use playfair_cipher::config::CipherConfig;
+//! use playfair_cipher::indicator::IndicatorSystem;
+//!
+//! let config = CipherConfig {
+//!     keys: vec![String::from("playfair example")],
+//!     grouping: Some(5),
+//!     ..CipherConfig::default()
+//! };
+//! let mut system = IndicatorSystem::builder()
+//!     .config(config)
+//!     .seed(42)
+//!     .build()
+//!     .unwrap();
+//! let message = system.encrypt("hide the gold").unwrap();
+//! assert_eq!(message.len(), 6 + 14);
+//! assert_eq!(system.decrypt(&message).unwrap(), "HIDETHEGOLDX");
+//! ```
+//!
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::CipherConfig;
+use crate::crack::Rng;
+use crate::cryptable::Cypher;
+use crate::errors::{CharNotInKeyError, IndicatorError, InvalidOptionError};
+
+/// How the indicator is mixed into a keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum KeyDerivation {
+    /// The indicator is put in front of the keyword, e.g. QFX and MONARCHY
+    /// yield QFXMONARCHY.
+    #[default]
+    Prefix,
+    /// The letters of the indicator are put between the letters of the
+    /// keyword, repeating the indicator, e.g. QFX and MONARCHY yield
+    /// MQOFNXAQRFCXHQYF. The whole square changes, not only its start.
+    Interleave,
+}
+
+impl KeyDerivation {
+    /// The keyword of a message with the indicator given.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::indicator::KeyDerivation;
+    ///
+    /// assert_eq!(KeyDerivation::Interleave.derive("monarchy", "QFX"), "MQOFNXAQRFCXHQYF");
+    /// ```
+    pub fn derive(&self, keyword: &str, indicator: &str) -> String {
+        match self {
+            KeyDerivation::Prefix => format!("{}{}", indicator, keyword),
+            KeyDerivation::Interleave => {
+                let keyword = keyword.to_uppercase();
+                let letters = keyword.chars().filter(char::is_ascii_alphabetic);
+                let mut derived = String::with_capacity(2 * keyword.len());
+                for (letter, mixed) in letters.zip(indicator.chars().cycle()) {
+                    derived.push(letter);
+                    derived.push(mixed);
+                }
+                derived
+            }
+        }
+    }
+}
+
+/// Encrypts and decrypts messages with indicator groups, see the module
+/// documentation.
+#[derive(Debug, Clone)]
+pub struct IndicatorSystem {
+    config: CipherConfig,
+    length: usize,
+    derivation: KeyDerivation,
+    alphabet: Vec<char>,
+    rng: Rng,
+}
+
+/// Builder for an IndicatorSystem.
+#[derive(Debug, Clone, Default)]
+pub struct IndicatorSystemBuilder {
+    config: CipherConfig,
+    length: Option<usize>,
+    derivation: KeyDerivation,
+    seed: Option<u64>,
+}
+
+impl IndicatorSystemBuilder {
+    /// The cipher with the keywords shared by the operators.
+    pub fn config(mut self, config: CipherConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Number of letters of the indicator group. Defaults to 5.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// How the indicator is mixed into the keywords, defaults to
+    /// KeyDerivation::Prefix.
+    pub fn derivation(mut self, derivation: KeyDerivation) -> Self {
+        self.derivation = derivation;
+        self
+    }
+
+    /// Seed of the random indicators. Defaults to the current time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Fails if the config doesn't describe a valid cipher or the length
+    /// is 0.
+    pub fn build(self) -> Result<IndicatorSystem, InvalidOptionError> {
+        let length = self.length.unwrap_or(5);
+        if length == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Indicator length must be greater than 0",
+            )));
+        }
+        let options = self.config.options()?;
+        self.config.build()?;
+        let seed = self.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        Ok(IndicatorSystem {
+            config: self.config,
+            length,
+            derivation: self.derivation,
+            alphabet: options.alphabet().collect(),
+            rng: Rng::new(seed),
+        })
+    }
+}
+
+impl IndicatorSystem {
+    /// Returns a builder to configure the system.
+    pub fn builder() -> IndicatorSystemBuilder {
+        IndicatorSystemBuilder::default()
+    }
+
+    /// A new random indicator group.
+    pub fn generate_indicator(&mut self) -> String {
+        (0..self.length)
+            .map(|_| self.alphabet[self.rng.below(self.alphabet.len())])
+            .collect()
+    }
+
+    /// The setup a message with this indicator is en- or decrypted with.
+    pub fn message_config(&self, indicator: &str) -> CipherConfig {
+        CipherConfig {
+            keys: self
+                .config
+                .keys
+                .iter()
+                .map(|key| self.derivation.derive(key, indicator))
+                .collect(),
+            ..self.config.clone()
+        }
+    }
+
+    fn message_cipher(&self, indicator: &str) -> Box<dyn Cypher> {
+        self.message_config(indicator)
+            .build()
+            .expect("the config was validated by the builder")
+    }
+
+    /// Encrypts a message with a new random indicator and prepends the
+    /// indicator group, separated by a space.
+    pub fn encrypt(&mut self, payload: &str) -> Result<String, CharNotInKeyError> {
+        let indicator = self.generate_indicator();
+        self.encrypt_with(&indicator, payload)
+    }
+
+    /// Encrypts a message with the indicator given, e.g. one taken from an
+    /// indicator table. The indicator should consist of letters of the
+    /// square, otherwise the message can't be split again.
+    pub fn encrypt_with(
+        &self,
+        indicator: &str,
+        payload: &str,
+    ) -> Result<String, CharNotInKeyError> {
+        let indicator = indicator.to_ascii_uppercase();
+        let crypt = self.message_cipher(&indicator).encrypt(payload)?;
+        Ok(format!("{} {}", indicator, crypt))
+    }
+
+    /// Reads the indicator off the message and decrypts the rest with the
+    /// setup derived from it. Whitespace is ignored.
+    pub fn decrypt(&self, message: &str) -> Result<String, IndicatorError> {
+        let (indicator, crypt) = self.split(message)?;
+        Ok(self.message_cipher(&indicator).decrypt(crypt)?)
+    }
+
+    /// Splits a message into its indicator and the cipher text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::config::CipherConfig;
+    /// use playfair_cipher::errors::IndicatorError;
+    /// use playfair_cipher::indicator::IndicatorSystem;
+    ///
+    /// let config = CipherConfig {
+    ///     keys: vec![String::from("monarchy")],
+    ///     ..CipherConfig::default()
+    /// };
+    /// let system = IndicatorSystem::builder().config(config).build().unwrap();
+    /// assert_eq!(
+    ///     system.split("QWE RT BMODZ").unwrap(),
+    ///     (String::from("QWERT"), " BMODZ")
+    /// );
+    /// assert!(matches!(
+    ///     system.split("QWJ"),
+    ///     Err(IndicatorError::InvalidChar { character: 'J', position: 2 })
+    /// ));
+    /// ```
+    pub fn split<'a>(&self, message: &'a str) -> Result<(String, &'a str), IndicatorError> {
+        let mut indicator = String::with_capacity(self.length);
+        for (position, (idx, character)) in message.char_indices().enumerate() {
+            if indicator.len() == self.length {
+                return Ok((indicator, &message[idx..]));
+            }
+            if character.is_whitespace() {
+                continue;
+            }
+            let letter = character.to_ascii_uppercase();
+            if !self.alphabet.contains(&letter) {
+                return Err(IndicatorError::InvalidChar {
+                    character,
+                    position,
+                });
+            }
+            indicator.push(letter);
+        }
+        match indicator.len() == self.length {
+            true => Ok((indicator, "")),
+            false => Err(IndicatorError::MissingIndicator {
+                expected: self.length,
+                found: indicator.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::config::Algorithm;
+
+    #[cfg(feature = "four-square")]
+    fn system(seed: u64) -> IndicatorSystem {
+        let config = CipherConfig {
+            algorithm: Algorithm::FourSquare,
+            keys: vec![String::from("EXAMPLE"), String::from("KEYWORD")],
+            ..CipherConfig::default()
+        };
+        IndicatorSystem::builder()
+            .config(config)
+            .length(4)
+            .seed(seed)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "four-square")]
+    fn test_indicators_differ() {
+        let mut system = system(7);
+        let first = system.encrypt("attack at dawn").unwrap();
+        let second = system.encrypt("attack at dawn").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(system.decrypt(&first).unwrap(), "ATTACKATDAWN");
+        assert_eq!(system.decrypt(&second).unwrap(), "ATTACKATDAWN");
+        // The same seed yields the same indicators
+        assert_eq!(self::system(7).encrypt("attack at dawn").unwrap(), first);
+    }
+
+    #[test]
+    #[cfg(feature = "four-square")]
+    fn test_derived_setup() {
+        let system = system(0);
+        let config = system.message_config("QRST");
+        assert_eq!(config.keys, ["QRSTEXAMPLE", "QRSTKEYWORD"]);
+        let message = system.encrypt_with("QRST", "attack at dawn").unwrap();
+        assert!(message.starts_with("QRST "));
+        let other = system.encrypt_with("ABCD", "attack at dawn").unwrap();
+        assert_ne!(message[5..], other[5..]);
+        assert!(matches!(
+            system.decrypt("QR"),
+            Err(IndicatorError::MissingIndicator {
+                expected: 4,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_trigram_interleaved() {
+        let config = CipherConfig {
+            keys: vec![String::from("monarchy")],
+            ..CipherConfig::default()
+        };
+        let mut system = IndicatorSystem::builder()
+            .config(config)
+            .length(3)
+            .derivation(KeyDerivation::Interleave)
+            .seed(3)
+            .build()
+            .unwrap();
+        let message = system.encrypt("instruments").unwrap();
+        let (indicator, _) = system.split(&message).unwrap();
+        assert_eq!(indicator.len(), 3);
+        assert_eq!(
+            system.message_config(&indicator).keys,
+            [KeyDerivation::Interleave.derive("monarchy", &indicator)]
+        );
+        assert_eq!(system.decrypt(&message).unwrap(), "INSTRUMENTSX");
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(IndicatorSystem::builder().length(0).build().is_err());
+        let config = CipherConfig {
+            algorithm: Algorithm::TwoSquare,
+            keys: vec![String::from("EXAMPLE")],
+            ..CipherConfig::default()
+        };
+        assert!(IndicatorSystem::builder().config(config).build().is_err());
+    }
+}
@@ -0,0 +1,566 @@
+//! A keyring holds the cipher setups of several correspondents or puzzles
+//! by name, so an application can pick the right one at encrypt or decrypt
+//! time and keep them all in a single file.
+//!
+//! A keyring is written as plain text, one record per line, each entry
+//! starting with its name:
+//!
+//! ```text
+//! playfair_cipher keyring 1
+//!
+//! entry alice
+//! algorithm two_square
+//! key EXAMPLE
+//! key KEYWORD
+//! grouping 5
+//! transparency swap
+//!
+//! entry puzzle 12
+//! algorithm playfair
+//! key playfair example
+//! padding Q
+//! final_digram strict
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored. With the feature
+//! `encrypted-keyring` the file can be written encrypted with a passphrase
+//! instead, see Keyring::seal.
+//!
+//! ```
+//! use playfair_cipher::config::CipherConfig;
+//! use playfair_cipher::keyring::Keyring;
+//!
+//! let mut keyring = Keyring::new();
+//! let config = CipherConfig {
+//!     keys: vec![String::from("playfair example")],
+//!     ..CipherConfig::default()
+//! };
+//! keyring.insert("bob", config).unwrap();
+//! assert_eq!(keyring.encrypt("bob", "hide the gold").unwrap(), "BMODZBXDNAGE");
+//! assert!(keyring.encrypt("eve", "hide the gold").is_err());
+//!
+//! let restored = Keyring::parse(&keyring.to_string()).unwrap();
+//! assert_eq!(restored, keyring);
+//! ```
+//!
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Algorithm, CipherConfig};
+use crate::cryptable::Cypher;
+use crate::errors::{InvalidOptionError, KeyringError};
+use crate::options::{FinalDigram, Transparency};
+
+const HEADER: &str = "playfair_cipher keyring 1";
+
+/// Named cipher setups, see the module documentation. The entries are kept
+/// sorted by name.
+///
+/// With the feature `serde` a keyring is serialized as a map of the names
+/// to the configs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct Keyring {
+    entries: BTreeMap<String, CipherConfig>,
+}
+
+impl Keyring {
+    /// An empty keyring.
+    pub fn new() -> Self {
+        Keyring::default()
+    }
+
+    /// Adds the config under the name, returning the config it replaces.
+    /// Fails if the config doesn't describe a valid cipher or the name is
+    /// empty or spans several lines.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        config: CipherConfig,
+    ) -> Result<Option<CipherConfig>, InvalidOptionError> {
+        let name = name.trim();
+        if name.is_empty() || name.contains('\n') {
+            return Err(InvalidOptionError::new(format!(
+                "Name of a keyring entry must be a non empty single line - got '{}'",
+                name
+            )));
+        }
+        if let Some(key) = config.keys.iter().find(|key| key.contains('\n')) {
+            return Err(InvalidOptionError::new(format!(
+                "Keys must be a single line - got '{}'",
+                key
+            )));
+        }
+        config.build()?;
+        Ok(self.entries.insert(String::from(name), config))
+    }
+
+    /// Removes the entry, returning its config.
+    pub fn remove(&mut self, name: &str) -> Option<CipherConfig> {
+        self.entries.remove(name)
+    }
+
+    /// The config stored under the name.
+    pub fn get(&self, name: &str) -> Option<&CipherConfig> {
+        self.entries.get(name)
+    }
+
+    /// The names of the entries, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The cipher of the entry.
+    pub fn cipher(&self, name: &str) -> Result<Box<dyn Cypher>, KeyringError> {
+        let config = self
+            .get(name)
+            .ok_or_else(|| KeyringError::UnknownEntry(String::from(name)))?;
+        Ok(config.build()?)
+    }
+
+    /// Encrypts the payload with the cipher of the entry.
+    pub fn encrypt(&self, name: &str, payload: &str) -> Result<String, KeyringError> {
+        Ok(self.cipher(name)?.encrypt(payload)?)
+    }
+
+    /// Decrypts the cipher text with the cipher of the entry.
+    pub fn decrypt(&self, name: &str, crypt: &str) -> Result<String, KeyringError> {
+        Ok(self.cipher(name)?.decrypt(crypt)?)
+    }
+
+    /// Reads a keyring written by Display.
+    pub fn parse(text: &str) -> Result<Self, KeyringError> {
+        let mut lines = text.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(malformed(1, format!("Expected header '{}'", HEADER)));
+        }
+        let mut keyring = Keyring::new();
+        // The entry read and the line it starts at
+        let mut entry: Option<(String, CipherConfig, usize)> = None;
+        for (index, line) in lines {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (record, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            if record == "entry" {
+                if let Some(entry) = entry.take() {
+                    keyring.insert_parsed(entry)?;
+                }
+                entry = Some((String::from(value), CipherConfig::default(), number));
+                continue;
+            }
+            let Some((_, config, _)) = entry.as_mut() else {
+                return Err(malformed(
+                    number,
+                    format!("Record '{}' outside of an entry", record),
+                ));
+            };
+            match record {
+                "algorithm" => {
+                    config.algorithm = algorithm_of(value).ok_or_else(|| {
+                        malformed(number, format!("Unknown algorithm '{}'", value))
+                    })?
+                }
+                "key" => config.keys.push(String::from(value)),
+                "padding" => config.padding = Some(letter_of(value, number)?),
+                "omit" => config.omit = Some(letter_of(value, number)?),
+                "grouping" => {
+                    config.grouping =
+                        Some(value.parse().map_err(|_| {
+                            malformed(number, format!("Invalid grouping '{}'", value))
+                        })?)
+                }
+                "final_digram" => {
+                    config.final_digram = Some(FinalDigram::of(value).ok_or_else(|| {
+                        malformed(number, format!("Unknown final digram '{}'", value))
+                    })?)
+                }
+                "seriation" => {
+                    config.seriation =
+                        Some(value.parse().map_err(|_| {
+                            malformed(number, format!("Invalid seriation '{}'", value))
+                        })?)
+                }
+                "transparency" => {
+                    config.transparency = Some(Transparency::of(value).ok_or_else(|| {
+                        malformed(number, format!("Unknown transparency '{}'", value))
+                    })?)
+                }
+                _ => return Err(malformed(number, format!("Unknown record '{}'", record))),
+            }
+        }
+        if let Some(entry) = entry {
+            keyring.insert_parsed(entry)?;
+        }
+        Ok(keyring)
+    }
+
+    fn insert_parsed(&mut self, entry: (String, CipherConfig, usize)) -> Result<(), KeyringError> {
+        let (name, config, number) = entry;
+        if self.entries.contains_key(&name) {
+            return Err(malformed(number, format!("Duplicate entry '{}'", name)));
+        }
+        self.insert(&name, config)
+            .map_err(|e| malformed(number, e.to_string()))?;
+        Ok(())
+    }
+
+    /// Writes the keyring to the file. The file is replaced at once, so a
+    /// crash while saving leaves the previous keyring in place.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KeyringError> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        fs::write(&partial, self.to_string())?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Reads a keyring written by save, see parse.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KeyringError> {
+        Keyring::parse(&fs::read_to_string(path)?)
+    }
+}
+
+// Layout of a sealed keyring: magic and version, the salt of the key
+// derivation, the nonce and the keyring text encrypted with
+// ChaCha20-Poly1305. Version 1 pins the Argon2id parameters below.
+#[cfg(feature = "encrypted-keyring")]
+const SEAL_MAGIC: &[u8; 5] = b"PFKR\x01";
+#[cfg(feature = "encrypted-keyring")]
+const SALT_LENGTH: usize = 16;
+#[cfg(feature = "encrypted-keyring")]
+const NONCE_LENGTH: usize = 12;
+
+#[cfg(feature = "encrypted-keyring")]
+impl Keyring {
+    /// Encrypts the keyring with a key derived from the passphrase by
+    /// Argon2id, using ChaCha20-Poly1305. Salt and nonce are random, so
+    /// sealing the same keyring twice yields different bytes.
+    ///
+    /// Only available with the feature `encrypted-keyring`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::config::CipherConfig;
+    /// use playfair_cipher::keyring::Keyring;
+    ///
+    /// let mut keyring = Keyring::new();
+    /// let config = CipherConfig {
+    ///     keys: vec![String::from("playfair example")],
+    ///     ..CipherConfig::default()
+    /// };
+    /// keyring.insert("bob", config).unwrap();
+    /// let sealed = keyring.seal("open sesame").unwrap();
+    /// assert!(!String::from_utf8_lossy(&sealed).contains("playfair example"));
+    /// assert_eq!(Keyring::open(&sealed, "open sesame").unwrap(), keyring);
+    /// assert!(Keyring::open(&sealed, "open sesam").is_err());
+    /// ```
+    pub fn seal(&self, passphrase: &str) -> Result<Vec<u8>, KeyringError> {
+        use chacha20poly1305::aead::{Aead, AeadCore, OsRng};
+        use chacha20poly1305::ChaCha20Poly1305;
+
+        let mut salt = [0u8; SALT_LENGTH];
+        chacha20poly1305::aead::rand_core::RngCore::try_fill_bytes(&mut OsRng, &mut salt)
+            .map_err(|e| KeyringError::Sealing(e.to_string()))?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let crypt = seal_cipher(passphrase, &salt)?
+            .encrypt(&nonce, self.to_string().as_bytes())
+            .map_err(|_| KeyringError::Sealing(String::from("Encrypting the keyring failed")))?;
+        let mut sealed =
+            Vec::with_capacity(SEAL_MAGIC.len() + SALT_LENGTH + NONCE_LENGTH + crypt.len());
+        sealed.extend_from_slice(SEAL_MAGIC);
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&crypt);
+        Ok(sealed)
+    }
+
+    /// Decrypts a keyring sealed with the passphrase. Fails if the
+    /// passphrase is wrong or the bytes were altered.
+    ///
+    /// Only available with the feature `encrypted-keyring`.
+    pub fn open(sealed: &[u8], passphrase: &str) -> Result<Self, KeyringError> {
+        use chacha20poly1305::aead::{Aead, Nonce};
+        use chacha20poly1305::ChaCha20Poly1305;
+
+        let header = SEAL_MAGIC.len() + SALT_LENGTH + NONCE_LENGTH;
+        if sealed.len() < header || !sealed.starts_with(SEAL_MAGIC) {
+            return Err(KeyringError::Sealing(String::from(
+                "Not an encrypted keyring",
+            )));
+        }
+        let (salt, rest) = sealed[SEAL_MAGIC.len()..].split_at(SALT_LENGTH);
+        let (nonce, crypt) = rest.split_at(NONCE_LENGTH);
+        let plain = seal_cipher(passphrase, salt)?
+            .decrypt(Nonce::<ChaCha20Poly1305>::from_slice(nonce), crypt)
+            .map_err(|_| {
+                KeyringError::Sealing(String::from(
+                    "Wrong passphrase or the encrypted keyring was altered",
+                ))
+            })?;
+        let text = String::from_utf8(plain)
+            .map_err(|_| KeyringError::Sealing(String::from("The keyring is not UTF-8")))?;
+        Keyring::parse(&text)
+    }
+
+    /// Writes the keyring sealed with the passphrase to the file, see seal.
+    /// The file is replaced at once like by save.
+    ///
+    /// Only available with the feature `encrypted-keyring`.
+    pub fn save_encrypted(
+        &self,
+        path: impl AsRef<Path>,
+        passphrase: &str,
+    ) -> Result<(), KeyringError> {
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        fs::write(&partial, self.seal(passphrase)?)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Reads a keyring written by save_encrypted, see open.
+    ///
+    /// Only available with the feature `encrypted-keyring`.
+    pub fn load_encrypted(path: impl AsRef<Path>, passphrase: &str) -> Result<Self, KeyringError> {
+        Keyring::open(&fs::read(path)?, passphrase)
+    }
+}
+
+#[cfg(feature = "encrypted-keyring")]
+fn seal_cipher(
+    passphrase: &str,
+    salt: &[u8],
+) -> Result<chacha20poly1305::ChaCha20Poly1305, KeyringError> {
+    use argon2::{Algorithm, Argon2, Params, Version};
+    use chacha20poly1305::KeyInit;
+
+    let params =
+        Params::new(19 * 1024, 2, 1, Some(32)).map_err(|e| KeyringError::Sealing(e.to_string()))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| KeyringError::Sealing(e.to_string()))?;
+    Ok(chacha20poly1305::ChaCha20Poly1305::new(&key.into()))
+}
+
+impl fmt::Display for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for (name, config) in &self.entries {
+            writeln!(f)?;
+            writeln!(f, "entry {}", name)?;
+            writeln!(f, "algorithm {}", algorithm_name(config.algorithm))?;
+            for key in &config.keys {
+                writeln!(f, "key {}", key)?;
+            }
+            if let Some(padding) = config.padding {
+                writeln!(f, "padding {}", padding)?;
+            }
+            if let Some(omit) = config.omit {
+                writeln!(f, "omit {}", omit)?;
+            }
+            if let Some(grouping) = config.grouping {
+                writeln!(f, "grouping {}", grouping)?;
+            }
+            if let Some(final_digram) = config.final_digram {
+                writeln!(f, "final_digram {}", final_digram.name())?;
+            }
+            if let Some(seriation) = config.seriation {
+                writeln!(f, "seriation {}", seriation)?;
+            }
+            if let Some(transparency) = config.transparency {
+                writeln!(f, "transparency {}", transparency.name())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The names are the ones used by serde.
+fn algorithm_name(algorithm: Algorithm) -> &'static str {
+    match algorithm {
+        Algorithm::PlayFair => "playfair",
+        Algorithm::TwoSquare => "two_square",
+        Algorithm::FourSquare => "four_square",
+    }
+}
+
+fn algorithm_of(name: &str) -> Option<Algorithm> {
+    [
+        Algorithm::PlayFair,
+        Algorithm::TwoSquare,
+        Algorithm::FourSquare,
+    ]
+    .into_iter()
+    .find(|algorithm| algorithm_name(*algorithm) == name)
+}
+
+fn letter_of(value: &str, line: usize) -> Result<char, KeyringError> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(malformed(
+            line,
+            format!("Expected a single letter - got '{}'", value),
+        )),
+    }
+}
+
+fn malformed(line: usize, error: String) -> KeyringError {
+    KeyringError::Malformed { line, error }
+}
+
+#[cfg(all(test, feature = "two-square"))]
+mod tests {
+
+    use super::*;
+
+    fn keyring() -> Keyring {
+        let mut keyring = Keyring::new();
+        keyring
+            .insert(
+                "alice",
+                CipherConfig {
+                    algorithm: Algorithm::TwoSquare,
+                    keys: vec![String::from("EXAMPLE"), String::from("KEYWORD")],
+                    grouping: Some(5),
+                    seriation: Some(4),
+                    transparency: Some(Transparency::Swap),
+                    ..CipherConfig::default()
+                },
+            )
+            .unwrap();
+        keyring
+            .insert(
+                "puzzle 12",
+                CipherConfig {
+                    keys: vec![String::from("playfair example")],
+                    padding: Some('Q'),
+                    omit: Some('K'),
+                    final_digram: Some(FinalDigram::Strict),
+                    ..CipherConfig::default()
+                },
+            )
+            .unwrap();
+        keyring
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let keyring = keyring();
+        assert_eq!(keyring.names().collect::<Vec<_>>(), ["alice", "puzzle 12"]);
+        let text = keyring.to_string();
+        assert!(text.contains("\nentry puzzle 12\nalgorithm playfair\nkey playfair example\n"));
+        assert!(text.contains("\nfinal_digram strict\n"));
+        assert!(text.contains("\nseriation 4\ntransparency swap\n"));
+        assert_eq!(Keyring::parse(&text).unwrap(), keyring);
+        assert!(keyring.encrypt("puzzle 12", "abc").is_err());
+        let tsq = crate::two_square::TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .grouping(5)
+            .seriation(4)
+            .transparency(Transparency::Swap)
+            .build()
+            .unwrap();
+        let crypt = keyring.encrypt("alice", "help me obi wan").unwrap();
+        assert_eq!(crypt, tsq.encrypt("help me obi wan").unwrap());
+        assert_eq!(
+            keyring.decrypt("alice", &crypt).unwrap(),
+            tsq.decrypt(&crypt).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join(format!("keyring-{}.txt", std::process::id()));
+        let keyring = keyring();
+        keyring.save(&path).unwrap();
+        let loaded = Keyring::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), keyring);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() {
+        let keyring = keyring();
+        let toml = toml::to_string(&keyring).unwrap();
+        assert!(toml.contains("[\"puzzle 12\"]"));
+        assert_eq!(toml::from_str::<Keyring>(&toml).unwrap(), keyring);
+    }
+
+    #[cfg(feature = "encrypted-keyring")]
+    #[test]
+    fn test_save_load_encrypted() {
+        let path = std::env::temp_dir().join(format!("keyring-{}.sealed", std::process::id()));
+        let keyring = keyring();
+        keyring.save_encrypted(&path, "passphrase").unwrap();
+        let mut sealed = fs::read(&path).unwrap();
+        let loaded = Keyring::load_encrypted(&path, "passphrase");
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), keyring);
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(matches!(
+            Keyring::open(&sealed, "passphrase"),
+            Err(KeyringError::Sealing(_))
+        ));
+        assert!(Keyring::open(b"playfair_cipher keyring 1", "passphrase").is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut keyring = keyring();
+        assert!(keyring.insert(" ", CipherConfig::default()).is_err());
+        // A Two Square takes two keys
+        let config = CipherConfig {
+            algorithm: Algorithm::TwoSquare,
+            ..CipherConfig::default()
+        };
+        assert!(keyring.insert("bob", config).is_err());
+        assert!(matches!(
+            keyring.encrypt("bob", "joe"),
+            Err(KeyringError::UnknownEntry(_))
+        ));
+        assert!(matches!(
+            Keyring::parse("playfair_cipher keyring 1\nkey EXAMPLE"),
+            Err(KeyringError::Malformed { line: 2, .. })
+        ));
+        assert!(matches!(
+            Keyring::parse("playfair_cipher keyring 1\nentry a\nalgorithm two_square\nkey A\n"),
+            Err(KeyringError::Malformed { line: 2, .. })
+        ));
+        let text = keyring.to_string() + "\nentry alice\nkey A\n";
+        assert!(matches!(
+            Keyring::parse(&text),
+            Err(KeyringError::Malformed { .. })
+        ));
+        assert!(matches!(
+            Keyring::parse("playfair_cipher keyring 1\nentry a\nkey A\nfinal_digram drop\n"),
+            Err(KeyringError::Malformed { line: 4, .. })
+        ));
+        assert!(Keyring::parse("entry a").is_err());
+    }
+}
@@ -9,9 +9,85 @@
 //! So you don't need to clear off not encryptable characters when using
 //! this library.
 //!
+//! Everything needed is available through the prelude:
+//!
+//! ```
+//! use playfair_cipher::prelude::*;
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! assert_eq!(
+//!     pfc.encrypt("hide the gold in the tree stump").unwrap(),
+//!     "BMODZBXDNABEKUDMUIXMMOUVIF"
+//! );
+//! ```
+//!
+pub mod analysis;
+pub mod armor;
+#[cfg(feature = "async")]
+pub mod async_stream;
+pub mod binary;
+pub mod chunked;
+pub mod cipher;
+pub mod config;
+pub mod crack;
 pub mod cryptable;
+#[cfg(feature = "diceware")]
+pub mod diceware;
+pub mod digram;
+pub mod digram_table;
 pub mod errors;
+#[cfg(feature = "extended")]
+pub mod extended;
+pub mod figure_shift;
+pub mod format;
+#[cfg(feature = "four-square")]
 pub mod four_square;
+pub mod garble;
+pub mod grid;
+pub mod historical;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod indicator;
+pub mod keyring;
+pub mod morse;
+pub mod options;
 pub mod playfair;
+pub mod prelude;
+pub mod puzzle;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod schedule;
+pub mod segment;
+mod seriation;
+pub mod session;
 mod structs;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
+pub mod text;
+#[cfg(feature = "two-square")]
 pub mod two_square;
+pub mod vic;
+
+pub use cryptable::Cypher;
+
+use options::PayloadOptions;
+
+/// Normalizes a payload exactly the way it's done before encryption, so it
+/// can be shown what is actually going to be encrypted. The payload is
+/// converted to uppercase, the omitted letter is substituted and any
+/// character not within A-Z is dropped. Doubled letters are not split and
+/// no padding is added.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{normalize, options::PayloadOptions};
+///
+/// let normalized = normalize("I would like 4 tins of jam.", &PayloadOptions::default());
+/// assert_eq!(normalized, "IWOULDLIKETINSOFIAM");
+/// ```
+pub fn normalize(payload: &str, options: &PayloadOptions) -> String {
+    structs::Payload::new(payload, options).payload.into_owned()
+}
@@ -0,0 +1,129 @@
+//! Conversion of cipher text to Morse code and back, so a message can go
+//! straight from plaintext to code ready for transmission.
+//!
+//! ```
+//! use playfair_cipher::{morse, playfair::PlayFairKey, cryptable::Cypher};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let code = morse::to_morse(&pfc.encrypt("hide the gold").unwrap());
+//! assert!(code.starts_with("-... -- --- -.."));
+//! assert_eq!(pfc.decrypt(&morse::from_morse(&code).unwrap()).unwrap(), "HIDETHEGOLDX");
+//! ```
+//!
+//! With the feature `morse-audio` the code can be rendered as audio, see
+//! the module audio.
+//!
+
+#[cfg(feature = "morse-audio")]
+pub mod audio;
+
+use crate::cryptable::Cypher;
+use crate::errors::{CharNotInKeyError, MorseError};
+use crate::format::CiphertextFormatter;
+
+const CODES: [&str; 26] = [
+    ".-", "-...", "-.-.", "-..", ".", "..-.", "--.", "....", "..", ".---", "-.-", ".-..", "--",
+    "-.", "---", ".--.", "--.-", ".-.", "...", "-", "..-", "...-", ".--", "-..-", "-.--", "--..",
+];
+
+// Separates the words, i.e. the groups of grouped cipher text.
+const WORD_SEPARATOR: &str = "/";
+
+/// Converts the letters of a text to Morse code. Letters are separated by
+/// a space, words by " / ". Anything but letters and whitespace is dropped.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::morse::to_morse;
+///
+/// assert_eq!(to_morse("BMODZ BX"), "-... -- --- -.. --.. / -... -..-");
+/// ```
+pub fn to_morse(text: &str) -> String {
+    let mut code = String::with_capacity(text.len() * 5);
+    for word in text.split_whitespace() {
+        let mut letters = word
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(char::is_ascii_uppercase)
+            .peekable();
+        if letters.peek().is_none() {
+            continue;
+        }
+        if !code.is_empty() {
+            code.push_str(" / ");
+        }
+        for (idx, letter) in letters.enumerate() {
+            if idx > 0 {
+                code.push(' ');
+            }
+            code.push_str(CODES[(letter as u8 - b'A') as usize]);
+        }
+    }
+    code
+}
+
+/// Converts Morse code back to letters. The codes of the letters must be
+/// separated by whitespace, a "/" separates words and is converted to a
+/// space - so decoded grouped cipher text can be decrypted as is.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::morse::from_morse;
+///
+/// assert_eq!(from_morse("-... -- / -.. ").unwrap(), "BM D");
+/// assert!(from_morse("-... ------").is_err());
+/// ```
+pub fn from_morse(code: &str) -> Result<String, MorseError> {
+    let mut text = String::with_capacity(code.len() / 3);
+    for (position, token) in code.split_whitespace().enumerate() {
+        if token == WORD_SEPARATOR {
+            if !text.is_empty() && !text.ends_with(' ') {
+                text.push(' ');
+            }
+            continue;
+        }
+        match CODES.iter().position(|c| *c == token) {
+            Some(idx) => text.push((b'A' + idx as u8) as char),
+            None => {
+                return Err(MorseError::new(format!(
+                    "'{}' at position {} is not the code of a letter",
+                    token, position
+                )))
+            }
+        }
+    }
+    Ok(text.trim_end().to_string())
+}
+
+/// Formats cipher text as Morse code, see to_morse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Morse;
+
+impl CiphertextFormatter for Morse {
+    fn format(&self, _cipher: &dyn Cypher, crypt: &str) -> Result<String, CharNotInKeyError> {
+        Ok(to_morse(crypt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let alphabet = "ABCDEFGHIJKLM NOPQRSTUVWXYZ";
+        assert_eq!(from_morse(&to_morse(alphabet)).unwrap(), alphabet);
+        assert_eq!(to_morse("sos"), "... --- ...");
+        assert_eq!(to_morse(" - "), "");
+    }
+
+    #[test]
+    fn test_from_morse_invalid() {
+        assert!(from_morse(".-.-.-").is_err());
+        assert!(from_morse("A").is_err());
+        assert_eq!(from_morse(" / .- / ").unwrap(), "A");
+    }
+}
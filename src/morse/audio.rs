@@ -0,0 +1,258 @@
+//! Morse code as audio, so cipher text can be keyed like on air. The code
+//! is rendered as a sine tone with the standard timing: a dash lasts three
+//! dots, the gap within a letter one dot, between letters three and
+//! between words seven dots. The length of a dot follows from the speed in
+//! words per minute, based on the word PARIS.
+//!
+//! Only available with the feature `morse-audio`.
+//!
+//! ```
+//! use playfair_cipher::morse::{audio::MorseAudio, to_morse};
+//! use playfair_cipher::{cryptable::Cypher, playfair::PlayFairKey};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let code = to_morse(&pfc.encrypt("hide the gold").unwrap());
+//! let audio = MorseAudio::builder().wpm(25).frequency(700).build().unwrap();
+//! let wav = audio.wav(&code).unwrap();
+//! assert_eq!(&wav[..4], b"RIFF");
+//! ```
+//!
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::errors::{InvalidOptionError, MorseError};
+
+/// Renders Morse code as audio samples, see the module documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MorseAudio {
+    wpm: u32,
+    frequency: u32,
+    sample_rate: u32,
+}
+
+/// Builder for MorseAudio.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MorseAudioBuilder {
+    wpm: Option<u32>,
+    frequency: Option<u32>,
+    sample_rate: Option<u32>,
+}
+
+impl MorseAudioBuilder {
+    /// Speed in words per minute. Defaults to 20.
+    pub fn wpm(mut self, wpm: u32) -> Self {
+        self.wpm = Some(wpm);
+        self
+    }
+
+    /// Pitch of the tone in Hz. Defaults to 600.
+    pub fn frequency(mut self, frequency: u32) -> Self {
+        self.frequency = Some(frequency);
+        self
+    }
+
+    /// Samples per second. Defaults to 8000.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Fails if the speed is 0 or the tone can't be represented at the
+    /// sample rate, i.e. it's 0 or not below half the sample rate.
+    pub fn build(self) -> Result<MorseAudio, InvalidOptionError> {
+        let wpm = self.wpm.unwrap_or(20);
+        let frequency = self.frequency.unwrap_or(600);
+        let sample_rate = self.sample_rate.unwrap_or(8000);
+        if wpm == 0 {
+            return Err(InvalidOptionError::new(String::from(
+                "Speed must be greater than 0 words per minute",
+            )));
+        }
+        if frequency == 0 || 2 * frequency >= sample_rate {
+            return Err(InvalidOptionError::new(format!(
+                "Frequency must be greater than 0 and below half the sample rate of {} - got {}",
+                sample_rate, frequency
+            )));
+        }
+        Ok(MorseAudio {
+            wpm,
+            frequency,
+            sample_rate,
+        })
+    }
+}
+
+// Elements of the code, the tones and gaps as multiples of a dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Tone(u32),
+    Gap(u32),
+}
+
+impl MorseAudio {
+    /// Returns a builder to configure the audio.
+    pub fn builder() -> MorseAudioBuilder {
+        MorseAudioBuilder::default()
+    }
+
+    pub fn wpm(&self) -> u32 {
+        self.wpm
+    }
+
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // Samples of a dot: a dot lasts 1.2 / wpm seconds.
+    fn dot_samples(&self) -> usize {
+        (self.sample_rate as usize * 6 / (5 * self.wpm as usize)).max(1)
+    }
+
+    // Reads code as written by to_morse: the dots and dashes of a letter
+    // together, letters separated by whitespace and words by a "/".
+    fn elements(code: &str) -> Result<Vec<Element>, MorseError> {
+        let mut elements = Vec::new();
+        let mut word_gap = false;
+        for (position, token) in code.split_whitespace().enumerate() {
+            if token == "/" {
+                word_gap = true;
+                continue;
+            }
+            if let Some(c) = token.chars().find(|c| *c != '.' && *c != '-') {
+                return Err(MorseError::new(format!(
+                    "'{}' at position {} is neither a dot nor a dash",
+                    c, position
+                )));
+            }
+            if !elements.is_empty() {
+                elements.push(Element::Gap(if word_gap { 7 } else { 3 }));
+            }
+            word_gap = false;
+            for (idx, symbol) in token.chars().enumerate() {
+                if idx > 0 {
+                    elements.push(Element::Gap(1));
+                }
+                elements.push(Element::Tone(if symbol == '.' { 1 } else { 3 }));
+            }
+        }
+        Ok(elements)
+    }
+
+    /// How long the code takes to send.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::morse::audio::MorseAudio;
+    /// use std::time::Duration;
+    ///
+    /// // PARIS takes 43 dots, 50 with the gap to the next word - so 20
+    /// // words take a minute at 20 wpm
+    /// let audio = MorseAudio::builder().build().unwrap();
+    /// let code = ".--. .- .-. .. ...";
+    /// assert_eq!(audio.duration(code).unwrap(), Duration::from_millis(43 * 60));
+    /// ```
+    pub fn duration(&self, code: &str) -> Result<Duration, MorseError> {
+        let dots: u32 = Self::elements(code)?
+            .iter()
+            .map(|element| match element {
+                Element::Tone(dots) | Element::Gap(dots) => dots,
+            })
+            .sum();
+        Ok(Duration::from_millis(dots as u64 * 1200 / self.wpm as u64))
+    }
+
+    /// The code as 16 bit mono samples. The tone fades in and out over a
+    /// few milliseconds, so it doesn't click.
+    pub fn samples(&self, code: &str) -> Result<Vec<i16>, MorseError> {
+        let dot = self.dot_samples();
+        let ramp = (self.sample_rate as usize / 200).min(dot / 2).max(1);
+        let step = 2.0 * std::f64::consts::PI * self.frequency as f64 / self.sample_rate as f64;
+        let mut samples = Vec::new();
+        for element in Self::elements(code)? {
+            match element {
+                Element::Gap(dots) => samples.resize(samples.len() + dots as usize * dot, 0),
+                Element::Tone(dots) => {
+                    let length = dots as usize * dot;
+                    samples.extend((0..length).map(|idx| {
+                        let envelope = (idx.min(length - 1 - idx) as f64 / ramp as f64).min(1.0);
+                        (envelope * (step * idx as f64).sin() * 0.8 * i16::MAX as f64) as i16
+                    }));
+                }
+            }
+        }
+        Ok(samples)
+    }
+
+    /// Writes the code as WAV file, 16 bit mono PCM.
+    pub fn write_wav<W: Write>(&self, mut writer: W, code: &str) -> Result<(), MorseError> {
+        let samples = self.samples(code)?;
+        let data_length = 2 * samples.len() as u32;
+        let mut write = || -> io::Result<()> {
+            writer.write_all(b"RIFF")?;
+            writer.write_all(&(36 + data_length).to_le_bytes())?;
+            writer.write_all(b"WAVEfmt ")?;
+            writer.write_all(&16u32.to_le_bytes())?;
+            // PCM, one channel
+            writer.write_all(&1u16.to_le_bytes())?;
+            writer.write_all(&1u16.to_le_bytes())?;
+            writer.write_all(&self.sample_rate.to_le_bytes())?;
+            writer.write_all(&(2 * self.sample_rate).to_le_bytes())?;
+            // Bytes per sample, bits per sample
+            writer.write_all(&2u16.to_le_bytes())?;
+            writer.write_all(&16u16.to_le_bytes())?;
+            writer.write_all(b"data")?;
+            writer.write_all(&data_length.to_le_bytes())?;
+            for sample in &samples {
+                writer.write_all(&sample.to_le_bytes())?;
+            }
+            Ok(())
+        };
+        write().map_err(|e| MorseError::new(format!("Writing the audio failed: {}", e)))
+    }
+
+    /// The code as WAV file, see write_wav.
+    pub fn wav(&self, code: &str) -> Result<Vec<u8>, MorseError> {
+        let mut wav = Vec::new();
+        self.write_wav(&mut wav, code)?;
+        Ok(wav)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_timing() {
+        let audio = MorseAudio::builder()
+            .wpm(12)
+            .sample_rate(1000)
+            .frequency(100)
+            .build()
+            .unwrap();
+        // A dot lasts 100 ms
+        assert_eq!(audio.dot_samples(), 100);
+        // ".-" and "." with a word gap: 1 + 1 + 3 + 7 + 1 dots
+        let samples = audio.samples(".- / .").unwrap();
+        assert_eq!(samples.len(), 1300);
+        assert!(samples[100..200].iter().all(|s| *s == 0));
+        assert!(samples[200..500].iter().any(|s| *s != 0));
+        assert!(samples[500..1200].iter().all(|s| *s == 0));
+        assert_eq!(audio.wav(".- / .").unwrap().len(), 44 + 2 * samples.len());
+        assert!(audio.samples(".- x").is_err());
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(MorseAudio::builder().wpm(0).build().is_err());
+        assert!(MorseAudio::builder().frequency(4000).build().is_err());
+        assert!(MorseAudio::builder().frequency(0).build().is_err());
+    }
+}
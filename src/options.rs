@@ -0,0 +1,227 @@
+//! Options controlling how a payload is prepared before it is encrypted
+//! and how the cipher text is formatted.
+//!
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::errors::InvalidOptionError;
+
+/// The options shared by all ciphers. The default reflects the classic rules:
+/// J is merged into I, X is used as padding and the cipher text is not
+/// grouped.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::options::PayloadOptions;
+///
+/// let options = PayloadOptions::new('Q', 'J', Some(5)).unwrap();
+/// assert_eq!(options.padding(), 'Q');
+/// assert!(PayloadOptions::new('J', 'J', None).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayloadOptions {
+    pub(crate) padding: char,
+    pub(crate) omit: char,
+    pub(crate) grouping: Option<usize>,
+    pub(crate) final_digram: FinalDigram,
+    // Period of the seriation, set by the ciphers offering it.
+    pub(crate) seriation: Option<usize>,
+}
+
+impl Default for PayloadOptions {
+    fn default() -> Self {
+        PayloadOptions::DEFAULT
+    }
+}
+
+/// How the last letter of a payload of odd length is handled, it has no
+/// partner to form a digram with.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{cryptable::Cypher, options::FinalDigram, playfair::PlayFairKey};
+///
+/// let pfc = PlayFairKey::builder()
+///     .key("playfair example")
+///     .final_digram(FinalDigram::PassThrough)
+///     .build()
+///     .unwrap();
+/// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAD");
+/// assert_eq!(pfc.decrypt("BMODZBXDNAD").unwrap(), "HIDETHEGOLD");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FinalDigram {
+    /// The letter is padded with the padding letter, the classic rule.
+    #[default]
+    Pad,
+    /// En- or decrypting fails, the payload must consist of whole digrams.
+    Strict,
+    /// The letter is added to the result as it is, as some puzzles do.
+    PassThrough,
+}
+
+impl FinalDigram {
+    // The name in armored keys and keyrings, the one used by serde.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            FinalDigram::Pad => "pad",
+            FinalDigram::Strict => "strict",
+            FinalDigram::PassThrough => "pass_through",
+        }
+    }
+
+    pub(crate) fn of(name: &str) -> Option<Self> {
+        [
+            FinalDigram::Pad,
+            FinalDigram::Strict,
+            FinalDigram::PassThrough,
+        ]
+        .into_iter()
+        .find(|final_digram| final_digram.name() == name)
+    }
+}
+
+/// How a digram whose letters are in the same column is encrypted. The
+/// classic rule leaves such a digram as it is, so about a fifth of the
+/// plain text shows through the cipher text. Applies to the Two Square
+/// cipher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Transparency {
+    /// The digram is left as it is, as described on Wikipedia.
+    #[default]
+    Keep,
+    /// The letters swap their rows: the first letter is taken from the row
+    /// of the second one in the top square and vice versa. Digrams whose
+    /// letters are in the same row as well are still left as they are.
+    Swap,
+    /// Both letters move one row down within their square, wrapping
+    /// around, like the column rule of the PlayFair cipher.
+    Shift,
+}
+
+impl Transparency {
+    // The name in keyrings, the one used by serde.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Transparency::Keep => "keep",
+            Transparency::Swap => "swap",
+            Transparency::Shift => "shift",
+        }
+    }
+
+    pub(crate) fn of(name: &str) -> Option<Self> {
+        [Transparency::Keep, Transparency::Swap, Transparency::Shift]
+            .into_iter()
+            .find(|transparency| transparency.name() == name)
+    }
+}
+
+impl PayloadOptions {
+    pub(crate) const DEFAULT: PayloadOptions = PayloadOptions {
+        padding: 'X',
+        omit: 'J',
+        grouping: None,
+        final_digram: FinalDigram::Pad,
+        seriation: None,
+    };
+
+    /// Constructs validated options.
+    ///
+    /// * `padding` - letter used to split doubled letters and to fill up a
+    ///   payload of odd length.
+    /// * `omit` - letter left out of the 5*5 square. J is replaced by I, any
+    ///   other omitted letter is dropped from key and payload.
+    /// * `grouping` - if given, the cipher text is split into groups of this
+    ///   many letters separated by a space.
+    pub fn new(
+        padding: char,
+        omit: char,
+        grouping: Option<usize>,
+    ) -> Result<Self, InvalidOptionError> {
+        let padding = padding.to_ascii_uppercase();
+        let omit = omit.to_ascii_uppercase();
+        if !omit.is_ascii_uppercase() {
+            return Err(InvalidOptionError::new(format!(
+                "Only chars A-Z can be omitted - got '{}'",
+                omit
+            )));
+        }
+        if !padding.is_ascii_uppercase() || padding == omit {
+            return Err(InvalidOptionError::new(format!(
+                "Padding must be a char A-Z other than the omitted '{}' - got '{}'",
+                omit, padding
+            )));
+        }
+        if grouping == Some(0) {
+            return Err(InvalidOptionError::new(String::from(
+                "Grouping must be greater than 0",
+            )));
+        }
+        Ok(PayloadOptions {
+            padding,
+            omit,
+            grouping,
+            final_digram: FinalDigram::Pad,
+            seriation: None,
+        })
+    }
+
+    /// The options with the policy for the last letter of a payload of odd
+    /// length, FinalDigram::Pad by default.
+    pub fn with_final_digram(mut self, final_digram: FinalDigram) -> Self {
+        self.final_digram = final_digram;
+        self
+    }
+
+    #[cfg(any(feature = "two-square", feature = "four-square"))]
+    pub(crate) fn with_seriation(mut self, seriation: Option<usize>) -> Self {
+        self.seriation = seriation;
+        self
+    }
+
+    pub fn padding(&self) -> char {
+        self.padding
+    }
+
+    pub fn omit(&self) -> char {
+        self.omit
+    }
+
+    pub fn grouping(&self) -> Option<usize> {
+        self.grouping
+    }
+
+    pub fn final_digram(&self) -> FinalDigram {
+        self.final_digram
+    }
+
+    /// The 25 letters of the square in alphabetical order.
+    pub(crate) fn alphabet(&self) -> impl Iterator<Item = char> + '_ {
+        ('A'..='Z').filter(move |c| *c != self.omit)
+    }
+
+    /// Maps an uppercase char to the one used in the square. Returns None
+    /// if the char has to be dropped.
+    pub(crate) fn substitute(&self, c: char) -> Option<char> {
+        if c != self.omit {
+            Some(c)
+        } else if c == 'J' {
+            Some('I')
+        } else {
+            None
+        }
+    }
+}
@@ -1,17 +1,17 @@
 //! This is the implentation of the PlayFair cipher as described
 //! <https://en.wikipedia.org/wiki/Playfair_cipher>
 //!
-use crate::cryptable::{Crypt, Cypher};
-use crate::errors::CharNotInKeyError;
-
-use crate::structs::{CryptModus, CryptResult, Payload, SquarePosition};
-
-pub(crate) const EMPTY_SQ_POS: &SquarePosition = &SquarePosition {
-    column: 42,
-    row: 42,
+use crate::cryptable::{
+    Crypt, Cypher, Internals, Layout, SquareCrypt, SquareCypher, SquareInternals,
 };
+use crate::digram::Rule;
+use crate::errors::{CharNotInKeyError, InvalidKeyError, InvalidOptionError};
+use crate::grid::Grid;
+use crate::options::{FinalDigram, PayloadOptions};
+
+use crate::structs::{CryptModus, CryptResult, KeyMap};
 
-use std::collections::HashMap;
+use std::fmt;
 
 const KEY_CARS: &str = "ABCDEFGHIKLMNOPQRSTUVWXYZ";
 pub(crate) const ROW_LENGTH: u8 = 5;
@@ -20,12 +20,77 @@ const KEY_LENGTH: usize = 25;
 /// Struct represents a PlayFaire Cypher. It's holding the key and the
 /// position of any character in the key.
 ///
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlayFairKey {
     /// PlayFair 5*5 matrix
     ///
-    pub(crate) key: Vec<char>,
-    pub(crate) key_map: HashMap<char, SquarePosition>,
+    pub(crate) key: [char; KEY_LENGTH],
+    pub(crate) key_map: KeyMap,
+    pub(crate) options: PayloadOptions,
+}
+
+/// Builder for a PlayFairKey, see PlayFairKey::builder.
+///
+#[derive(Debug, Clone)]
+pub struct PlayFairKeyBuilder {
+    key: String,
+    padding: char,
+    omit: char,
+    grouping: Option<usize>,
+    final_digram: FinalDigram,
+}
+
+impl Default for PlayFairKeyBuilder {
+    fn default() -> Self {
+        let options = PayloadOptions::default();
+        PlayFairKeyBuilder {
+            key: String::new(),
+            padding: options.padding,
+            omit: options.omit,
+            grouping: options.grouping,
+            final_digram: options.final_digram,
+        }
+    }
+}
+
+impl PlayFairKeyBuilder {
+    /// The keyword the square is built of.
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = String::from(key);
+        self
+    }
+
+    /// Letter used to split doubled letters and to pad a payload of odd length.
+    pub fn padding(mut self, padding: char) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Letter left out of the square. J is replaced by I, any other letter
+    /// is dropped.
+    pub fn omit(mut self, omit: char) -> Self {
+        self.omit = omit;
+        self
+    }
+
+    /// Splits the cipher text into groups of this many letters.
+    pub fn grouping(mut self, grouping: usize) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// How the last letter of a payload of odd length is handled, defaults
+    /// to FinalDigram::Pad.
+    pub fn final_digram(mut self, final_digram: FinalDigram) -> Self {
+        self.final_digram = final_digram;
+        self
+    }
+
+    pub fn build(self) -> Result<PlayFairKey, InvalidOptionError> {
+        let options = PayloadOptions::new(self.padding, self.omit, self.grouping)?
+            .with_final_digram(self.final_digram);
+        Ok(PlayFairKey::with_options(&self.key, options))
+    }
 }
 
 impl PlayFairKey {
@@ -39,204 +104,668 @@ impl PlayFairKey {
     /// let pfc = PlayFairKey::new("Secret");
     /// ```
     pub fn new(key: &str) -> Self {
-        let raw_key: String = key.to_uppercase().replace(' ', "").replace('J', "I") + KEY_CARS;
-
-        let mut temp_key = String::with_capacity(KEY_LENGTH);
-        let mut counter = 0;
-        // Position counter reflects the position in the
-        // imaginary 5*5 square. So to be consistent, it start from 0
-        let mut row_counter = 0;
-        let mut col_counter = 0;
-        let mut key_map: HashMap<char, SquarePosition> = HashMap::new();
-
-        while counter < raw_key.len() && temp_key.len() < KEY_LENGTH {
-            if col_counter > 4 {
-                col_counter = 0;
-                row_counter += 1;
-            }
+        PlayFairKey::with_options(key, PayloadOptions::default())
+    }
 
-            let temp_key_char = &raw_key[counter..counter + 1];
-            counter += 1;
-            if temp_key.contains(temp_key_char) {
+    /// Returns a builder to construct a PlayFaire cipher with options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::playfair::PlayFairKey;
+    /// use playfair_cipher::cryptable::Cypher;
+    ///
+    /// let pfc = PlayFairKey::builder()
+    ///     .key("playfair example")
+    ///     .padding('Q')
+    ///     .omit('J')
+    ///     .grouping(5)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZ BXDNA GO");
+    /// ```
+    pub fn builder() -> PlayFairKeyBuilder {
+        PlayFairKeyBuilder::default()
+    }
+
+    /// The options the cipher was constructed with.
+    pub fn options(&self) -> &PayloadOptions {
+        &self.options
+    }
+
+    pub(crate) fn with_options(key: &str, options: PayloadOptions) -> Self {
+        let raw_key = key
+            .chars()
+            .flat_map(char::to_uppercase)
+            // Only letters can be looked up, anything else would take the
+            // cell of a letter.
+            .filter(char::is_ascii_uppercase)
+            .filter_map(|c| options.substitute(c))
+            .chain(options.alphabet());
+
+        let mut temp_key = ['*'; KEY_LENGTH];
+        let mut key_length = 0;
+        let mut key_map: KeyMap = KeyMap::new();
+
+        for temp_key_char in raw_key {
+            if key_length == KEY_LENGTH {
+                break;
+            }
+            if temp_key[..key_length].contains(&temp_key_char) {
                 continue;
-            } else {
-                temp_key += temp_key_char;
-                let temp_key_char_vec: Vec<char> = temp_key_char.chars().collect();
-
-                key_map.insert(
-                    match temp_key_char_vec.first() {
-                        Some(k) => *k,
-                        None => '*',
-                    },
-                    SquarePosition {
-                        row: row_counter,
-                        column: col_counter,
-                    },
-                );
-                col_counter += 1;
             }
+            temp_key[key_length] = temp_key_char;
+            // The cell reflects the position in the imaginary 5*5 square.
+            // So to be consistent, it start from 0
+            key_map.insert(temp_key_char, key_length as u8);
+            key_length += 1;
         }
 
         PlayFairKey {
-            key: temp_key.chars().collect(),
+            key: temp_key,
             key_map,
+            options,
         }
     }
-}
 
-impl Crypt for PlayFairKey {
-    fn crypt(
-        &self,
-        a: char,
-        b: char,
-        modus: &CryptModus,
-    ) -> Result<CryptResult, CharNotInKeyError> {
-        let a_sq_pos = match self.key_map.get(&a) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        let b_sq_pos = match self.key_map.get(&b) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        if a_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                a, &self.key
-            )));
-        } else if b_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                b, &self.key
-            )));
+    /// Constructs the cipher from a square computed by square_from_keyword.
+    /// Used by the playfair_key! macro, use PlayFairKey::new otherwise.
+    #[doc(hidden)]
+    pub fn from_square(square: [char; KEY_LENGTH]) -> Self {
+        PlayFairKey::from_square_with_options(square, PayloadOptions::default())
+    }
+
+    pub(crate) fn from_square_with_options(
+        square: [char; KEY_LENGTH],
+        options: PayloadOptions,
+    ) -> Self {
+        let mut key_map: KeyMap = KeyMap::new();
+        for (idx, c) in square.iter().enumerate() {
+            key_map.insert(*c, idx as u8);
         }
-        let mut a_crypted_idx: u8 = 0;
-        let mut b_crypted_idx: u8 = 0;
-        if a_sq_pos.column != b_sq_pos.column && a_sq_pos.row != b_sq_pos.row {
-            // in square mode
-            // example 1:
-            // _ a _ y _
-            // _ _ _ _ _
-            // _ z _ b _
-            // _ _ _ _ _
-            // _ _ _ _ _
-
-            // example 2:
-            // _ b _ z _
-            // _ _ _ _ _
-            // _ y _ a _
-            // _ _ _ _ _
-            // _ _ _ _ _
-
-            a_crypted_idx = a_sq_pos.row * ROW_LENGTH + b_sq_pos.column;
-            b_crypted_idx = b_sq_pos.row * ROW_LENGTH + a_sq_pos.column;
-        } else if a_sq_pos.column == b_sq_pos.column {
-            // in column mode
-            // example 1
-            // _ a _ _ _
-            // _ y _ _ _
-            // _ b _ _ _
-            // _ z _ _ _
-            // _ _ _ _ _
-
-            // example 2
-            // _ y _ _ _
-            // _ _ _ _ _
-            // _ b _ _ _
-            // _ z _ _ _
-            // _ a _ _ _
-
-            if modus == &CryptModus::Encrypt {
-                if a_sq_pos.row == 4 {
-                    // In the last row - so going back to row 0
-                    a_crypted_idx = a_sq_pos.column;
-                } else {
-                    a_crypted_idx = (a_sq_pos.row + 1) * ROW_LENGTH + a_sq_pos.column
-                }
-                if b_sq_pos.row == 4 {
-                    // In the last row - so going back to row 0
-                    b_crypted_idx = b_sq_pos.column;
-                } else {
-                    b_crypted_idx = (b_sq_pos.row + 1) * ROW_LENGTH + b_sq_pos.column
-                }
-            } else {
-                // Decrypting
-                if a_sq_pos.row == 0 {
-                    a_crypted_idx = 20 + a_sq_pos.column;
-                } else {
-                    a_crypted_idx = (a_sq_pos.row - 1) * ROW_LENGTH + a_sq_pos.column;
-                }
-                if b_sq_pos.row == 0 {
-                    b_crypted_idx = 20 + b_sq_pos.column;
-                } else {
-                    b_crypted_idx = (b_sq_pos.row - 1) * ROW_LENGTH + b_sq_pos.column;
+        PlayFairKey {
+            key: square,
+            key_map,
+            options,
+        }
+    }
+}
+
+/// A change of the key square. Mutating a key slightly and checking whether
+/// the result decrypts better is the base of the solvers in the crack module.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMutation {
+    /// Swaps the letters of two cells (row * 5 + column).
+    SwapCells(u8, u8),
+    /// Swaps two rows.
+    SwapRows(u8, u8),
+    /// Swaps two columns.
+    SwapColumns(u8, u8),
+    /// Mirrors the square along its main diagonal, so rows become columns.
+    Transpose,
+    /// Reverses the order of the rows.
+    FlipRows,
+    /// Reverses the order of the columns.
+    FlipColumns,
+    /// Reverses the order of all letters, which rotates the square by 180 degrees.
+    Reverse,
+}
+
+impl PlayFairKey {
+    /// The 5*5 square row by row.
+    pub fn square(&self) -> &[char; KEY_LENGTH] {
+        &self.key
+    }
+
+    /// Returns a new key with the mutation applied to the square. The options
+    /// are kept.
+    ///
+    /// # Panics
+    ///
+    /// If a cell is not below 25 or a row or column is not below 5.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::playfair::{KeyMutation, PlayFairKey};
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// let mutated = pfc.mutated(KeyMutation::SwapRows(0, 4));
+    /// assert_eq!(
+    ///     mutated.to_string(),
+    ///     "T U V W Z\nI R E X M\nB C D G H\nK N O Q S\nP L A Y F\n"
+    /// );
+    /// ```
+    pub fn mutated(&self, mutation: KeyMutation) -> PlayFairKey {
+        let length = ROW_LENGTH as usize;
+        let mut square = self.key;
+        match mutation {
+            KeyMutation::SwapCells(a, b) => square.swap(a as usize, b as usize),
+            KeyMutation::SwapRows(a, b) => {
+                assert!(a < ROW_LENGTH && b < ROW_LENGTH, "row out of range");
+                for column in 0..length {
+                    square.swap(a as usize * length + column, b as usize * length + column);
                 }
             }
-        } else if a_sq_pos.row == b_sq_pos.row {
-            // in row mode
-            // _ _ _ _ _
-            // _ _ _ _ _
-            // _ a y b z
-            // _ _ _ _ _
-            // _ _ _ _ _
-
-            // P L A Y F
-            // I R E X M
-            // B C D G H
-            // K N O Q S
-            // T U V W Z
-            if modus == &CryptModus::Encrypt {
-                // moving right
-                if a_sq_pos.column == 4 {
-                    a_crypted_idx = a_sq_pos.row * ROW_LENGTH;
-                } else {
-                    a_crypted_idx = a_sq_pos.row * ROW_LENGTH + a_sq_pos.column + 1;
+            KeyMutation::SwapColumns(a, b) => {
+                assert!(a < ROW_LENGTH && b < ROW_LENGTH, "column out of range");
+                for row in 0..length {
+                    square.swap(row * length + a as usize, row * length + b as usize);
                 }
-                if b_sq_pos.column == 4 {
-                    b_crypted_idx = b_sq_pos.row * ROW_LENGTH;
-                } else {
-                    b_crypted_idx = b_sq_pos.row * ROW_LENGTH + b_sq_pos.column + 1;
+            }
+            KeyMutation::Transpose => {
+                for (cell, c) in square.iter_mut().enumerate() {
+                    *c = self.key[(cell % length) * length + cell / length];
                 }
-            } else {
-                // decrypt
-                // moving left
-                if a_sq_pos.column == 0 {
-                    a_crypted_idx = (a_sq_pos.row * ROW_LENGTH) + 4;
-                } else {
-                    a_crypted_idx = a_sq_pos.row * ROW_LENGTH + a_sq_pos.column - 1;
+            }
+            KeyMutation::FlipRows => {
+                for (cell, c) in square.iter_mut().enumerate() {
+                    *c = self.key[(length - 1 - cell / length) * length + cell % length];
                 }
-                if b_sq_pos.column == 0 {
-                    b_crypted_idx = (b_sq_pos.row * ROW_LENGTH) + 4;
-                } else {
-                    b_crypted_idx = b_sq_pos.row * ROW_LENGTH + b_sq_pos.column - 1;
+            }
+            KeyMutation::FlipColumns => {
+                for (cell, c) in square.iter_mut().enumerate() {
+                    *c = self.key[(cell / length) * length + length - 1 - cell % length];
                 }
             }
+            KeyMutation::Reverse => square.reverse(),
+        }
+        PlayFairKey::from_square_with_options(square, self.options)
+    }
+}
+
+/// Estimate of how much a square differs from the standard alphabetical
+/// one, see PlayFairKey::quality.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyQuality {
+    /// Number of letters taken from the keyword.
+    pub keyword_length: usize,
+    /// Number of letters at the end of the square which are just the rest
+    /// of the alphabet in order.
+    pub tail_length: usize,
+    /// Upper bound of the entropy of the keyword part in bits: the keyword
+    /// letters could be any of the possible arrangements of that many
+    /// distinct letters. A random square has about 83.7 bits.
+    pub entropy: f64,
+}
+
+impl KeyQuality {
+    /// Share of the square which is the alphabetical tail, from 0 to 1.
+    pub fn tail_ratio(&self) -> f64 {
+        self.tail_length as f64 / KEY_LENGTH as f64
+    }
+}
+
+impl PlayFairKey {
+    /// Estimates the quality of the key square, so weak keywords like "A"
+    /// or "" can be warned of. Only the square is known, so letters at the
+    /// end of the keyword which continue the alphabetical order of the tail
+    /// count as tail.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::playfair::PlayFairKey;
+    ///
+    /// let quality = PlayFairKey::new("playfair example").quality();
+    /// assert_eq!(quality.keyword_length, 10);
+    /// assert_eq!(quality.tail_length, 15);
+    /// assert!(quality.entropy > 40.0);
+    /// assert_eq!(PlayFairKey::new("A").quality().tail_ratio(), 1.0);
+    /// ```
+    pub fn quality(&self) -> KeyQuality {
+        let mut tail_length = 1;
+        while tail_length < KEY_LENGTH
+            && self.key[KEY_LENGTH - tail_length - 1] < self.key[KEY_LENGTH - tail_length]
+        {
+            tail_length += 1;
+        }
+        let keyword_length = KEY_LENGTH - tail_length;
+        // log2(25! / (25 - k)!)
+        let entropy = (KEY_LENGTH - keyword_length + 1..=KEY_LENGTH)
+            .map(|n| (n as f64).log2())
+            .sum();
+        KeyQuality {
+            keyword_length,
+            tail_length,
+            entropy,
+        }
+    }
+}
+
+/// How the chars of a keyword went into a square, see
+/// PlayFairKey::keyword_report. Whitespace is ignored silently.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeywordReport {
+    /// The chars placed in the square in their order, as they appear in
+    /// the square - e.g. J as I.
+    pub used: Vec<char>,
+    /// Chars skipped as they were already placed.
+    pub repeated: Vec<char>,
+    /// Chars the square can't hold and which were dropped, e.g. digits in
+    /// a 5*5 square.
+    pub stripped: Vec<char>,
+}
+
+impl KeywordReport {
+    // Records a char of the keyword: the char as it appears in the square
+    // or None if the square can't hold it.
+    pub(crate) fn record(&mut self, c: char, in_square: Option<char>) {
+        match in_square {
+            Some(c) if self.used.contains(&c) => self.repeated.push(c),
+            Some(c) => self.used.push(c),
+            None => self.stripped.push(c),
+        }
+    }
+
+    /// Whether the keyword contained digits that were dropped.
+    pub fn has_stripped_digits(&self) -> bool {
+        self.stripped.iter().any(char::is_ascii_digit)
+    }
+
+    /// Whether every char of the keyword made it into the square.
+    pub fn is_lossless(&self) -> bool {
+        self.stripped.is_empty()
+    }
+}
+
+impl PlayFairKey {
+    /// Reports which chars of the keyword end up in the square a key of the
+    /// keyword and options has. The 5*5 square only holds letters, digits
+    /// and any other char are dropped - so "KEY2024" yields the same square
+    /// as "KEY". To keep digits use an ExtendedPlayFair.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::options::PayloadOptions;
+    /// use playfair_cipher::playfair::PlayFairKey;
+    ///
+    /// let report = PlayFairKey::keyword_report("Jazz 42", &PayloadOptions::default());
+    /// assert_eq!(report.used, ['I', 'A', 'Z']);
+    /// assert_eq!(report.repeated, ['Z']);
+    /// assert_eq!(report.stripped, ['4', '2']);
+    /// assert!(report.has_stripped_digits());
+    /// ```
+    pub fn keyword_report(keyword: &str, options: &PayloadOptions) -> KeywordReport {
+        let mut report = KeywordReport::default();
+        for c in keyword
+            .chars()
+            .flat_map(char::to_uppercase)
+            .filter(|c| !c.is_whitespace())
+        {
+            let in_square = Some(c)
+                .filter(char::is_ascii_uppercase)
+                .and_then(|c| options.substitute(c));
+            report.record(c, in_square);
+        }
+        report
+    }
+}
+
+impl TryFrom<[[char; 5]; 5]> for PlayFairKey {
+    type Error = InvalidKeyError;
+
+    /// Constructs the cipher from a ready made square, e.g. one generated by
+    /// a program. The square must consist of 25 distinct letters A-Z, lower
+    /// case letters are converted to upper case. The letter missing in the
+    /// square becomes the omitted one, X is used as padding unless X is
+    /// missing - then it's Z.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::try_from([
+    ///     ['P', 'L', 'A', 'Y', 'F'],
+    ///     ['I', 'R', 'E', 'X', 'M'],
+    ///     ['B', 'C', 'D', 'G', 'H'],
+    ///     ['K', 'N', 'O', 'Q', 'S'],
+    ///     ['T', 'U', 'V', 'W', 'Z'],
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAGE");
+    /// ```
+    fn try_from(rows: [[char; 5]; 5]) -> Result<Self, Self::Error> {
+        let mut square = ['*'; KEY_LENGTH];
+        let mut used = [false; 26];
+        for (idx, c) in rows.iter().flatten().enumerate() {
+            let c = c.to_ascii_uppercase();
+            if !c.is_ascii_uppercase() {
+                return Err(InvalidKeyError::new(format!(
+                    "Only chars A-Z possible - got '{}' at row {}, column {}",
+                    c,
+                    idx / ROW_LENGTH as usize,
+                    idx % ROW_LENGTH as usize
+                )));
+            }
+            if used[(c as u8 - b'A') as usize] {
+                return Err(InvalidKeyError::new(format!(
+                    "'{}' at row {}, column {} is already part of the square",
+                    c,
+                    idx / ROW_LENGTH as usize,
+                    idx % ROW_LENGTH as usize
+                )));
+            }
+            used[(c as u8 - b'A') as usize] = true;
+            square[idx] = c;
         }
-        let a_crypted: char = match self.key.get(a_crypted_idx as usize) {
-            Some(c) => *c,
+        let omit = match used.iter().position(|u| !u) {
+            Some(idx) => (b'A' + idx as u8) as char,
             None => '*',
         };
-        let b_crypted: char = match self.key.get(b_crypted_idx as usize) {
-            Some(c) => *c,
-            None => '*',
+        let padding = if omit == 'X' { 'Z' } else { 'X' };
+        let options = PayloadOptions::new(padding, omit, None)
+            .map_err(|e| InvalidKeyError::new(e.to_string()))?;
+        Ok(PlayFairKey::from_square_with_options(square, options))
+    }
+}
+
+impl PlayFairKey {
+    /// Parses a square printed the way books and Wikipedia do: five lines
+    /// of five letters separated by whitespace. Empty lines before and
+    /// after the square are ignored. The letters are checked as by
+    /// PlayFairKey::try_from, rows and columns in the errors are counted
+    /// from 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let pfc = PlayFairKey::from_matrix_str(
+    ///     "
+    ///     P L A Y F
+    ///     I R E X M
+    ///     B C D G H
+    ///     K N O Q S
+    ///     T U V W Z
+    ///     ",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAGE");
+    /// let e = PlayFairKey::from_matrix_str("P L A Y F\nI R E X\nB C D G H\nK N O Q S\nT U V W Z");
+    /// assert_eq!(e.unwrap_err().to_string(), "Row 1 holds 4 letters - expected 5");
+    /// ```
+    pub fn from_matrix_str(matrix: &str) -> Result<Self, InvalidKeyError> {
+        let lines: Vec<&str> = matrix
+            .trim_matches(|c: char| c.is_whitespace())
+            .lines()
+            .collect();
+        let length = ROW_LENGTH as usize;
+        if lines.len() != length {
+            return Err(InvalidKeyError::new(format!(
+                "The square holds {} rows - expected {}",
+                lines.len(),
+                length
+            )));
+        }
+        let mut rows = [['*'; 5]; 5];
+        for (row, line) in lines.iter().enumerate() {
+            let letters: Vec<&str> = line.split_whitespace().collect();
+            for (column, letter) in letters.iter().enumerate() {
+                let mut chars = letter.chars();
+                let letter = match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => {
+                        return Err(InvalidKeyError::new(format!(
+                            "'{}' at row {}, column {} is not a single letter",
+                            letter, row, column
+                        )))
+                    }
+                };
+                if column < length {
+                    rows[row][column] = letter;
+                }
+            }
+            if letters.len() != length {
+                return Err(InvalidKeyError::new(format!(
+                    "Row {} holds {} letters - expected {}",
+                    row,
+                    letters.len(),
+                    length
+                )));
+            }
+        }
+        PlayFairKey::try_from(rows)
+    }
+}
+
+/// Computes the 5*5 square for a keyword in a const context. Unlike
+/// PlayFairKey::new it only accepts the letters A-Z (in any case) and
+/// spaces, anything else panics - which fails the build when evaluated at
+/// compile time. Used by the playfair_key! macro.
+#[doc(hidden)]
+pub const fn square_from_keyword(keyword: &str) -> [char; KEY_LENGTH] {
+    let keyword = keyword.as_bytes();
+    let key_cars = KEY_CARS.as_bytes();
+    let mut square = ['*'; KEY_LENGTH];
+    let mut used = [false; 26];
+    let mut square_len = 0;
+    let mut counter = 0;
+    while counter < keyword.len() + key_cars.len() && square_len < KEY_LENGTH {
+        let mut c = if counter < keyword.len() {
+            keyword[counter].to_ascii_uppercase()
+        } else {
+            key_cars[counter - keyword.len()]
         };
+        counter += 1;
+        if c == b' ' {
+            continue;
+        }
+        if !c.is_ascii_uppercase() {
+            panic!("Only chars A-Z and spaces are allowed in a keyword");
+        }
+        if c == b'J' {
+            c = b'I';
+        }
+        if !used[(c - b'A') as usize] {
+            used[(c - b'A') as usize] = true;
+            square[square_len] = c as char;
+            square_len += 1;
+        }
+    }
+    square
+}
+
+/// Constructs a PlayFairKey whose square is computed at compile time. The
+/// keyword must be a constant expression and may only contain the letters
+/// A-Z and spaces, otherwise the build fails.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::playfair_key;
+/// use playfair_cipher::cryptable::Cypher;
+///
+/// let pfc = playfair_key!("playfair example");
+/// assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAGE");
+/// ```
+///
+/// An invalid keyword does not compile:
+///
+/// ```compile_fail
+/// use playfair_cipher::playfair_key;
+///
+/// let pfc = playfair_key!("playfair 4 example");
+/// ```
+#[macro_export]
+macro_rules! playfair_key {
+    ($keyword:expr) => {{
+        const SQUARE: [char; 25] = $crate::playfair::square_from_keyword($keyword);
+        $crate::playfair::PlayFairKey::from_square(SQUARE)
+    }};
+}
+
+impl PlayFairKey {
+    // Cell (row * 5 + column) of a char within the square.
+    pub(crate) fn cell(&self, c: char) -> Result<u8, CharNotInKeyError> {
+        match self.key_map.get(&c) {
+            Some(cell) => Ok(cell),
+            None => Err(CharNotInKeyError::new(
+                format!(
+                    "Only chars A-Z possible - '{}' was not found in key {:?}",
+                    c, &self.key
+                ),
+                c,
+            )),
+        }
+    }
+
+    // One row of the square with the chars separated by spaces, e.g. "P L A Y F"
+    pub(crate) fn row(&self, row: usize) -> String {
+        let start = row * ROW_LENGTH as usize;
+        let mut row_str = String::with_capacity(2 * ROW_LENGTH as usize);
+        for c in &self.key[start..start + ROW_LENGTH as usize] {
+            if !row_str.is_empty() {
+                row_str.push(' ');
+            }
+            row_str.push(*c);
+        }
+        row_str
+    }
+}
+
+impl fmt::Display for PlayFairKey {
+    /// Prints the 5*5 square row by row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::playfair::PlayFairKey;
+    ///
+    /// let pfc = PlayFairKey::new("playfair example");
+    /// assert_eq!(
+    ///     pfc.to_string(),
+    ///     "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W Z\n"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..ROW_LENGTH as usize {
+            writeln!(f, "{}", self.row(row))?;
+        }
+        Ok(())
+    }
+}
+
+impl Crypt for PlayFairKey {
+    fn crypt(
+        &self,
+        a: char,
+        b: char,
+        modus: &CryptModus,
+    ) -> Result<CryptResult, CharNotInKeyError> {
+        let (a_cell, b_cell) = crypt_cells(self.cell(a)?, self.cell(b)?, ROW_LENGTH, modus);
         Ok(CryptResult {
-            a: a_crypted,
-            b: b_crypted,
+            a: self.key[a_cell as usize],
+            b: self.key[b_cell as usize],
         })
     }
 
-    fn crypt_payload(
-        &self,
-        payload: &str,
-        modus: &crate::structs::CryptModus,
-    ) -> Result<String, crate::errors::CharNotInKeyError> {
-        let mut payload_iter = Payload::new(payload);
+    fn payload_options(&self) -> &PayloadOptions {
+        &self.options
+    }
 
-        payload_iter.crypt_payload(self, modus)
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt> {
+        Some(self)
     }
 }
 
+impl SquareCrypt for PlayFairKey {
+    fn cipher_squares(&self) -> [&PlayFairKey; 2] {
+        [self, self]
+    }
+
+    fn plain_squares(&self) -> [&PlayFairKey; 2] {
+        [self, self]
+    }
+
+    fn layout(&self) -> Layout<'_> {
+        Layout {
+            squares: vec![self],
+            columns: 1,
+            plain: [0, 0],
+            crypt: [0, 0],
+        }
+    }
+
+    fn rule(&self, a: char, b: char) -> Result<Rule, CharNotInKeyError> {
+        let (a_cell, b_cell) = (self.cell(a)?, self.cell(b)?);
+        Ok(if a_cell % ROW_LENGTH == b_cell % ROW_LENGTH {
+            Rule::Column
+        } else if a_cell / ROW_LENGTH == b_cell / ROW_LENGTH {
+            Rule::Row
+        } else {
+            Rule::Rectangle
+        })
+    }
+}
+
+// Cells a digram in the cells a and b is en- or decrypted to, within a
+// square of row_length * row_length cells.
+pub(crate) fn crypt_cells(a_cell: u8, b_cell: u8, row_length: u8, modus: &CryptModus) -> (u8, u8) {
+    let grid = Grid::square(row_length as usize);
+    let (a, b) = (a_cell as usize, b_cell as usize);
+    // Moving one step right or down when encrypting, one step left or up
+    // when decrypting.
+    let shift = match modus {
+        CryptModus::Encrypt => 1,
+        CryptModus::Decrypt => -1,
+    };
+    let (a, b) = if !grid.same_column(a, b) && !grid.same_row(a, b) {
+        // in square mode
+        // example 1:
+        // _ a _ y _
+        // _ _ _ _ _
+        // _ z _ b _
+        // _ _ _ _ _
+        // _ _ _ _ _
+
+        // example 2:
+        // _ b _ z _
+        // _ _ _ _ _
+        // _ y _ a _
+        // _ _ _ _ _
+        // _ _ _ _ _
+        grid.rectangle_corners(a, b)
+    } else if grid.same_column(a, b) {
+        // in column mode
+        // example 1
+        // _ a _ _ _
+        // _ y _ _ _
+        // _ b _ _ _
+        // _ z _ _ _
+        // _ _ _ _ _
+
+        // example 2
+        // _ y _ _ _
+        // _ _ _ _ _
+        // _ b _ _ _
+        // _ z _ _ _
+        // _ a _ _ _
+        //
+        // In the last row going back to row 0 and vice versa.
+        (grid.shift_down(a, shift), grid.shift_down(b, shift))
+    } else {
+        // in row mode
+        // _ _ _ _ _
+        // _ _ _ _ _
+        // _ a y b z
+        // _ _ _ _ _
+        // _ _ _ _ _
+
+        // P L A Y F
+        // I R E X M
+        // B C D G H
+        // K N O Q S
+        // T U V W Z
+        (grid.shift_right(a, shift), grid.shift_right(b, shift))
+    };
+    (a as u8, b as u8)
+}
+
 impl Cypher for PlayFairKey {
     /// Encrypts a string. Note as the PlayFair cipher is only able to encrypt the
     /// characters A-I and L-Z any spaces and J are cleared off.
@@ -284,26 +813,83 @@ impl Cypher for PlayFairKey {
     fn decrypt(&self, payload: &str) -> Result<String, CharNotInKeyError> {
         self.crypt_payload(payload, &CryptModus::Decrypt)
     }
+
+    fn internals(&self) -> Option<Internals<'_>> {
+        Some(Internals(self))
+    }
+}
+
+impl SquareCypher for PlayFairKey {
+    fn square_internals(&self) -> SquareInternals<'_> {
+        SquareInternals(self)
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::cryptable::{ChangedChar, CharChange};
+    use crate::errors::{CharErrorKind, WriteError};
+    use crate::structs::Payload;
+    use std::borrow::Cow;
 
     #[test]
     fn test_payload() {
-        let payload = Payload::new("I would like 4 tins of jam.");
+        let payload = Payload::new("I would like 4 tins of jam.", &PayloadOptions::default());
         assert_eq!(payload.payload, "IWOULDLIKETINSOFIAM");
         // becomes "IWOULDLIKETINSOFIAM"
     }
 
+    #[test]
+    fn test_payload_multibyte() {
+        let payload = Payload::new("Straße Épée", &PayloadOptions::default());
+        assert_eq!(payload.payload, "STRASSEPE");
+        assert_eq!(payload.origins, vec![0, 1, 2, 3, 4, 4, 5, 8, 10]);
+    }
+
+    #[test]
+    fn test_payload_normalized() {
+        let payload = Payload::new("IWOULDLIKE", &PayloadOptions::default());
+        assert!(matches!(payload.payload, Cow::Borrowed("IWOULDLIKE")));
+        let payload = Payload::new("IWOULDLIKEJAM", &PayloadOptions::default());
+        assert!(matches!(payload.payload, Cow::Owned(_)));
+        assert_eq!(payload.payload, "IWOULDLIKEIAM");
+    }
+
+    #[test]
+    fn test_error_position_normalized() {
+        let mut pfc = PlayFairKey::new("playfair example");
+        pfc.key_map.remove(&'T');
+        match pfc.encrypt("HIDETHEGOLD") {
+            Ok(s) => panic!("expected CharNotInKeyError, got {}", s),
+            Err(e) => {
+                assert_eq!(e.position(), Some(4));
+                assert_eq!(e.original_position(), Some(4));
+            }
+        };
+    }
+
+    #[test]
+    fn test_error_position() {
+        let mut pfc = PlayFairKey::new("playfair example");
+        pfc.key_map.remove(&'T');
+        match pfc.encrypt("hide the gold") {
+            Ok(s) => panic!("expected CharNotInKeyError, got {}", s),
+            Err(e) => {
+                assert_eq!(e.character(), 'T');
+                assert_eq!(e.position(), Some(4));
+                assert_eq!(e.original_position(), Some(5));
+            }
+        };
+    }
+
     #[test]
     fn test_key_gen_empty_key() {
         let pfk = PlayFairKey::new("");
         assert_eq!(
             pfk.key,
-            vec![
+            [
                 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
                 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'
             ]
@@ -315,19 +901,25 @@ mod tests {
         let pfk = PlayFairKey::new("simple");
         assert_eq!(
             pfk.key,
-            vec![
+            [
                 'S', 'I', 'M', 'P', 'L', 'E', 'A', 'B', 'C', 'D', 'F', 'G', 'H', 'K', 'N', 'O',
                 'Q', 'R', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z'
             ]
         )
     }
 
+    #[test]
+    fn test_key_gen_non_letters() {
+        let pfk = PlayFairKey::new("\0zy0 ä-simple");
+        assert_eq!(pfk.key, PlayFairKey::new("zysimple").key);
+    }
+
     #[test]
     fn test_key_gen_seecretisjj() {
         let pfk = PlayFairKey::new("seecretisJJ");
         assert_eq!(
             pfk.key,
-            vec![
+            [
                 'S', 'E', 'C', 'R', 'T', 'I', 'A', 'B', 'D', 'F', 'G', 'H', 'K', 'L', 'M', 'N',
                 'O', 'P', 'Q', 'U', 'V', 'W', 'X', 'Y', 'Z'
             ]
@@ -339,16 +931,133 @@ mod tests {
         let pfk = PlayFairKey::new("ZYXWVUTSRQPONMLKJIHGFECA");
         assert_eq!(
             pfk.key,
-            vec![
+            [
                 'Z', 'Y', 'X', 'W', 'V', 'U', 'T', 'S', 'R', 'Q', 'P', 'O', 'N', 'M', 'L', 'K',
                 'I', 'H', 'G', 'F', 'E', 'C', 'A', 'B', 'D'
             ]
         )
     }
 
+    #[test]
+    fn test_key_gen_macro() {
+        for keyword in ["", "simple", "seecretisJJ", "ZYXWVUTSRQPONMLKJIHGFECA"] {
+            let pfk = PlayFairKey::new(keyword);
+            let square = square_from_keyword(keyword);
+            assert_eq!(pfk.key, square, "keyword {}", keyword);
+            assert_eq!(PlayFairKey::from_square(square).key_map.len(), KEY_LENGTH);
+        }
+        let pfk = crate::playfair_key!("playfair example");
+        assert_eq!(pfk.key, PlayFairKey::new("playfair example").key);
+    }
+
+    #[test]
+    fn test_key_gen_omit_q() {
+        let pfk = PlayFairKey::builder()
+            .key("quit joke")
+            .omit('Q')
+            .build()
+            .unwrap();
+        assert_eq!(
+            pfk.key,
+            [
+                'U', 'I', 'T', 'J', 'O', 'K', 'E', 'A', 'B', 'C', 'D', 'F', 'G', 'H', 'L', 'M',
+                'N', 'P', 'R', 'S', 'V', 'W', 'X', 'Y', 'Z'
+            ]
+        );
+        let payload = Payload::new("quick jump", &pfk.options);
+        assert_eq!(payload.payload, "UICKJUMP");
+    }
+
+    #[test]
+    fn test_builder_invalid_options() {
+        assert!(PlayFairKey::builder().padding('J').build().is_err());
+        assert!(PlayFairKey::builder().padding('4').build().is_err());
+        assert!(PlayFairKey::builder().omit('?').build().is_err());
+        assert!(PlayFairKey::builder().grouping(0).build().is_err());
+    }
+
+    #[test]
+    fn test_iterator_padding() {
+        let options = PayloadOptions::new('Q', 'J', None).unwrap();
+        let payload = Payload::new("see", &options);
+        assert_eq!(payload.collect::<Vec<_>>(), vec![['S', 'E'], ['E', 'Q']]);
+        let payload = Payload::new("balloon", &options);
+        assert_eq!(
+            payload.collect::<Vec<_>>(),
+            vec![['B', 'A'], ['L', 'Q'], ['L', 'O'], ['O', 'N']]
+        );
+    }
+
+    #[test]
+    fn test_encrypt_to_writer() {
+        struct Refusing;
+        impl std::fmt::Write for Refusing {
+            fn write_str(&mut self, _s: &str) -> std::fmt::Result {
+                Err(std::fmt::Error)
+            }
+        }
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let mut crypt = String::new();
+        pfc.encrypt_to_writer("hide the gold", &mut crypt).unwrap();
+        assert_eq!(crypt, "BMODZ BXDNA GE");
+        let mut plain = String::new();
+        pfc.decrypt_to_writer(&crypt, &mut plain).unwrap();
+        assert_eq!(plain, "HIDETHEGOLDX");
+        assert!(matches!(
+            pfc.encrypt_to_writer("hide the gold", &mut Refusing),
+            Err(WriteError::Fmt(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_try_from_rows() {
+        let pfk = PlayFairKey::try_from([
+            ['s', 'i', 'm', 'p', 'l'],
+            ['e', 'a', 'b', 'c', 'd'],
+            ['f', 'g', 'h', 'k', 'n'],
+            ['o', 'q', 'r', 't', 'u'],
+            ['v', 'w', 'x', 'y', 'z'],
+        ])
+        .unwrap();
+        assert_eq!(pfk.key, PlayFairKey::new("simple").key);
+        assert_eq!(pfk.options, PayloadOptions::default());
+        assert_eq!(pfk.key_map.get(&'K'), Some(13));
+
+        let pfk = PlayFairKey::try_from([
+            ['A', 'B', 'C', 'D', 'E'],
+            ['F', 'G', 'H', 'I', 'J'],
+            ['K', 'L', 'M', 'N', 'O'],
+            ['P', 'R', 'S', 'T', 'U'],
+            ['V', 'W', 'X', 'Y', 'Z'],
+        ])
+        .unwrap();
+        assert_eq!(pfk.options.omit(), 'Q');
+
+        let duplicate = PlayFairKey::try_from([
+            ['A', 'B', 'C', 'D', 'E'],
+            ['F', 'G', 'H', 'I', 'K'],
+            ['L', 'M', 'N', 'O', 'P'],
+            ['Q', 'R', 'S', 'T', 'U'],
+            ['V', 'W', 'X', 'Y', 'A'],
+        ]);
+        assert!(duplicate.is_err());
+        let not_a_letter = PlayFairKey::try_from([
+            ['A', 'B', 'C', 'D', 'E'],
+            ['F', 'G', 'H', 'I', 'K'],
+            ['L', 'M', '4', 'O', 'P'],
+            ['Q', 'R', 'S', 'T', 'U'],
+            ['V', 'W', 'X', 'Y', 'Z'],
+        ]);
+        assert!(not_a_letter.is_err());
+    }
+
     #[test]
     fn test_iterator() {
-        let mut payload = Payload::new("my secret message");
+        let mut payload = Payload::new("my secret message", &PayloadOptions::default());
         let mut digrams: Vec<[char; 2]> = Vec::new();
 
         loop {
@@ -385,54 +1094,20 @@ mod tests {
     #[test]
     fn test_position_map() {
         let pfx = PlayFairKey::new("playfair example");
-        let valid_positions: Vec<SquarePosition> = vec![
-            SquarePosition { row: 0, column: 0 },
-            SquarePosition { row: 0, column: 1 },
-            SquarePosition { row: 0, column: 2 },
-            SquarePosition { row: 0, column: 3 },
-            SquarePosition { row: 0, column: 4 },
-            SquarePosition { row: 1, column: 0 },
-            SquarePosition { row: 1, column: 1 },
-            SquarePosition { row: 1, column: 2 },
-            SquarePosition { row: 1, column: 3 },
-            SquarePosition { row: 1, column: 4 },
-            SquarePosition { row: 2, column: 0 },
-            SquarePosition { row: 2, column: 1 },
-            SquarePosition { row: 2, column: 2 },
-            SquarePosition { row: 2, column: 3 },
-            SquarePosition { row: 2, column: 4 },
-            SquarePosition { row: 3, column: 0 },
-            SquarePosition { row: 3, column: 1 },
-            SquarePosition { row: 3, column: 2 },
-            SquarePosition { row: 3, column: 3 },
-            SquarePosition { row: 3, column: 4 },
-            SquarePosition { row: 4, column: 0 },
-            SquarePosition { row: 4, column: 1 },
-            SquarePosition { row: 4, column: 2 },
-            SquarePosition { row: 4, column: 3 },
-            SquarePosition { row: 4, column: 4 },
-        ];
-        let mut valid_positions_iter = valid_positions.iter();
-        let empty_must_be_sqrt_pos = SquarePosition {
-            row: 43,
-            column: 43,
-        };
         for (counter, c) in pfx.key.into_iter().enumerate() {
-            let must_be_sqrt_pos = match valid_positions_iter.next() {
+            let check_cell = match pfx.key_map.get(&c) {
                 Some(t) => t,
-                None => &empty_must_be_sqrt_pos,
-            };
-            let check_sqrt_pos = match pfx.key_map.get(&c) {
-                Some(t) => t,
-                None => EMPTY_SQ_POS,
+                None => panic!("{} not found in key map", c),
             };
             assert_eq!(
-                check_sqrt_pos.row, must_be_sqrt_pos.row,
+                check_cell / ROW_LENGTH,
+                counter as u8 / 5,
                 "row assertion failed at iteration {}",
                 counter
             );
             assert_eq!(
-                check_sqrt_pos.column, must_be_sqrt_pos.column,
+                check_cell % ROW_LENGTH,
+                counter as u8 % 5,
                 "column assertion failed at iteration {}",
                 counter
             );
@@ -572,4 +1247,317 @@ mod tests {
             Err(e) => panic!("CharNotInKeyError {}", e),
         };
     }
+
+    #[test]
+    fn test_mutated() {
+        let pfc = PlayFairKey::new("playfair example");
+        let swapped = pfc.mutated(KeyMutation::SwapCells(0, 24));
+        assert_eq!(swapped.key[0], 'Z');
+        assert_eq!(swapped.key[24], 'P');
+        assert_eq!(swapped.cell('P').unwrap(), 24);
+        let transposed = pfc.mutated(KeyMutation::Transpose);
+        assert_eq!(transposed.row(0), "P I B K T");
+        assert_eq!(transposed.mutated(KeyMutation::Transpose).key, pfc.key);
+        let columns = pfc.mutated(KeyMutation::SwapColumns(0, 4));
+        assert_eq!(columns.row(1), "M R E X I");
+        let flipped = pfc
+            .mutated(KeyMutation::FlipRows)
+            .mutated(KeyMutation::FlipColumns);
+        assert_eq!(flipped.key, pfc.mutated(KeyMutation::Reverse).key);
+        // Rows and columns of a Playfair square are cyclic, so swapping
+        // the whole rows around doesn't change the cipher
+        let rotated = pfc
+            .mutated(KeyMutation::SwapRows(0, 1))
+            .mutated(KeyMutation::SwapRows(1, 2))
+            .mutated(KeyMutation::SwapRows(2, 3))
+            .mutated(KeyMutation::SwapRows(3, 4));
+        assert_eq!(
+            rotated.encrypt("hide the gold").unwrap(),
+            pfc.encrypt("hide the gold").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let pfk = PlayFairKey::builder()
+            .key("quick jump")
+            .omit('Q')
+            .padding('Z')
+            .build()
+            .unwrap();
+        let verification = pfk.verify(None).unwrap();
+        assert!(verification.passed);
+        assert!(!verification.normalized.contains('Q'));
+        assert_eq!(verification.crypt.len(), verification.decrypted.len());
+    }
+
+    #[test]
+    fn test_validate_input() {
+        let pfk = PlayFairKey::builder()
+            .key("quick jump")
+            .omit('Q')
+            .padding('Z')
+            .build()
+            .unwrap();
+        let report = pfk.validate_input("Quiet Straße, tall");
+        let dropped: Vec<char> = report
+            .changes
+            .iter()
+            .filter(|c| c.change == CharChange::Dropped)
+            .map(|c| c.character)
+            .collect();
+        assert_eq!(dropped, ['Q', ' ', ',', ' ']);
+        assert_eq!(
+            report.changes[2],
+            ChangedChar {
+                position: 10,
+                character: 'ß',
+                change: CharChange::Substituted(String::from("SS"))
+            }
+        );
+        assert!(!report.is_lossless());
+        let crypt = pfk.encrypt("Quiet Straße, tall").unwrap();
+        assert_eq!(report.crypt_length(), crypt.len());
+        // UIETSTRASSETALL: SS and LL split, the final L is padded
+        assert_eq!((report.fillers, report.padded), (2, true));
+
+        let report = pfk.validate_input("ZZ");
+        assert!(report.is_lossless());
+        assert_eq!((report.fillers, report.padded), (1, true));
+        assert_eq!(report.crypt_length(), pfk.encrypt("ZZ").unwrap().len());
+    }
+
+    #[test]
+    fn test_encrypt_with_outcome() {
+        let pfk = PlayFairKey::builder()
+            .key("quick jump")
+            .omit('Q')
+            .padding('Z')
+            .grouping(5)
+            .build()
+            .unwrap();
+        let outcome = pfk.encrypt_with_outcome("Quiet Straße, tall").unwrap();
+        assert_eq!(
+            outcome.ciphertext,
+            pfk.encrypt("Quiet Straße, tall").unwrap()
+        );
+        assert_eq!(
+            (outcome.dropped, outcome.fillers, outcome.padded),
+            (4, 2, true)
+        );
+        assert_eq!(outcome.digrams, 9);
+
+        let pfk = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::PassThrough)
+            .build()
+            .unwrap();
+        // TA LX L - the last L is passed through
+        let outcome = pfk.encrypt_with_outcome("tall").unwrap();
+        assert_eq!(
+            (outcome.dropped, outcome.fillers, outcome.padded),
+            (0, 1, false)
+        );
+        assert_eq!(outcome.digrams, 2);
+        let pfk = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::Strict)
+            .build()
+            .unwrap();
+        assert!(pfk.encrypt_with_outcome("tall").is_err());
+    }
+
+    #[test]
+    fn test_encrypt_on_drop() {
+        #[derive(Debug, PartialEq)]
+        enum HookError {
+            Digit(usize),
+            NotInKey,
+        }
+        impl From<CharNotInKeyError> for HookError {
+            fn from(_: CharNotInKeyError) -> Self {
+                HookError::NotInKey
+            }
+        }
+        let pfk = PlayFairKey::new("playfair example");
+        let reject_digits = |changed: &ChangedChar| match changed.character.is_ascii_digit() {
+            true => Err(HookError::Digit(changed.position)),
+            false => Ok(()),
+        };
+        assert_eq!(
+            pfk.encrypt_on_drop("Jam, 4 tins", reject_digits),
+            Err(HookError::Digit(5))
+        );
+        let mut changes = Vec::new();
+        let crypt = pfk
+            .encrypt_on_drop("Jam, tins", |changed| {
+                changes.push(changed.clone());
+                Ok::<(), HookError>(())
+            })
+            .unwrap();
+        assert_eq!(crypt, pfk.encrypt("Jam, tins").unwrap());
+        assert_eq!(changes, pfk.validate_input("Jam, tins").changes);
+        assert_eq!(
+            changes[0].change,
+            CharChange::Substituted(String::from("I"))
+        );
+    }
+
+    #[test]
+    fn test_encrypt_iter() {
+        for final_digram in [FinalDigram::Pad, FinalDigram::PassThrough] {
+            let pfk = PlayFairKey::builder()
+                .key("playfair example")
+                .grouping(3)
+                .final_digram(final_digram)
+                .build()
+                .unwrap();
+            for payload in ["", "a", "Hide the gold!", "Balloons aa see", "HIDETHEGOLD"] {
+                let crypt: Result<String, _> = pfk.encrypt_iter(payload).collect();
+                assert_eq!(crypt.unwrap(), pfk.encrypt(payload).unwrap());
+            }
+        }
+        let pfk = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::Strict)
+            .build()
+            .unwrap();
+        let mut chars = pfk.encrypt_iter("hide the gold!");
+        let crypt: String = chars.by_ref().map_while(Result::ok).collect();
+        assert_eq!(crypt, "BMODZBXDNA");
+        assert!(chars.next().is_none());
+        let error = pfk
+            .encrypt_iter("hide the gold!")
+            .last()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(error.original_position(), Some(12));
+    }
+
+    #[test]
+    fn test_encrypt_positioned() {
+        let pfk = PlayFairKey::new("playfair example");
+        let payload = "Maß all";
+        let digrams = pfk.encrypt_positioned(payload).unwrap();
+        // MA SX SA LX LX - ß yields SS, split by a filler as is LL
+        let plain: Vec<[char; 2]> = digrams.iter().map(|d| d.plain).collect();
+        assert_eq!(
+            plain,
+            [['M', 'A'], ['S', 'X'], ['S', 'A'], ['L', 'X'], ['L', 'X']]
+        );
+        assert_eq!(digrams[1].positions, [Some(2), None]);
+        assert_eq!(digrams[2].original_positions, [Some(2), Some(4)]);
+        assert_eq!(digrams[4].original_positions, [Some(6), None]);
+        let crypt = pfk.encrypt(payload).unwrap();
+        for digram in &digrams {
+            let found: String = crypt.chars().skip(digram.crypt_position).take(2).collect();
+            assert_eq!(found, digram.crypt.iter().collect::<String>());
+        }
+    }
+
+    #[test]
+    fn test_final_digram() {
+        let strict = PlayFairKey::builder()
+            .key("playfair example")
+            .final_digram(FinalDigram::Strict)
+            .build()
+            .unwrap();
+        assert_eq!(strict.encrypt("hide the goldx!").unwrap(), "BMODZBXDNAGE");
+        let e = strict.encrypt("hide the gold!").unwrap_err();
+        assert_eq!(e.character(), 'D');
+        assert_eq!(e.kind(), CharErrorKind::Unpaired);
+        assert_eq!((e.position(), e.original_position()), (Some(10), Some(12)));
+        let e = strict.decrypt("BMODZBXDNAG").unwrap_err();
+        assert_eq!(e.kind(), CharErrorKind::Unpaired);
+        let e = strict.encrypt_digram('J', 'A').unwrap_err();
+        assert_eq!(e.kind(), CharErrorKind::NotInKey);
+        assert_eq!(strict.validate_input("gol").unpaired, Some('L'));
+
+        let pass_through = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(2)
+            .final_digram(FinalDigram::PassThrough)
+            .build()
+            .unwrap();
+        // The doubled L is still split, the last L is left as it is
+        let crypt = pass_through.encrypt("tall").unwrap();
+        assert_eq!(crypt, "VP YR L");
+        assert_eq!(pass_through.decrypt(&crypt).unwrap(), "TALXL");
+        let report = pass_through.validate_input("tall");
+        assert_eq!((report.fillers, report.padded), (1, false));
+        assert_eq!(report.crypt_length(), 5);
+        let mut chunks = Vec::new();
+        pass_through
+            .encrypt_with("tall", |chunk| chunks.push(chunk.to_string()))
+            .unwrap();
+        assert_eq!(chunks, ["VP", " YR", " L"]);
+    }
+
+    #[test]
+    fn test_from_matrix_str() {
+        let printed = PlayFairKey::new("playfair example").to_string();
+        let pfc = PlayFairKey::from_matrix_str(&printed).unwrap();
+        assert_eq!(pfc.square(), PlayFairKey::new("playfair example").square());
+        let errors = [
+            (
+                "P L A Y F\nI R E X M",
+                "The square holds 2 rows - expected 5",
+            ),
+            (
+                "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W Z\nA B C D E",
+                "The square holds 6 rows - expected 5",
+            ),
+            (
+                "P L A Y F\nI R EX M\nB C D G H\nK N O Q S\nT U V W Z",
+                "'EX' at row 1, column 2 is not a single letter",
+            ),
+            (
+                "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W 1",
+                "Only chars A-Z possible - got '1' at row 4, column 4",
+            ),
+            (
+                "P L A Y F\nI R E X M\nB C D G H\nK N O Q S\nT U V W P",
+                "'P' at row 4, column 4 is already part of the square",
+            ),
+        ];
+        for (matrix, error) in errors {
+            assert_eq!(
+                PlayFairKey::from_matrix_str(matrix)
+                    .unwrap_err()
+                    .to_string(),
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_quality() {
+        let quality = PlayFairKey::new("").quality();
+        assert_eq!(quality.keyword_length, 0);
+        assert_eq!(quality.entropy, 0.0);
+        let quality = PlayFairKey::new("zyx").quality();
+        assert_eq!(quality.keyword_length, 3);
+        assert_eq!(quality.tail_length, 22);
+        assert!((quality.entropy - (25.0f64 * 24.0 * 23.0).log2()).abs() < 1e-9);
+        let reversed = PlayFairKey::new("").mutated(KeyMutation::Reverse).quality();
+        assert_eq!(reversed.tail_length, 1);
+        assert!((reversed.entropy - 83.68).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_keyword_report() {
+        let options = PayloadOptions::new('X', 'Q', None).unwrap();
+        let report = PlayFairKey::keyword_report("quiz-2", &options);
+        assert_eq!(report.used, ['U', 'I', 'Z']);
+        assert_eq!(report.stripped, ['Q', '-', '2']);
+        assert!(!report.is_lossless());
+        let report = PlayFairKey::keyword_report("playfair example", &options);
+        assert!(report.is_lossless());
+        assert!(!report.has_stripped_digits());
+        let key: String = report.used.iter().collect();
+        assert_eq!(
+            PlayFairKey::with_options(&key, options).square(),
+            PlayFairKey::with_options("playfair example", options).square()
+        );
+    }
 }
@@ -0,0 +1,27 @@
+//! Re-exports of the ciphers, the Cypher traits and the error types, so a
+//! single import makes everything available.
+//!
+//! ```
+//! use playfair_cipher::prelude::*;
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZBXDNAGE");
+//! ```
+
+pub use crate::cipher::Cipher;
+pub use crate::config::Algorithm;
+pub use crate::cryptable::{Cypher, EncryptOutcome, SquareCypher};
+pub use crate::digram::{Digram, PositionedDigram, Rule, TraceStep};
+pub use crate::errors::{
+    BinaryError, CharErrorKind, CharNotInKeyError, ConfigError, CoordinateError, DecryptError,
+    InvalidKeyError, InvalidOptionError, StreamError, WriteError,
+};
+#[cfg(feature = "four-square")]
+pub use crate::four_square::FourSquare;
+pub use crate::normalize;
+pub use crate::options::{FinalDigram, PayloadOptions, Transparency};
+pub use crate::playfair::PlayFairKey;
+pub use crate::segment::Segmentation;
+pub use crate::text::{CipherText, Plaintext};
+#[cfg(feature = "two-square")]
+pub use crate::two_square::TwoSquare;
@@ -0,0 +1,350 @@
+//! Generator of PlayFair puzzles, e.g. for escape rooms or puzzle hunts: a
+//! keyword from a word list, a plain text from a corpus, the cipher text
+//! and, depending on the difficulty, hints to get started.
+//!
+
+use crate::analysis::scoring::ENGLISH_CORPUS;
+use crate::crack::Rng;
+use crate::cryptable::Cypher;
+use crate::errors::InvalidOptionError;
+use crate::playfair::PlayFairKey;
+
+const KEYWORDS: [&str; 40] = [
+    "ANCHOR",
+    "BANJO",
+    "BLACKSMITH",
+    "CAMEL",
+    "CASTLE",
+    "CHIMNEY",
+    "CLOCKWORK",
+    "COMPASS",
+    "DOLPHIN",
+    "DRAGONFLY",
+    "EMBASSY",
+    "FALCON",
+    "GAZELLE",
+    "HARBOUR",
+    "JOURNEY",
+    "KINGDOM",
+    "LANTERN",
+    "MACHINE",
+    "MONARCHY",
+    "NIGHTFALL",
+    "ORCHARD",
+    "PALMYRA",
+    "PLAYFAIR",
+    "QUARTZ",
+    "RAINBOW",
+    "SCHOLAR",
+    "SHIPWRECK",
+    "SPHINX",
+    "THUNDER",
+    "TRUMPET",
+    "UMBRELLA",
+    "VOLCANO",
+    "WHEATSTONE",
+    "WINDMILL",
+    "WIZARD",
+    "XYLOPHONE",
+    "YACHT",
+    "ZEPHYR",
+    "COURIER",
+    "BISCUIT",
+];
+
+/// How hard a puzzle is to solve by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Difficulty {
+    /// A long plain text, a short keyword, a crib and 10 letters of the
+    /// square given.
+    Easy,
+    /// A medium plain text and keyword and a crib.
+    #[default]
+    Medium,
+    /// A short plain text, a long keyword and no hints.
+    Hard,
+}
+
+impl Difficulty {
+    // Range of the number of letters of the plain text.
+    fn plain_text_letters(&self) -> (usize, usize) {
+        match self {
+            Difficulty::Easy => (80, 200),
+            Difficulty::Medium => (50, 120),
+            Difficulty::Hard => (25, 70),
+        }
+    }
+
+    // Range of the number of distinct letters of the keyword.
+    fn keyword_letters(&self) -> (usize, usize) {
+        match self {
+            Difficulty::Easy => (4, 6),
+            Difficulty::Medium => (6, 8),
+            Difficulty::Hard => (8, 25),
+        }
+    }
+
+    fn revealed_cells(&self) -> usize {
+        match self {
+            Difficulty::Easy => 10,
+            Difficulty::Medium | Difficulty::Hard => 0,
+        }
+    }
+}
+
+/// Hints to get a solver started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hints {
+    /// A word of the plain text.
+    pub crib: Option<String>,
+    /// Some letters of the square at their place, row by row.
+    pub partial_square: Option<[Option<char>; 25]>,
+}
+
+/// A generated puzzle with its solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub keyword: String,
+    pub plain_text: String,
+    /// The plain text encrypted in groups of 5.
+    pub cipher_text: String,
+    pub hints: Hints,
+}
+
+/// Generator of puzzles, see PuzzleGenerator::builder.
+///
+#[derive(Debug, Clone)]
+pub struct PuzzleGenerator {
+    difficulty: Difficulty,
+    rng: Rng,
+    keywords: Vec<String>,
+    sentences: Vec<String>,
+}
+
+/// Builder for a PuzzleGenerator.
+///
+#[derive(Debug, Clone, Default)]
+pub struct PuzzleGeneratorBuilder {
+    difficulty: Difficulty,
+    seed: u64,
+    words: Option<Vec<String>>,
+    corpus: Option<String>,
+}
+
+impl PuzzleGeneratorBuilder {
+    /// Defaults to Medium.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Seed of the random choices. The same seed yields the same puzzles.
+    /// Defaults to 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Words the keywords are chosen from. Words fitting the difficulty
+    /// are preferred. Defaults to a built-in list.
+    pub fn words<S: AsRef<str>>(mut self, words: &[S]) -> Self {
+        self.words = Some(words.iter().map(|w| w.as_ref().to_string()).collect());
+        self
+    }
+
+    /// Text the plain texts are taken from, sentence by sentence. Defaults
+    /// to a built-in English corpus.
+    pub fn corpus(mut self, corpus: &str) -> Self {
+        self.corpus = Some(String::from(corpus));
+        self
+    }
+
+    /// Fails if there are no words or the corpus lacks a sentence of a
+    /// length fitting the difficulty.
+    pub fn build(self) -> Result<PuzzleGenerator, InvalidOptionError> {
+        let words: Vec<String> = match self.words {
+            Some(words) => words
+                .iter()
+                .map(|w| w.trim().to_uppercase())
+                .filter(|w| w.chars().any(|c| c.is_ascii_uppercase()))
+                .collect(),
+            None => KEYWORDS.iter().map(|w| w.to_string()).collect(),
+        };
+        if words.is_empty() {
+            return Err(InvalidOptionError::new(String::from(
+                "At least one word containing letters A-Z is needed",
+            )));
+        }
+        let (min, max) = self.difficulty.keyword_letters();
+        let fitting: Vec<String> = words
+            .iter()
+            .filter(|w| (min..=max).contains(&distinct_letters(w)))
+            .cloned()
+            .collect();
+        let keywords = if fitting.is_empty() { words } else { fitting };
+
+        let corpus = self.corpus.as_deref().unwrap_or(ENGLISH_CORPUS);
+        let (min, max) = self.difficulty.plain_text_letters();
+        let sentences: Vec<String> = corpus
+            .split_inclusive(['.', '!', '?'])
+            .map(|s| s.split_whitespace().collect::<Vec<&str>>().join(" "))
+            .filter(|s| (min..=max).contains(&s.chars().filter(char::is_ascii_alphabetic).count()))
+            .collect();
+        if sentences.is_empty() {
+            return Err(InvalidOptionError::new(format!(
+                "The corpus lacks a sentence of {} to {} letters",
+                min, max
+            )));
+        }
+        Ok(PuzzleGenerator {
+            difficulty: self.difficulty,
+            rng: Rng::new(self.seed),
+            keywords,
+            sentences,
+        })
+    }
+}
+
+impl PuzzleGenerator {
+    /// Returns a builder to configure the generator.
+    pub fn builder() -> PuzzleGeneratorBuilder {
+        PuzzleGeneratorBuilder::default()
+    }
+
+    /// Generates the next puzzle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::puzzle::{Difficulty, PuzzleGenerator};
+    /// use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+    ///
+    /// let mut generator = PuzzleGenerator::builder()
+    ///     .difficulty(Difficulty::Easy)
+    ///     .seed(7)
+    ///     .build()
+    ///     .unwrap();
+    /// let puzzle = generator.generate();
+    /// let pfc = PlayFairKey::new(&puzzle.keyword);
+    /// assert!(pfc
+    ///     .decrypt(&puzzle.cipher_text)
+    ///     .unwrap()
+    ///     .contains(puzzle.hints.crib.as_deref().unwrap()));
+    /// ```
+    pub fn generate(&mut self) -> Puzzle {
+        let keyword = self.keywords[self.rng.below(self.keywords.len())].clone();
+        let plain_text = self.sentences[self.rng.below(self.sentences.len())].clone();
+        let pfc = PlayFairKey::builder()
+            .key(&keyword)
+            .grouping(5)
+            .build()
+            .expect("default options with grouping are valid");
+        let cipher_text = pfc
+            .encrypt(&plain_text)
+            .expect("normalized plain text only consists of letters of the key");
+
+        let crib = match self.difficulty {
+            Difficulty::Hard => None,
+            _ => {
+                let words: Vec<String> = plain_text
+                    .split(|c: char| !c.is_ascii_alphabetic())
+                    .filter(|w| w.len() >= 4)
+                    .map(|w| w.to_uppercase())
+                    .collect();
+                match words.is_empty() {
+                    true => None,
+                    false => Some(words[self.rng.below(words.len())].clone()),
+                }
+            }
+        };
+        let partial_square = match self.difficulty.revealed_cells() {
+            0 => None,
+            revealed => {
+                let mut cells: Vec<usize> = (0..25).collect();
+                self.rng.shuffle(&mut cells);
+                let mut square = [None; 25];
+                for cell in &cells[..revealed] {
+                    square[*cell] = Some(pfc.square()[*cell]);
+                }
+                Some(square)
+            }
+        };
+        Puzzle {
+            keyword,
+            plain_text,
+            cipher_text,
+            hints: Hints {
+                crib,
+                partial_square,
+            },
+        }
+    }
+}
+
+fn distinct_letters(word: &str) -> usize {
+    let mut used = [false; 26];
+    for c in word.chars().filter(char::is_ascii_uppercase) {
+        used[(c as u8 - b'A') as usize] = true;
+    }
+    used.iter().filter(|u| **u).count()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let build = |seed| {
+            PuzzleGenerator::builder()
+                .difficulty(Difficulty::Hard)
+                .seed(seed)
+                .words(&["blacksmith", "a"])
+                .build()
+                .unwrap()
+        };
+        let puzzle = build(3).generate();
+        assert_eq!(puzzle, build(3).generate());
+        assert_eq!(puzzle.keyword, "BLACKSMITH");
+        assert_eq!(puzzle.hints.crib, None);
+        assert_eq!(puzzle.hints.partial_square, None);
+        let letters = puzzle
+            .plain_text
+            .chars()
+            .filter(char::is_ascii_alphabetic)
+            .count();
+        assert!((25..=70).contains(&letters));
+    }
+
+    #[test]
+    fn test_generate_easy() {
+        let mut generator = PuzzleGenerator::builder()
+            .seed(1)
+            .difficulty(Difficulty::Easy)
+            .build()
+            .unwrap();
+        for _ in 0..10 {
+            let puzzle = generator.generate();
+            let square = PlayFairKey::new(&puzzle.keyword);
+            let partial = puzzle.hints.partial_square.unwrap();
+            assert_eq!(partial.iter().flatten().count(), 10);
+            for (cell, c) in partial.iter().enumerate() {
+                assert!(c.is_none() || *c == Some(square.square()[cell]));
+            }
+            assert!((4..=6).contains(&distinct_letters(&puzzle.keyword)));
+        }
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert!(PuzzleGenerator::builder()
+            .words(&["", "42"])
+            .build()
+            .is_err());
+        assert!(PuzzleGenerator::builder()
+            .corpus("Too short.")
+            .build()
+            .is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Python bindings, available with the feature `python`. The module
+//! playfair_cipher offers the classes PlayFairKey, TwoSquare and
+//! FourSquare, built with e.g. maturin:
+//!
+//! ```python
+//! from playfair_cipher import PlayFairKey, InvalidOptionError
+//!
+//! pfc = PlayFairKey("playfair example", grouping=5)
+//! assert pfc.encrypt("hide the gold") == "BMODZ BXDNA GE"
+//! try:
+//!     PlayFairKey("playfair example", padding="1")
+//! except InvalidOptionError as e:
+//!     print(e)
+//! ```
+//!
+//! A CharNotInKeyError is raised as the exception of the same name, an
+//! InvalidOptionError as InvalidOptionError. Both derive from ValueError.
+//!
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::cryptable::Cypher;
+use crate::errors;
+#[cfg(feature = "four-square")]
+use crate::four_square::FourSquare;
+use crate::playfair::PlayFairKey;
+#[cfg(feature = "two-square")]
+use crate::two_square::TwoSquare;
+
+create_exception!(
+    playfair_cipher,
+    CharNotInKeyError,
+    PyValueError,
+    "A character of the payload could not be found in the key."
+);
+create_exception!(
+    playfair_cipher,
+    InvalidOptionError,
+    PyValueError,
+    "An invalid option was given when constructing a cipher."
+);
+
+impl From<errors::CharNotInKeyError> for PyErr {
+    fn from(e: errors::CharNotInKeyError) -> Self {
+        CharNotInKeyError::new_err(e.to_string())
+    }
+}
+
+impl From<errors::InvalidOptionError> for PyErr {
+    fn from(e: errors::InvalidOptionError) -> Self {
+        InvalidOptionError::new_err(e.to_string())
+    }
+}
+
+/// The PlayFair cipher, see playfair::PlayFairKey.
+#[pyclass(name = "PlayFairKey", module = "playfair_cipher", frozen)]
+pub struct PyPlayFairKey {
+    cipher: PlayFairKey,
+}
+
+#[pymethods]
+impl PyPlayFairKey {
+    #[new]
+    #[pyo3(signature = (key, padding = None, omit = None, grouping = None))]
+    fn new(
+        key: &str,
+        padding: Option<char>,
+        omit: Option<char>,
+        grouping: Option<usize>,
+    ) -> PyResult<Self> {
+        let mut builder = PlayFairKey::builder().key(key);
+        if let Some(padding) = padding {
+            builder = builder.padding(padding);
+        }
+        if let Some(omit) = omit {
+            builder = builder.omit(omit);
+        }
+        if let Some(grouping) = grouping {
+            builder = builder.grouping(grouping);
+        }
+        Ok(PyPlayFairKey {
+            cipher: builder.build()?,
+        })
+    }
+
+    fn encrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.encrypt(payload)?)
+    }
+
+    fn decrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.decrypt(payload)?)
+    }
+
+    fn __str__(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+#[cfg(feature = "two-square")]
+/// The Two Square cipher, see two_square::TwoSquare.
+#[pyclass(name = "TwoSquare", module = "playfair_cipher", frozen)]
+pub struct PyTwoSquare {
+    cipher: TwoSquare,
+}
+
+#[cfg(feature = "two-square")]
+#[pymethods]
+impl PyTwoSquare {
+    #[new]
+    #[pyo3(signature = (key0, key1, padding = None, omit = None, grouping = None))]
+    fn new(
+        key0: &str,
+        key1: &str,
+        padding: Option<char>,
+        omit: Option<char>,
+        grouping: Option<usize>,
+    ) -> PyResult<Self> {
+        let mut builder = TwoSquare::builder().keys(key0, key1);
+        if let Some(padding) = padding {
+            builder = builder.padding(padding);
+        }
+        if let Some(omit) = omit {
+            builder = builder.omit(omit);
+        }
+        if let Some(grouping) = grouping {
+            builder = builder.grouping(grouping);
+        }
+        Ok(PyTwoSquare {
+            cipher: builder.build()?,
+        })
+    }
+
+    fn encrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.encrypt(payload)?)
+    }
+
+    fn decrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.decrypt(payload)?)
+    }
+
+    fn __str__(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+#[cfg(feature = "four-square")]
+/// The Four Square cipher, see four_square::FourSquare.
+#[pyclass(name = "FourSquare", module = "playfair_cipher", frozen)]
+pub struct PyFourSquare {
+    cipher: FourSquare,
+}
+
+#[cfg(feature = "four-square")]
+#[pymethods]
+impl PyFourSquare {
+    #[new]
+    #[pyo3(signature = (key0, key1, padding = None, omit = None, grouping = None))]
+    fn new(
+        key0: &str,
+        key1: &str,
+        padding: Option<char>,
+        omit: Option<char>,
+        grouping: Option<usize>,
+    ) -> PyResult<Self> {
+        let mut builder = FourSquare::builder().keys(key0, key1);
+        if let Some(padding) = padding {
+            builder = builder.padding(padding);
+        }
+        if let Some(omit) = omit {
+            builder = builder.omit(omit);
+        }
+        if let Some(grouping) = grouping {
+            builder = builder.grouping(grouping);
+        }
+        Ok(PyFourSquare {
+            cipher: builder.build()?,
+        })
+    }
+
+    fn encrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.encrypt(payload)?)
+    }
+
+    fn decrypt(&self, payload: &str) -> PyResult<String> {
+        Ok(self.cipher.decrypt(payload)?)
+    }
+
+    fn __str__(&self) -> String {
+        self.cipher.to_string()
+    }
+}
+
+/// The Python module playfair_cipher.
+#[pymodule]
+#[pyo3(name = "playfair_cipher")]
+fn playfair_cipher(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPlayFairKey>()?;
+    #[cfg(feature = "two-square")]
+    m.add_class::<PyTwoSquare>()?;
+    #[cfg(feature = "four-square")]
+    m.add_class::<PyFourSquare>()?;
+    m.add("CharNotInKeyError", m.py().get_type::<CharNotInKeyError>())?;
+    m.add(
+        "InvalidOptionError",
+        m.py().get_type::<InvalidOptionError>(),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_crypt() {
+        let pfc = PyPlayFairKey::new("playfair example", None, None, Some(5)).unwrap();
+        assert_eq!(pfc.encrypt("hide the gold").unwrap(), "BMODZ BXDNA GE");
+        #[cfg(feature = "two-square")]
+        assert!(PyTwoSquare::new("EXAMPLE", "KEYWORD", Some('1'), None, None).is_err());
+        #[cfg(feature = "four-square")]
+        {
+            let fsq = PyFourSquare::new("EXAMPLE", "KEYWORD", None, None, None).unwrap();
+            assert_eq!(fsq.decrypt(&fsq.encrypt("joe").unwrap()).unwrap(), "IOEX");
+        }
+    }
+
+    #[test]
+    fn test_errors_mapped() {
+        Python::initialize();
+        Python::attach(|py| {
+            let e = PyErr::from(errors::CharNotInKeyError::new(String::from("No T"), 'T'));
+            assert!(e.is_instance_of::<CharNotInKeyError>(py));
+            assert!(e.is_instance_of::<PyValueError>(py));
+            let e = PyErr::from(errors::InvalidOptionError::new(String::from("No 1")));
+            assert!(e.is_instance_of::<InvalidOptionError>(py));
+        });
+    }
+}
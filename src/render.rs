@@ -0,0 +1,118 @@
+//! Rendering of key squares as SVG images, e.g. for worksheets or puzzles.
+//! Only available with the feature `render`.
+//!
+
+use std::fmt::Write;
+
+/// How a square is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Width and height of a cell in pixels.
+    pub cell_size: usize,
+    /// Cells (row * side length + column) to fill with the highlight colour.
+    pub highlight: Vec<usize>,
+    /// Any SVG colour, e.g. "#ffd54f" or "yellow".
+    pub highlight_colour: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            cell_size: 40,
+            highlight: Vec::new(),
+            highlight_colour: String::from("#ffd54f"),
+        }
+    }
+}
+
+/// Renders a square, given row by row, as standalone SVG image. The side
+/// length is derived from the number of letters, so a 5*5 square as well
+/// as a 6*6 one can be drawn.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::playfair::PlayFairKey;
+/// use playfair_cipher::render::{square_svg, RenderOptions};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let options = RenderOptions {
+///     highlight: vec![0, 6],
+///     ..RenderOptions::default()
+/// };
+/// let svg = square_svg(pfc.square(), &options);
+/// assert!(svg.starts_with("<svg"));
+/// assert_eq!(svg.matches("<rect").count(), 25);
+/// assert_eq!(svg.matches("#ffd54f").count(), 2);
+/// ```
+pub fn square_svg(square: &[char], options: &RenderOptions) -> String {
+    let mut side = square.len().isqrt();
+    if side * side < square.len() {
+        side += 1;
+    }
+    let cell_size = options.cell_size.max(1);
+    let size = side * cell_size;
+    let mut svg = String::with_capacity(square.len() * 200);
+    // Writing into a String can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+    for (cell, letter) in square.iter().enumerate() {
+        let (x, y) = ((cell % side) * cell_size, (cell / side) * cell_size);
+        let fill = match options.highlight.contains(&cell) {
+            true => options.highlight_colour.as_str(),
+            false => "white",
+        };
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{x}" y="{y}" width="{cell_size}" height="{cell_size}" fill="{}" stroke="black"/>"#,
+            escape(fill)
+        );
+        let _ = writeln!(
+            svg,
+            r#"  <text x="{}" y="{}" text-anchor="middle" dominant-baseline="central" font-family="monospace" font-size="{}">{}</text>"#,
+            x + cell_size / 2,
+            y + cell_size / 2,
+            cell_size / 2,
+            escape(&letter.to_string())
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Escapes the chars with a meaning in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_square_svg_6x6() {
+        let square: Vec<char> = ('A'..='Z').chain('0'..='9').collect();
+        let svg = square_svg(&square, &RenderOptions::default());
+        assert_eq!(svg.matches("<text").count(), 36);
+        assert!(svg.contains(r#"width="240""#));
+        assert!(!svg.contains("#ffd54f"));
+    }
+
+    #[test]
+    fn test_escape() {
+        let options = RenderOptions {
+            highlight: vec![0],
+            highlight_colour: String::from("\"><script>"),
+            ..RenderOptions::default()
+        };
+        let svg = square_svg(&['<'], &options);
+        assert!(!svg.contains("<script>"));
+        assert!(svg.contains(">&lt;</text>"));
+    }
+}
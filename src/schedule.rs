@@ -0,0 +1,152 @@
+//! Daily keys derived from a master passphrase, the way key lists were
+//! issued historically: every day has its own square, but only the
+//! passphrase has to be shared. The passphrase and the ISO date are hashed
+//! into the seed of a shuffled square. With the feature `chrono` the key of
+//! a chrono::NaiveDate can be looked up directly.
+//!
+//! ```
+//! use playfair_cipher::options::PayloadOptions;
+//! use playfair_cipher::schedule::KeySchedule;
+//!
+//! let schedule = KeySchedule::new("correct horse battery staple", PayloadOptions::default());
+//! let monday = schedule.key_for_iso("2026-10-19").unwrap();
+//! let tuesday = schedule.key_for_iso("2026-10-20").unwrap();
+//! assert_ne!(monday.square(), tuesday.square());
+//! assert_eq!(monday.square(), schedule.key_for_iso("2026-10-19").unwrap().square());
+//! ```
+//!
+
+use crate::crack::Rng;
+use crate::errors::InvalidOptionError;
+use crate::options::PayloadOptions;
+use crate::playfair::PlayFairKey;
+
+/// Derives a key square per date, see the module documentation.
+#[derive(Debug, Clone)]
+pub struct KeySchedule {
+    passphrase: String,
+    options: PayloadOptions,
+}
+
+impl KeySchedule {
+    /// A schedule of the passphrase. The keys take the options given.
+    pub fn new(passphrase: &str, options: PayloadOptions) -> Self {
+        KeySchedule {
+            passphrase: String::from(passphrase),
+            options,
+        }
+    }
+
+    /// The seed the square of the date is shuffled with: FNV-1a of the
+    /// passphrase and the date, so it's the same on every platform and in
+    /// every version.
+    pub fn seed(&self, iso_date: &str) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in self.passphrase.bytes().chain([0]).chain(iso_date.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+
+    /// The key of a date given as YYYY-MM-DD. Only the format is checked,
+    /// not whether the date exists.
+    pub fn key_for_iso(&self, iso_date: &str) -> Result<PlayFairKey, InvalidOptionError> {
+        let valid = iso_date.len() == 10
+            && iso_date.char_indices().all(|(idx, c)| match idx {
+                4 | 7 => c == '-',
+                _ => c.is_ascii_digit(),
+            });
+        if !valid {
+            return Err(InvalidOptionError::new(format!(
+                "Date must be given as YYYY-MM-DD - got '{}'",
+                iso_date
+            )));
+        }
+        let mut square: Vec<char> = self.options.alphabet().collect();
+        Rng::new(self.seed(iso_date)).shuffle(&mut square);
+        let mut cells = ['*'; 25];
+        cells.copy_from_slice(&square);
+        Ok(PlayFairKey::from_square_with_options(cells, self.options))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl KeySchedule {
+    /// The key of the date.
+    ///
+    /// Only available with the feature `chrono`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use playfair_cipher::options::PayloadOptions;
+    /// use playfair_cipher::schedule::KeySchedule;
+    ///
+    /// let schedule = KeySchedule::new("correct horse battery staple", PayloadOptions::default());
+    /// let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+    /// assert_eq!(
+    ///     schedule.key_for(date).square(),
+    ///     schedule.key_for_iso("2026-10-19").unwrap().square()
+    /// );
+    /// ```
+    pub fn key_for(&self, date: chrono::NaiveDate) -> PlayFairKey {
+        use chrono::Datelike;
+
+        let iso_date = format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day());
+        self.key_for_iso(&iso_date)
+            .expect("years 0-9999 are formatted as YYYY-MM-DD")
+    }
+
+    /// The keys of a number of days starting with the date given, e.g. to
+    /// print a key list.
+    ///
+    /// Only available with the feature `chrono`.
+    pub fn key_list(
+        &self,
+        start: chrono::NaiveDate,
+        days: usize,
+    ) -> Vec<(chrono::NaiveDate, PlayFairKey)> {
+        start
+            .iter_days()
+            .take(days)
+            .map(|date| (date, self.key_for(date)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_key_for_iso() {
+        let schedule = KeySchedule::new("passphrase", PayloadOptions::new('Q', 'K', None).unwrap());
+        let key = schedule.key_for_iso("2026-01-01").unwrap();
+        assert!(!key.square().contains(&'K'));
+        assert!(key.square().contains(&'J'));
+        assert_eq!(key.options().padding(), 'Q');
+        let other = KeySchedule::new("passphrasf", *key.options());
+        assert_ne!(
+            other.key_for_iso("2026-01-01").unwrap().square(),
+            key.square()
+        );
+        assert!(schedule.key_for_iso("2026-1-01").is_err());
+        assert!(schedule.key_for_iso("2026/01/01").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_key_list() {
+        let schedule = KeySchedule::new("passphrase", PayloadOptions::default());
+        let start = chrono::NaiveDate::from_ymd_opt(2026, 12, 30).unwrap();
+        let list = schedule.key_list(start, 3);
+        assert_eq!(list.len(), 3);
+        assert_eq!(
+            list[2].1.square(),
+            schedule.key_for_iso("2027-01-01").unwrap().square()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Segmented en- and decryption: the payload is split into segments which
+//! are en- or decrypted independently, the text separating them is kept as
+//! is. This trades some security for readability, e.g. for classroom
+//! exercises where the length of the words should be preserved.
+//!
+//! ```
+//! use playfair_cipher::{cryptable::Cypher, playfair::PlayFairKey, segment::Segmentation};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let crypt = pfc.encrypt_segmented("hide the  gold", Segmentation::Words).unwrap();
+//! assert_eq!(crypt, "BMOD ZBXM  DQAC");
+//! assert_eq!(
+//!     pfc.decrypt_segmented(&crypt, Segmentation::Words).unwrap(),
+//!     "HIDE THEX  GOLD"
+//! );
+//! ```
+//!
+
+use crate::cryptable::{engine, Crypt, Cypher};
+use crate::errors::CharNotInKeyError;
+use crate::structs::CryptModus;
+
+/// How a payload is split into segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segmentation {
+    /// Each whitespace separated word is a segment, the whitespace is kept.
+    /// The cipher text of a word is never grouped, a group would split it.
+    Words,
+    /// Each sentence is a segment and padded on its own, so a long document
+    /// decrypts into recognizable sentences and a garbled letter doesn't
+    /// shift the digrams of the sentences after it. A sentence ends with a
+    /// run of ".", "!" or "?" followed by whitespace or the end of the text,
+    /// the run and the whitespace are kept.
+    Sentences,
+    /// Each line is a segment and padded on its own, so logs, poems and
+    /// letters keep their line breaks. Empty lines are kept as well.
+    Lines,
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?')
+}
+
+// The length of the segment ending at the index given, or spanning the
+// whole text, and the end of the run of separator chars following it.
+fn separated(text: &str, end: Option<usize>, is_separator: fn(char) -> bool) -> (usize, usize) {
+    let end = end.unwrap_or(text.len());
+    let separator_length = text[end..]
+        .find(|c: char| !is_separator(c))
+        .unwrap_or(text.len() - end);
+    (end, end + separator_length)
+}
+
+// Index of the terminator ending the first sentence of the text.
+fn sentence_end(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        if !is_terminator(c) {
+            continue;
+        }
+        while chars.next_if(|(_, c)| is_terminator(*c)).is_some() {}
+        match chars.peek() {
+            Some((_, c)) if !c.is_whitespace() => continue,
+            _ => return Some(idx),
+        }
+    }
+    None
+}
+
+impl Segmentation {
+    // Splits the text into segments, each with the separator following it.
+    fn split<'a>(&self, text: &'a str) -> Vec<(&'a str, &'a str)> {
+        let mut segments = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let (segment_length, separator_end) = match self {
+                Segmentation::Words => {
+                    separated(rest, rest.find(char::is_whitespace), |c| c.is_whitespace())
+                }
+                Segmentation::Sentences => separated(rest, sentence_end(rest), |c| {
+                    c.is_whitespace() || is_terminator(c)
+                }),
+                Segmentation::Lines => match rest.find('\n') {
+                    Some(idx) if rest[..idx].ends_with('\r') => (idx - 1, idx + 1),
+                    Some(idx) => (idx, idx + 1),
+                    None => (rest.len(), rest.len()),
+                },
+            };
+            segments.push((
+                &rest[..segment_length],
+                &rest[segment_length..separator_end],
+            ));
+            rest = &rest[separator_end..];
+        }
+        segments
+    }
+
+    // Whether the cipher text of a segment must not contain spaces.
+    fn ungrouped(&self) -> bool {
+        matches!(self, Segmentation::Words)
+    }
+}
+
+// En- or decrypts the segments of the text. The positions of an error are
+// the ones within the whole text.
+pub(crate) fn crypt_segmented(
+    cipher: &(impl Cypher + ?Sized),
+    text: &str,
+    segmentation: Segmentation,
+    modus: &CryptModus,
+) -> Result<String, CharNotInKeyError> {
+    let engine = engine(cipher);
+    let options = engine.payload_options();
+    let mut result = String::with_capacity(text.len() + text.len() / 2);
+    // Chars of the text and of its normalized form before the segment
+    let (mut original_offset, mut offset) = (0, 0);
+    for (segment, separator) in segmentation.split(text) {
+        let crypt = match modus {
+            CryptModus::Encrypt => cipher.encrypt(segment),
+            CryptModus::Decrypt => cipher.decrypt(segment),
+        }
+        .map_err(|e| match (e.position, e.original_position) {
+            (Some(position), Some(original_position)) => {
+                e.at(offset + position, original_offset + original_position)
+            }
+            _ => e,
+        })?;
+        match segmentation.ungrouped() {
+            true => result.extend(crypt.chars().filter(|c| *c != ' ')),
+            false => result.push_str(&crypt),
+        }
+        result.push_str(separator);
+        original_offset += segment.chars().count() + separator.chars().count();
+        offset += crate::normalize(segment, options).chars().count();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            Segmentation::Words.split(" a bc\n\td "),
+            [("", " "), ("a", " "), ("bc", "\n\t"), ("d", " ")]
+        );
+        assert!(Segmentation::Words.split("").is_empty());
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            Segmentation::Sentences.split("Pi is 3.14. Really?! Yes\n\nNo."),
+            [("Pi is 3.14", ". "), ("Really", "?! "), ("Yes\n\nNo", ".")]
+        );
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(
+            Segmentation::Lines.split("Dear Bob,\r\n\nbye\n"),
+            [("Dear Bob,", "\r\n"), ("", "\n"), ("bye", "\n")]
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let pfc = PlayFairKey::new("playfair example");
+        let poem = "Roses are red\n\nviolets are blue\n";
+        let crypt = pfc.encrypt_segmented(poem, Segmentation::Lines).unwrap();
+        assert_eq!(crypt.lines().count(), 3);
+        assert!(crypt.lines().nth(1).unwrap().is_empty());
+        assert_eq!(
+            pfc.decrypt_segmented(&crypt, Segmentation::Lines).unwrap(),
+            "ROSESAREREDX\n\nVIOLETSAREBLUE\n"
+        );
+    }
+
+    #[test]
+    fn test_sentences() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let crypt = pfc
+            .encrypt_segmented("Hide the gold. Now!", Segmentation::Sentences)
+            .unwrap();
+        assert_eq!(crypt, "BMODZ BXDNA GE. OQYG!");
+        // A lost letter only garbles its own sentence
+        let garbled = crypt.replacen('M', "", 1);
+        let plain = pfc
+            .decrypt_segmented(&garbled, Segmentation::Sentences)
+            .unwrap();
+        assert!(plain.ends_with(". NOWX!"));
+    }
+
+    #[test]
+    fn test_words_grouped() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(2)
+            .build()
+            .unwrap();
+        let crypt = pfc
+            .encrypt_segmented("hidden treasure 42", Segmentation::Words)
+            .unwrap();
+        assert_eq!(crypt.split(' ').count(), 3);
+        assert!(crypt.ends_with(' '));
+        let plain = pfc.decrypt_segmented(&crypt, Segmentation::Words).unwrap();
+        assert_eq!(plain, "HIDXDENX TREASURE ");
+    }
+
+    #[test]
+    fn test_error_position() {
+        let mut pfc = PlayFairKey::new("playfair example");
+        pfc.key_map.remove(&'T');
+        let e = pfc
+            .decrypt_segmented("BMOD, ZBTX", Segmentation::Words)
+            .unwrap_err();
+        assert_eq!(e.character(), 'T');
+        assert_eq!(e.original_position(), Some(8));
+        assert_eq!(e.position(), Some(6));
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(any(feature = "two-square", feature = "four-square"))]
+use crate::errors::InvalidOptionError;
+use crate::structs::Payload;
+
+// Seriation as used by several ACA cipher types: the payload is written in
+// blocks of two lines of `period` letters each and the digrams are taken
+// vertically, e.g. HELPMEOB with period 4 becomes
+//
+//   H E L P
+//   M E O B
+//
+// with the digrams HM EE LO PB. The last block may be shorter, its lines
+// are half of the letters left each. The cipher text is written back the
+// same way, line by line. Doubled letters are not split, the ciphers
+// seriation is offered for don't need it, and a padding letter completing
+// the last digram is added to the end of the last block.
+
+#[cfg(any(feature = "two-square", feature = "four-square"))]
+pub(crate) fn validate_period(period: Option<usize>) -> Result<(), InvalidOptionError> {
+    match period {
+        Some(0) => Err(InvalidOptionError::new(String::from(
+            "Seriation period must be greater than 0",
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Rearranges the letters so each vertical pair of a block is adjacent.
+pub(crate) fn to_pairs<T: Copy>(letters: &[T], period: usize) -> Vec<T> {
+    let mut pairs = Vec::with_capacity(letters.len());
+    for block in letters.chunks(2 * period) {
+        let (top, bottom) = block.split_at(block.len() / 2);
+        for (a, b) in top.iter().zip(bottom) {
+            pairs.push(*a);
+            pairs.push(*b);
+        }
+    }
+    pairs
+}
+
+// Reverses to_pairs.
+pub(crate) fn from_pairs<T: Copy>(pairs: &[T], period: usize) -> Vec<T> {
+    let mut letters = Vec::with_capacity(pairs.len());
+    for block in pairs.chunks(2 * period) {
+        letters.extend(block.iter().step_by(2));
+        letters.extend(block.iter().skip(1).step_by(2));
+    }
+    letters
+}
+
+// Index in the result of the letter taken at the index given from the
+// pairs of `paired` letters, see to_pairs.
+pub(crate) fn unpaired_index(index: usize, period: usize, paired: usize) -> usize {
+    let start = index - index % (2 * period);
+    let half = (2 * period).min(paired - start) / 2;
+    let pair = (index - start) / 2;
+    match index % 2 {
+        0 => start + pair,
+        _ => start + half + pair,
+    }
+}
+
+// Member of its digram, 0 or 1, the letter at the index given of a result
+// of `length` letters is, so it can be looked up in the right square. A
+// trailing letter without partner counts as first member.
+pub(crate) fn member(index: usize, period: Option<usize>, length: usize) -> usize {
+    let paired = length - length % 2;
+    match period {
+        _ if index >= paired => 0,
+        Some(period) => {
+            let start = index - index % (2 * period);
+            let half = (2 * period).min(paired - start) / 2;
+            usize::from(index - start >= half)
+        }
+        None => index % 2,
+    }
+}
+
+// Puts the results of the digrams of a payload in the order of the result.
+// If the payload is seriated the results of a block are held back until the
+// block is complete and then released line by line, otherwise each result
+// is released as it is.
+pub(crate) struct Unpairing {
+    period: Option<usize>,
+    // Number of letters taken in pairs
+    paired: usize,
+    taken: usize,
+    letters: Vec<char>,
+    released: bool,
+}
+
+impl Unpairing {
+    pub(crate) fn new(payload: &Payload) -> Self {
+        let period = payload.options.seriation;
+        Unpairing {
+            period,
+            paired: payload.order.len(),
+            taken: 0,
+            letters: Vec::with_capacity(period.map_or(2, |period| 2 * period)),
+            released: false,
+        }
+    }
+
+    // Takes the result of the next digram, see released.
+    pub(crate) fn push(&mut self, a: char, b: char) {
+        if self.released {
+            self.letters.clear();
+        }
+        self.letters.push(a);
+        self.letters.push(b);
+        self.taken += 2;
+        self.released = match self.period {
+            Some(period) => self.taken.is_multiple_of(2 * period) || self.taken >= self.paired,
+            None => true,
+        };
+        if let (true, Some(period)) = (self.released, self.period) {
+            self.letters = from_pairs(&self.letters, period);
+        }
+    }
+
+    // The letters released by the last digram taken.
+    pub(crate) fn released(&self) -> &[char] {
+        match self.released {
+            true => &self.letters,
+            false => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let letters: Vec<char> = "HELPMEOBIWAN".chars().collect();
+        let pairs: String = to_pairs(&letters, 4).into_iter().collect();
+        // The last block of 4 letters has lines of 2
+        assert_eq!(pairs, "HMEELOPBIAWN");
+        let pairs: Vec<char> = pairs.chars().collect();
+        assert_eq!(from_pairs(&pairs, 4), letters);
+        let unpaired: Vec<usize> = (0..12).map(|idx| unpaired_index(idx, 4, 12)).collect();
+        assert_eq!(unpaired, [0, 4, 1, 5, 2, 6, 3, 7, 8, 10, 9, 11]);
+        let members: Vec<usize> = (0..13).map(|idx| member(idx, Some(4), 13)).collect();
+        assert_eq!(members, [0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 1, 1, 0]);
+        let members: Vec<usize> = (0..3).map(|idx| member(idx, None, 3)).collect();
+        assert_eq!(members, [0, 1, 0]);
+    }
+}
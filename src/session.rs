@@ -0,0 +1,137 @@
+//! Session objects for en- or decrypting many small messages with the same
+//! cipher. A session owns the buffers for the normalized payload and the
+//! result, so they are allocated once and reused for every message.
+//!
+
+use crate::{
+    cryptable::{engine, Crypt, Cypher},
+    errors::CharNotInKeyError,
+    structs::{CryptModus, Payload},
+};
+
+struct Session<'a, C: Cypher> {
+    cipher: &'a C,
+    payload: Payload<'static>,
+    output: String,
+}
+
+impl<'a, C: Cypher> Session<'a, C> {
+    fn new(cipher: &'a C) -> Self {
+        Session {
+            cipher,
+            payload: Payload::new("", engine(cipher).payload_options()),
+            output: String::new(),
+        }
+    }
+
+    fn process(&mut self, payload: &str, modus: &CryptModus) -> Result<&str, CharNotInKeyError> {
+        self.payload.fill(payload);
+        self.output.clear();
+        self.output.reserve(self.payload.output_capacity(modus));
+        let output = &mut self.output;
+        self.payload.crypt_payload_with(
+            &engine(self.cipher),
+            modus,
+            |chunk| -> Result<(), CharNotInKeyError> {
+                output.push_str(chunk);
+                Ok(())
+            },
+        )?;
+        Ok(&self.output)
+    }
+}
+
+/// Encrypts message after message with the borrowed cipher.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{playfair::PlayFairKey, session::Encryptor};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut encryptor = Encryptor::new(&pfc);
+/// assert_eq!(encryptor.process("hide the gold").unwrap(), "BMODZBXDNAGE");
+/// assert_eq!(encryptor.process("in the tree stump").unwrap(), "RKZBIVEXMOUVIF");
+/// ```
+pub struct Encryptor<'a, C: Cypher> {
+    session: Session<'a, C>,
+}
+
+impl<'a, C: Cypher> Encryptor<'a, C> {
+    pub fn new(cipher: &'a C) -> Self {
+        Encryptor {
+            session: Session::new(cipher),
+        }
+    }
+
+    /// Encrypts the payload. The result is valid until the next call.
+    pub fn process(&mut self, payload: &str) -> Result<&str, CharNotInKeyError> {
+        self.session.process(payload, &CryptModus::Encrypt)
+    }
+}
+
+/// Decrypts message after message with the borrowed cipher.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::{playfair::PlayFairKey, session::Decryptor};
+///
+/// let pfc = PlayFairKey::new("playfair example");
+/// let mut decryptor = Decryptor::new(&pfc);
+/// assert_eq!(decryptor.process("BMODZBXDNAGE").unwrap(), "HIDETHEGOLDX");
+/// assert_eq!(decryptor.process("RKZBIVEXMOUVIF").unwrap(), "INTHETREESTUMP");
+/// ```
+pub struct Decryptor<'a, C: Cypher> {
+    session: Session<'a, C>,
+}
+
+impl<'a, C: Cypher> Decryptor<'a, C> {
+    pub fn new(cipher: &'a C) -> Self {
+        Decryptor {
+            session: Session::new(cipher),
+        }
+    }
+
+    /// Decrypts the payload. The result is valid until the next call.
+    pub fn process(&mut self, payload: &str) -> Result<&str, CharNotInKeyError> {
+        self.session.process(payload, &CryptModus::Decrypt)
+    }
+}
+
+#[cfg(all(test, feature = "two-square", feature = "four-square"))]
+mod tests {
+
+    use super::*;
+    use crate::{four_square::FourSquare, two_square::TwoSquare};
+
+    #[test]
+    fn test_session_matches_cypher() {
+        let fsq = FourSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let tsq = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let mut fsq_encryptor = Encryptor::new(&fsq);
+        let mut fsq_decryptor = Decryptor::new(&fsq);
+        let mut tsq_encryptor = Encryptor::new(&tsq);
+        for payload in [
+            "joe",
+            "The quick red fox jumps over the lazy brown dog.",
+            "",
+            "a",
+        ] {
+            let crypt = fsq.encrypt(payload).unwrap();
+            assert_eq!(fsq_encryptor.process(payload).unwrap(), crypt);
+            assert_eq!(
+                fsq_decryptor.process(&crypt).unwrap(),
+                fsq.decrypt(&crypt).unwrap()
+            );
+            assert_eq!(
+                tsq_encryptor.process(payload).unwrap(),
+                tsq.encrypt(payload).unwrap()
+            );
+        }
+    }
+}
@@ -1,8 +1,16 @@
-use crate::cryptable::Crypt;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
 
-// For each character from the key, its position within the imaged square stored in
-// this struct.
-// Having this square
+use crate::{
+    cryptable::Crypt,
+    errors::{CharNotInKeyError, WriteError},
+    options::{FinalDigram, PayloadOptions},
+    seriation::{self, Unpairing},
+};
+
+// For each character from the key, its cell within the imaged square is stored
+// in this struct. The cell is row * 5 + column, e.g. having this square
 //
 //        columns
 //        0 1 2 3 4
@@ -11,10 +19,57 @@ use crate::cryptable::Crypt;
 //  row 2 _ _ _ _ _
 //  row 3 _ _ _ _ _
 //  row 4 _ _ _ _ _
-#[derive(Debug)]
-pub(crate) struct SquarePosition {
-    pub row: u8,
-    pub column: u8,
+//
+// the char in row 2, column 3 is in cell 13. The cells are indexed by
+// `c as u8 - b'A'`, so a lookup is just an array access.
+#[derive(Debug, Clone)]
+pub(crate) struct KeyMap {
+    cells: [u8; 26],
+}
+
+const NOT_IN_KEY: u8 = u8::MAX;
+
+impl KeyMap {
+    pub(crate) fn new() -> Self {
+        KeyMap {
+            cells: [NOT_IN_KEY; 26],
+        }
+    }
+
+    fn index(c: char) -> Option<usize> {
+        match c.is_ascii_uppercase() {
+            true => Some((c as u8 - b'A') as usize),
+            false => None,
+        }
+    }
+
+    pub(crate) fn get(&self, c: &char) -> Option<u8> {
+        match KeyMap::index(*c) {
+            Some(idx) if self.cells[idx] != NOT_IN_KEY => Some(self.cells[idx]),
+            _ => None,
+        }
+    }
+
+    // Chars other than A-Z can't be looked up, so they are not stored.
+    pub(crate) fn insert(&mut self, c: char, cell: u8) {
+        if let Some(idx) = KeyMap::index(c) {
+            self.cells[idx] = cell;
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn remove(&mut self, c: &char) -> Option<u8> {
+        let cell = self.get(c);
+        if let Some(idx) = KeyMap::index(*c) {
+            self.cells[idx] = NOT_IN_KEY;
+        }
+        cell
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.cells.iter().filter(|c| **c != NOT_IN_KEY).count()
+    }
 }
 
 pub(crate) struct CryptResult {
@@ -22,9 +77,29 @@ pub(crate) struct CryptResult {
     pub b: char,
 }
 
-pub(crate) struct Payload {
-    pub payload: String,
+// Where a letter of a digram comes from: its index in the normalized payload
+// and the index of the char in the original input it was derived from. None
+// for a padding letter inserted.
+pub(crate) type Origin = Option<(usize, usize)>;
+
+pub(crate) struct Payload<'a> {
+    // Borrowed if the input was already normalized.
+    pub payload: Cow<'a, str>,
     pub counter: usize,
+    // For each character of the normalized payload the index (counted in chars)
+    // of the character in the original input it was derived from. Empty if
+    // the payload is borrowed - each character then originates from the
+    // same index.
+    pub origins: Vec<usize>,
+    // For a seriated payload the indices of the letters in the order they're
+    // paired in, the index past the payload stands for the padding letter.
+    // Empty otherwise.
+    pub order: Vec<usize>,
+    // Padding letters inserted by next_digram so far: the fillers splitting
+    // doubled letters and the one completing the last digram.
+    pub fillers: usize,
+    pub padded: bool,
+    pub options: PayloadOptions,
 }
 
 #[derive(PartialEq)]
@@ -33,78 +108,438 @@ pub(crate) enum CryptModus {
     Decrypt,
 }
 
-impl Payload {
-    pub(crate) fn new(payload: &str) -> Self {
-        let mut counter: usize = 0;
-        let mut payload_cleared = String::with_capacity(payload.len());
-        let payload_uc = payload.to_uppercase();
-        while counter < payload_uc.len() {
-            let character = &payload_uc[counter..counter + 1];
-            if character == "J" {
-                payload_cleared += "I";
-            } else if ("A"..="Z").contains(&character) {
-                payload_cleared += character;
-            }
-            counter += 1;
+impl<'a> Payload<'a> {
+    pub(crate) fn new(payload: &'a str, options: &PayloadOptions) -> Self {
+        if Payload::is_normalized(payload, options) {
+            // Nothing to clear off, e.g. when chaining ciphers.
+            let mut payload_iter = Payload {
+                payload: Cow::Borrowed(payload),
+                counter: 0,
+                origins: Vec::new(),
+                order: Vec::new(),
+                fillers: 0,
+                padded: false,
+                options: *options,
+            };
+            payload_iter.seriate();
+            return payload_iter;
         }
-        Payload {
-            payload: payload_cleared,
+        let mut payload_iter = Payload {
+            payload: Cow::Owned(String::with_capacity(payload.len())),
             counter: 0,
+            origins: Vec::with_capacity(payload.len()),
+            order: Vec::new(),
+            fillers: 0,
+            padded: false,
+            options: *options,
+        };
+        payload_iter.fill(payload);
+        payload_iter
+    }
+
+    // True if the payload consists of the letters of the square only.
+    fn is_normalized(payload: &str, options: &PayloadOptions) -> bool {
+        payload
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() && b as char != options.omit)
+    }
+
+    // Replaces the content by the normalized payload, reusing the buffers.
+    pub(crate) fn fill(&mut self, payload: &str) {
+        let mut payload_cleared = std::mem::take(&mut self.payload).into_owned();
+        payload_cleared.clear();
+        self.origins.clear();
+        self.counter = 0;
+        self.fillers = 0;
+        self.padded = false;
+        for (original_idx, original_char) in payload.chars().enumerate() {
+            if original_char.is_ascii() {
+                self.push(
+                    &mut payload_cleared,
+                    original_char.to_ascii_uppercase(),
+                    original_idx,
+                );
+            } else {
+                // Uppercasing a single char may yield more than one char, e.g. 'ß' -> "SS"
+                for character in original_char.to_uppercase() {
+                    self.push(&mut payload_cleared, character, original_idx);
+                }
+            }
+        }
+        self.payload = Cow::Owned(payload_cleared);
+        self.seriate();
+    }
+
+    // Takes the order the letters are paired in if the payload is seriated.
+    fn seriate(&mut self) {
+        self.order.clear();
+        if let Some(period) = self.options.seriation {
+            let length = self.payload.len();
+            let paired = match (length % 2, self.options.final_digram) {
+                (0, _) => length,
+                (_, FinalDigram::Pad) => length + 1,
+                _ => length - 1,
+            };
+            let letters: Vec<usize> = (0..paired).collect();
+            self.order = seriation::to_pairs(&letters, period);
+        }
+    }
+
+    fn push(&mut self, payload_cleared: &mut String, character: char, original_idx: usize) {
+        if !character.is_ascii_uppercase() {
+            return;
+        }
+        if let Some(character) = self.options.substitute(character) {
+            payload_cleared.push(character);
+            self.origins.push(original_idx);
+        }
+    }
+
+    // Index in the original input the character at the position of the
+    // normalized payload originates from.
+    pub(crate) fn origin(&self, position: usize) -> usize {
+        match self.payload {
+            Cow::Borrowed(_) => position,
+            Cow::Owned(_) => self.origins[position],
+        }
+    }
+
+    // Number of chars of the original input the normalized payload is
+    // derived from, the others were dropped.
+    pub(crate) fn derived(&self) -> usize {
+        match self.payload {
+            Cow::Borrowed(payload) => payload.len(),
+            // Chars substituted by several letters, like ß, occur repeatedly
+            Cow::Owned(_) => self.origins.chunk_by(|a, b| a == b).count(),
         }
     }
+
     pub(crate) fn crypt_payload(
         &mut self,
         cipher: &impl Crypt,
         modus: &crate::structs::CryptModus,
     ) -> Result<String, crate::errors::CharNotInKeyError> {
-        let mut payload_encrypted = String::new();
+        let mut payload_encrypted = String::with_capacity(self.output_capacity(modus));
+        self.crypt_payload_with(cipher, modus, |chunk| {
+            payload_encrypted.push_str(chunk);
+            Ok(())
+        })?;
+        Ok(payload_encrypted)
+    }
+
+    // Expected length of the result: the payload rounded up to an even length
+    // plus the spaces between groups. Fillers for doubled letters aren't
+    // known up front, the buffer grows if there are any.
+    pub(crate) fn output_capacity(&self, modus: &CryptModus) -> usize {
+        let length = self.payload.len() + self.payload.len() % 2;
+        match (modus, self.options.grouping) {
+            (CryptModus::Encrypt, Some(g)) => length + length / g,
+            _ => length,
+        }
+    }
+
+    pub(crate) fn crypt_payload_to_writer(
+        &mut self,
+        cipher: &impl Crypt,
+        modus: &CryptModus,
+        writer: &mut dyn fmt::Write,
+    ) -> Result<(), WriteError> {
+        self.crypt_payload_with(cipher, modus, |chunk| {
+            writer.write_str(chunk).map_err(WriteError::from)
+        })
+    }
 
+    // Runs the payload through the cipher, handing the result of each digram
+    // to the sink, preceded by the space starting a new group. Grouping is
+    // applied when encrypting. The result of a seriated payload is handed
+    // out two letters at a time once their block is complete.
+    pub(crate) fn crypt_payload_with<E: From<CharNotInKeyError>>(
+        &mut self,
+        cipher: &impl Crypt,
+        modus: &CryptModus,
+        mut sink: impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let grouping = match modus {
+            CryptModus::Encrypt => self.options.grouping,
+            CryptModus::Decrypt => None,
+        };
+        let mut written: usize = 0;
+        let mut chunk = String::with_capacity(4);
+        let mut push = |chunk: &mut String, c: char| {
+            if let Some(g) = grouping {
+                if written > 0 && written.is_multiple_of(g) {
+                    chunk.push(' ');
+                }
+            }
+            chunk.push(c);
+            written += 1;
+        };
+        let mut unpairing = Unpairing::new(self);
+        let lone =
+            self.crypt_digrams_with(cipher, modus, |_, _, digram_crypt| -> Result<(), E> {
+                unpairing.push(digram_crypt.a, digram_crypt.b);
+                for letters in unpairing.released().chunks(2) {
+                    chunk.clear();
+                    for c in letters {
+                        push(&mut chunk, *c);
+                    }
+                    sink(&chunk)?;
+                }
+                Ok(())
+            })?;
+        match lone {
+            Some(c) => {
+                chunk.clear();
+                push(&mut chunk, c);
+                sink(&chunk)
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Runs the payload through the cipher, handing any digram together with
+    // the origins of its letters and its result to the sink. Returns the
+    // last letter of a payload of odd length if it's passed through, fails
+    // on it in strict mode.
+    pub(crate) fn crypt_digrams_with<E: From<CharNotInKeyError>>(
+        &mut self,
+        cipher: &(impl Crypt + ?Sized),
+        modus: &CryptModus,
+        mut sink: impl FnMut([char; 2], [Origin; 2], CryptResult) -> Result<(), E>,
+    ) -> Result<Option<char>, E> {
         loop {
-            let digram = self.next();
-            let [a, b] = match digram {
-                Some(d) => d,
+            let digram_start = self.counter;
+            let [a, b] = match self.next_digram(modus) {
+                Some(Step::Digram(digram)) => digram,
+                Some(Step::Lone(c)) => return self.lone(c, digram_start).map_err(E::from),
                 None => break,
             };
+            let origins = self.origins(digram_start);
             match cipher.crypt(a, b, modus) {
-                Ok(digram_crypt) => {
-                    payload_encrypted += &String::from(digram_crypt.a);
-                    payload_encrypted += &String::from(digram_crypt.b);
-                }
-                Err(e) => return Err(e),
+                Ok(digram_crypt) => sink([a, b], origins, digram_crypt)?,
+                Err(e) => return Err(self.locate(e, a, digram_start).into()),
             };
         }
-        Ok(payload_encrypted)
+        Ok(None)
     }
-}
 
-impl Iterator for Payload {
-    type Item = [char; 2];
+    // The origins of the letters of the digram starting at digram_start, once
+    // next_digram took it. The second member is only taken from the payload
+    // if two characters were consumed - otherwise it's the filler.
+    pub(crate) fn origins(&self, digram_start: usize) -> [Origin; 2] {
+        if self.options.seriation.is_some() {
+            return [digram_start, digram_start + 1].map(|idx| {
+                let position = self.order[idx];
+                (position < self.payload.len()).then(|| (position, self.origin(position)))
+            });
+        }
+        [
+            Some((digram_start, self.origin(digram_start))),
+            match self.counter.min(self.payload.len()) - digram_start == 2 {
+                true => Some((digram_start + 1, self.origin(digram_start + 1))),
+                false => None,
+            },
+        ]
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.counter < self.payload.len() {
-            let first_member = &self.payload[self.counter..self.counter + 1];
-            // do not overrun string bounderies.
-            let second_member = match self.counter + 2 <= self.payload.len() {
-                true => &self.payload[self.counter + 1..self.counter + 2],
-                false => "X",
-            };
+    // Attaches the position of the letter not found in the key to the error
+    // raised on the digram starting at digram_start with the letter a.
+    pub(crate) fn locate(
+        &self,
+        e: CharNotInKeyError,
+        a: char,
+        digram_start: usize,
+    ) -> CharNotInKeyError {
+        let [first, second] = self.origins(digram_start);
+        let (position, original_position) = match (first, second) {
+            (_, Some(second)) if e.character != a => second,
+            (Some(first), _) => first,
+            _ => (digram_start, self.origin(digram_start)),
+        };
+        e.at(position, original_position)
+    }
 
-            //&payload[counter + 1..counter + 2];
-            if first_member == second_member {
-                // first and second are the same, so stuff it
-                let char_list: Vec<char> = first_member.chars().collect();
+    // Applies the final digram policy to the last letter of a payload of
+    // odd length at the position given.
+    pub(crate) fn lone(&self, c: char, position: usize) -> Result<Option<char>, CharNotInKeyError> {
+        match self.options.final_digram {
+            FinalDigram::Strict => Err(unpaired(c).at(position, self.origin(position))),
+            _ => Ok(Some(c)),
+        }
+    }
+}
 
-                self.counter += 1;
-                Some([char_list[0], 'X'])
-            } else {
-                let char_list_first: Vec<char> = first_member.chars().collect();
-                let char_list_second: Vec<char> = second_member.chars().collect();
+// Error of the strict final digram policy for the last letter of a payload of
+// odd length.
+pub(crate) fn unpaired(c: char) -> CharNotInKeyError {
+    CharNotInKeyError::unpaired(
+        format!(
+            "'{}' is left without partner, the payload must consist of whole digrams",
+            c
+        ),
+        c,
+    )
+}
+
+// A step through the payload: a digram, or the last letter of a payload of
+// odd length if the final digram policy doesn't pad it.
+pub(crate) enum Step {
+    Digram([char; 2]),
+    Lone(char),
+}
+
+impl Payload<'_> {
+    // The next digram to en- or decrypt. Doubled letters are only split when
+    // encrypting: cipher text is taken pair by pair, as Two Square and Four
+    // Square may well encrypt a digram to a doubled letter.
+    pub(crate) fn next_digram(&mut self, modus: &CryptModus) -> Option<Step> {
+        if self.options.seriation.is_some() {
+            return self.next_seriated();
+        }
+        // The normalized payload consists of the chars A-Z only, so any byte
+        // is a char.
+        let payload = self.payload.as_bytes();
+        if self.counter >= payload.len() {
+            return None;
+        }
+        let first_member = payload[self.counter] as char;
+        // do not overrun string bounderies.
+        if self.counter + 1 == payload.len() {
+            self.counter += 1;
+            return Some(match self.options.final_digram {
+                FinalDigram::Pad => {
+                    self.padded = true;
+                    Step::Digram([first_member, self.options.padding])
+                }
+                FinalDigram::Strict | FinalDigram::PassThrough => Step::Lone(first_member),
+            });
+        }
+        let second_member = payload[self.counter + 1] as char;
+        if first_member == second_member && *modus == CryptModus::Encrypt {
+            // first and second are the same, so stuff it
+            self.counter += 1;
+            self.fillers += 1;
+            Some(Step::Digram([first_member, self.options.padding]))
+        } else {
+            self.counter += 2;
+            Some(Step::Digram([first_member, second_member]))
+        }
+    }
 
+    // The next digram of a seriated payload, taken in the order of the
+    // pairs. Doubled letters are never split.
+    fn next_seriated(&mut self) -> Option<Step> {
+        let payload = self.payload.as_bytes();
+        let letter = |idx: usize| {
+            payload
+                .get(idx)
+                .map_or(self.options.padding, |c| *c as char)
+        };
+        match self.order.get(self.counter..self.counter + 2) {
+            Some(&[a, b]) => {
                 self.counter += 2;
-                Some([char_list_first[0], char_list_second[0]])
+                self.padded |= b == payload.len();
+                Some(Step::Digram([letter(a), letter(b)]))
             }
-        } else {
-            None
+            _ if self.counter < payload.len() => {
+                self.counter = payload.len();
+                Some(Step::Lone(letter(payload.len() - 1)))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Runs the payload through the cipher lazily, yielding the result char by
+// char with the spaces between groups. Stops after the first error.
+pub(crate) struct CryptChars<'a, C> {
+    cipher: C,
+    payload: Payload<'a>,
+    modus: CryptModus,
+    grouping: Option<usize>,
+    written: usize,
+    unpairing: Unpairing,
+    // The chars of the last digram not yielded yet
+    pending: VecDeque<char>,
+    done: bool,
+}
+
+impl<'a, C: Crypt> CryptChars<'a, C> {
+    pub(crate) fn new(cipher: C, payload: &'a str, modus: CryptModus) -> Self {
+        let options = cipher.payload_options();
+        let payload = Payload::new(payload, options);
+        CryptChars {
+            unpairing: Unpairing::new(&payload),
+            payload,
+            grouping: match modus {
+                CryptModus::Encrypt => options.grouping,
+                CryptModus::Decrypt => None,
+            },
+            cipher,
+            modus,
+            written: 0,
+            pending: VecDeque::with_capacity(4),
+            done: false,
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if let Some(g) = self.grouping {
+            if self.written > 0 && self.written.is_multiple_of(g) {
+                self.pending.push_back(' ');
+            }
+        }
+        self.pending.push_back(c);
+        self.written += 1;
+    }
+}
+
+impl<C: Crypt> Iterator for CryptChars<'_, C> {
+    type Item = Result<char, CharNotInKeyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.done {
+                return None;
+            }
+            let digram_start = self.payload.counter;
+            match self.payload.next_digram(&self.modus) {
+                None => self.done = true,
+                Some(Step::Lone(c)) => {
+                    self.done = true;
+                    match self.payload.lone(c, digram_start) {
+                        Ok(Some(c)) => self.push(c),
+                        Ok(None) => {}
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                Some(Step::Digram([a, b])) => match self.cipher.crypt(a, b, &self.modus) {
+                    Ok(digram_crypt) => {
+                        self.unpairing.push(digram_crypt.a, digram_crypt.b);
+                        for idx in 0..self.unpairing.released().len() {
+                            self.push(self.unpairing.released()[idx]);
+                        }
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(self.payload.locate(e, a, digram_start)));
+                    }
+                },
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+// Yields the digrams to encrypt, a last letter which isn't padded is left
+// out.
+impl Iterator for Payload<'_> {
+    type Item = [char; 2];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_digram(&CryptModus::Encrypt)? {
+            Step::Digram(digram) => Some(digram),
+            Step::Lone(_) => None,
         }
     }
 }
@@ -0,0 +1,162 @@
+//! Published test vectors of the ciphers, so ports and reimplementations
+//! can check their conformance. Only available with the feature
+//! `test-vectors`.
+//!
+//! ```
+//! use playfair_cipher::test_vectors::{run_all, VECTORS};
+//!
+//! let outcomes = run_all();
+//! assert_eq!(outcomes.len(), VECTORS.len());
+//! assert!(outcomes.iter().all(|o| o.passed()));
+//! ```
+//!
+
+use crate::config::{Algorithm, CipherConfig};
+
+/// A plain text with its cipher text, the cipher and the settings used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestVector {
+    pub name: &'static str,
+    /// Where the vector is published.
+    pub source: &'static str,
+    pub algorithm: Algorithm,
+    pub keys: &'static [&'static str],
+    pub padding: char,
+    pub omit: char,
+    pub plain_text: &'static str,
+    pub cipher_text: &'static str,
+    /// The cipher text decrypted: the normalized plain text with doubled
+    /// letters split and padded.
+    pub decrypted: &'static str,
+}
+
+/// All test vectors.
+pub const VECTORS: [TestVector; 5] = [
+    TestVector {
+        name: "playfair-wikipedia",
+        source: "https://en.wikipedia.org/wiki/Playfair_cipher",
+        algorithm: Algorithm::PlayFair,
+        keys: &["playfair example"],
+        padding: 'X',
+        omit: 'J',
+        plain_text: "Hide the gold in the tree stump",
+        cipher_text: "BMODZBXDNABEKUDMUIXMMOUVIF",
+        decrypted: "HIDETHEGOLDINTHETREXESTUMP",
+    },
+    TestVector {
+        name: "playfair-stallings",
+        source: "W. Stallings, Cryptography and Network Security",
+        algorithm: Algorithm::PlayFair,
+        keys: &["monarchy"],
+        padding: 'X',
+        omit: 'J',
+        plain_text: "balloon",
+        cipher_text: "IBSUPMNA",
+        decrypted: "BALXLOON",
+    },
+    TestVector {
+        name: "playfair-monarchy-z",
+        source: "https://www.geeksforgeeks.org/playfair-cipher-with-examples/",
+        algorithm: Algorithm::PlayFair,
+        keys: &["monarchy"],
+        padding: 'Z',
+        omit: 'J',
+        plain_text: "instruments",
+        cipher_text: "GATLMZCLRQTX",
+        decrypted: "INSTRUMENTSZ",
+    },
+    TestVector {
+        name: "two-square-wikipedia",
+        source: "https://en.wikipedia.org/wiki/Two-square_cipher",
+        algorithm: Algorithm::TwoSquare,
+        keys: &["EXAMPLE", "KEYWORD"],
+        padding: 'X',
+        omit: 'Q',
+        plain_text: "Help me Obi Wan Kenobi",
+        cipher_text: "HEDLXWSDJYANHOTKDG",
+        decrypted: "HELPMEOBIWANKENOBI",
+    },
+    TestVector {
+        name: "four-square-wikipedia",
+        source: "https://en.wikipedia.org/wiki/Four-square_cipher",
+        algorithm: Algorithm::FourSquare,
+        keys: &["EXAMPLE", "KEYWORD"],
+        padding: 'X',
+        omit: 'Q',
+        plain_text: "Help me Obi Wan Kenobi",
+        cipher_text: "FYGMKYHOBXMFKKKIMD",
+        decrypted: "HELPMEOBIWANKENOBI",
+    },
+];
+
+/// Result of running a test vector through the cipher of this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub vector: TestVector,
+    pub encrypted: String,
+    pub decrypted: String,
+}
+
+impl Outcome {
+    /// Whether en- and decryption yielded the published texts.
+    pub fn passed(&self) -> bool {
+        self.encrypted == self.vector.cipher_text && self.decrypted == self.vector.decrypted
+    }
+}
+
+impl TestVector {
+    /// The settings of the vector as config, e.g. to construct the cipher.
+    pub fn config(&self) -> CipherConfig {
+        CipherConfig {
+            algorithm: self.algorithm,
+            keys: self.keys.iter().map(|k| k.to_string()).collect(),
+            padding: Some(self.padding),
+            omit: Some(self.omit),
+            grouping: None,
+            final_digram: None,
+            seriation: None,
+            transparency: None,
+        }
+    }
+
+    /// En- and decrypts the vector with the cipher of this crate. Panics if
+    /// the cipher isn't available.
+    pub fn run(&self) -> Outcome {
+        let cipher = self
+            .config()
+            .build()
+            .expect("the settings of the vectors are valid");
+        let letters = "the vectors only consist of letters of the key";
+        Outcome {
+            vector: *self,
+            encrypted: cipher.encrypt(self.plain_text).expect(letters),
+            decrypted: cipher.decrypt(self.cipher_text).expect(letters),
+        }
+    }
+}
+
+/// Runs all vectors of the ciphers available, see TestVector::run and
+/// Algorithm::is_available.
+pub fn run_all() -> Vec<Outcome> {
+    VECTORS
+        .iter()
+        .filter(|vector| vector.algorithm.is_available())
+        .map(TestVector::run)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_vectors_pass() {
+        for outcome in run_all() {
+            assert!(outcome.passed(), "{:?}", outcome);
+        }
+        let mut wrong = VECTORS[0];
+        wrong.omit = 'Q';
+        assert!(!wrong.run().passed());
+    }
+}
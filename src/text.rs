@@ -0,0 +1,209 @@
+//! Cipher text carrying how it's laid out. A CipherText knows its grouping
+//! and casing, so it's displayed the same wherever it goes and can be
+//! turned back into the compact letters at any time, instead of formatting
+//! the plain string again and again.
+//!
+//! ```
+//! use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher, format::Case};
+//!
+//! let pfc = PlayFairKey::builder()
+//!     .key("playfair example")
+//!     .grouping(5)
+//!     .build()
+//!     .unwrap();
+//! let crypt = pfc.encrypt_text("hide the gold").unwrap();
+//! assert_eq!(crypt.to_string(), "BMODZ BXDNA GE");
+//! assert_eq!(crypt.grouping(), Some(5));
+//! let crypt = crypt.with_grouping(Some(4)).with_case(Case::Lower);
+//! assert_eq!(crypt.as_str(), "bmod zbxd nage");
+//! assert_eq!(crypt.to_raw(), "BMODZBXDNAGE");
+//! ```
+//!
+//! Together with Plaintext it lets the types tell plain and cipher text
+//! apart: Cypher::encrypt_typed only takes a Plaintext and
+//! Cypher::decrypt_typed only a CipherText, so a value can't be encrypted
+//! twice or decrypted by mistake.
+//!
+//! ```
+//! use playfair_cipher::{playfair::PlayFairKey, cryptable::Cypher};
+//! use playfair_cipher::text::{CipherText, Plaintext};
+//!
+//! let pfc = PlayFairKey::new("playfair example");
+//! let crypt = pfc.encrypt_typed(&Plaintext::new("hide the gold")).unwrap();
+//! let plain = pfc.decrypt_typed(&crypt).unwrap();
+//! assert_eq!(plain.as_str(), "HIDETHEGOLDX");
+//! // Cipher text received is taken explicitly
+//! let received = CipherText::new("bmodz bxdna ge", None);
+//! assert_eq!(pfc.decrypt_typed(&received).unwrap(), plain);
+//! ```
+//!
+
+use std::fmt;
+
+use crate::format::Case;
+
+/// Text to be encrypted or the result of a decryption, see
+/// Cypher::encrypt_typed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Plaintext(String);
+
+impl Plaintext {
+    pub fn new(text: impl Into<String>) -> Self {
+        Plaintext(text.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl From<String> for Plaintext {
+    fn from(text: String) -> Self {
+        Plaintext(text)
+    }
+}
+
+impl From<&str> for Plaintext {
+    fn from(text: &str) -> Self {
+        Plaintext(String::from(text))
+    }
+}
+
+impl fmt::Display for Plaintext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Plaintext {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Cipher text with its grouping and casing, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CipherText {
+    text: String,
+    grouping: Option<usize>,
+    case: Case,
+}
+
+impl CipherText {
+    /// Takes cipher text as returned by encrypt, laid out with the grouping
+    /// given in uppercase. Whitespace of the text is dropped first, a
+    /// grouping of 0 is taken as none.
+    pub fn new(crypt: &str, grouping: Option<usize>) -> Self {
+        CipherText::render(crypt, grouping.filter(|g| *g > 0), Case::Upper)
+    }
+
+    /// The same cipher text grouped anew.
+    pub fn with_grouping(self, grouping: Option<usize>) -> Self {
+        CipherText::render(&self.text, grouping.filter(|g| *g > 0), self.case)
+    }
+
+    /// The same cipher text in another case.
+    pub fn with_case(self, case: Case) -> Self {
+        CipherText::render(&self.text, self.grouping, case)
+    }
+
+    /// Number of letters per group, None if the letters aren't grouped.
+    pub fn grouping(&self) -> Option<usize> {
+        self.grouping
+    }
+
+    pub fn case(&self) -> Case {
+        self.case
+    }
+
+    /// The cipher text as displayed.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The compact cipher text: the letters in uppercase without any
+    /// whitespace, as returned by encrypt without grouping.
+    pub fn to_raw(&self) -> String {
+        self.text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+
+    fn render(crypt: &str, grouping: Option<usize>, case: Case) -> Self {
+        let mut text = String::with_capacity(crypt.len() + crypt.len() / 2);
+        for (idx, c) in crypt.chars().filter(|c| !c.is_whitespace()).enumerate() {
+            if let Some(g) = grouping {
+                if idx > 0 && idx.is_multiple_of(g) {
+                    text.push(' ');
+                }
+            }
+            text.push(match case {
+                Case::Upper => c.to_ascii_uppercase(),
+                Case::Lower => c.to_ascii_lowercase(),
+            });
+        }
+        CipherText {
+            text,
+            grouping,
+            case,
+        }
+    }
+}
+
+impl fmt::Display for CipherText {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl AsRef<str> for CipherText {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::cryptable::Cypher;
+    use crate::playfair::PlayFairKey;
+
+    #[test]
+    fn test_render() {
+        let crypt = CipherText::new("BMODZ BXDNA GE", None);
+        assert_eq!(crypt.as_str(), "BMODZBXDNAGE");
+        let crypt = crypt.with_case(Case::Lower).with_grouping(Some(3));
+        assert_eq!(crypt.to_string(), "bmo dzb xdn age");
+        assert_eq!((crypt.grouping(), crypt.case()), (Some(3), Case::Lower));
+        assert_eq!(
+            crypt.clone().with_grouping(Some(0)).as_str(),
+            "bmodzbxdnage"
+        );
+        assert_eq!(crypt.to_raw(), "BMODZBXDNAGE");
+        assert_eq!(CipherText::new("", Some(5)).as_str(), "");
+    }
+
+    #[test]
+    fn test_typed() {
+        let pfc = PlayFairKey::builder()
+            .key("playfair example")
+            .grouping(5)
+            .build()
+            .unwrap();
+        let plain = Plaintext::from("Hide the gold in the tree stump");
+        let crypt = pfc.encrypt_typed(&plain).unwrap();
+        assert_eq!(crypt.grouping(), Some(5));
+        assert_eq!(crypt.as_str(), pfc.encrypt(plain.as_str()).unwrap());
+        let decrypted = pfc.decrypt_typed(&crypt).unwrap();
+        assert_eq!(decrypted.as_str(), "HIDETHEGOLDINTHETREXESTUMP");
+        let recased = crypt.with_grouping(None).with_case(Case::Lower);
+        assert_eq!(pfc.decrypt_typed(&recased).unwrap(), decrypted);
+    }
+}
@@ -3,13 +3,19 @@
 //!
 
 use crate::{
-    cryptable::{Crypt, Cypher},
-    errors::CharNotInKeyError,
-    playfair::{EMPTY_SQ_POS, ROW_LENGTH},
-    structs::{CryptModus, CryptResult, Payload},
+    cryptable::{Crypt, Cypher, Internals, Layout, SquareCrypt, SquareCypher, SquareInternals},
+    digram::Rule,
+    errors::{CharNotInKeyError, InvalidOptionError},
+    options::{FinalDigram, PayloadOptions},
+    playfair::ROW_LENGTH,
+    seriation,
+    structs::{CryptModus, CryptResult},
 };
 
 use super::playfair::PlayFairKey;
+use std::fmt;
+
+pub use crate::options::Transparency;
 
 /// Two square cipher works as its name suggests with those 4 squares.
 /// E.g. having this key matrix
@@ -27,18 +33,240 @@ use super::playfair::PlayFairKey;
 /// T U V X Z
 ///
 ///
+#[derive(Debug, Clone)]
 pub struct TwoSquare {
     top: PlayFairKey,
     bottom: PlayFairKey,
+    options: PayloadOptions,
+    transparency: Transparency,
 }
 
 impl TwoSquare {
     pub fn new(key0: &str, key1: &str) -> Self {
+        TwoSquare::with_options(key0, key1, PayloadOptions::default())
+    }
+
+    /// Returns a builder to construct a Two Square cipher with options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::two_square::TwoSquare;
+    /// use playfair_cipher::cryptable::Cypher;
+    ///
+    /// let tsq = TwoSquare::builder()
+    ///     .keys("EXAMPLE", "KEYWORD")
+    ///     .grouping(4)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(tsq.encrypt("help me obi wan").unwrap(), "HECM XWSR KYXP");
+    /// ```
+    pub fn builder() -> TwoSquareBuilder {
+        TwoSquareBuilder::default()
+    }
+
+    /// The options the cipher was constructed with.
+    pub fn options(&self) -> &PayloadOptions {
+        &self.options
+    }
+
+    /// The top square.
+    pub fn top(&self) -> &PlayFairKey {
+        &self.top
+    }
+
+    /// The bottom square.
+    pub fn bottom(&self) -> &PlayFairKey {
+        &self.bottom
+    }
+
+    /// The rule for letters in the same column.
+    pub fn transparency(&self) -> Transparency {
+        self.transparency
+    }
+
+    /// The period of the seriation, if the payload is seriated.
+    pub fn seriation(&self) -> Option<usize> {
+        self.options.seriation
+    }
+
+    /// Counts the digrams of the payload which the cipher leaves as they
+    /// are, so the leak of a message can be judged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::two_square::{Transparency, TwoSquare};
+    ///
+    /// let tsq = TwoSquare::new("EXAMPLE", "KEYWORD");
+    /// assert_eq!(tsq.count_transparent("help me obi wan kenobi").unwrap(), 2);
+    /// let tsq = TwoSquare::builder()
+    ///     .keys("EXAMPLE", "KEYWORD")
+    ///     .transparency(Transparency::Shift)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(tsq.count_transparent("help me obi wan kenobi").unwrap(), 0);
+    /// ```
+    pub fn count_transparent(&self, payload: &str) -> Result<usize, CharNotInKeyError> {
+        Ok(self
+            .encrypt_digrams(payload)?
+            .iter()
+            .filter(|digram| digram.plain == digram.crypt)
+            .count())
+    }
+
+    pub(crate) fn with_options(key0: &str, key1: &str, options: PayloadOptions) -> Self {
+        TwoSquare::from_keys(
+            PlayFairKey::with_options(key0, options),
+            PlayFairKey::with_options(key1, options),
+            options,
+        )
+    }
+
+    pub(crate) fn from_keys(
+        top: PlayFairKey,
+        bottom: PlayFairKey,
+        options: PayloadOptions,
+    ) -> Self {
         TwoSquare {
-            top: PlayFairKey::new(key0),
-            bottom: PlayFairKey::new(key1),
+            top,
+            bottom,
+            options,
+            transparency: Transparency::Keep,
         }
     }
+
+    // The cipher with the settings beyond the payload options. The squares
+    // are taken without seriation, they may be used on their own.
+    pub(crate) fn with_settings(
+        self,
+        transparency: Transparency,
+        seriation: Option<usize>,
+    ) -> Result<Self, InvalidOptionError> {
+        seriation::validate_period(seriation)?;
+        Ok(TwoSquare {
+            transparency,
+            options: self.options.with_seriation(seriation),
+            ..self
+        })
+    }
+}
+
+/// Builder for a TwoSquare, see TwoSquare::builder.
+///
+#[derive(Debug, Clone)]
+pub struct TwoSquareBuilder {
+    key0: String,
+    key1: String,
+    padding: char,
+    omit: char,
+    grouping: Option<usize>,
+    final_digram: FinalDigram,
+    transparency: Transparency,
+    seriation: Option<usize>,
+}
+
+impl Default for TwoSquareBuilder {
+    fn default() -> Self {
+        let options = PayloadOptions::default();
+        TwoSquareBuilder {
+            key0: String::new(),
+            key1: String::new(),
+            padding: options.padding,
+            omit: options.omit,
+            grouping: options.grouping,
+            final_digram: options.final_digram,
+            transparency: Transparency::default(),
+            seriation: None,
+        }
+    }
+}
+
+impl TwoSquareBuilder {
+    /// The keywords the squares are built of.
+    pub fn keys(mut self, key0: &str, key1: &str) -> Self {
+        self.key0 = String::from(key0);
+        self.key1 = String::from(key1);
+        self
+    }
+
+    /// Letter used to split doubled letters and to pad a payload of odd length.
+    pub fn padding(mut self, padding: char) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Letter left out of the squares. J is replaced by I, any other letter
+    /// is dropped.
+    pub fn omit(mut self, omit: char) -> Self {
+        self.omit = omit;
+        self
+    }
+
+    /// Splits the cipher text into groups of this many letters.
+    pub fn grouping(mut self, grouping: usize) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// How the last letter of a payload of odd length is handled, defaults
+    /// to FinalDigram::Pad.
+    pub fn final_digram(mut self, final_digram: FinalDigram) -> Self {
+        self.final_digram = final_digram;
+        self
+    }
+
+    /// The rule for letters in the same column, defaults to
+    /// Transparency::Keep.
+    pub fn transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Seriates the payload with the period: it's written in blocks of two
+    /// lines of this many letters and the digrams are taken vertically.
+    /// Doubled letters are not split then. Applies to all the ways the
+    /// cipher en- and decrypts, streams included.
+    pub fn seriation(mut self, period: usize) -> Self {
+        self.seriation = Some(period);
+        self
+    }
+
+    pub fn build(self) -> Result<TwoSquare, InvalidOptionError> {
+        let options = PayloadOptions::new(self.padding, self.omit, self.grouping)?
+            .with_final_digram(self.final_digram);
+        TwoSquare::with_options(&self.key0, &self.key1, options)
+            .with_settings(self.transparency, self.seriation)
+    }
+}
+
+impl fmt::Display for TwoSquare {
+    /// Prints the top square above the bottom one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::two_square::TwoSquare;
+    ///
+    /// let tsq = TwoSquare::new("EXAMPLE", "KEYWORD");
+    /// print!("{}", tsq);
+    /// // E X A M P
+    /// // L B C D F
+    /// // G H I K N
+    /// // O Q R S T
+    /// // U V W Y Z
+    /// //
+    /// // K E Y W O
+    /// // R D A B C
+    /// // F G H I L
+    /// // M N P Q S
+    /// // T U V X Z
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.top)?;
+        writeln!(f)?;
+        write!(f, "{}", self.bottom)
+    }
 }
 
 impl Crypt for TwoSquare {
@@ -46,7 +274,7 @@ impl Crypt for TwoSquare {
         &self,
         a: char,
         b: char,
-        _modus: &crate::structs::CryptModus,
+        modus: &crate::structs::CryptModus,
     ) -> Result<crate::structs::CryptResult, crate::errors::CharNotInKeyError> {
         // E X A M P
         // L B C D F
@@ -64,51 +292,67 @@ impl Crypt for TwoSquare {
         // Ciphertext: HE CM XW SR KY XP HW NO DG
         //
 
-        let a_sq_pos = match self.top.key_map.get(&a) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        let b_sq_pos = match self.bottom.key_map.get(&b) {
-            Some(p) => p,
-            None => EMPTY_SQ_POS,
-        };
-        if a_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                a, &self.top.key
-            )));
-        } else if b_sq_pos.column == EMPTY_SQ_POS.column {
-            return Err(CharNotInKeyError::new(format!(
-                "Only chars A-Z possible - '{}' was not found in key {:?}",
-                b, &self.bottom.key
-            )));
+        let a_cell = self.top.cell(a)?;
+        let b_cell = self.bottom.cell(b)?;
+        let (a_row, a_column) = (a_cell / ROW_LENGTH, a_cell % ROW_LENGTH);
+        let (b_row, b_column) = (b_cell / ROW_LENGTH, b_cell % ROW_LENGTH);
+        if a_column == b_column {
+            let (a_row, b_row) = match (self.transparency, modus) {
+                (Transparency::Keep, _) => (a_row, b_row),
+                (Transparency::Swap, _) => (b_row, a_row),
+                (Transparency::Shift, CryptModus::Encrypt) => {
+                    ((a_row + 1) % ROW_LENGTH, (b_row + 1) % ROW_LENGTH)
+                }
+                (Transparency::Shift, CryptModus::Decrypt) => (
+                    (a_row + ROW_LENGTH - 1) % ROW_LENGTH,
+                    (b_row + ROW_LENGTH - 1) % ROW_LENGTH,
+                ),
+            };
+            return Ok(CryptResult {
+                a: self.top.key[(a_row * ROW_LENGTH + a_column) as usize],
+                b: self.bottom.key[(b_row * ROW_LENGTH + b_column) as usize],
+            });
         }
-        let (a_crypted_idx, b_crypted_idx) = (
-            a_sq_pos.row * ROW_LENGTH + b_sq_pos.column,
-            b_sq_pos.row * ROW_LENGTH + a_sq_pos.column,
-        );
-        let a_crypted = match self.top.key.get(a_crypted_idx as usize) {
-            Some(s) => *s,
-            None => '*',
-        };
-        let b_crypted = match self.bottom.key.get(b_crypted_idx as usize) {
-            Some(s) => *s,
-            None => '*',
-        };
         Ok(CryptResult {
-            a: a_crypted,
-            b: b_crypted,
+            a: self.top.key[(a_row * ROW_LENGTH + b_column) as usize],
+            b: self.bottom.key[(b_row * ROW_LENGTH + a_column) as usize],
         })
     }
 
-    fn crypt_payload(
-        &self,
-        payload: &str,
-        modus: &crate::structs::CryptModus,
-    ) -> Result<String, crate::errors::CharNotInKeyError> {
-        let mut payload_iter = Payload::new(payload);
+    fn payload_options(&self) -> &PayloadOptions {
+        &self.options
+    }
 
-        payload_iter.crypt_payload(self, modus)
+    fn square_crypt(&self) -> Option<&dyn SquareCrypt> {
+        Some(self)
+    }
+}
+
+impl SquareCrypt for TwoSquare {
+    fn cipher_squares(&self) -> [&PlayFairKey; 2] {
+        [&self.top, &self.bottom]
+    }
+
+    fn plain_squares(&self) -> [&PlayFairKey; 2] {
+        [&self.top, &self.bottom]
+    }
+
+    fn layout(&self) -> Layout<'_> {
+        Layout {
+            squares: vec![&self.top, &self.bottom],
+            columns: 1,
+            plain: [0, 1],
+            crypt: [0, 1],
+        }
+    }
+
+    fn rule(&self, a: char, b: char) -> Result<Rule, CharNotInKeyError> {
+        // Letters in the same column are left as they are, unless another
+        // Transparency is chosen
+        match self.top.cell(a)? % ROW_LENGTH == self.bottom.cell(b)? % ROW_LENGTH {
+            true => Ok(Rule::Column),
+            false => Ok(Rule::Rectangle),
+        }
     }
 }
 
@@ -157,6 +401,16 @@ impl Cypher for TwoSquare {
     fn decrypt(&self, payload: &str) -> Result<String, crate::errors::CharNotInKeyError> {
         self.crypt_payload(payload, &CryptModus::Decrypt)
     }
+
+    fn internals(&self) -> Option<Internals<'_>> {
+        Some(Internals(self))
+    }
+}
+
+impl SquareCypher for TwoSquare {
+    fn square_internals(&self) -> SquareInternals<'_> {
+        SquareInternals(self)
+    }
 }
 
 #[cfg(test)]
@@ -185,20 +439,30 @@ mod tests {
 
         assert!(
             two_square.top.key
-                == vec![
+                == [
                     'E', 'X', 'A', 'M', 'P', 'L', 'B', 'C', 'D', 'F', 'G', 'H', 'I', 'K', 'N', 'O',
                     'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'Y', 'Z'
                 ]
         );
         assert!(
             two_square.bottom.key
-                == vec![
+                == [
                     'K', 'E', 'Y', 'W', 'O', 'R', 'D', 'A', 'B', 'C', 'F', 'G', 'H', 'I', 'L', 'M',
                     'N', 'P', 'Q', 'S', 'T', 'U', 'V', 'X', 'Z'
                 ]
         );
     }
 
+    #[test]
+    fn test_two_square_display() {
+        let two_square = TwoSquare::new("EXAMPLE", "KEYWORD");
+        assert_eq!(
+            two_square.to_string(),
+            "E X A M P\nL B C D F\nG H I K N\nO Q R S T\nU V W Y Z\n\n\
+             K E Y W O\nR D A B C\nF G H I L\nM N P Q S\nT U V X Z\n"
+        );
+    }
+
     #[test]
     fn test_two_square_encrypt() {
         let two_square = TwoSquare::new("EXAMPLE", "KEYWORD");
@@ -217,6 +481,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_two_square_decrypt_doubled() {
+        // XZ encrypts to a doubled letter, which must not be split
+        let two_square = TwoSquare::new("Z", "");
+        assert_eq!(two_square.encrypt("XZ").unwrap(), "YY");
+        assert_eq!(two_square.decrypt("YY").unwrap(), "XZ");
+    }
+
     #[test]
     fn test_two_square_encrypt_second() {
         let two_square = TwoSquare::new("UEMFUI", "NIHKGDTMSXSEMLGIFW");
@@ -234,4 +506,190 @@ mod tests {
             Err(e) => panic!("CharNotInKeyError {}", e),
         }
     }
+
+    #[test]
+    fn test_two_square_coordinates() {
+        let cipher = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let coordinates = cipher.encrypt_coordinates("joe").unwrap();
+        assert_eq!(coordinates, "35 13 14 51");
+        assert_eq!(cipher.decrypt_coordinates(&coordinates).unwrap(), "IOEX");
+        assert!(matches!(
+            cipher.decrypt_coordinates("123"),
+            Err(crate::errors::CoordinateError::OddLength { length: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_two_square_seriation_coordinates() {
+        let two_square = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .seriation(4)
+            .build()
+            .unwrap();
+        // HELP over MIND, the vertical digrams HM EI LN PD. The top line of
+        // the cipher text is taken from the top square, the bottom line
+        // from the bottom square.
+        let plain = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let digrams: Vec<String> = ["HM", "EI", "LN", "PD"]
+            .iter()
+            .map(|d| plain.encrypt_coordinates(d).unwrap())
+            .collect();
+        let top: Vec<&str> = digrams.iter().map(|d| &d[..2]).collect();
+        let bottom: Vec<&str> = digrams.iter().map(|d| &d[3..]).collect();
+        let coordinates = two_square.encrypt_coordinates("help mind").unwrap();
+        assert_eq!(coordinates, [top, bottom].concat().join(" "));
+        assert_eq!(
+            two_square.decrypt_coordinates(&coordinates).unwrap(),
+            "HELPMIND"
+        );
+        assert_eq!(
+            two_square
+                .encrypt_formatted("help mind", &crate::format::Coordinates)
+                .unwrap(),
+            coordinates
+        );
+    }
+
+    #[test]
+    fn test_two_square_transparency() {
+        let payload = "HELPMEOBIWANKENOBI";
+        for transparency in [Transparency::Swap, Transparency::Shift] {
+            let two_square = TwoSquare::builder()
+                .keys("EXAMPLE", "KEYWORD")
+                .transparency(transparency)
+                .build()
+                .unwrap();
+            let crypt = two_square.encrypt(payload).unwrap();
+            // HE and NO are in the same column, the rectangles are kept
+            assert_ne!(&crypt[..2], "HE");
+            assert_eq!(&crypt[2..12], "CMXWSRKYXP");
+            assert_eq!(two_square.decrypt(&crypt).unwrap(), payload);
+        }
+        // Swapping the rows of letters in the same row changes nothing
+        let two_square = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .transparency(Transparency::Swap)
+            .build()
+            .unwrap();
+        assert_eq!(two_square.encrypt_digram('E', 'K').unwrap(), ['E', 'K']);
+        assert_eq!(two_square.encrypt_digram('L', 'K').unwrap(), ['E', 'R']);
+    }
+
+    #[test]
+    fn test_two_square_seriation_final_digram() {
+        let builder = TwoSquare::builder().keys("EXAMPLE", "KEYWORD").seriation(4);
+        let pass_through = builder
+            .clone()
+            .final_digram(FinalDigram::PassThrough)
+            .build()
+            .unwrap();
+        let crypt = pass_through.encrypt("help meob i").unwrap();
+        assert!(crypt.starts_with(&pass_through.encrypt("help meob").unwrap()));
+        assert!(crypt.ends_with('I'));
+        assert_eq!(pass_through.decrypt(&crypt).unwrap(), "HELPMEOBI");
+        let strict = builder.final_digram(FinalDigram::Strict).build().unwrap();
+        assert_eq!(
+            strict
+                .encrypt("help meob i")
+                .unwrap_err()
+                .original_position(),
+            Some(10)
+        );
+    }
+
+    #[test]
+    fn test_two_square_seriation() {
+        let two_square = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .seriation(4)
+            .build()
+            .unwrap();
+        assert_eq!(two_square.seriation(), Some(4));
+        // HELP over MEOB, the vertical digrams HM EE LO PB
+        let crypt = two_square.encrypt("help meob").unwrap();
+        let digrams: Vec<[char; 2]> = ["HM", "EE", "LO", "PB"]
+            .iter()
+            .map(|d| {
+                let d: Vec<char> = d.chars().collect();
+                two_square.encrypt_digram(d[0], d[1]).unwrap()
+            })
+            .collect();
+        let top: String = digrams.iter().map(|d| d[0]).collect();
+        let bottom: String = digrams.iter().map(|d| d[1]).collect();
+        assert_eq!(crypt, top + &bottom);
+        let payload = "HELPMEOBIWANKENOBI";
+        let crypt = two_square.encrypt(payload).unwrap();
+        assert_ne!(
+            crypt,
+            TwoSquare::new("EXAMPLE", "KEYWORD")
+                .encrypt(payload)
+                .unwrap()
+        );
+        assert_eq!(two_square.decrypt(&crypt).unwrap(), payload);
+        assert!(TwoSquare::builder().seriation(0).build().is_err());
+    }
+
+    #[test]
+    fn test_two_square_seriation_paths() {
+        let two_square = TwoSquare::builder()
+            .keys("EXAMPLE", "KEYWORD")
+            .seriation(4)
+            .grouping(5)
+            .build()
+            .unwrap();
+        let payload = "Help me, Obi-Wan Kenobi. You're my only hope";
+        let crypt = two_square.encrypt(payload).unwrap();
+        let mut written = String::new();
+        two_square.encrypt_to_writer(payload, &mut written).unwrap();
+        assert_eq!(written, crypt);
+        let mut chunks = String::new();
+        two_square
+            .encrypt_with(payload, |chunk| chunks.push_str(chunk))
+            .unwrap();
+        assert_eq!(chunks, crypt);
+        let lazy: String = two_square
+            .encrypt_iter(payload)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(lazy, crypt);
+        let mut encryptor = crate::session::Encryptor::new(&two_square);
+        assert_eq!(encryptor.process(payload).unwrap(), crypt);
+        let table = crate::digram_table::DigramTable::new(two_square.clone());
+        assert_eq!(table.encrypt(payload).unwrap(), crypt);
+        assert_eq!(
+            table.decrypt(&crypt).unwrap(),
+            two_square.decrypt(&crypt).unwrap()
+        );
+
+        // The digrams are the vertical pairs, HELP over MEOB
+        let digrams = two_square.encrypt_digrams(payload).unwrap();
+        let outcome = two_square.encrypt_with_outcome(payload).unwrap();
+        assert_eq!(outcome.ciphertext, crypt);
+        assert_eq!(
+            (outcome.fillers, outcome.padded, outcome.digrams),
+            (0, true, digrams.len())
+        );
+        let report = two_square.validate_input(payload);
+        assert_eq!((report.fillers, report.padded), (0, true));
+        assert_eq!(digrams[0].plain, ['H', 'M']);
+        let positioned = two_square.encrypt_positioned(payload).unwrap();
+        assert_eq!(positioned[1].positions, [Some(1), Some(5)]);
+        assert_eq!(positioned[1].original_positions, [Some(1), Some(6)]);
+        let letters: Vec<char> = crypt.chars().collect();
+        for (digram, positioned) in digrams.iter().zip(&positioned) {
+            assert_eq!(digram.crypt, positioned.crypt);
+            assert_eq!(letters[positioned.crypt_position], positioned.crypt[0]);
+        }
+    }
+
+    #[test]
+    fn test_two_square_digrams() {
+        let two_square = TwoSquare::new("EXAMPLE", "KEYWORD");
+        let digrams = two_square.encrypt_digrams("help me").unwrap();
+        assert_eq!(digrams[0].crypt, ['H', 'E']);
+        assert_eq!(digrams[0].rule, crate::digram::Rule::Column);
+        assert_eq!(digrams[1].plain, ['L', 'P']);
+        assert_eq!(digrams[1].crypt, ['C', 'M']);
+        assert_eq!(digrams[1].rule, crate::digram::Rule::Rectangle);
+    }
 }
@@ -0,0 +1,426 @@
+//! The core of the VIC cipher, the hand cipher used by the Soviet spy Reino
+//! Häyhänen in the 1950s: the plain text is turned into digits by a
+//! straddling checkerboard, a digit key is added without carry and the
+//! digits are transposed by columns.
+//!
+//! The stages are available on their own, so variants can be composed:
+//! StraddlingCheckerboard, add_key and subtract_key, transpose and
+//! untranspose. VicCipher chains them. The derivation of the keys from a
+//! phrase, a date and a personal number of the original is left out - the
+//! keys are given directly.
+//!
+//! ```
+//! use playfair_cipher::vic::VicCipher;
+//!
+//! let vic = VicCipher::builder()
+//!     .checkerboard_key("SPY")
+//!     .blanks(2, 6)
+//!     .additive("3947")
+//!     .transposition("MOSCOW")
+//!     .grouping(5)
+//!     .build()
+//!     .unwrap();
+//! let crypt = vic.encrypt("Meet at the bridge.");
+//! assert!(crypt.chars().all(|c| c.is_ascii_digit() || c == ' '));
+//! assert_eq!(vic.decrypt(&crypt).unwrap(), "MEETATTHEBRIDGE.");
+//! ```
+//!
+
+use crate::errors::{InvalidOptionError, VicError};
+
+// Letters and the two symbols filling the 28 cells of the checkerboard.
+const CELLS: usize = 28;
+
+/// Turns letters into digits: the eight letters of the top row become a
+/// single digit, the others two digits - the digit of one of the blank
+/// columns of the top row followed by their column. Besides A-Z the
+/// checkerboard holds '.' and '/'.
+///
+/// ```text
+///     0 1 2 3 4 5 6 7 8 9
+///     S P   Y A B   C D E
+///  2  F G H I J K L M N O
+///  6  Q R T U V W X Z . /
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StraddlingCheckerboard {
+    // The chars row by row, the top row without the blank columns.
+    chars: [char; CELLS],
+    blanks: [u8; 2],
+}
+
+impl StraddlingCheckerboard {
+    /// Builds the checkerboard of the keyword mixed alphabet. The blanks
+    /// are the two columns left empty in the top row, they must differ and
+    /// be below 10.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use playfair_cipher::vic::StraddlingCheckerboard;
+    ///
+    /// let board = StraddlingCheckerboard::new("SPY", [2, 6]).unwrap();
+    /// assert_eq!(board.encode("spy"), "013");
+    /// assert_eq!(board.decode("013").unwrap(), "SPY");
+    /// assert!(StraddlingCheckerboard::new("SPY", [2, 2]).is_err());
+    /// ```
+    pub fn new(keyword: &str, blanks: [u8; 2]) -> Result<Self, InvalidOptionError> {
+        if blanks[0] == blanks[1] || blanks.iter().any(|b| *b > 9) {
+            return Err(InvalidOptionError::new(format!(
+                "Blanks must be two distinct columns 0-9 - got {} and {}",
+                blanks[0], blanks[1]
+            )));
+        }
+        let mut chars = ['*'; CELLS];
+        let mut length = 0;
+        let mixed = keyword
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(char::is_ascii_uppercase)
+            .chain('A'..='Z')
+            .chain(['.', '/']);
+        for c in mixed {
+            if !chars[..length].contains(&c) {
+                chars[length] = c;
+                length += 1;
+            }
+        }
+        Ok(StraddlingCheckerboard { chars, blanks })
+    }
+
+    // Digits a char is encoded to, None if it is not on the board.
+    fn code(&self, c: char) -> Option<(u8, Option<u8>)> {
+        let idx = self.chars.iter().position(|x| *x == c)?;
+        if idx < 8 {
+            let column = (0..10).filter(|d| !self.blanks.contains(d)).nth(idx)?;
+            return Some((column, None));
+        }
+        let row = (idx - 8) / 10;
+        Some((self.blanks[row], Some(((idx - 8) % 10) as u8)))
+    }
+
+    /// Encodes the text into digits. Letters are converted to uppercase,
+    /// chars not on the board are dropped.
+    pub fn encode(&self, text: &str) -> String {
+        let mut digits = String::with_capacity(2 * text.len());
+        for c in text.chars().map(|c| c.to_ascii_uppercase()) {
+            if let Some((first, second)) = self.code(c) {
+                digits.push((b'0' + first) as char);
+                if let Some(second) = second {
+                    digits.push((b'0' + second) as char);
+                }
+            }
+        }
+        digits
+    }
+
+    /// Decodes digits produced by encode. Whitespace is ignored.
+    pub fn decode(&self, digits: &str) -> Result<String, VicError> {
+        let mut text = String::with_capacity(digits.len());
+        let mut row: Option<usize> = None;
+        let mut last = 0;
+        for (position, character) in digits.chars().enumerate() {
+            if character.is_whitespace() {
+                continue;
+            }
+            let digit = character.to_digit(10).ok_or(VicError::InvalidChar {
+                character,
+                position,
+            })? as u8;
+            last = position;
+            match row {
+                Some(r) => {
+                    text.push(self.chars[8 + r * 10 + digit as usize]);
+                    row = None;
+                }
+                None => match self.blanks.iter().position(|b| *b == digit) {
+                    Some(r) => row = Some(r),
+                    None => {
+                        let column = (0..10).filter(|d| !self.blanks.contains(d));
+                        let idx = column.take_while(|d| *d != digit).count();
+                        text.push(self.chars[idx]);
+                    }
+                },
+            }
+        }
+        match row {
+            Some(_) => Err(VicError::Truncated { position: last }),
+            None => Ok(text),
+        }
+    }
+}
+
+/// Adds the key digit by digit without carry, repeating the key. Chars
+/// other than digits are kept.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::vic::{add_key, subtract_key};
+///
+/// assert_eq!(add_key("12345", &[9, 9]), "01234");
+/// assert_eq!(subtract_key("01234", &[9, 9]), "12345");
+/// ```
+pub fn add_key(digits: &str, key: &[u8]) -> String {
+    shift_digits(digits, key, |d, k| (d + k) % 10)
+}
+
+/// Reverses add_key.
+pub fn subtract_key(digits: &str, key: &[u8]) -> String {
+    shift_digits(digits, key, |d, k| (d + 10 - k % 10) % 10)
+}
+
+fn shift_digits(digits: &str, key: &[u8], shift: impl Fn(u8, u8) -> u8) -> String {
+    if key.is_empty() {
+        return String::from(digits);
+    }
+    let mut key = key.iter().cycle();
+    digits
+        .chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) => (b'0' + shift(d as u8, *key.next().unwrap_or(&0))) as char,
+            None => c,
+        })
+        .collect()
+}
+
+// Order in which the columns are read: by the letters of the key, equal
+// letters from left to right.
+fn column_order(key: &str) -> Vec<usize> {
+    let key: Vec<char> = key.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let mut order: Vec<usize> = (0..key.len()).collect();
+    order.sort_by_key(|idx| key[*idx]);
+    order
+}
+
+/// Writes the text row by row under the key and reads it off column by
+/// column in the alphabetical order of the key letters. The last row may
+/// be incomplete.
+///
+/// # Example
+///
+/// ```
+/// use playfair_cipher::vic::{transpose, untranspose};
+///
+/// assert_eq!(transpose("WEAREDISCOVERED", "ZEBRAS"), "EVACDESERODEWIR");
+/// assert_eq!(untranspose("EVACDESERODEWIR", "ZEBRAS"), "WEAREDISCOVERED");
+/// ```
+pub fn transpose(text: &str, key: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let width = key.chars().count();
+    if width == 0 {
+        return String::from(text);
+    }
+    column_order(key)
+        .iter()
+        .flat_map(|column| chars.iter().skip(*column).step_by(width))
+        .collect()
+}
+
+/// Reverses transpose.
+pub fn untranspose(text: &str, key: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let width = key.chars().count();
+    if width == 0 {
+        return String::from(text);
+    }
+    let mut plain = vec!['*'; chars.len()];
+    let mut read = chars.iter();
+    for column in column_order(key) {
+        for idx in (column..chars.len()).step_by(width) {
+            if let Some(c) = read.next() {
+                plain[idx] = *c;
+            }
+        }
+    }
+    plain.into_iter().collect()
+}
+
+/// The checkerboard, additive and transposition stages chained, see the
+/// module documentation.
+#[derive(Debug, Clone)]
+pub struct VicCipher {
+    checkerboard: StraddlingCheckerboard,
+    additive: Vec<u8>,
+    transposition: String,
+    grouping: Option<usize>,
+}
+
+/// Builder for a VicCipher.
+#[derive(Debug, Clone)]
+pub struct VicCipherBuilder {
+    checkerboard_key: String,
+    blanks: [u8; 2],
+    additive: String,
+    transposition: String,
+    grouping: Option<usize>,
+}
+
+impl Default for VicCipherBuilder {
+    fn default() -> Self {
+        VicCipherBuilder {
+            checkerboard_key: String::new(),
+            blanks: [2, 6],
+            additive: String::new(),
+            transposition: String::new(),
+            grouping: None,
+        }
+    }
+}
+
+impl VicCipherBuilder {
+    /// Keyword the alphabet of the checkerboard is mixed with.
+    pub fn checkerboard_key(mut self, key: &str) -> Self {
+        self.checkerboard_key = String::from(key);
+        self
+    }
+
+    /// The blank columns of the checkerboard, defaults to 2 and 6.
+    pub fn blanks(mut self, first: u8, second: u8) -> Self {
+        self.blanks = [first, second];
+        self
+    }
+
+    /// The digits added to the encoded text.
+    pub fn additive(mut self, digits: &str) -> Self {
+        self.additive = String::from(digits);
+        self
+    }
+
+    /// The key of the columnar transposition.
+    pub fn transposition(mut self, key: &str) -> Self {
+        self.transposition = String::from(key);
+        self
+    }
+
+    /// Splits the cipher text into groups of this many digits.
+    pub fn grouping(mut self, grouping: usize) -> Self {
+        self.grouping = Some(grouping);
+        self
+    }
+
+    /// Fails if the blanks are invalid, the additive is not made of digits
+    /// or a key is empty.
+    pub fn build(self) -> Result<VicCipher, InvalidOptionError> {
+        let checkerboard = StraddlingCheckerboard::new(&self.checkerboard_key, self.blanks)?;
+        let additive: Option<Vec<u8>> = self
+            .additive
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8))
+            .collect();
+        let additive = match additive {
+            Some(additive) if !additive.is_empty() => additive,
+            _ => {
+                return Err(InvalidOptionError::new(format!(
+                    "The additive must consist of digits - got '{}'",
+                    self.additive
+                )))
+            }
+        };
+        if self.transposition.is_empty() {
+            return Err(InvalidOptionError::new(String::from(
+                "The transposition key must not be empty",
+            )));
+        }
+        if self.grouping == Some(0) {
+            return Err(InvalidOptionError::new(String::from(
+                "Grouping must be greater than 0",
+            )));
+        }
+        Ok(VicCipher {
+            checkerboard,
+            additive,
+            transposition: self.transposition,
+            grouping: self.grouping,
+        })
+    }
+}
+
+impl VicCipher {
+    /// Returns a builder to construct the cipher.
+    pub fn builder() -> VicCipherBuilder {
+        VicCipherBuilder::default()
+    }
+
+    pub fn checkerboard(&self) -> &StraddlingCheckerboard {
+        &self.checkerboard
+    }
+
+    /// Encodes, adds the additive and transposes the digits.
+    pub fn encrypt(&self, payload: &str) -> String {
+        let digits = self.checkerboard.encode(payload);
+        let digits = transpose(&add_key(&digits, &self.additive), &self.transposition);
+        match self.grouping {
+            Some(grouping) => digits
+                .as_bytes()
+                .chunks(grouping)
+                .map(|group| String::from_utf8_lossy(group).into_owned())
+                .collect::<Vec<String>>()
+                .join(" "),
+            None => digits,
+        }
+    }
+
+    /// Reverses encrypt. Whitespace is ignored.
+    pub fn decrypt(&self, payload: &str) -> Result<String, VicError> {
+        let mut digits = String::with_capacity(payload.len());
+        for (position, character) in payload.chars().enumerate() {
+            match character {
+                '0'..='9' => digits.push(character),
+                c if c.is_whitespace() => continue,
+                _ => {
+                    return Err(VicError::InvalidChar {
+                        character,
+                        position,
+                    })
+                }
+            }
+        }
+        let digits = subtract_key(&untranspose(&digits, &self.transposition), &self.additive);
+        self.checkerboard.decode(&digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_checkerboard() {
+        let board = StraddlingCheckerboard::new("SPY", [2, 6]).unwrap();
+        // The top row skips the blank columns 2 and 6
+        assert_eq!(board.encode("SPYABCDE"), "01345789");
+        assert_eq!(board.encode("FO./"), "20296869");
+        let text = "THEQUICKBROWNFOXJUMPSOVERTHELAZYDOG.";
+        assert_eq!(board.decode(&board.encode(text)).unwrap(), text);
+        assert!(matches!(
+            board.decode("012"),
+            Err(VicError::Truncated { position: 2 })
+        ));
+        assert!(matches!(
+            board.decode("01x"),
+            Err(VicError::InvalidChar {
+                character: 'x',
+                position: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_transpose_uneven() {
+        for length in 0..20 {
+            let text: String = "0123456789ABCDEFGHIJ".chars().take(length).collect();
+            assert_eq!(untranspose(&transpose(&text, "VIC"), "VIC"), text);
+        }
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        let builder = VicCipher::builder().additive("123").transposition("KEY");
+        assert!(builder.clone().build().is_ok());
+        assert!(builder.clone().additive("12a").build().is_err());
+        assert!(builder.clone().transposition("").build().is_err());
+        assert!(builder.blanks(3, 10).build().is_err());
+    }
+}